@@ -106,11 +106,66 @@ and SQLPage adds a few more:
    - if the argument is a string containing a valid json list, returns the parsed list,
    - otherwise returns a list containing only the argument
 - `array_contains`: returns true if a list contains a value
-- `icon_img`: generate an svg icon from a *tabler* icon name
-- `markdown`: renders markdown text
+- `icon`: generates an svg icon from a [*tabler* icon](https://tabler-icons.io/) name, with an optional size in pixels (24 by default) and [color](colors.sql): `{{icon ''user'' 32 ''red''}}`, or `{{icon icon size=16}}`. It was previously named `icon_img`, and the old name still works.
+- `format_date`: formats a date using [strftime syntax](https://docs.rs/chrono/latest/chrono/format/strftime/index.html), in an optional time zone. For instance: `{{format_date created_at ''%d/%m/%Y %H:%M'' tz=''Europe/Paris''}}`.
+   - the date can be an ISO 8601 string, a `YYYY-MM-DD HH:MM:SS` string, or a unix timestamp. Dates without a time zone are considered to be in UTC.
+   - the format defaults to `%Y-%m-%d %H:%M`.
+   - `tz` can be `UTC` (the default), `local` (the time zone of the server), an offset like `+02:00`, or a time zone name from the system''s IANA time zone database.
+- `format_number`: formats a number with the decimal and thousands separators of the language of the user (as sent by their browser). For instance: `{{format_number price decimals=2}}` displays `1,234.50` in English and `1.234,50` in German. The language can be forced with `locale=''fr''`.
+- `currency`: formats an amount of money in a currency given by its [ISO 4217 code](https://en.wikipedia.org/wiki/ISO_4217), with the conventions of the language of the user. For instance: `{{currency price ''EUR''}}` displays `€1,234.50` in English and `1 234,50 €` in French. It accepts the same `decimals` and `locale` options as `format_number`.
+- `markdown`: renders markdown text to html, the same way the built-in components render their `_md` properties. Use triple braces so that the generated html is not escaped: `{{{markdown description}}}`. Html tags written in the markdown text are escaped.
+   - it can also be used as a block, to render markdown written directly in the template: `{{#markdown}}# Hello {{name}}{{/markdown}}`.
+- `sanitize_html`: removes dangerous tags and attributes from a piece of html, with the same levels as the [html component](component.sql?component=html): `{{{sanitize_html comment ''basic''}}}`.
+- `is_current_path`: returns true if a link points to the page that is currently displayed. Its arguments are concatenated: `{{#if (is_current_path name ''.sql'')}}active{{/if}}`.
+- `csrf_token`: the token that forms must contain in a hidden `_sqlpage_csrf` field to be accepted when [CSRF protection](https://github.com/lovasoa/SQLpage/blob/main/configuration.md#csrf-protection) is enabled: `<input type="hidden" name="_sqlpage_csrf" value="{{csrf_token}}">`.
+- `csp_nonce`: the nonce of the [content security policy](https://github.com/lovasoa/SQLpage/blob/main/configuration.md#content-security-policy) of the page, that inline scripts must carry to be executed: `<script nonce="{{csp_nonce}}">...</script>`.
 - `each_row`: iterates over the rows of a query result
 - `typeof`: returns the type of a value (`string`, `number`, `boolean`, `object`, `array`, `null`)
 
+### Custom helpers
+
+You can define your own helpers without recompiling SQLPage, by writing them as handlebars templates
+in the `sqlpage/helpers` folder. A file named `sqlpage/helpers/status_badge.handlebars` defines a `status_badge` helper.
+The positional parameters of the helper are available in the `args` list, and its named parameters as variables.
+
+For instance, with the following `sqlpage/helpers/status_badge.handlebars`:
+
+```handlebars
+<span class="badge bg-{{default color ''blue''}}">{{args.[0]}}</span>
+```
+
+you can write `{{status_badge status color=''green''}}` in your components.
+
+Custom helpers are loaded when SQLPage starts, so you need to restart SQLPage after changing them.
+A custom helper with the same name as a built-in helper replaces it.
+
+### Partials
+
+Partials are pieces of templates that can be included in other templates with `{{> partial_name}}`.
+Some built-in components are made of partials that you can reuse in your own components,
+so that you do not have to copy the entire built-in component to make a small change.
+For instance, the rows of the `table` component are rendered by the `table_header` and `table_row` partials:
+
+```handlebars
+<table class="table table-sm">
+    {{#each_row}}
+        {{#if (eq @row_index 0)}}{{> table_header}}{{/if}}
+        {{> table_row}}
+    {{/each_row}}
+</table>
+```
+
+You can define your own partials in the `sqlpage/partials` folder: a file named `sqlpage/partials/signature.handlebars`
+can be included with `{{> signature}}`. A partial with the same name as a built-in partial replaces it in all the components.
+Like custom helpers, partials are loaded when SQLPage starts.
+
+When a component has a `group_by` property, the first row of each group has a `_sqlpage_group` property
+with the `value` of the group, and the number of displayed `columns`.
+The end of a group is marked by a `_sqlpage_group_end` property on the first row of the next group,
+and on the component itself for the last group. It contains the `value` of the group, the `count` of its rows,
+and the totals of its `group_sum` columns in `sum`.
+[See the built-in partials](https://github.com/lovasoa/SQLpage/blob/main/sqlpage/partials).
+
 ## Overwriting the default components
 
 You can overwrite the default components, including the `shell` component,
@@ -0,0 +1,65 @@
+select 'dynamic' as component, properties FROM example WHERE component = 'shell' LIMIT 1;
+
+select 'text' as component, '
+
+# Clean URLs with path parameters
+
+By default, the URL of a page is the path of its SQL file, and its parameters are in the query string:
+`/user.sql?id=42` executes `user.sql` with `$id` set to `42`.
+
+A file or a directory with a name in square brackets matches any value in its position in the URL,
+and makes the value available as a variable with the name in brackets:
+
+| File                        | URL                              | Variables                      |
+|-----------------------------|----------------------------------|--------------------------------|
+| `users/[id].sql`            | `/users/42`, or `/users/42.sql`  | `$id` is `42`                  |
+| `users/[id]/index.sql`      | `/users/42/`                     | `$id` is `42`                  |
+| `blog/[slug]/comments.sql`  | `/blog/hello-world/comments.sql` | `$slug` is `hello-world`       |
+
+Path parameters are used like the parameters of the query string,
+and they take precedence over parameters with the same name in the query string.
+
+## Example
+
+`users/[id].sql`:
+
+```sql
+select ''card'' as component;
+select name as title, email as description from users where id = $id;
+```
+
+A list of links to these pages:
+
+```sql
+select ''list'' as component;
+select name as title, ''/users/'' || id as link from users;
+```
+
+## Resolution rules
+
+ - A file with a literal name always takes precedence: if `users/new.sql` exists, `/users/new.sql` executes it, and not `users/[id].sql`.
+ - A URL without extension, like `/users/42`, is served by `users/[id].sql` only when there is no `users/42/` directory. Otherwise, it redirects to `/users/42/` as usual.
+ - Only the files in the web root directory are considered, not the files stored in the `sqlpage_files` table of the database.
+' as contents_md;
+
+select 'text' as component, '
+## Request methods
+
+Requests with the methods `POST`, `PUT`, `PATCH` and `DELETE` are handled by a file with the name of the method before its extension, when it exists.
+This makes it possible to build JSON APIs that follow REST conventions:
+
+| File                       | Request                          |
+|----------------------------|----------------------------------|
+| `items/[id].sql`           | `GET /items/42`                  |
+| `items/[id].put.sql`       | `PUT /items/42`                  |
+| `items/[id].delete.sql`    | `DELETE /items/42`               |
+| `items.post.sql`           | `POST /items.sql`                |
+
+When there is no file for the method of the request, the file without method is used,
+and the method is available with [`sqlpage.request_method()`](functions.sql?function=request_method#function).
+A file named after a method can only be accessed with that method: other methods get a `405 Method Not Allowed` error.
+
+`HEAD` requests execute the file only until the headers of the response are known:
+the queries that display the body of the page are not executed, unless the page uses an `ETag`.
+`OPTIONS` requests are answered without executing the file, with an `Allow` header that lists the methods the file accepts.
+' as contents_md;
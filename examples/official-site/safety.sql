@@ -82,6 +82,8 @@ that disallows the execution of any inline JavaScript code, and only allows load
 
 If you have some legitimate JavaScript code that you want to execute on your website, you can use the `javascript`
 parameter of the [`shell`](documentation.sql?component=shell#component) component to do so.
+The policy can be changed with the `content_security_policy` [configuration option](https://github.com/lovasoa/SQLpage/blob/main/configuration.md#content-security-policy),
+and custom components can allow their own inline scripts with a nonce that SQLPage generates for each page.
 
 ## Authentication
 
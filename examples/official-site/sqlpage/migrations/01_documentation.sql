@@ -31,6 +31,8 @@ INSERT INTO parameter(component, name, description, type, top_level, optional) S
     ('empty_description', 'Description to display if the list is empty.', 'TEXT', TRUE, TRUE),
     ('empty_description_md', 'Description to display if the list is empty, in Markdown format.', 'TEXT', TRUE, TRUE),
     ('empty_link', 'URL to which the user should be taken if they click on the empty list.', 'URL', TRUE, TRUE),
+    ('group_by', 'Name of a column by which the items are grouped. A header with the value of the column is displayed before each group of consecutive items that have the same value, so the query should be sorted by this column. Introduced in v0.18.0.', 'TEXT', TRUE, TRUE),
+    ('group_sum', 'Name of a numeric column whose total in each group is displayed at the end of the group, when `group_by` is set. This argument can be repeated multiple times to display the totals of multiple columns. Introduced in v0.18.0.', 'TEXT', TRUE, TRUE),
     -- item level
     ('title', 'Name of the list item, displayed prominently.', 'TEXT', FALSE, FALSE),
     ('description', 'A description of the list item, displayed as greyed-out text.', 'TEXT', FALSE, TRUE),
@@ -63,6 +65,8 @@ INSERT INTO parameter(component, name, description, type, top_level, optional) S
     ('description', 'A short paragraph displayed below the title.', 'TEXT', TRUE, TRUE),
     ('description_md', 'A short paragraph displayed below the title - formatted using markdown.', 'TEXT', TRUE, TRUE),
     ('columns', 'The number of columns in the grid of cards. This is just a hint, the grid will adjust dynamically to the user''s screen size, rendering fewer columns if needed to fit the contents.', 'INTEGER', TRUE, TRUE),
+    ('density', 'Spacing between and inside the cards. One of ''compact'', ''spacious'', or nothing for the default spacing. Compact grids are well suited to long catalogs of products.', 'TEXT', TRUE, TRUE),
+    ('image_height', 'Height, in pixels, of the images at the top of the cards. Images are cropped to fill this height, so that all the cards in the grid are aligned.', 'INTEGER', TRUE, TRUE),
     -- item level
     ('title', 'Name of the card, displayed at the top.', 'TEXT', FALSE, FALSE),
     ('description', 'The body of the card, where you put the main text contents of the card.
@@ -73,10 +77,13 @@ INSERT INTO parameter(component, name, description, type, top_level, optional) S
         This is useful if you want to display a lot of text in the card, with many options for formatting, such as
         line breaks, **bold**, *italics*, lists, #titles, [links](target.sql), ![images](photo.jpg), etc.', 'TEXT', FALSE, TRUE),
     ('top_image', 'The URL (absolute or relative) of an image to display at the top of the card.', 'URL', FALSE, TRUE),
+    ('image_url', 'The URL (absolute or relative) of an image to display at the top of the card. Same as top_image.', 'URL', FALSE, TRUE),
+    ('image_alt', 'A textual description of the image, for users who cannot see it.', 'TEXT', FALSE, TRUE),
     ('footer', 'Muted text to display at the bottom of the card.', 'TEXT', FALSE, TRUE),
     ('footer_md', 'Muted text to display at the bottom of the card, with rich text formatting in Markdown format.', 'TEXT', FALSE, TRUE),
     ('link', 'An URL to which the user should be taken when they click on the card.', 'URL', FALSE, TRUE),
     ('footer_link', 'An URL to which the user should be taken when they click on the footer.', 'URL', FALSE, TRUE),
+    ('_sqlpage_children', 'Components to display inside the card, as a JSON list of rows, like the properties of the dynamic component. For instance, `''[{"component": "list"}, {"title": "First item"}]''` displays a small list in the card. Introduced in v0.18.0.', 'JSON', FALSE, TRUE),
     ('icon', 'Name of an icon to display on the left side of the card.', 'ICON', FALSE, TRUE),
     ('color', 'The name of a color, to be displayed on the left of the card to highlight it.', 'COLOR', FALSE, TRUE),
     ('active', 'Whether this item in the grid is considered "active". Active items are displayed more prominently.', 'BOOLEAN', FALSE, TRUE)
@@ -92,6 +99,12 @@ INSERT INTO example(component, description, properties) VALUES
             json('[{"component":"card", "title":"Popular websites", "columns": 2}, '||
             '{"title":"Google", "link":"https://google.com", "description": "A search engine", "color": "red", "icon":"brand-google", "footer": "Owned by Alphabet Inc.", "footer_link": "https://abc.xyz/" }, '||
             '{"title":"Wikipedia", "link":"https://wikipedia.org", "description": "An encyclopedia", "color": "blue", "icon":"world", "active": true, "footer": "Owned by the Wikimedia Foundation", "footer_link": "https://wikimediafoundation.org/"}]')),
+    ('card', 'A compact product catalog, with images of the same height and a price in the footer.',
+        json('[
+            {"component":"card", "columns": 4, "density": "compact", "image_height": 150},
+            {"title": "Lynx plush", "image_url": "https://upload.wikimedia.org/wikipedia/commons/thumb/d/d8/Lynx_lynx-4.JPG/640px-Lynx_lynx-4.JPG", "image_alt": "A lynx", "description": "Soft and cuddly.", "footer": "$24.99", "link": "#" },
+            {"title": "Squirrel plush", "image_url": "https://upload.wikimedia.org/wikipedia/commons/thumb/b/be/Tamias-rufus-001.jpg/640px-Tamias-rufus-001.jpg", "image_alt": "A chipmunk", "description": "Small and striped.", "footer": "$14.99", "link": "#" }
+        ]')),
     ('card', 'A gallery of images.',
         json('[
             {"component":"card", "title":"My favorite animals in pictures", "columns": 3},
@@ -145,6 +158,7 @@ INSERT INTO parameter(component, name, description, type, top_level, optional) S
     ('counter', 'Display the number of the step on top of its name.', 'TEXT', TRUE, TRUE),
     ('title', 'Title of the section.', 'TEXT', TRUE, TRUE),
     ('description', 'Description of the section.', 'TEXT', TRUE, TRUE),
+    ('current', 'Number of the current step, starting at 1. Typically a URL parameter, like $step. The step is displayed as active, and the previous steps as completed. Introduced in v0.18.0.', 'INTEGER', TRUE, TRUE),
     -- item level
     ('title', 'Name of the step.', 'TEXT', FALSE, TRUE),
     ('description', 'Tooltip to display when the user passes their mouse over the step''s name.', 'TEXT', FALSE, TRUE),
@@ -161,7 +175,19 @@ INSERT INTO example(component, description, properties) VALUES
             '{"title": "Email confirmation", "icon": "mail", "link": "https://sql.ophir.dev", "description": "Confirm your email by clicking on a link in a validation email."},' ||
             '{"title": "ID verification", "description": "Checking personal information", "icon": "user", "link": "#"},' ||
             '{"title": "Final account approval", "description": "ophir.dev", "link": "https://ophir.dev/", "icon":"eye-check", "active": true},' ||
-            '{"title":"Account creation", "icon":"check"}]'));
+            '{"title":"Account creation", "icon":"check"}]')),
+    ('steps', 'A multi-page form wizard. Each step of the form is submitted to the same page, with the number of the next step in the URL:
+```sql
+select ''steps'' as component, coalesce($step, 1) as current;
+select ''Account'' as title; select ''Address'' as title; select ''Confirmation'' as title;
+
+select ''form'' as component, ''?step='' || (coalesce($step, 1) + 1) as action where coalesce($step, 1) < 3;
+select ''email'' as name where coalesce($step, 1) = 1;
+select ''address'' as name where $step = 2;
+select ''hidden'' as type, ''email'' as name, :email as value where $step = 2;
+```
+The values of the previous steps are kept in hidden fields, or saved in the database at each step.',
+            json('[{"component":"steps", "counter": true, "current": 2}, {"title": "Account"}, {"title": "Address"}, {"title": "Confirmation"}]'));
 
 INSERT INTO component(name, icon, description) VALUES
     ('text', 'align-left', 'A paragraph of text. The entire component will render as a single paragraph, with each item being rendered as a span of text inside it, the styling of which can be customized using parameters.');
@@ -170,6 +196,7 @@ INSERT INTO parameter(component, name, description, type, top_level, optional) S
     -- top level
     ('title', 'Text header before the paragraph.', 'TEXT', TRUE, TRUE),
     ('center', 'Whether to center the title.', 'BOOLEAN', TRUE, TRUE),
+    ('icon', 'Name of an icon to display before the title. Introduced in v0.18.0.', 'ICON', TRUE, TRUE),
     ('width', 'How wide the paragraph should be, in characters.', 'INTEGER', TRUE, TRUE),
     ('html', 'Raw html code to include on the page. Don''t use that if you are not sure what you are doing, it may have security implications.', 'TEXT', TRUE, TRUE),
     ('contents', 'A top-level paragraph of text to display, without any formatting, without having to make additional queries.', 'TEXT', TRUE, TRUE),
@@ -183,7 +210,8 @@ INSERT INTO parameter(component, name, description, type, top_level, optional) S
     ('code', 'Use a monospace font. Useful to display the text as code.', 'BOOLEAN', FALSE, TRUE),
     ('italics', 'Whether the span of text should be displayed as italics.', 'BOOLEAN', FALSE, TRUE),
     ('break', 'Indicates that the current span of text starts a new paragraph.', 'BOOLEAN', FALSE, TRUE),
-    ('size', 'A number between 1 and 6 indicating the font size.', 'INTEGER', FALSE, TRUE)
+    ('size', 'A number between 1 and 6 indicating the font size.', 'INTEGER', FALSE, TRUE),
+    ('icon', 'Name of an icon to display before this span of text. It has the same color as the text. Introduced in v0.18.0.', 'ICON', FALSE, TRUE)
 ) x;
 
 INSERT INTO example(component, description, properties) VALUES
@@ -255,7 +283,7 @@ INSERT INTO parameter(component, name, description, type, top_level, optional) S
     ('options', 'A json array of objects containing the label and value of all possible options of a select field. Used only when type=select. JSON objects in the array can contain the properties "label", "value" and "selected".', 'JSON', FALSE, TRUE),
     ('required', 'Set this to true to prevent the form contents from being sent if this field is left empty by the user.', 'BOOL', FALSE, TRUE),
     ('min', 'The minimum value to accept for an input of type number', 'NUMBER', FALSE, TRUE),
-    ('max', 'The minimum value to accept for an input of type number', 'NUMBER', FALSE, TRUE),
+    ('max', 'The maximum value to accept for an input of type number', 'NUMBER', FALSE, TRUE),
     ('checked', 'Used only for checkboxes and radio buttons. Indicates whether the checkbox should appear as already checked.', 'BOOL', FALSE, TRUE),
     ('multiple', 'Used only for select elements. Indicates that multiple elements can be selected simultaneously. When using multiple, you should add square brackets after the variable name: ''my_variable[]'' as name', 'BOOL', FALSE, TRUE),
     ('step', 'The increment of values in an input of type number. Set to 1 to allow only integers.', 'NUMBER', FALSE, TRUE),
@@ -267,7 +295,8 @@ INSERT INTO parameter(component, name, description, type, top_level, optional) S
     ('minlength', 'Minimum length of text allowed in the field.', 'NUMBER', FALSE, TRUE),
     ('maxlength', 'Maximum length of text allowed in the field.', 'NUMBER', FALSE, TRUE),
     ('formaction', 'When type is "submit", this specifies the URL of the file that will handle the form submission. Useful when you need multiple submit buttons.', 'TEXT', FALSE, TRUE),
-    ('class', 'A CSS class to apply to the form element.', 'TEXT', FALSE, TRUE)
+    ('class', 'A CSS class to apply to the form element.', 'TEXT', FALSE, TRUE),
+    ('error', 'An error message to display below the field, which is then highlighted in red. Use it to show the user why the value they submitted was rejected by your SQL code.', 'TEXT', FALSE, TRUE)
 ) x;
 INSERT INTO example(component, description, properties) VALUES
     (
@@ -303,6 +332,25 @@ When loading the page, the value for `:username` will be `NULL` if no value has
     '{"name": "Password", "type": "password", "pattern": "^(?=.*[A-Za-z])(?=.*\\d)[A-Za-z\\d]{8,}$", "required": true, "description": "Minimum eight characters, at least one letter and one number."},'||
     '{"label": "I accept the terms and conditions", "name": "terms", "type": "checkbox", "required": true}'||
     ']')),
+    ('form', '
+### Displaying validation errors
+
+The browser checks attributes such as `required`, `pattern` or `min` before submitting the form,
+but your SQL code must still check the submitted values, since they can be sent by any program.
+When a value is rejected, display the form again with an `error` message on the invalid field,
+and the value the user entered, so that they can fix it:
+
+```sql
+set email = sqlpage.validate(:email, ''email'');
+insert into subscribers(email) select $email where $email is not null;
+
+select ''form'' as component;
+select ''email'' as name, ''email'' as type, true as required, :email as value,
+    case when :email is not null and $email is null then ''This is not a valid email address'' end as error;
+```
+',
+    json('[{"component":"form", "validate": "Subscribe"}, '||
+    '{"name": "email", "type": "email", "required": true, "value": "john@example", "error": "This is not a valid email address"}]')),
     ('form', 'This example illustrates the use of the `select` type.
 In this select input, the various options are hardcoded, but they could also be loaded from a database table,
 using a function to convert the rows into a json array like 
@@ -479,13 +527,16 @@ name,description,price
 ;
 
 INSERT INTO component(name, icon, description) VALUES
-    ('chart', 'timeline', 'A component that plots data. Line, area, bar, and pie charts are all supported. Each item in the component is a data point in the graph.');
+    ('chart', 'timeline', 'A component that plots data. Line, area, bar, pie, scatter, bubble, heatmap and histogram charts are all supported. Each item in the component is a data point in the graph.');
 
 INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'chart', * FROM (VALUES
     -- top level
     ('title', 'The name of the chart.', 'TEXT', TRUE, TRUE),
-    ('type', 'The type of chart: "line", "area", "bar", "column", "pie", "scatter", "bubble", or "heatmap".', 'TEXT', TRUE, FALSE),
-    ('time', 'Whether the x-axis represents time. If set to true, the x values will be parsed and formatted as dates for the user.', 'BOOLEAN', TRUE, TRUE),
+    ('type', 'The type of chart: "line", "area", "bar", "column", "pie", "scatter", "bubble", "heatmap", or "histogram".', 'TEXT', TRUE, FALSE),
+    ('bins', 'Number of bars in a histogram. By default, it is the square root of the number of values.', 'INTEGER', TRUE, TRUE),
+    ('time', 'Whether the x-axis represents time. If set to true, the x values will be parsed and formatted as dates for the user, in their local time zone. Dates can be in the ISO 8601 format (2024-01-15T10:00:00Z), or in the YYYY-MM-DD HH:MM:SS format returned by many databases.', 'BOOLEAN', TRUE, TRUE),
+    ('zoom', 'Let the user zoom on a part of the x-axis by selecting it with the mouse. Useful for long time series.', 'BOOLEAN', TRUE, TRUE),
+    ('moving_average', 'Add, for each series, a line showing the average of the given number of previous points. Useful to see trends in noisy metrics.', 'INTEGER', TRUE, TRUE),
     ('ymin', 'The minimal value for the y-axis.', 'NUMBER', TRUE, TRUE),
     ('ymax', 'The maximum value for the y-axis.', 'NUMBER', TRUE, TRUE),
     ('xtitle', 'Title of the x axis, displayed below it.', 'TEXT', TRUE, TRUE),
@@ -525,6 +576,18 @@ INSERT INTO example(component, description, properties) VALUES
         {"x":"2023-04-01T00:00:00Z","y":106},
         {"x":"2023-07-01T00:00:00Z","y":53}
     ]')),
+    ('chart', 'A monitoring chart of a metric, with a 3-point moving average, that the user can zoom into.
+Dates in the `YYYY-MM-DD HH:MM:SS` format, as returned by `CURRENT_TIMESTAMP` in SQLite, are supported.', json('[
+    {"component": "chart", "title": "Requests per minute", "type": "line", "time": true, "zoom": true, "moving_average": 3, "color": ["azure", "orange"]},
+        {"x":"2024-01-15 10:00:00","y":120},
+        {"x":"2024-01-15 10:01:00","y":135},
+        {"x":"2024-01-15 10:02:00","y":98},
+        {"x":"2024-01-15 10:03:00","y":180},
+        {"x":"2024-01-15 10:04:00","y":142},
+        {"x":"2024-01-15 10:05:00","y":160},
+        {"x":"2024-01-15 10:06:00","y":210},
+        {"x":"2024-01-15 10:07:00","y":190}
+    ]')),
     ('chart', 'A pie chart.', json('[{"component":"chart", "title": "Answers", "type": "pie", "labels": true}, '||
     '{"label": "Yes", "value": 65}, '||
     '{"label": "No", "value": 35}]')),
@@ -535,6 +598,21 @@ INSERT INTO example(component, description, properties) VALUES
     '{"series": "Marketing", "x": 2022, "value": 15}, '||
     '{"series": "Human resources", "x": 2021, "value": 30}, '||
     '{"series": "Human resources", "x": 2022, "value": 55}]')),
+    ('chart', '
+## Histograms
+
+A histogram shows how the values of a numeric column are distributed.
+Select one row per value, with the value in the `value` (or `y`) property, and SQLPage counts how many values fall in each interval.
+Use `series` to compare the distributions of several groups.
+
+```sql
+select ''chart'' as component, ''histogram'' as type, ''Order amounts'' as title, 5 as bins;
+select total as value from orders;
+```
+',
+    json('[{"component":"chart", "title": "Order amounts", "type": "histogram", "bins": 5, "xtitle": "Amount (USD)", "ytitle": "Orders"}, '||
+    '{"value": 12}, {"value": 15}, {"value": 18}, {"value": 22}, {"value": 25}, {"value": 27}, {"value": 31}, '||
+    '{"value": 33}, {"value": 35}, {"value": 38}, {"value": 41}, {"value": 49}, {"value": 56}, {"value": 72}]')),
     ('chart', 'A scatter plot with multiple custom options.',
     json('[
         {"component":"chart", "title": "Gross domestic product and its growth", "type": "scatter",
@@ -568,15 +646,18 @@ The `color` property sets the color of each series separately, in order.
       ]'));
 
 INSERT INTO component(name, icon, description) VALUES
-    ('table', 'table', 'A table with optional filtering and sorting. Unlike most others, this component does not have a fixed set of item properties, any property that is used will be rendered directly as a column in the table.');
+    ('table', 'table', 'A table with optional filtering, sorting and pagination. Unlike most others, this component does not have a fixed set of item properties, any property that is used will be rendered directly as a column in the table.');
 
 INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'table', * FROM (VALUES
     -- top level
     ('sort', 'Make the columns clickable to let the user sort by the value contained in the column.', 'BOOLEAN', TRUE, TRUE),
     ('search', 'Add a search bar at the top of the table, letting users easily filter table rows by value.', 'BOOLEAN', TRUE, TRUE),
+    ('page_size', 'Display the rows in pages of this size, with buttons to navigate between pages. All rows are still sent to the browser, so sorting and searching apply to the whole table. For very large tables, prefer paginating in SQL with LIMIT and OFFSET.', 'INTEGER', TRUE, TRUE),
     ('markdown', 'Set this to the name of a column whose content should be interpreted as markdown . Used to display rich text with links in the table. This argument can be repeated multiple times to intepret multiple columns as markdown.', 'TEXT', TRUE, TRUE),
     ('icon', 'Set this to the name of a column whose content should be interpreted as a tabler icon name. Used to display icons in the table. This argument can be repeated multiple times to intepret multiple columns as icons. Introduced in v0.8.0.', 'TEXT', TRUE, TRUE),
     ('align_right', 'Name of a column the contents of which should be right-aligned. This argument can be repeated multiple times to align multiple columns to the right. Introduced in v0.15.0.', 'TEXT', TRUE, TRUE),
+    ('group_by', 'Name of a column by which the rows are grouped. A header row with the value of the column is displayed before each group of consecutive rows that have the same value, so the query should be sorted by this column. Grouped tables cannot be sorted, searched, or split into pages. Introduced in v0.18.0.', 'TEXT', TRUE, TRUE),
+    ('group_sum', 'Name of a numeric column whose total in each group is displayed in a row at the end of the group, when `group_by` is set. This argument can be repeated multiple times to display the totals of multiple columns. Introduced in v0.18.0.', 'TEXT', TRUE, TRUE),
     -- row level
     ('_sqlpage_css_class', 'For advanced users. Sets a css class on the table row. Added in v0.8.0.', 'TEXT', FALSE, TRUE),
     ('_sqlpage_color', 'Sets the background color of the row. Added in v0.8.0.', 'TEXT', FALSE, TRUE)
@@ -589,6 +670,16 @@ INSERT INTO example(component, description, properties) VALUES
         json('[{"component":"table", "sort":true, "search":true}, '||
         '{"Forename": "Ophir", "Surname": "Lojkine", "Pseudonym": "lovasoa"},' ||
         '{"Forename": "Linus", "Surname": "Torvalds", "Pseudonym": "torvalds"}]')),
+    ('table', 'A long table split into pages of 3 rows, that can be sorted and searched.',
+        json('[{"component":"table", "sort": true, "search": true, "page_size": 3}, '||
+        '{"Country": "France", "Capital": "Paris"}, {"Country": "Germany", "Capital": "Berlin"}, '||
+        '{"Country": "Italy", "Capital": "Rome"}, {"Country": "Spain", "Capital": "Madrid"}, '||
+        '{"Country": "Portugal", "Capital": "Lisbon"}, {"Country": "Belgium", "Capital": "Brussels"}, '||
+        '{"Country": "Austria", "Capital": "Vienna"}]')),
+    ('table', 'A table of sales grouped by region, with the total of each region. The rows must be sorted by the grouping column.',
+        json('[{"component":"table", "group_by": "Region", "group_sum": "Amount", "align_right": "Amount"}, '||
+        '{"Region": "North", "Product": "Apples", "Amount": 120}, {"Region": "North", "Product": "Pears", "Amount": 80}, '||
+        '{"Region": "South", "Product": "Oranges", "Amount": 200}, {"Region": "South", "Product": "Lemons", "Amount": 45}]')),
     ('table', 'A table that uses markdown to display links',
         json('[{"component":"table", "markdown": "Documentation", "icon": "icon", "sort": true, "search": true}, '||
         '{"icon": "table", "name": "Table", "description": "Displays SQL results as a searchable table.", "Documentation": "[docs](documentation.sql?component=table)", "_sqlpage_color": "red"},
@@ -608,7 +699,11 @@ INSERT INTO example(component, description, properties) VALUES
 
 
 INSERT INTO component(name, icon, description) VALUES
-    ('csv', 'download', 'A button that lets the user download data as a CSV file. Each column from the items in the component will map to a column in the resulting CSV.');
+    ('csv', 'download', 'Lets the user download data as a CSV file. Each column from the items in the component will map to a column in the resulting CSV.
+
+When it is the first component on the page, the whole response is a CSV file, that browsers download directly.
+The rows are streamed to the browser as they are read from the database, so this is suitable for very large exports.
+When used after other components, the csv component displays a button that contains the data, which is only suitable for small amounts of data.');
 
 INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'csv', * FROM (VALUES
     -- top level
@@ -616,9 +711,23 @@ INSERT INTO parameter(component, name, description, type, top_level, optional) S
     ('title', 'The text displayed on the download button.', 'TEXT', TRUE, FALSE),
     ('filename', 'The name of the file that should be downloaded (without the extension).', 'TEXT', TRUE, TRUE),
     ('icon', 'Name of the icon (from tabler-icons.io) to display in the button.', 'ICON', TRUE, TRUE),
-    ('color', 'Color of the button', 'COLOR', TRUE, TRUE)
+    ('color', 'Color of the button', 'COLOR', TRUE, TRUE),
+    ('bom', 'Whether to start the file with a byte order mark, which lets Microsoft Excel detect that the file is encoded in UTF-8. Only used when csv is the first component on the page.', 'BOOLEAN', TRUE, TRUE)
 ) x;
 
+INSERT INTO example(component, description) VALUES
+    ('csv', '
+### Downloading a large export
+
+When the csv component is the first one in your SQL file, the page itself becomes a CSV file download.
+Link to this page from another one to let users export data.
+
+```sql
+select ''csv'' as component, ''orders'' as filename, '';'' as separator, true as bom;
+select id, customer, total, created_at from orders;
+```
+');
+
 INSERT INTO example(component, description, properties) VALUES
     ('csv', 'CSV download button',
         json('[{"component":"csv", "title": "Download my data", "filename": "people", "icon": "file-download", "color": "green"}, '||
@@ -724,20 +833,27 @@ INSERT INTO component(name, icon, description) VALUES
 INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'shell', * FROM (VALUES
     -- top level
     ('title', 'The title of your page. Will be shown in a top bar above the page contents. Also usually displayed by web browsers as the name of the web page''s tab.', 'TEXT', TRUE, TRUE),
-    ('description', 'A description of the page. It can be displayed by search engines when your page appears in their results.', 'TEXT', TRUE, TRUE),
+    ('description', 'A description of the page. It can be displayed by search engines when your page appears in their results, and in link previews when the page is shared.', 'TEXT', TRUE, TRUE),
     ('link', 'The target of the link in the top navigation bar.', 'URL', TRUE, TRUE),
-    ('css', 'The URL of a CSS file to load and apply to the page.', 'URL', TRUE, TRUE),
-    ('javascript', 'The URL of a Javascript file to load and execute on the page.', 'URL', TRUE, TRUE),
-    ('image', 'The URL of an image to display next to the page title.', 'URL', TRUE, TRUE),
+    ('css', 'The URL of a CSS file to load and apply to the page. Can be a JSON array of URLs to load multiple files.', 'URL', TRUE, TRUE),
+    ('javascript', 'The URL of a Javascript file to load and execute on the page. Can be a JSON array of URLs to load multiple files.', 'URL', TRUE, TRUE),
+    ('style', 'CSS rules to include directly in the page, in a <style> tag. Useful for small adjustments that do not deserve a separate CSS file. Can be a JSON array of strings.', 'TEXT', TRUE, TRUE),
+    ('image', 'The URL of an image to display next to the page title. Also used as the preview image (og:image) when the page is shared on social networks, which requires an absolute URL.', 'URL', TRUE, TRUE),
+    ('favicon', 'The URL of the icon displayed by browsers in the tab of the page. Introduced in v0.18.0.', 'URL', TRUE, TRUE),
+    ('canonical', 'The preferred absolute URL of the page, for search engines and link previews, when the same page can be reached from several URLs. Introduced in v0.18.0.', 'URL', TRUE, TRUE),
     ('icon', 'Name of an icon (from tabler-icons.io) to display next to the title in the navigation bar.', 'ICON', TRUE, TRUE),
-    ('menu_item', 'Adds a menu item in the navigation bar at the top of the page. The menu item will have the specified name, and will link to as .sql file of the same name. A dropdown can be generated by passing a json object with a `title` and `submenu` properties.', 'TEXT', TRUE, TRUE),
+    ('menu_item', 'Adds a menu item in the navigation bar at the top of the page. The menu item will have the specified name, and will link to as .sql file of the same name. A dropdown can be generated by passing a json object with a `title` and `submenu` properties. Menu items objects can also have a `link` and an `icon`, and submenus can be nested in other submenus (since v0.18.0). The item that links to the current page is highlighted.', 'TEXT', TRUE, TRUE),
     ('search_target', 'When this is set, a search field will appear in the top navigation bar, and load the specified sql file with an URL parameter named "search" when the user searches something.', 'TEXT', TRUE, TRUE),
     ('norobot', 'Forbids robots to save this page in their database and follow the links on this page. This will prevent this page to appear in Google search results for any query, for instance.', 'BOOLEAN', TRUE, TRUE),
     ('font', 'Name of a font to display the text in. This has to be a valid font name from fonts.google.com.', 'TEXT', TRUE, TRUE),
     ('font_size', 'Font size on the page, in pixels. Set to 18 by default.', 'INTEGER', TRUE, TRUE),
-    ('language', 'The language of the page. This can be used by search engines and screen readers to determine in which language the page is written.', 'TEXT', TRUE, TRUE),
+    ('language', 'The language of the page. This can be used by search engines and screen readers to determine in which language the page is written. Pages in languages that are written from right to left, such as Arabic (`ar`) or Hebrew (`he`), are displayed from right to left.', 'TEXT', TRUE, TRUE),
+    ('rtl', 'Displays the page from right to left, regardless of its language. Introduced in v0.18.0.', 'BOOLEAN', TRUE, TRUE),
+    ('print', 'Displays a print-friendly version of the page: without the menu and the footer, in the light theme, with all the rows of the tables and all the collapsed sections expanded. Any page can also be displayed this way by adding `?_print=1` to its URL, to let users print it or save it as a PDF. Introduced in v0.18.0.', 'BOOLEAN', TRUE, TRUE),
     ('refresh', 'Number of seconds after which the page should refresh. This can be useful to display dynamic content that updates automatically.', 'INTEGER', TRUE, TRUE),
-    ('theme', 'Set to "dark" to use a dark theme.', 'TEXT', TRUE, TRUE),
+    ('rss', 'URL of an RSS or Atom feed for the site, generated with the feed component. Lets browsers and feed readers discover it automatically. Introduced in v0.18.0.', 'URL', TRUE, TRUE),
+    ('theme', 'Set to "dark" to use a dark theme, or to "auto" (since v0.18.0) to follow the preference of the operating system of the user.', 'TEXT', TRUE, TRUE),
+    ('theme_toggle', 'Shows a button in the navigation bar that lets users switch between the light and dark themes. Their choice is remembered in a cookie. Introduced in v0.18.0.', 'BOOLEAN', TRUE, TRUE),
     ('footer', 'Muted text to display in the footer of the page. This can be used to display a link to the terms and conditions of your application, for instance. By default, shows "Built with SQLPage". Supports links with markdown.', 'TEXT', TRUE, TRUE)
 ) x;
 
@@ -774,4 +890,20 @@ On other engines (such as SQLite), you can use the [`dynamic`](?component=dynami
                            "https://cdn.jsdelivr.net/npm/prismjs@1/plugins/autoloader/prism-autoloader.min.js"],
             "css": "/prism-tabler-theme.css",
             "footer": "Official [SQLPage](https://sql.ophir.dev) documentation"
-        }]'));
+        }]')),
+    ('shell', '
+### Partial page updates
+
+When a page is requested with the `_sqlpage_embed` URL parameter, as in `/list.sql?_sqlpage_embed`,
+the shell is not rendered: the response contains only the components of the page,
+and the properties of the `shell` component are ignored.
+The same happens automatically for requests made by [htmx](https://htmx.org/), except for boosted links.
+
+This lets you update a part of a page without reloading it:
+
+```html
+<button hx-get="/todo_list.sql" hx-target="#todos">Refresh</button>
+```
+
+Introduced in v0.18.0.
+', NULL);
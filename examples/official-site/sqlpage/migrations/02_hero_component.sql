@@ -66,6 +66,27 @@ FROM (
                 TRUE,
                 TRUE
             ),
+            (
+                'background_image',
+                'The URL of an image to display behind the title, on the whole width of the page. The image is darkened so that the text stays readable. Introduced in v0.18.0.',
+                'URL',
+                TRUE,
+                TRUE
+            ),
+            (
+                'secondary_link',
+                'Creates a second, less prominent, call to action button next to the first one. Introduced in v0.18.0.',
+                'URL',
+                TRUE,
+                TRUE
+            ),
+            (
+                'secondary_link_text',
+                'The text to display in the second call to action button. Defaults to "Learn more". Introduced in v0.18.0.',
+                'TEXT',
+                TRUE,
+                TRUE
+            ),
             -- item level
             (
                 'title',
@@ -130,4 +151,20 @@ VALUES (
          '{"title": "Beautiful", "description": "Uses pre-defined components that look professional.", "icon": "eye", "color": "green", "link": "/"},' ||
          '{"title": "Easy", "description_md": "You can teach yourself enough SQL to use [**SQLPage**](https://sql.ophir.dev) in a weekend.", "icon": "sofa", "color": "blue", "link": "/"}' || ']'
         )
+    ),
+    (
+        'hero',
+        'A landing page banner, with the title on top of a background image, and two call to action buttons.',
+        json(
+            '[{
+            "component":"hero",
+            "title": "Build apps in SQL",
+            "description": "From your database to a web application in minutes.",
+            "background_image": "https://upload.wikimedia.org/wikipedia/commons/thumb/e/e4/Lac_de_Zoug.jpg/1280px-Lac_de_Zoug.jpg",
+            "link": "/your-first-sql-website/",
+            "link_text": "Get started",
+            "secondary_link": "/documentation.sql",
+            "secondary_link_text": "Documentation"
+        }]'
+        )
     );
@@ -57,7 +57,7 @@ VALUES (
         'alert',
         'dismissible',
         'Whether the user can close the alert message.',
-        'TEXT',
+        'BOOLEAN',
         TRUE,
         TRUE
     ),
@@ -77,6 +77,22 @@ VALUES (
         TRUE,
         TRUE
     ),
+    (
+        'alert',
+        'toast',
+        'Display the alert as a notification in the top right corner of the screen, on top of the page contents. Toasts disappear automatically after a few seconds. Introduced in v0.18.0.',
+        'BOOLEAN',
+        TRUE,
+        TRUE
+    ),
+    (
+        'alert',
+        'delay',
+        'Number of milliseconds after which the alert disappears automatically. Defaults to 5000 for toasts. Set it to 0 to keep a toast visible until the user closes it. Introduced in v0.18.0.',
+        'INTEGER',
+        TRUE,
+        TRUE
+    ),
     (
         'alert',
         'link_text',
@@ -172,6 +188,20 @@ VALUES (
             }]'
         )
     ),
+    (
+        'alert',
+        'A notification after a form submission, that disappears after 3 seconds.
+Alerts can be placed anywhere in the page, for instance after a query that inserts the submitted data:
+
+```sql
+insert into users(name) select :name where :name is not null;
+select ''alert'' as component, ''User created'' as title, TRUE as toast, 3000 as delay, ''green'' as color
+where :name is not null;
+```',
+        JSON(
+            '[{"component":"alert", "title":"User created", "description":"John Doe was added to the list of users.", "icon":"check", "color":"green", "toast": true, "delay": 3000}]'
+        )
+    ),
     (
         'alert',
         'An alert message with a Markdown-formatted description',
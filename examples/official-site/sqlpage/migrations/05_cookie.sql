@@ -62,7 +62,7 @@ VALUES (
     (
         'cookie',
         'http_only',
-        'Whether the cookie should only be accessible via HTTP and not via client-side scripts. If not specified, the cookie will be accessible via both HTTP and client-side scripts.',
+        'Whether the cookie should only be accessible via HTTP and not via client-side scripts. Defaults to TRUE. Set it to FALSE if you need to read the cookie from JavaScript.',
         'BOOLEAN',
         TRUE,
         TRUE
@@ -70,7 +70,7 @@ VALUES (
     (
         'cookie',
         'remove',
-        'Set to TRUE to remove the cookie from the client browser. When specified, other parameters are ignored.',
+        'Set to TRUE to remove the cookie from the client browser. When specified, other parameters are ignored, except for `path` and `domain`, which must be the same as the ones that were used to create the cookie.',
         'BOOLEAN',
         TRUE,
         TRUE
@@ -78,7 +78,7 @@ VALUES (
     (
         'cookie',
         'max_age',
-        'The maximum age of the cookie in seconds. number of seconds until the cookie expires. If both Expires and Max-Age are set, Max-Age has precedence.',
+        'The maximum age of the cookie, in seconds. If both `expires` and `max_age` are set, `max_age` has precedence.',
         'INTEGER',
         TRUE,
         TRUE
@@ -44,6 +44,14 @@ VALUES (
         'TEXT',
         TRUE,
         TRUE
+    ),
+    (
+        'authentication',
+        'condition',
+        'A condition that must be true for the user to access the page, such as the existence of a valid session. When both a condition and a password_hash are given, both must be valid: a missing password denies access.',
+        'BOOLEAN',
+        TRUE,
+        TRUE
     );
 
 -- Insert an example usage of the http_header component into the example table
@@ -116,4 +124,13 @@ RETURNING
     ''session_token'' AS name,
     id AS value;
 ```
+
+Then, on the protected pages, use the `condition` property to check that the session token is valid,
+without having to hash any password:
+
+```sql
+SELECT ''authentication'' AS component,
+    ''login.sql'' AS link,
+    EXISTS (SELECT 1 FROM session WHERE id = sqlpage.cookie(''session_token'')) AS condition;
+```
 ');
@@ -28,6 +28,14 @@ VALUES (
         'TEXT',
         TRUE,
         FALSE
+    ),
+    (
+        'redirect',
+        'status',
+        'The HTTP status code of the redirection. 302 (Found) by default. Use 303 (See Other) after a form submission to make sure the browser loads the target page with a GET request, 307 to have the browser repeat the same request on the new URL, and 301 or 308 for permanent redirections.',
+        'INTEGER',
+        TRUE,
+        TRUE
     );
 -- Insert an example usage of the http_header component into the example table
 INSERT INTO example (component, description)
@@ -41,4 +49,16 @@ SELECT ''redirect'' AS component, ''login.sql'' AS link
 WHERE NOT EXISTS (SELECT 1 FROM login_session WHERE id = sqlpage.cookie(''session_id''));
 ```
 '
-    );
\ No newline at end of file
+    ),
+    (
+        'redirect',
+        '
+Redirect the user to the list of items after a form submission, using a [303 See Other](https://developer.mozilla.org/en-US/docs/Web/HTTP/Status/303) status,
+so that reloading the target page does not submit the form again:
+
+```sql
+INSERT INTO items(name) VALUES (:name);
+SELECT ''redirect'' AS component, ''items.sql'' AS link, 303 AS status;
+```
+'
+    );
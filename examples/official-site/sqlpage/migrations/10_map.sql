@@ -17,7 +17,7 @@ INSERT INTO parameter (
 VALUES (
         'map',
         'latitude',
-        'Latitude of the center of the map.',
+        'Latitude of the center of the map. If the center of the map is not set, the map is zoomed to show all the markers.',
         'REAL',
         TRUE,
         TRUE
@@ -33,7 +33,7 @@ VALUES (
     (
         'map',
         'zoom',
-        'Zoom Level to apply to the map. Defaults to 5.',
+        'Zoom Level to apply to the map. Defaults to 5. When the center of the map is not set, this is the maximum zoom level used to show all the markers.',
         'REAL',
         TRUE,
         TRUE
@@ -152,6 +152,16 @@ VALUES (
             '[{ "component": "map", "zoom": 1 }, { "title": "New Delhi", "latitude": 28.6139, "longitude": 77.2090 }]'
         )
     ),
+    (
+        'map',
+        'When the center of the map is not set, the map automatically zooms to show all the markers.',
+        JSON(
+            '[{ "component": "map" },
+            { "title": "Lisbon", "latitude": 38.7223, "longitude": -9.1393 },
+            { "title": "Warsaw", "latitude": 52.2297, "longitude": 21.0122 },
+            { "title": "Athens", "latitude": 37.9838, "longitude": 23.7275 }]'
+        )
+    ),
     (
         'map',
         'Basic marker defined in GeoJSON. Using [leaflet marker options](https://leafletjs.com/reference.html#marker-option) as GeoJSON properties.',
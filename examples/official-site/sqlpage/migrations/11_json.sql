@@ -2,7 +2,9 @@ INSERT INTO component (name, description, icon, introduced_in_version)
 VALUES (
         'json',
         'For advanced users, allows you to easily build an API over your database.
-        The json component responds to the current HTTP request with a JSON object.
+        The json component responds to the current HTTP request with JSON instead of an HTML page:
+        either a single JSON object that you build yourself, or all the rows returned by the following queries,
+        as a JSON array or as JSON lines.
         This component must appear at the top of your SQL file, before any other data has been sent to the browser.',
         'code',
         '0.9.0'
@@ -19,10 +21,18 @@ INSERT INTO parameter (
 VALUES (
         'json',
         'contents',
-        'The JSON payload to send. You should use your database''s built-in json functions to build the value to enter here.',
+        'The JSON payload to send. You should use your database''s built-in json functions to build the value to enter here. When this property is not set, the rows returned by the following queries are sent instead.',
         'TEXT',
         TRUE,
-        FALSE
+        TRUE
+    ),
+    (
+        'json',
+        'type',
+        'How to send the rows of the following queries, when `contents` is not set. `array` (the default) sends a single JSON array containing one object per row. `jsonlines` sends one JSON object per line, with the `application/x-ndjson` content type, which lets clients process rows as soon as they arrive.',
+        'TEXT',
+        TRUE,
+        TRUE
     );
 -- Insert an example usage of the http_header component into the example table
 INSERT INTO example (component, description)
@@ -64,4 +74,31 @@ This will return a JSON response that looks like this:
 ```
 
 '
-    );
\ No newline at end of file
+    ),
+    (
+        'json',
+        '
+### Sending the results of a query
+
+When you do not set the `contents` property, every row returned by the queries that follow the json component
+is sent as a JSON object, without any HTML around it.
+Rows are streamed to the client as they come out of the database, so this works even for very large results.
+
+```sql
+SELECT ''json'' AS component;
+SELECT id, username FROM users;
+```
+
+This will return a JSON array:
+
+```json
+[
+{"id":1,"username":"James"},
+{"id":2,"username":"Ophir"}
+]
+```
+
+Use `''jsonlines'' AS type` to get one JSON object per line instead.
+If an error occurs while the response is being sent, an object with an `error` property is added to the output.
+'
+    );
@@ -32,7 +32,7 @@ VALUES (
     (
         'tab',
         'active',
-        'Whether the tab is active or not. Defaults to false.',
+        'Whether the tab is active or not. When no tab is explicitly marked as active, the tab selected in the URL is active, or the first tab if none is selected.',
         'BOOLEAN',
         FALSE,
         TRUE
@@ -61,6 +61,14 @@ VALUES (
         FALSE,
         TRUE
     ),
+    (
+        'tab',
+        'parameter',
+        'Name of the URL parameter that holds the title of the selected tab, for tabs without a custom link. Defaults to ''tab''. Use a different name when a page contains several sets of tabs. The other URL parameters of the page are kept when switching tabs.',
+        'TEXT',
+        TRUE,
+        TRUE
+    ),
     (
         'tab',
         'center',
@@ -105,5 +113,24 @@ View the [dynamic tabs example](examples/tabs.sql).
             { "title": "Card", "link": "?component=card#component", "icon": "credit-card" }
         ]'
         )
+    ),
+    (
+        'tab',
+        'A detail page with several views. The tabs do not need a link: they keep the `id` parameter of the page,
+and set a `view` parameter to the title of the selected tab. The first tab is selected by default.
+The components that follow the tabs display the contents of the selected view, for instance:
+
+```sql
+select ''table'' as component;
+select * from orders where customer_id = $id and coalesce($view, ''Orders'') = ''Orders'';
+```',
+        JSON(
+            '[
+            { "component": "tab", "parameter": "view" },
+            { "title": "Orders", "icon": "shopping-cart" },
+            { "title": "Invoices", "icon": "file-invoice" },
+            { "title": "Messages", "icon": "mail" }
+        ]'
+        )
     )
     ;
\ No newline at end of file
@@ -142,7 +142,8 @@ VALUES (
     'allowed_mime_type',
     'Makes the function return NULL if the uploaded file is not of the specified MIME type.
     If omitted, any MIME type is allowed.
-    This makes it possible to restrict the function to only accept certain file types.',
+    This makes it possible to restrict the function to only accept certain file types.
+    Use a wildcard such as `image/*` to accept all the types of a family.',
     'TEXT'
 ),
 (
@@ -26,5 +26,7 @@ will return `https://example.com/example.sql`.
 > Note that the path is URL-encoded. The protocol is resolved in this order:
 > - `Forwarded` header
 > - `X-Forwarded-Proto` header
-> request target / URI
+> - request target / URI
+>
+> The headers are only used when the request comes from one of the `trusted_proxies` of the configuration.
 ');
\ No newline at end of file
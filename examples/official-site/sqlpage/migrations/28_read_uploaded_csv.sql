@@ -0,0 +1,48 @@
+INSERT INTO sqlpage_functions (
+    "name",
+    "introduced_in_version",
+    "icon",
+    "description_md"
+)
+VALUES (
+    'read_uploaded_csv',
+    '0.18.0',
+    'file-spreadsheet',
+    'Returns the contents of an uploaded CSV file as a JSON array of objects.
+
+The first line of the file must contain the column names.
+Each following line becomes a JSON object whose keys are the column names, and whose values are the cell contents, as strings.
+
+If no file was uploaded with the given name, the function returns NULL.
+
+This is an alternative to the [`COPY` statement](/documentation.sql?component=form#component) that lets you
+process all the rows of the file in a single SQL statement, using your database''s JSON functions.
+
+## Example
+
+### Inserting all the rows of an uploaded file at once
+
+```sql
+insert into people(name, age)
+select json_extract(person.value, ''$.name''), json_extract(person.value, ''$.age'')
+from json_each(sqlpage.read_uploaded_csv(''people_file'')) as person;
+```
+
+On PostgreSQL, use `json_array_elements` instead of `json_each`,
+and on MySQL, use `JSON_TABLE`.
+');
+
+INSERT INTO sqlpage_function_parameters (
+    "function",
+    "index",
+    "name",
+    "description_md",
+    "type"
+)
+VALUES (
+    'read_uploaded_csv',
+    1,
+    'name',
+    'Name of the file input field in the form that was used to upload the CSV file.',
+    'TEXT'
+);
@@ -0,0 +1,64 @@
+INSERT INTO sqlpage_functions (
+    "name",
+    "introduced_in_version",
+    "icon",
+    "description_md"
+)
+VALUES (
+    'json_param',
+    '0.18.0',
+    'braces',
+    'Extracts a value from a request parameter that contains JSON, using a [JSONPath](https://goessner.net/articles/JsonPath/) expression.
+
+This is useful to handle JSON payloads, such as webhooks, consistently across all databases,
+without depending on the JSON functions of your database.
+
+The supported JSONPath syntax is:
+ - `$` for the root of the document,
+ - `.key` or `[''key'']` to access an object property,
+ - `[0]` to access an array element.
+
+Strings are returned without quotes. Objects, arrays, numbers and booleans are returned as JSON text.
+If the parameter is not set, or if the path does not match anything, the function returns NULL.
+
+## Example
+
+If a webhook posts a form field named `payload` containing `{"items": [{"sku": "A-42", "qty": 3}]}`:
+
+```sql
+insert into orders(sku, quantity)
+values (
+    sqlpage.json_param('':payload'', ''$.items[0].sku''),
+    sqlpage.json_param('':payload'', ''$.items[0].qty'')
+);
+```
+
+It also works with variables containing a whole row, set with a parenthesized subquery that returns several columns:
+
+```sql
+set user = (select id, name, role from users where id = $id);
+select ''text'' as component, ''Hello, '' || sqlpage.json_param(''$user'', ''$.name'') as contents;
+```
+');
+
+INSERT INTO sqlpage_function_parameters (
+    "function",
+    "index",
+    "name",
+    "description_md",
+    "type"
+)
+VALUES (
+    'json_param',
+    1,
+    'variable',
+    'The request parameter containing the JSON document, such as `:payload` or `$payload`.',
+    'TEXT'
+),
+(
+    'json_param',
+    2,
+    'path',
+    'The JSONPath of the value to extract, such as `$.items[0].sku`.',
+    'TEXT'
+);
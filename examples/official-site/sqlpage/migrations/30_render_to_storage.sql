@@ -0,0 +1,76 @@
+INSERT INTO sqlpage_functions (
+    "name",
+    "introduced_in_version",
+    "icon",
+    "description_md"
+)
+VALUES (
+    'render_to_storage',
+    '0.18.0',
+    'cloud-upload',
+    'Executes another SQL file, and uploads the rendered result to an S3-compatible object storage service
+(such as Amazon S3, Cloudflare R2, or MinIO) instead of sending it to the browser.
+Returns the URL of the created object.
+
+This is useful for large exports and for reports generated on a schedule:
+the generated file is stored directly in the object storage, and you can then store its URL in your database,
+or send it to your users.
+
+The rendered file is produced exactly as it would be if it was requested from a browser,
+so you can use any component, including the [`csv`](documentation.sql?component=csv#component) and [`json`](documentation.sql?component=json#component) components.
+If an error occurs while rendering the file, nothing is uploaded and the error is displayed.
+
+The content type of the uploaded object is guessed from the extension of the destination.
+
+This function requires the `storage_endpoint`, `storage_access_key_id`, and `storage_secret_access_key`
+[configuration options](https://github.com/lovasoa/SQLpage/blob/main/configuration.md) to be set.
+
+> The rendered file runs its queries on a separate database connection.
+> Make sure your connection pool allows at least two connections.
+> A rendered file can itself call `render_to_storage`, up to three levels deep, each level using one more connection.
+
+## Example
+
+### Exporting a monthly report
+
+```sql
+insert into exports(month, url)
+values (
+    $month,
+    sqlpage.render_to_storage(
+        ''reports/monthly.sql'',
+        json_object(''month'', $month),
+        ''my-bucket/reports/'' || $month || ''.csv''
+    )
+);
+```
+');
+
+INSERT INTO sqlpage_function_parameters (
+    "function",
+    "index",
+    "name",
+    "description_md",
+    "type"
+)
+VALUES (
+    'render_to_storage',
+    1,
+    'file',
+    'Path to the SQL file to render, relative to the web root.',
+    'TEXT'
+),
+(
+    'render_to_storage',
+    2,
+    'parameters',
+    'A JSON object containing the parameters to pass to the SQL file. They are accessible as `$name` variables in the rendered file.',
+    'JSON'
+),
+(
+    'render_to_storage',
+    3,
+    'destination',
+    'The bucket and key of the object to create, in the form `bucket/path/to/file.csv`.',
+    'TEXT'
+);
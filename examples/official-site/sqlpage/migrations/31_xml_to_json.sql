@@ -0,0 +1,70 @@
+INSERT INTO sqlpage_functions (
+    "name",
+    "introduced_in_version",
+    "icon",
+    "description_md"
+)
+VALUES (
+    'xml_to_json',
+    '0.18.0',
+    'file-code',
+    'Converts an XML document to JSON, so that it can be queried with the JSON functions of your database,
+or with [`sqlpage.json_param`](?function=json_param#function).
+
+This is useful to handle XML payloads, such as legacy webhooks or RSS feeds.
+
+The conversion follows these rules:
+ - the result is an object with a single key: the name of the root element,
+ - attributes are stored with an `@` prefix (`<item id="1">` becomes `{"@id": "1"}`),
+ - child elements are stored under their name, and repeated elements become arrays,
+ - the text of an element is stored under `#text`, or directly as a string if the element has no attributes and no children,
+ - empty elements become `null`.
+
+All values are strings: no attempt is made to guess numbers or booleans.
+Comments, processing instructions and the doctype are ignored. CDATA sections are treated as text.
+If the parameter is not set, the function returns NULL. If it does not contain valid XML, an error is displayed.
+
+## Example
+
+If a webhook posts an XML document in a form field named `payload`:
+
+```xml
+<order id="42">
+    <item sku="A1">Tea</item>
+    <item sku="B2">Coffee</item>
+</order>
+```
+
+then `sqlpage.xml_to_json(:payload)` returns
+
+```json
+{"order": {"@id": "42", "item": [{"@sku": "A1", "#text": "Tea"}, {"@sku": "B2", "#text": "Coffee"}]}}
+```
+
+and you can store the order items with:
+
+```sql
+set order_json = sqlpage.xml_to_json(:payload);
+
+insert into order_items(order_id, sku)
+select
+    json_extract($order_json, ''$.order.@id''),
+    json_extract(value, ''$.@sku'')
+from json_each(json_extract($order_json, ''$.order.item''));
+```
+');
+
+INSERT INTO sqlpage_function_parameters (
+    "function",
+    "index",
+    "name",
+    "description_md",
+    "type"
+)
+VALUES (
+    'xml_to_json',
+    1,
+    'xml',
+    'The XML document to convert, typically a request parameter such as `:payload`.',
+    'TEXT'
+);
@@ -0,0 +1,151 @@
+INSERT INTO sqlpage_functions (
+    "name",
+    "introduced_in_version",
+    "icon",
+    "description_md"
+)
+VALUES (
+    'generate_backup_codes',
+    '0.18.0',
+    'lifebuoy',
+    'Generates random one-time backup codes, that your users can use to recover their account
+if they lose their password or their second authentication factor.
+
+Returns a JSON array of objects with two properties:
+ - `code`: the code itself, such as `7kq2m-x9fht`. It must be shown to the user only once, and never stored.
+ - `hash`: a secure [argon2](https://en.wikipedia.org/wiki/Argon2) hash of the code, that you should store in your database.
+
+Codes are case-insensitive, and the dash is optional when the user types them.
+Hashing is a deliberately slow operation, so generating many codes takes some time. At most 100 codes can be generated at once.
+
+## Example
+
+```sql
+set codes = sqlpage.generate_backup_codes(10);
+
+delete from backup_codes where user_id = $user_id;
+insert into backup_codes(user_id, hash)
+select $user_id, json_extract(value, ''$.hash'') from json_each($codes);
+
+select ''backup_codes'' as component;
+select json_extract(value, ''$.code'') as code from json_each($codes);
+```
+
+See [`sqlpage.verify_backup_code`](?function=verify_backup_code#function) to check a code entered by a user,
+and the [`backup_codes` component](documentation.sql?component=backup_codes#component) to display them.
+'),
+(
+    'verify_backup_code',
+    '0.18.0',
+    'key',
+    'Checks a backup code entered by a user against the hashes generated by
+[`sqlpage.generate_backup_codes`](?function=generate_backup_codes#function).
+
+Returns the hash that matches the code, or NULL if the code is not valid.
+Backup codes can be used only once: when a code matches, delete its hash from your database.
+
+## Example
+
+```sql
+set hashes = (select json_group_array(hash) from backup_codes where user_id = $user_id);
+set used_hash = sqlpage.verify_backup_code(:code, $hashes);
+
+select ''redirect'' as component, ''recover.sql?error'' as link where $used_hash is null;
+
+delete from backup_codes where user_id = $user_id and hash = $used_hash;
+-- the user is now authenticated: create a session...
+```
+');
+
+INSERT INTO sqlpage_function_parameters (
+    "function",
+    "index",
+    "name",
+    "description_md",
+    "type"
+)
+VALUES (
+    'generate_backup_codes',
+    1,
+    'count',
+    'The number of codes to generate, as a literal integer between 1 and 100.',
+    'INTEGER'
+),
+(
+    'verify_backup_code',
+    1,
+    'code',
+    'The code entered by the user, such as `:code`.',
+    'TEXT'
+),
+(
+    'verify_backup_code',
+    2,
+    'hashes',
+    'The stored hashes of the valid codes of the user, as a JSON array of strings. A single hash is also accepted.',
+    'TEXT'
+);
+
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'backup_codes',
+    'Displays account recovery codes generated by [`sqlpage.generate_backup_codes`](functions.sql?function=generate_backup_codes#function),
+    with a warning telling the user to store them safely. Since only the hashes of the codes should be stored, this component is meant to be displayed only once, right after the codes are generated.',
+    'lifebuoy',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES (
+    'backup_codes',
+    'title',
+    'Title of the card containing the codes. Defaults to "Backup codes".',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'backup_codes',
+    'description',
+    'A short text displayed below the title.',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'backup_codes',
+    'description_md',
+    'A text in Markdown displayed below the title.',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'backup_codes',
+    'warning',
+    'The warning displayed above the codes. Defaults to a message telling the user to store the codes in a safe place.',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'backup_codes',
+    'code',
+    'A backup code to display.',
+    'TEXT',
+    FALSE,
+    FALSE
+);
+
+INSERT INTO example (component, description, properties)
+VALUES (
+    'backup_codes',
+    'Recovery codes displayed after the user enabled two-factor authentication. In a real application, the codes would come from `sqlpage.generate_backup_codes`.',
+    JSON('[
+        { "component": "backup_codes", "description": "Use one of these codes to log in if you lose access to your phone." },
+        { "code": "7kq2m-x9fht" },
+        { "code": "p4sbd-e8wnz" },
+        { "code": "3hvca-yr6gj" },
+        { "code": "tm5xu-2k9qe" }
+    ]')
+);
@@ -0,0 +1,135 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'session',
+    'Stores a session variable for the current user, that can be read in subsequent requests using
+[`sqlpage.session(''name'')`](functions.sql?function=session#function).
+
+Session variables are useful to keep state across multiple pages, such as the current step of a multi-step form,
+without having to pass all the previous answers through hidden form fields.
+
+Contrary to [cookies](?component=cookie#component), session variables are signed by the server:
+users can see their session variables, but cannot modify them. Do not store secrets in session variables.
+The signing secret can be set with the `session_secret` [configuration option](https://github.com/lovasoa/SQLpage/blob/main/configuration.md).
+When it is not set, a random secret is generated when SQLPage starts, and all session variables are lost when it restarts.
+
+By default, each session variable is stored in its own cookie. To keep larger or private data, set the `session_store` configuration option
+to `memory`, `database` or the url of a Redis server: the variables are then stored on the server,
+and the browser only receives a signed session identifier.
+
+Session variables can also be read as `$session.name`, and set with `SET $session.name = ...`,
+which is equivalent to using this component, but also changes the value for the rest of the page.
+Each variable expires `session_max_age_seconds` (one day by default) after it was last set.
+
+Like the cookie component, this component must be placed at the top of the page, before any component that generates output.
+The new value is visible from the next request onwards: `sqlpage.session` still returns the previous value in the page that sets it.',
+    'database-export',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES (
+    'session',
+    'name',
+    'The name of the session variable to set. Required, unless `destroy` is set.',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'session',
+    'value',
+    'The value of the session variable. If it is null, the session variable is removed.',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'session',
+    'secure',
+    'Whether the session variable should only be sent over secure (HTTPS) connections. Defaults to true. Set it to false if your site is served over plain HTTP on a domain other than localhost.',
+    'BOOLEAN',
+    TRUE,
+    TRUE
+),
+(
+    'session',
+    'destroy',
+    'Set to true to remove all the session variables of the user, for instance when they log out.',
+    'BOOLEAN',
+    TRUE,
+    TRUE
+);
+
+INSERT INTO example (component, description)
+VALUES (
+    'session',
+    'Remember the answer to the first step of a multi-step form, in `step1.sql`:
+
+```sql
+SELECT ''session'' AS component, ''plan'' AS name, :plan AS value;
+SELECT ''redirect'' AS component, ''step2.sql'' AS link;
+```
+
+and read it in `step2.sql`:
+
+```sql
+SELECT ''text'' AS component, ''You chose the '' || sqlpage.session(''plan'') || '' plan.'' AS contents;
+```
+
+The same can be written with the `$session` variables:
+
+```sql
+SET $session.plan = :plan;
+SELECT ''text'' AS component, ''You chose the '' || $session.plan || '' plan.'' AS contents;
+```
+
+When the form is complete, remove the session variable:
+
+```sql
+SELECT ''session'' AS component, ''plan'' AS name, NULL AS value;
+```
+
+When the user logs out, remove all their session variables:
+
+```sql
+SELECT ''session'' AS component, TRUE AS destroy;
+```
+'
+);
+
+INSERT INTO sqlpage_functions (
+    "name",
+    "introduced_in_version",
+    "icon",
+    "description_md"
+)
+VALUES (
+    'session',
+    '0.18.0',
+    'database-export',
+    'Reads a session variable set with the [`session` component](documentation.sql?component=session#component).
+
+Returns NULL if the variable is not set, or if it was tampered with.
+When called without an argument, returns all the session variables of the current user as a JSON object.
+
+## Example
+
+```sql
+SELECT ''text'' AS component, ''Current step: '' || coalesce(sqlpage.session(''step''), ''1'') AS contents;
+```
+');
+
+INSERT INTO sqlpage_function_parameters (
+    "function",
+    "index",
+    "name",
+    "description_md",
+    "type"
+)
+VALUES (
+    'session',
+    1,
+    'name',
+    'The name of the session variable to read, as a literal string. Optional.',
+    'TEXT'
+);
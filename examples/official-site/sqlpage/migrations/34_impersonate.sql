@@ -0,0 +1,101 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'impersonate',
+    'Lets an administrator temporarily assume the identity of another user, to see the application exactly as this user sees it, and then drop back to their own identity.
+This is useful for support teams debugging user-specific views.
+
+This component works with [session variables](?component=session#component), and expects the identifier of the logged-in user
+to be stored in the `user` session variable (with `SELECT ''session'' AS component, ''user'' AS name, $user_id AS value;` when the user logs in).
+
+When impersonation starts, the `user` session variable is replaced with the identifier of the impersonated user,
+and the identifier of the administrator is stored in the `impersonator` session variable.
+Your pages can call [`sqlpage.impersonator()`](functions.sql?function=impersonator#function) to display a banner while an impersonation is in progress.
+When impersonation stops, the original `user` is restored and `impersonator` is removed.
+
+Every start and stop is logged as a warning with the `sqlpage::audit` log target, including the identities of both users and the reason given.
+Store these events in your own audit table too if you need them in the database.
+
+SQLPage does not know who is an administrator, so starting an impersonation requires the `allowed` property,
+that your query computes from the permissions of the current user, as in the example below.
+When it is missing or false, the impersonation is refused and the attempt is logged.
+Like the session component, it must be placed at the top of the page.',
+    'spy',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES (
+    'impersonate',
+    'user',
+    'The identifier of the user to impersonate. Required unless stop is set.',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'impersonate',
+    'allowed',
+    'Whether the current user has the permission to impersonate other users, such as `is_admin` from your users table. Required to start an impersonation: when it is not true, an error is displayed.',
+    'BOOLEAN',
+    TRUE,
+    TRUE
+),
+(
+    'impersonate',
+    'stop',
+    'Set to true to stop impersonating, and go back to the identity of the administrator.',
+    'BOOLEAN',
+    TRUE,
+    TRUE
+),
+(
+    'impersonate',
+    'reason',
+    'Why the impersonation was started or stopped. Included in the audit log.',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'impersonate',
+    'secure',
+    'Whether the session variables should only be sent over secure (HTTPS) connections. Defaults to true.',
+    'BOOLEAN',
+    TRUE,
+    TRUE
+);
+
+INSERT INTO example (component, description)
+VALUES (
+    'impersonate',
+    'In `login_as.sql`, let administrators assume the identity of the user whose id is given in the URL:
+
+```sql
+INSERT INTO audit_log(admin_id, user_id, action) 
+SELECT sqlpage.session(''user''), $id, ''impersonate''
+WHERE EXISTS (SELECT 1 FROM users WHERE id = sqlpage.session(''user'') AND is_admin);
+
+SELECT ''impersonate'' AS component, $id AS user, ''Support request '' || $ticket AS reason,
+    EXISTS (SELECT 1 FROM users WHERE id = sqlpage.session(''user'') AND is_admin) AS allowed;
+
+SELECT ''redirect'' AS component, ''/'' AS link;
+```
+
+Display a banner on all pages during the impersonation:
+
+```sql
+SELECT ''alert'' AS component,
+    ''You are viewing the application as '' || sqlpage.session(''user'') AS title,
+    ''stop_impersonating.sql'' AS link,
+    ''Go back to my account'' AS link_text
+WHERE sqlpage.session(''impersonator'') IS NOT NULL;
+```
+
+And in `stop_impersonating.sql`:
+
+```sql
+SELECT ''impersonate'' AS component, TRUE AS stop;
+SELECT ''redirect'' AS component, ''/'' AS link;
+```
+'
+);
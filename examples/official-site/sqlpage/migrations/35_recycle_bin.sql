@@ -0,0 +1,123 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'recycle_bin',
+    'Lists soft-deleted items, with buttons to restore them or delete them forever.
+
+Soft deletion means that instead of deleting rows, you set their `deleted_at` column to the current date.
+This gives your users an undo story: deleted items can be restored from a recycle bin.
+
+To avoid repeating `WHERE deleted_at IS NULL` in every query of your application, list your soft-deleted tables in the `soft_delete_tables`
+[configuration option](https://github.com/lovasoa/SQLpage/blob/main/configuration.md).
+SQLPage will then automatically add this condition to all the `SELECT` queries that read from these tables,
+unless the query mentions the `deleted_at` column in its `WHERE` clause, as a recycle bin page does.
+To list all the rows of a query, deleted or not, add `sqlpage.include_deleted()` to its `WHERE` clause:
+`SELECT * FROM documents WHERE owner = $user AND sqlpage.include_deleted()`.
+The name of the column can be changed with the `soft_delete_column` configuration option.
+
+Buttons send a POST request to the restore and delete links, so that the target pages can update the database.',
+    'trash',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES (
+    'recycle_bin',
+    'title',
+    'Title of the recycle bin. Defaults to "Recycle bin".',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'recycle_bin',
+    'icon',
+    'Name of an icon to display next to the title.',
+    'ICON',
+    TRUE,
+    TRUE
+),
+(
+    'recycle_bin',
+    'empty_title',
+    'Text displayed when there are no deleted items.',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'recycle_bin',
+    'restore_label',
+    'Text of the restore buttons. Defaults to "Restore".',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'recycle_bin',
+    'delete_label',
+    'Text of the permanent deletion buttons. Defaults to "Delete forever".',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'recycle_bin',
+    'deleted_label',
+    'Text displayed before the deletion date. Defaults to "Deleted".',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'recycle_bin',
+    'title',
+    'Name of the deleted item.',
+    'TEXT',
+    FALSE,
+    FALSE
+),
+(
+    'recycle_bin',
+    'description',
+    'A short description of the deleted item.',
+    'TEXT',
+    FALSE,
+    TRUE
+),
+(
+    'recycle_bin',
+    'deleted_at',
+    'When the item was deleted.',
+    'TEXT',
+    FALSE,
+    TRUE
+),
+(
+    'recycle_bin',
+    'restore_link',
+    'URL of a page that restores the item when it receives a POST request. If it is not set, no restore button is displayed.',
+    'URL',
+    FALSE,
+    TRUE
+),
+(
+    'recycle_bin',
+    'delete_link',
+    'URL of a page that permanently deletes the item when it receives a POST request. If it is not set, no delete button is displayed.',
+    'URL',
+    FALSE,
+    TRUE
+);
+
+INSERT INTO example (component, description, properties)
+VALUES (
+    'recycle_bin',
+    'A recycle bin listing deleted documents. In a real application, the rows would come from a query such as
+`SELECT title, deleted_at, ''restore.sql?id='' || id AS restore_link FROM documents WHERE deleted_at IS NOT NULL`,
+and `restore.sql` would contain `UPDATE documents SET deleted_at = NULL WHERE id = $id`.',
+    JSON('[
+        { "component": "recycle_bin" },
+        { "title": "Quarterly report.docx", "description": "Finance", "deleted_at": "2024-01-15", "restore_link": "#", "delete_link": "#" },
+        { "title": "Team photo.jpg", "deleted_at": "2024-01-12", "restore_link": "#", "delete_link": "#" }
+    ]')
+);
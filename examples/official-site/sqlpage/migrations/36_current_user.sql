@@ -0,0 +1,32 @@
+INSERT INTO sqlpage_functions (
+    "name",
+    "introduced_in_version",
+    "icon",
+    "description_md"
+)
+VALUES (
+    'current_user',
+    '0.18.0',
+    'user-circle',
+    'Returns the identifier of the logged-in user, or NULL if the request is anonymous.
+
+The user is identified, in order of priority:
+ 1. by the `user` [session variable](documentation.sql?component=session#component), that you set when the user logs in with
+    `SELECT ''session'' AS component, ''user'' AS name, $user_id AS value;`
+    (during an [impersonation](documentation.sql?component=impersonate#component), this is the impersonated user),
+ 2. by the username sent with [HTTP basic authentication](?function=basic_auth_username#function),
+    only when its password was checked by a `basic_auth` rule of the [configuration](https://github.com/lovasoa/SQLpage/blob/main/configuration.md).
+    Otherwise, anyone could send any username: check the password yourself and store the user in a session variable.
+
+Contrary to `sqlpage.basic_auth_username()`, this function does not ask the browser for credentials when the request is anonymous.
+Use it to restrict access to your pages, or to filter data by user, without re-implementing the authentication logic on every page.
+
+## Example
+
+```sql
+SELECT ''redirect'' AS component, ''login.sql'' AS link WHERE sqlpage.current_user() IS NULL;
+
+SELECT ''list'' AS component, ''My documents'' AS title;
+SELECT title FROM documents WHERE owner = sqlpage.current_user();
+```
+');
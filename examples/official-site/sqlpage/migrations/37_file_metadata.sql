@@ -0,0 +1,89 @@
+INSERT INTO sqlpage_functions (
+    "name",
+    "introduced_in_version",
+    "icon",
+    "description_md"
+)
+VALUES (
+    'file_exists',
+    '0.18.0',
+    'file-check',
+    'Returns `true` if a file exists in the web root (the directory from which SQLPage serves your site), and `false` otherwise.
+
+This is useful to display links to files generated outside of SQLPage, such as nightly exports, only when they are available.
+
+Paths are relative to the web root. For security reasons, paths containing `..` are refused, and files in the `sqlpage` configuration directory cannot be inspected.
+Only files on the local disk are considered, not files stored in the `sqlpage_files` database table.
+
+## Example
+
+```sql
+set export_path = ''exports/'' || CURRENT_DATE || ''.csv'';
+
+select ''button'' as component;
+select ''Download today''''s export'' as title, ''/'' || $export_path as link
+where sqlpage.file_exists($export_path) = ''true'';
+```
+'),
+(
+    'file_size',
+    '0.18.0',
+    'file-analytics',
+    'Returns the size in bytes of a file in the web root, or NULL if the file does not exist or is a directory.
+
+The same path restrictions as in [`sqlpage.file_exists`](?function=file_exists#function) apply.
+
+## Example
+
+```sql
+set path = ''exports/report.pdf'';
+select ''text'' as component, ''The report weighs '' || (CAST(sqlpage.file_size($path) AS INTEGER) / 1024) || '' KiB'' as contents;
+```
+'),
+(
+    'file_modified',
+    '0.18.0',
+    'file-time',
+    'Returns the date and time at which a file in the web root was last modified, in the [RFC 3339](https://www.rfc-editor.org/rfc/rfc3339) format (such as `2024-01-15T04:00:12.123456+00:00`),
+or NULL if the file does not exist.
+
+The same path restrictions as in [`sqlpage.file_exists`](?function=file_exists#function) apply.
+
+## Example
+
+```sql
+set path = ''exports/nightly.csv'';
+select ''list'' as component;
+select ''Nightly export'' as title, ''/'' || $path as link, ''Generated on '' || sqlpage.file_modified($path) as description
+where sqlpage.file_exists($path) = ''true'';
+```
+');
+
+INSERT INTO sqlpage_function_parameters (
+    "function",
+    "index",
+    "name",
+    "description_md",
+    "type"
+)
+VALUES (
+    'file_exists',
+    1,
+    'path',
+    'Path to the file, relative to the web root.',
+    'TEXT'
+),
+(
+    'file_size',
+    1,
+    'path',
+    'Path to the file, relative to the web root.',
+    'TEXT'
+),
+(
+    'file_modified',
+    1,
+    'path',
+    'Path to the file, relative to the web root.',
+    'TEXT'
+);
@@ -0,0 +1,81 @@
+INSERT INTO sqlpage_functions (
+    "name",
+    "introduced_in_version",
+    "icon",
+    "description_md"
+)
+VALUES (
+    'feature',
+    '0.18.0',
+    'toggle-right',
+    'Returns `true` if the given feature flag is enabled, and `false` otherwise.
+
+Feature flags let you roll out new parts of your site progressively, or switch them off without redeploying your SQL files.
+
+Flags are read from a table named `sqlpage_feature_flags` in your database, that you can create with:
+
+```sql
+CREATE TABLE sqlpage_feature_flags(
+    name VARCHAR(255) NOT NULL PRIMARY KEY,
+    enabled BOOLEAN NOT NULL
+);
+```
+
+Flags that are not present in this table are read from the `feature_flags` object in the [configuration file](https://github.com/lovasoa/SQLpage/blob/main/configuration.md).
+Unknown flags are disabled.
+
+To avoid querying the database on every request, the contents of the table are cached
+for `feature_flags_cache_seconds` seconds (5 by default),
+so a flag can take up to this duration to be switched on or off on all pages.
+Call [`sqlpage.refresh_feature_flags()`](?function=refresh_feature_flags#function) after changing the table
+to read it again immediately.
+
+## Example
+
+```sql
+select ''card'' as component;
+select ''New dashboard'' as title, ''dashboard_v2.sql'' as link
+where sqlpage.feature(''new_dashboard'') = ''true'';
+select ''Dashboard'' as title, ''dashboard.sql'' as link
+where sqlpage.feature(''new_dashboard'') = ''false'';
+```
+');
+
+INSERT INTO sqlpage_function_parameters (
+    "function",
+    "index",
+    "name",
+    "description_md",
+    "type"
+)
+VALUES (
+    'feature',
+    1,
+    'name',
+    'The name of the feature flag.',
+    'TEXT'
+);
+
+INSERT INTO sqlpage_functions (
+    "name",
+    "introduced_in_version",
+    "icon",
+    "description_md"
+)
+VALUES (
+    'refresh_feature_flags',
+    '0.18.0',
+    'refresh',
+    'Reads the `sqlpage_feature_flags` table again, without waiting for the flags cached by
+[`sqlpage.feature`](?function=feature#function) to expire,
+and returns the number of flags found in the table.
+
+## Example
+
+In the page that switches a feature flag on or off:
+
+```sql
+UPDATE sqlpage_feature_flags SET enabled = NOT enabled WHERE name = $name;
+SELECT ''redirect'' AS component, ''flags.sql?count='' || sqlpage.refresh_feature_flags() AS link;
+```
+');
@@ -0,0 +1,99 @@
+INSERT INTO sqlpage_functions (
+    "name",
+    "introduced_in_version",
+    "icon",
+    "description_md"
+)
+VALUES (
+    'validate',
+    '0.18.0',
+    'checkbox',
+    'Checks that a value respects a validation rule.
+Returns the value itself if it is valid, and NULL otherwise (or if the value is NULL).
+
+This lets you validate user input in the same way whatever database you use,
+instead of relying on the regular expression syntax of your database, which varies a lot from one database to the other.
+
+The following rules are available:
+
+| rule | accepted values |
+|------|-----------------|
+| `email` | email addresses such as `john.doe@example.com` |
+| `url` | absolute `http` and `https` URLs |
+| `integer` | whole numbers such as `-42` |
+| `number` | decimal numbers such as `3.14` or `1e6` |
+| `date` | valid dates in the `YYYY-MM-DD` format |
+| `uuid` | UUIDs such as `123e4567-e89b-12d3-a456-426614174000` |
+| `alphanumeric` | non-empty strings made only of letters and digits |
+
+To validate a value against a custom pattern, use [`sqlpage.matches`](?function=matches#function).
+
+## Example
+
+```sql
+set email = sqlpage.validate(:email, ''email'');
+
+select ''alert'' as component, ''Invalid email address'' as title, ''red'' as color
+where :email is not null and $email is null;
+
+insert into newsletter_subscribers(email)
+select $email where $email is not null;
+```
+'),
+(
+    'matches',
+    '0.18.0',
+    'regex',
+    'Checks that a value matches a [regular expression](https://docs.rs/regex/latest/regex/#syntax).
+Returns the value itself if it matches, and NULL otherwise (or if the value is NULL).
+
+Like the `pattern` attribute of HTML form fields, the regular expression must match the entire value:
+`sqlpage.matches(:code, ''[0-9]{5}'')` accepts `75001`, but not `750012`.
+
+The regular expression is always interpreted by SQLPage, not by the database, so it behaves the same way on all databases.
+
+## Example
+
+```sql
+set zip_code = sqlpage.matches(:zip_code, ''[0-9]{5}'');
+
+select ''alert'' as component, ''A zip code is made of 5 digits'' as title, ''red'' as color
+where :zip_code is not null and $zip_code is null;
+```
+');
+
+INSERT INTO sqlpage_function_parameters (
+    "function",
+    "index",
+    "name",
+    "description_md",
+    "type"
+)
+VALUES (
+    'validate',
+    1,
+    'value',
+    'The value to validate, usually a form field such as `:email`.',
+    'TEXT'
+),
+(
+    'validate',
+    2,
+    'rule',
+    'The name of the validation rule, as a literal string: `email`, `url`, `integer`, `number`, `date`, `uuid` or `alphanumeric`.',
+    'TEXT'
+),
+(
+    'matches',
+    1,
+    'value',
+    'The value to validate, usually a form field.',
+    'TEXT'
+),
+(
+    'matches',
+    2,
+    'regex',
+    'The regular expression that the whole value must match, as a literal string.',
+    'TEXT'
+);
@@ -0,0 +1,46 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+        'status_code',
+        'For advanced users. Sets the [HTTP status code](https://developer.mozilla.org/en-US/docs/Web/HTTP/Status) of the response,
+        which is 200 (OK) by default.
+        The rest of the page is rendered normally.
+        This component must appear at the top of your SQL file, before any other data has been sent to the browser.',
+        'error-404',
+        '0.18.0'
+    );
+
+INSERT INTO parameter (
+        component,
+        name,
+        description,
+        type,
+        top_level,
+        optional
+    )
+VALUES (
+        'status_code',
+        'status',
+        'The HTTP status code to send, between 100 and 999. For instance 404 when a record does not exist, 403 when the user is not allowed to see it, or 201 after a record has been created.',
+        'INTEGER',
+        TRUE,
+        FALSE
+    );
+
+INSERT INTO example (component, description)
+VALUES (
+        'status_code',
+        '
+Returns a 404 error when the requested product does not exist,
+so that search engines and API clients know that the page is not a real product page.
+
+```sql
+SELECT ''status_code'' AS component, 404 AS status
+WHERE NOT EXISTS (SELECT 1 FROM products WHERE id = $id);
+
+SELECT ''text'' AS component, COALESCE(
+    (SELECT description FROM products WHERE id = $id),
+    ''This product does not exist.''
+) AS contents;
+```
+'
+    );
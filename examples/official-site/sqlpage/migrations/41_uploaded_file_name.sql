@@ -0,0 +1,40 @@
+INSERT INTO sqlpage_functions (
+    "name",
+    "introduced_in_version",
+    "icon",
+    "description_md"
+)
+VALUES (
+    'uploaded_file_name',
+    '0.18.0',
+    'file-description',
+    'Returns the original name of an uploaded file, as it was on the computer of the user, or NULL if no file was uploaded in the given field.
+
+This name is chosen by the user, so it should not be used directly as a path on the server.
+
+## Example
+
+```sql
+insert into documents (title, original_name, contents)
+values (
+    :title,
+    sqlpage.uploaded_file_name(''my_document''),
+    sqlpage.read_file_as_data_url(sqlpage.uploaded_file_path(''my_document''))
+);
+```
+');
+
+INSERT INTO sqlpage_function_parameters (
+    "function",
+    "index",
+    "name",
+    "description_md",
+    "type"
+)
+VALUES (
+    'uploaded_file_name',
+    1,
+    'name',
+    'Name of the file input field in the form.',
+    'TEXT'
+);
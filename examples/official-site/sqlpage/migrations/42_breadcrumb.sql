@@ -0,0 +1,82 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'breadcrumb',
+    'A navigation trail that shows where the current page is located in the hierarchy of your site.
+Each row is one level of the hierarchy, from the root of the site to the current page.
+Useful in multi-level admin interfaces, where users need to go back to a parent page quickly.',
+    'chevrons-right',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES (
+    'breadcrumb',
+    'separator',
+    'Style of the separator between levels. One of ''arrows'', ''bullets'', or ''dots''. Defaults to a slash.',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'breadcrumb',
+    'title',
+    'Name of the level of the hierarchy.',
+    'TEXT',
+    FALSE,
+    FALSE
+),
+(
+    'breadcrumb',
+    'link',
+    'URL of the page that corresponds to this level.',
+    'URL',
+    FALSE,
+    TRUE
+),
+(
+    'breadcrumb',
+    'active',
+    'Whether this level is the current page. The active level is not clickable. Usually set on the last row.',
+    'BOOLEAN',
+    FALSE,
+    TRUE
+),
+(
+    'breadcrumb',
+    'icon',
+    'Name of an icon to display before the title.',
+    'ICON',
+    FALSE,
+    TRUE
+),
+(
+    'breadcrumb',
+    'description',
+    'Text displayed when the user hovers over the link.',
+    'TEXT',
+    FALSE,
+    TRUE
+);
+
+INSERT INTO example (component, description, properties)
+VALUES (
+    'breadcrumb',
+    'A basic breadcrumb, for a page that displays a single user in an admin interface.',
+    JSON('[
+        { "component": "breadcrumb" },
+        { "title": "Home", "link": "/", "icon": "home" },
+        { "title": "Users", "link": "users.sql" },
+        { "title": "John Doe", "active": true }
+    ]')
+),
+(
+    'breadcrumb',
+    'A breadcrumb with arrows as separators. In a real application, the rows would typically come from
+a [recursive query](https://www.sqlite.org/lang_with.html#recursive_common_table_expressions) on a table of categories.',
+    JSON('[
+        { "component": "breadcrumb", "separator": "arrows" },
+        { "title": "Catalog", "link": "catalog.sql" },
+        { "title": "Animals", "link": "catalog.sql?category=1" },
+        { "title": "Cats", "link": "catalog.sql?category=4", "active": true }
+    ]')
+);
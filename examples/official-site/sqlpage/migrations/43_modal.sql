@@ -0,0 +1,54 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'modal',
+    'A dialog box that opens on top of the page.
+Useful for confirmation dialogs and quick-edit forms, without having to create a separate page.
+
+The modal is opened by the button displayed by the component, by any link to `#modal_id` (where `modal_id` is the `id` of the modal),
+for instance a link from the [button](?component=button#component) component, or automatically when the page loads if `open` is set.
+
+Each row of the component is either a paragraph of text, or, when it has a `name`, a form field.',
+    'app-window',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES
+('modal', 'id', 'Identifier of the modal. Links to "#" followed by this identifier open the modal. Defaults to "modal". Required when a page contains several modals.', 'TEXT', TRUE, TRUE),
+('modal', 'title', 'Title displayed at the top of the modal.', 'TEXT', TRUE, TRUE),
+('modal', 'button', 'Text of a button that opens the modal. When not set, no button is displayed, and the modal has to be opened with a link or with the open property.', 'TEXT', TRUE, TRUE),
+('modal', 'button_icon', 'Name of an icon to display in the button that opens the modal.', 'ICON', TRUE, TRUE),
+('modal', 'button_color', 'Color of the button that opens the modal.', 'COLOR', TRUE, TRUE),
+('modal', 'open', 'Whether to open the modal as soon as the page is loaded.', 'BOOLEAN', TRUE, TRUE),
+('modal', 'size', 'Size of the modal: sm, lg, or full-width.', 'TEXT', TRUE, TRUE),
+('modal', 'color', 'Color of the bar at the top of the modal, and of its validation button. Use red for dangerous actions.', 'COLOR', TRUE, TRUE),
+('modal', 'link', 'URL to go to when the user confirms. Displays a validation button.', 'URL', TRUE, TRUE),
+('modal', 'action', 'URL to which the form fields of the modal are submitted, with a POST request. Displays a submit button.', 'URL', TRUE, TRUE),
+('modal', 'validate', 'Text of the validation button. Defaults to "OK" for links, and "Submit" for forms.', 'TEXT', TRUE, TRUE),
+('modal', 'cancel', 'Text of the button that closes the modal. Defaults to "Cancel".', 'TEXT', TRUE, TRUE),
+('modal', 'contents', 'A paragraph of text.', 'TEXT', FALSE, TRUE),
+('modal', 'contents_md', 'A paragraph of text, formatted using markdown.', 'TEXT', FALSE, TRUE),
+('modal', 'name', 'Name of a form field. When set, the row is displayed as an input field, submitted to the action URL.', 'TEXT', FALSE, TRUE),
+('modal', 'label', 'Label of the form field. Defaults to its name.', 'TEXT', FALSE, TRUE),
+('modal', 'type', 'Type of the form field, like in the form component. Defaults to text.', 'TEXT', FALSE, TRUE),
+('modal', 'value', 'Initial value of the form field.', 'TEXT', FALSE, TRUE),
+('modal', 'placeholder', 'Text displayed in the form field when it is empty.', 'TEXT', FALSE, TRUE),
+('modal', 'required', 'Whether the form field must be filled before submitting.', 'BOOLEAN', FALSE, TRUE);
+
+INSERT INTO example (component, description, properties)
+VALUES (
+    'modal',
+    'A confirmation dialog before deleting an item.',
+    JSON('[
+        { "component": "modal", "id": "delete_confirm", "title": "Delete this user ?", "button": "Delete", "button_icon": "trash", "button_color": "red", "color": "red", "link": "delete_user.sql?id=42", "validate": "Delete" },
+        { "contents_md": "The user **John Doe** will be deleted. This cannot be undone." }
+    ]')
+),
+(
+    'modal',
+    'A quick-edit form. In a real application, the initial values would come from the database.',
+    JSON('[
+        { "component": "modal", "id": "rename", "title": "Rename the project", "button": "Rename", "button_icon": "pencil", "action": "rename_project.sql?id=1", "validate": "Save" },
+        { "name": "name", "label": "Project name", "value": "SQLPage", "required": true }
+    ]')
+);
@@ -0,0 +1,44 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'board',
+    'A kanban board, where each row is a card, displayed in the column that corresponds to its status.
+
+When an `action` is set, cards can be dragged from one column to another.
+Each move sends a POST request to the action URL, with the `id` of the card and its new `status` as form fields,
+that you can read with `:id` and `:status` in the target SQL file:
+
+```sql
+update tasks set status = :status where id = :id;
+```
+
+If the request fails, the card goes back to its original column.',
+    'layout-kanban',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES
+('board', 'title', 'Title displayed above the board.', 'TEXT', TRUE, TRUE),
+('board', 'columns', 'A JSON array of the statuses to display as columns, in order. Columns are displayed even when they contain no card. Statuses that are not in the list get a column at the end of the board.', 'JSON', TRUE, TRUE),
+('board', 'action', 'URL of the SQL file that updates the status of a card when it is moved to another column. When not set, cards cannot be moved.', 'URL', TRUE, TRUE),
+('board', 'status', 'Name of the column in which the card is displayed.', 'TEXT', FALSE, FALSE),
+('board', 'id', 'Identifier of the card, sent to the action URL when the card is moved. Cards without an id cannot be moved.', 'TEXT', FALSE, TRUE),
+('board', 'title', 'Title of the card.', 'TEXT', FALSE, TRUE),
+('board', 'description', 'Text displayed in the card.', 'TEXT', FALSE, TRUE),
+('board', 'description_md', 'Text displayed in the card, formatted using markdown.', 'TEXT', FALSE, TRUE),
+('board', 'link', 'URL to which the title of the card links.', 'URL', FALSE, TRUE),
+('board', 'color', 'Color of a bar on the left side of the card.', 'COLOR', FALSE, TRUE),
+('board', 'footer', 'Muted text displayed at the bottom of the card.', 'TEXT', FALSE, TRUE);
+
+INSERT INTO example (component, description, properties)
+VALUES (
+    'board',
+    'A task board. In a real application, the cards would come from a query like `select id, title, status from tasks`.',
+    JSON('[
+        { "component": "board", "columns": ["To do", "In progress", "Done"], "action": "move_task.sql" },
+        { "id": 1, "status": "To do", "title": "Write the documentation", "footer": "Due tomorrow" },
+        { "id": 2, "status": "To do", "title": "Fix the login bug", "color": "red", "description_md": "Users cannot log in with **uppercase** emails." },
+        { "id": 3, "status": "In progress", "title": "Design the new logo", "color": "blue" },
+        { "id": 4, "status": "Done", "title": "Set up the database" }
+    ]')
+);
@@ -0,0 +1,35 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'carousel',
+    'A slideshow of images, where each row is an image. Users can browse the images with arrows, or with thumbnails, and open them in full screen.',
+    'carousel-horizontal',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES
+('carousel', 'title', 'Title displayed above the images.', 'TEXT', TRUE, TRUE),
+('carousel', 'id', 'Identifier of the carousel. Required when a page contains several carousels.', 'TEXT', TRUE, TRUE),
+('carousel', 'height', 'Height of the images, in pixels. By default, the carousel takes the height of the current image.', 'INTEGER', TRUE, TRUE),
+('carousel', 'cover', 'Whether to crop the images to fill the whole height, instead of showing them entirely. Requires height to be set.', 'BOOLEAN', TRUE, TRUE),
+('carousel', 'thumbnails', 'Whether to display small versions of all the images at the bottom of the carousel, to go directly to an image.', 'BOOLEAN', TRUE, TRUE),
+('carousel', 'lightbox', 'Whether clicking on an image opens it in full screen. Images that have a link are not affected.', 'BOOLEAN', TRUE, TRUE),
+('carousel', 'auto', 'Whether to go to the next image automatically every few seconds.', 'BOOLEAN', TRUE, TRUE),
+('carousel', 'fade', 'Whether to fade between images, instead of sliding.', 'BOOLEAN', TRUE, TRUE),
+('carousel', 'image_url', 'The URL (absolute or relative) of the image.', 'URL', FALSE, FALSE),
+('carousel', 'title', 'Caption displayed on top of the image.', 'TEXT', FALSE, TRUE),
+('carousel', 'description', 'Text displayed below the caption.', 'TEXT', FALSE, TRUE),
+('carousel', 'alt', 'A textual description of the image, for users who cannot see it. Defaults to the title.', 'TEXT', FALSE, TRUE),
+('carousel', 'link', 'An URL to which the user should be taken when they click on the image.', 'URL', FALSE, TRUE);
+
+INSERT INTO example (component, description, properties)
+VALUES (
+    'carousel',
+    'A gallery of pictures, with thumbnails and a full screen view.',
+    JSON('[
+        { "component": "carousel", "title": "Animals", "height": 400, "cover": true, "thumbnails": true, "lightbox": true },
+        { "image_url": "https://upload.wikimedia.org/wikipedia/commons/thumb/d/d8/Lynx_lynx-4.JPG/640px-Lynx_lynx-4.JPG", "title": "Lynx", "description": "A medium-sized wild cat." },
+        { "image_url": "https://upload.wikimedia.org/wikipedia/commons/thumb/b/be/Tamias-rufus-001.jpg/640px-Tamias-rufus-001.jpg", "title": "Chipmunk" },
+        { "image_url": "https://upload.wikimedia.org/wikipedia/commons/thumb/a/ab/Jumping_spiders_%28Salticidae%29.jpg/640px-Jumping_spiders_%28Salticidae%29.jpg", "title": "Jumping spider", "link": "https://en.wikipedia.org/wiki/Jumping_spider" }
+    ]')
+);
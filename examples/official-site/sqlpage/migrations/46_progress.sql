@@ -0,0 +1,47 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'progress',
+    'Displays values compared to a maximum, as progress bars or as round gauges.
+Useful in dashboards, to show the usage of a resource, or the progress towards a goal.
+Bars become yellow and red when the value reaches the warning and danger thresholds.',
+    'progress',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES
+('progress', 'title', 'Title displayed above the values.', 'TEXT', TRUE, TRUE),
+('progress', 'type', 'Either ''bar'' (the default) or ''gauge''.', 'TEXT', TRUE, TRUE),
+('progress', 'color', 'Color of the values that are below the warning threshold. Defaults to primary.', 'COLOR', TRUE, TRUE),
+('progress', 'warning', 'Value from which the bars become yellow, for all rows.', 'REAL', TRUE, TRUE),
+('progress', 'danger', 'Value from which the bars become red, for all rows.', 'REAL', TRUE, TRUE),
+('progress', 'title', 'Name of the value.', 'TEXT', FALSE, TRUE),
+('progress', 'value', 'The current value.', 'REAL', FALSE, FALSE),
+('progress', 'max', 'The maximum value. Defaults to 100.', 'REAL', FALSE, TRUE),
+('progress', 'unit', 'Unit displayed after the value, like % or GB.', 'TEXT', FALSE, TRUE),
+('progress', 'description', 'Small text displayed below the value.', 'TEXT', FALSE, TRUE),
+('progress', 'color', 'Color of the value. Overrides the thresholds.', 'COLOR', FALSE, TRUE),
+('progress', 'warning', 'Value from which this bar becomes yellow. Overrides the top-level warning threshold.', 'REAL', FALSE, TRUE),
+('progress', 'danger', 'Value from which this bar becomes red. Overrides the top-level danger threshold.', 'REAL', FALSE, TRUE);
+
+INSERT INTO example (component, description, properties)
+VALUES (
+    'progress',
+    'Disk usage of servers, with thresholds.',
+    JSON('[
+        { "component": "progress", "title": "Disk usage", "warning": 70, "danger": 90 },
+        { "title": "Web server", "value": 45, "unit": "%" },
+        { "title": "Database server", "value": 78, "unit": "%", "description": "Old backups can be deleted" },
+        { "title": "Backup server", "value": 95, "unit": "%" }
+    ]')
+),
+(
+    'progress',
+    'Sales targets as gauges.',
+    JSON('[
+        { "component": "progress", "type": "gauge", "color": "green" },
+        { "title": "January", "value": 120, "max": 150, "unit": "k$" },
+        { "title": "February", "value": 150, "max": 150, "unit": "k$" },
+        { "title": "March", "value": 40, "max": 150, "unit": "k$", "description": "In progress" }
+    ]')
+);
@@ -0,0 +1,29 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'code',
+    'Displays blocks of code, like SQL queries, JSON documents, or logs, in a monospace font, with a button to copy them.
+When a language is given, the code is colored with [Prism](https://prismjs.com/), which supports [hundreds of languages](https://prismjs.com/#supported-languages).',
+    'code',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES
+('code', 'no_copy', 'Hide the buttons that copy the code to the clipboard.', 'BOOLEAN', TRUE, TRUE),
+('code', 'highlight_js', 'Base URL from which Prism is loaded. Change it to host Prism yourself, for instance in an intranet without internet access. Defaults to https://cdn.jsdelivr.net/npm/prismjs@1.29.0/', 'URL', TRUE, TRUE),
+('code', 'contents', 'The code to display.', 'TEXT', FALSE, FALSE),
+('code', 'language', 'Language of the code, used for syntax highlighting, like sql, json, python, or log. Without a language, the code is displayed without colors.', 'TEXT', FALSE, TRUE),
+('code', 'title', 'Title displayed above the code.', 'TEXT', FALSE, TRUE),
+('code', 'description', 'Text displayed between the title and the code.', 'TEXT', FALSE, TRUE),
+('code', 'description_md', 'Text displayed between the title and the code, formatted using markdown.', 'TEXT', FALSE, TRUE);
+
+INSERT INTO example (component, description, properties)
+VALUES (
+    'code',
+    'Displaying a SQL query and its result as JSON.',
+    JSON('[
+        { "component": "code" },
+        { "title": "The query", "language": "sql", "contents": "SELECT name, age\nFROM users\nWHERE age > 18;" },
+        { "title": "The result", "language": "json", "contents": "[{\"name\": \"John\", \"age\": 42}]" }
+    ]')
+);
@@ -0,0 +1,42 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'qr_code',
+    'Displays QR codes, that users can scan with their phone.
+Useful for tickets, to share a link to a page, or to pair a device with an account.
+
+QR codes are generated by SQLPage on the server, as images embedded in the page, so no external service is involved,
+and the encoded text never leaves your server.',
+    'qrcode',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES
+('qr_code', 'size', 'Maximum width of the QR codes, in pixels. Defaults to 200.', 'INTEGER', TRUE, TRUE),
+('qr_code', 'error_correction', 'Error correction level of all the QR codes: L (7% of the code can be damaged), M (15%, the default), Q (25%), or H (30%). Higher levels make codes easier to scan, but larger.', 'TEXT', TRUE, TRUE),
+('qr_code', 'center', 'Whether to center the QR codes on the page.', 'BOOLEAN', TRUE, TRUE),
+('qr_code', 'contents', 'The text or URL to encode in the QR code.', 'TEXT', FALSE, FALSE),
+('qr_code', 'title', 'Text displayed below the QR code.', 'TEXT', FALSE, TRUE),
+('qr_code', 'description', 'Small text displayed below the title.', 'TEXT', FALSE, TRUE),
+('qr_code', 'link', 'URL to which the QR code links when it is clicked.', 'URL', FALSE, TRUE),
+('qr_code', 'error_correction', 'Error correction level of this QR code. Overrides the top-level error_correction.', 'TEXT', FALSE, TRUE);
+
+INSERT INTO example (component, description, properties)
+VALUES (
+    'qr_code',
+    'A QR code that links to the SQLPage website.',
+    JSON('[
+        { "component": "qr_code", "center": true },
+        { "contents": "https://sql.ophir.dev", "title": "SQLPage", "description": "Scan me with your phone" }
+    ]')
+),
+(
+    'qr_code',
+    'Event tickets. In a real application, the rows would come from a query like
+`select ''https://example.com/check_ticket.sql?code='' || code as contents, owner as title from tickets where event_id = $event`.',
+    JSON('[
+        { "component": "qr_code", "size": 150, "error_correction": "H" },
+        { "contents": "https://example.com/check_ticket.sql?code=A7F3K", "title": "John Doe", "description": "Seat 12A" },
+        { "contents": "https://example.com/check_ticket.sql?code=Q9P2M", "title": "Jane Doe", "description": "Seat 12B" }
+    ]')
+);
@@ -0,0 +1,37 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'big_number',
+    'Key performance indicators for dashboards: each row is a large number with a label,
+its evolution compared to a previous period, and an optional small chart of its recent history.',
+    'chart-arrows-vertical',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES
+('big_number', 'title', 'Title displayed above the numbers.', 'TEXT', TRUE, TRUE),
+('big_number', 'columns', 'Number of numbers per line on large screens. Defaults to 4.', 'INTEGER', TRUE, TRUE),
+('big_number', 'title', 'Label of the number.', 'TEXT', FALSE, FALSE),
+('big_number', 'value', 'The number to display. It is displayed as returned by the database, so you can format it in SQL.', 'TEXT', FALSE, FALSE),
+('big_number', 'unit', 'Unit displayed after the number, like € or users.', 'TEXT', FALSE, TRUE),
+('big_number', 'delta', 'Change compared to the previous period, like 12.5 or -3. Positive changes are displayed in green with an upward arrow, and negative changes in red with a downward arrow.', 'REAL', FALSE, TRUE),
+('big_number', 'delta_unit', 'Unit of the change. Defaults to %.', 'TEXT', FALSE, TRUE),
+('big_number', 'inverse', 'Set this for numbers that are better when they go down, like costs or response times: decreases are then displayed in green, and increases in red.', 'BOOLEAN', FALSE, TRUE),
+('big_number', 'sparkline', 'A JSON array of numbers, displayed as a small line chart at the bottom of the card. Typically built with json_group_array in SQLite, json_agg in PostgreSQL, or JSON_ARRAYAGG in MySQL.', 'JSON', FALSE, TRUE),
+('big_number', 'description', 'Small text displayed below the number.', 'TEXT', FALSE, TRUE),
+('big_number', 'icon', 'Name of an icon to display next to the label.', 'ICON', FALSE, TRUE),
+('big_number', 'color', 'Color of the icon, of the line chart, and of a bar at the top of the card.', 'COLOR', FALSE, TRUE),
+('big_number', 'link', 'URL to which the number links, for instance to a detailed report.', 'URL', FALSE, TRUE);
+
+INSERT INTO example (component, description, properties)
+VALUES (
+    'big_number',
+    'An executive dashboard. In a real application, each row would be computed with a query like
+`select ''Revenue'' as title, sum(amount) as value from orders where created_at > date(''now'', ''-30 days'')`.',
+    JSON('[
+        { "component": "big_number", "columns": 3 },
+        { "title": "Revenue", "value": "42,300", "unit": "€", "delta": 12.5, "icon": "currency-euro", "color": "green", "sparkline": [31, 35, 33, 38, 40, 39, 42] },
+        { "title": "New users", "value": 1250, "delta": -3.2, "description": "Last 30 days", "sparkline": [1400, 1350, 1300, 1320, 1290, 1250] },
+        { "title": "Response time", "value": 180, "unit": "ms", "delta": -25, "delta_unit": "ms", "inverse": true, "icon": "clock", "link": "performance.sql" }
+    ]')
+);
@@ -0,0 +1,53 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'tree',
+    'Displays hierarchical data as a tree, where each item can be expanded to show its children.
+Useful for categories of products, org charts, or file browsers.
+
+The hierarchy is given either by an `id` and a `parent_id` column, as in most tables that store a hierarchy,
+or by a `path` column, like `documents/2023/report.pdf`.
+Rows can be returned in any order.',
+    'binary-tree',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES
+('tree', 'title', 'Title displayed above the tree.', 'TEXT', TRUE, TRUE),
+('tree', 'expanded', 'Whether all the items are expanded when the page loads. By default, only the top-level items and the parents of the active item are visible.', 'BOOLEAN', TRUE, TRUE),
+('tree', 'separator', 'Separator between the levels of the path column. Defaults to /.', 'TEXT', TRUE, TRUE),
+('tree', 'id', 'Unique identifier of the item.', 'TEXT', FALSE, TRUE),
+('tree', 'parent_id', 'Identifier of the parent of the item. Items without a parent are displayed at the top level.', 'TEXT', FALSE, TRUE),
+('tree', 'path', 'Full path of the item, that can be used instead of id and parent_id. Parents must have their own row.', 'TEXT', FALSE, TRUE),
+('tree', 'title', 'Text of the item. Defaults to the last part of the path.', 'TEXT', FALSE, TRUE),
+('tree', 'link', 'URL to which the item links.', 'URL', FALSE, TRUE),
+('tree', 'icon', 'Name of an icon to display before the item.', 'ICON', FALSE, TRUE),
+('tree', 'color', 'Color of the icon.', 'COLOR', FALSE, TRUE),
+('tree', 'description', 'Small text displayed after the item.', 'TEXT', FALSE, TRUE),
+('tree', 'active', 'Whether the item is the current one. It is displayed in bold, and its parents are expanded.', 'BOOLEAN', FALSE, TRUE);
+
+INSERT INTO example (component, description, properties)
+VALUES (
+    'tree',
+    'Categories of products, from a table with a `parent_id` column: `select id, parent_id, name as title, ''products.sql?category='' || id as link from categories`.',
+    JSON('[
+        { "component": "tree", "title": "Categories" },
+        { "id": 1, "title": "Computers", "icon": "device-laptop" },
+        { "id": 2, "parent_id": 1, "title": "Laptops", "link": "#laptops" },
+        { "id": 3, "parent_id": 1, "title": "Desktops", "link": "#desktops", "active": true },
+        { "id": 4, "title": "Phones", "icon": "device-mobile" },
+        { "id": 5, "parent_id": 4, "title": "Smartphones", "description": "42 products" }
+    ]')
+),
+(
+    'tree',
+    'A file browser, using paths.',
+    JSON('[
+        { "component": "tree", "expanded": true },
+        { "path": "documents", "icon": "folder" },
+        { "path": "documents/invoices", "icon": "folder" },
+        { "path": "documents/invoices/2024-01.pdf", "icon": "file-type-pdf", "color": "red" },
+        { "path": "documents/report.docx", "icon": "file-text" },
+        { "path": "photos", "icon": "folder" }
+    ]')
+);
@@ -0,0 +1,71 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'feed',
+    'Responds to the current HTTP request with an RSS or Atom feed instead of an HTML page,
+so that visitors can subscribe to your blog, changelog, or list of events in their feed reader.
+
+Each row returned by the following queries becomes an item of the feed.
+Text is escaped and dates are converted to the format required by the feed type.
+This component must appear at the top of your SQL file, before any other data has been sent to the browser.',
+    'rss',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES
+('feed', 'type', 'Format of the feed: `rss` (the default, RSS 2.0) or `atom`.', 'TEXT', TRUE, TRUE),
+('feed', 'title', 'Name of the feed, as displayed in feed readers.', 'TEXT', TRUE, FALSE),
+('feed', 'link', 'URL of the website the feed belongs to. Also used as the identifier of Atom feeds.', 'URL', TRUE, FALSE),
+('feed', 'description', 'Short description of the feed.', 'TEXT', TRUE, TRUE),
+('feed', 'language', 'Language of the feed, such as `en` or `fr-FR`.', 'TEXT', TRUE, TRUE),
+('feed', 'updated', 'Date of the last update of the feed. Atom feeds default to the current date.', 'TEXT', TRUE, TRUE),
+('feed', 'title', 'Title of the item.', 'TEXT', FALSE, FALSE),
+('feed', 'link', 'URL of the full content of the item.', 'URL', FALSE, TRUE),
+('feed', 'description', 'Summary or full content of the item. HTML is allowed, and will be escaped as required by the feed format.', 'TEXT', FALSE, TRUE),
+('feed', 'date', 'Publication date of the item, as an ISO 8601 string like `2024-01-15 10:30:00` or `2024-01-15T10:30:00+01:00`, or as a unix timestamp. Dates without a time zone are interpreted as UTC. Required for Atom feeds.', 'TEXT', FALSE, TRUE),
+('feed', 'author', 'Author of the item. In RSS feeds, this should be an email address.', 'TEXT', FALSE, TRUE),
+('feed', 'id', 'Unique and permanent identifier of the item. Defaults to the link.', 'TEXT', FALSE, TRUE);
+
+INSERT INTO example (component, description)
+VALUES (
+    'feed',
+    '
+Create a file named `rss.sql` that publishes the latest posts of your blog:
+
+```sql
+select ''feed'' as component,
+    ''My blog'' as title,
+    ''https://example.com/'' as link,
+    ''Thoughts about databases'' as description,
+    ''en'' as language;
+
+select title,
+    ''https://example.com/post.sql?id='' || id as link,
+    summary as description,
+    created_at as date
+from posts
+order by created_at desc
+limit 20;
+```
+
+Then let browsers and feed readers discover it by adding a link to it in the `<head>` of your pages,
+using the `shell` component''s `rss` property.
+'
+),
+(
+    'feed',
+    '
+An Atom feed of the releases of a project, built from a `releases` table:
+
+```sql
+select ''feed'' as component, ''atom'' as type, ''Changelog'' as title, ''https://example.com/changelog.sql'' as link;
+
+select ''Version '' || version as title,
+    ''urn:release:'' || version as id,
+    notes as description,
+    released_at as date
+from releases
+order by released_at desc;
+```
+'
+);
@@ -0,0 +1,45 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'pdf',
+    'Sends the page as a PDF document instead of an HTML page.
+Useful for invoices, reports, or anything your users need to print or archive.
+
+The rest of the page is written with the usual components, and converted to PDF on the server once it is complete.
+The conversion is done by an external program, that you choose with the `pdf_converter` [configuration option](https://github.com/lovasoa/SQLpage/blob/main/configuration.md).
+It receives the HTML page on its standard input, and must write the PDF to its standard output.
+[WeasyPrint](https://weasyprint.org/) (`weasyprint - -`) supports page headers and footers.
+
+This component must appear at the top of your SQL file, before any other data has been sent to the browser.',
+    'file-type-pdf',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES
+('pdf', 'filename', 'Name of the PDF file, without the .pdf extension. Defaults to "document".', 'TEXT', TRUE, TRUE),
+('pdf', 'download', 'Whether the browser should download the file instead of displaying it.', 'BOOLEAN', TRUE, TRUE),
+('pdf', 'size', 'Size of the pages, such as A4 (the default), A5, letter, legal, or explicit dimensions like "210mm 297mm".', 'TEXT', TRUE, TRUE),
+('pdf', 'orientation', 'Orientation of the pages: portrait (the default) or landscape.', 'TEXT', TRUE, TRUE),
+('pdf', 'margin', 'Margins around the contents of each page, as a CSS length like 2cm. Defaults to 1.5cm.', 'TEXT', TRUE, TRUE),
+('pdf', 'header', 'Text displayed at the top of each page. {page} is replaced by the page number, and {pages} by the number of pages.', 'TEXT', TRUE, TRUE),
+('pdf', 'footer', 'Text displayed at the bottom of each page. {page} is replaced by the page number, and {pages} by the number of pages.', 'TEXT', TRUE, TRUE);
+
+INSERT INTO example (component, description)
+VALUES (
+    'pdf',
+    '
+An invoice, in a file named `invoice.sql` that takes the invoice number as a parameter:
+
+```sql
+select ''pdf'' as component,
+    ''invoice-'' || $id as filename,
+    ''Invoice '' || $id as header,
+    ''Page {page} of {pages}'' as footer;
+
+select ''shell'' as component, ''Invoice '' || $id as title;
+
+select ''table'' as component;
+select product, quantity, price from invoice_lines where invoice_id = $id;
+```
+'
+);
@@ -0,0 +1,36 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'flush',
+    'Controls how the page is sent to the browser while it is being generated.
+
+SQLPage sends each row to the browser as soon as it is rendered, so that long pages and slow reports display progressively.
+However, compression and reverse proxies such as nginx keep the page in a buffer until it is large enough,
+and the results of a long-running report may then appear all at once.
+
+Use this component at the top of a page with `buffering` set to false to disable compression and proxy buffering for this page.
+Elsewhere in the page, it marks the places where the results are expected to be sent to the browser, and has no other effect.',
+    'player-skip-forward',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES
+('flush', 'buffering', 'Set to false to disable compression and reverse proxy buffering for the whole page. This can only be done at the top of the page, before any other data has been sent to the browser. The page is then larger, since it is not compressed.', 'BOOLEAN', TRUE, TRUE);
+
+INSERT INTO example (component, description)
+VALUES (
+    'flush',
+    '
+A report that runs several slow queries, and displays the result of each of them as soon as it is available:
+
+```sql
+select ''flush'' as component, false as buffering;
+
+select ''card'' as component, ''Sales'' as title;
+select region as title, sum(amount) as description from sales group by region;
+
+select ''chart'' as component, ''Yearly revenue'' as title, ''area'' as type;
+select year as x, revenue as y from very_slow_revenue_view;
+```
+'
+);
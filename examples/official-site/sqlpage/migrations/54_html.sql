@@ -0,0 +1,23 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'html',
+    'Inserts raw html in the page.
+
+By default, the html is inserted as is, and must come from a trusted source:
+any script it contains will run in the browser of your users.
+When displaying html written by your users, or fetched from an external source,
+set `sanitize` to `strict` or `basic` to remove everything that is not simple formatting.',
+    'code',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES
+('html', 'html', 'Raw html code to insert in the page.', 'TEXT', TRUE, TRUE),
+('html', 'sanitize', 'How the html is cleaned before being displayed. `none` (the default) inserts the html unchanged. `strict` keeps only text formatting: paragraphs, titles, lists, emphasis, and code. `basic` also keeps links, images, and tables. Scripts, styles, `class` and `style` attributes, event handlers, and links to anything other than http, https, mailto, and tel URLs are always removed when sanitizing.', 'TEXT', TRUE, TRUE),
+('html', 'html', 'Raw html code to insert in the page, sanitized with the level set on the component.', 'TEXT', FALSE, TRUE);
+
+INSERT INTO example (component, description, properties)
+VALUES
+    ('html', 'Insert a static piece of html', JSON('[{"component":"html", "html":"<p>This paragraph contains <mark>highlighted</mark> text and a <kbd>keyboard key</kbd>.</p>"}]')),
+    ('html', 'Display comments written by users, keeping only their formatting and links. The script and the `onclick` attribute are removed.', JSON('[{"component":"html", "sanitize":"basic"}, {"html":"<p>I <strong>love</strong> <a href=\"https://sql.datapage.app\" onclick=\"steal()\">SQLPage</a>!</p><script>alert(\"hacked\")</script>"}]'));
@@ -0,0 +1,60 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'sse',
+    'Updates parts of the page live, without reloading it, for dashboards and monitoring pages.
+
+When the page is displayed, this component opens a connection to the server using
+[server-sent events](https://developer.mozilla.org/en-US/docs/Web/API/Server-sent_events).
+While the page stays open, its SQL file is executed again at a regular `interval`,
+and each row of this component is sent to the browser.
+A row updates the element of the page that has the same `id`, such as a [text](?component=text#component) component with an `id`.
+
+Every query of the file is executed again each time, but only the rows of the `sse` component are sent.
+Avoid putting queries that modify the database in a page that contains this component.
+
+With PostgreSQL, the page can instead be executed again only when something changes,
+by sending a notification with [`NOTIFY`](https://www.postgresql.org/docs/current/sql-notify.html) on a `channel`.
+All the open pages share a single database connection, that receives the notifications.
+
+Components that control access to the page, such as [authentication](?component=authentication#component)
+and [redirect](?component=redirect#component), are checked each time the page is executed:
+when they deny access, the updates stop.',
+    'broadcast',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES
+('sse', 'interval', 'Number of seconds between two executions of the page, at least 1. Defaults to 5 seconds, or to never when a `channel` is set.', 'NUMBER', TRUE, TRUE),
+('sse', 'channel', 'PostgreSQL only. Name of a notification channel: the page is executed again each time `NOTIFY channel_name` is executed on the database. When an `interval` is also set, the page is executed again at least at this interval.', 'TEXT', TRUE, TRUE),
+('sse', 'url', 'Address of the page that sends the updates. Defaults to the current page, with the same URL parameters.', 'URL', TRUE, TRUE),
+('sse', 'id', 'Identifier of the element of the page to update.', 'TEXT', FALSE, FALSE),
+('sse', 'contents', 'New text of the element.', 'TEXT', FALSE, TRUE),
+('sse', 'html', 'New html contents of the element. It is inserted as is, and must come from a trusted source.', 'TEXT', FALSE, TRUE),
+('sse', 'value', 'New value of a form field.', 'TEXT', FALSE, TRUE);
+
+INSERT INTO example (component, description)
+VALUES (
+    'sse',
+    '
+A dashboard that displays the number of orders, and updates it every 10 seconds:
+
+```sql
+select ''text'' as component, ''orders_count'' as id, count(*) as contents from orders;
+
+select ''sse'' as component, 10 as interval;
+select ''orders_count'' as id, count(*) as contents from orders;
+```
+
+With PostgreSQL, the number is updated as soon as a new order is inserted,
+if the table has a trigger that runs `NOTIFY new_order`:
+
+```sql
+select ''sse'' as component, ''new_order'' as channel;
+select ''orders_count'' as id, count(*) as contents from orders;
+```
+
+Custom scripts can also react to the updates: each row sent by the server
+triggers a `sqlpage:update` event, with the row in its `detail` property.
+'
+);
@@ -0,0 +1,29 @@
+INSERT INTO sqlpage_functions (
+        "name",
+        "introduced_in_version",
+        "icon",
+        "description_md"
+    )
+VALUES (
+        'client_ip',
+        '0.18.0',
+        'network',
+        'Returns the IP address of the client that requested the current page, or `NULL` if it is unknown.
+
+### Example
+
+```sql
+insert into page_visits (path, ip) values (sqlpage.path(), sqlpage.client_ip());
+```
+
+### Behind a reverse proxy
+
+When SQLPage runs behind a reverse proxy such as nginx or Cloudflare,
+the connections come from the proxy, which passes the address of the client in the `X-Forwarded-For` header.
+Anyone can send this header, so SQLPage only reads it when the request comes from one of the
+`trusted_proxies` of the [configuration](https://github.com/lovasoa/SQLpage/blob/main/configuration.md).
+By default, only the proxies running on the same machine as SQLPage are trusted.
+
+The addresses in `X-Forwarded-For` are read from right to left, and the first one that is not a trusted proxy is returned.
+'
+    );
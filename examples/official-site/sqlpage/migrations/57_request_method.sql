@@ -0,0 +1,27 @@
+INSERT INTO sqlpage_functions (
+        "name",
+        "introduced_in_version",
+        "icon",
+        "description_md"
+    )
+VALUES (
+        'request_method',
+        '0.18.0',
+        'http-delete',
+        'Returns the [HTTP method](https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods) of the current request,
+such as `GET`, `POST`, `PUT`, `PATCH` or `DELETE`.
+
+This is useful to build JSON APIs that follow REST conventions with a single file.
+
+### Example
+
+```sql
+delete from items where id = $id and sqlpage.request_method() = ''DELETE'';
+```
+
+Alternatively, the requests with the methods `POST`, `PUT`, `PATCH` and `DELETE` are handled by a file
+with the name of the method before its extension, when it exists:
+a `DELETE` request to `/items.sql` executes `items.delete.sql` if it exists, and `items.sql` otherwise.
+Such files can only be accessed with their method.
+'
+    );
@@ -0,0 +1,46 @@
+INSERT INTO sqlpage_functions (
+    "name",
+    "introduced_in_version",
+    "icon",
+    "description_md"
+)
+VALUES (
+    'user_info',
+    '0.18.0',
+    'id-badge',
+    'Returns a claim of the user logged in with [OpenID Connect](https://github.com/lovasoa/SQLpage/blob/main/configuration.md#single-sign-on-with-openid-connect),
+such as their `email`, `name` or `preferred_username`, or NULL if the user is not logged in or the claim is missing.
+
+Without argument, it returns all the claims of the user as a JSON object.
+The identifier of the user (the `sub` claim) is also returned by [`sqlpage.current_user()`](?function=current_user#function).
+
+The claims that are available depend on the provider, and on the `oidc_scopes` of the configuration.
+They are only kept when a `session_store` is configured, because they would not fit in a cookie:
+with the default cookie sessions, only the `sub` claim is available.
+Claims that are not strings, like `email_verified`, are returned as JSON.
+
+## Example
+
+```sql
+SELECT ''text'' AS component, ''Welcome, '' || sqlpage.user_info(''name'') || ''!'' AS contents;
+
+INSERT INTO users (id, email)
+SELECT sqlpage.current_user(), sqlpage.user_info(''email'')
+WHERE NOT EXISTS (SELECT 1 FROM users WHERE id = sqlpage.current_user());
+```
+');
+
+INSERT INTO sqlpage_function_parameters (
+    "function",
+    "index",
+    "name",
+    "description_md",
+    "type"
+)
+VALUES (
+    'user_info',
+    1,
+    'claim',
+    'The name of the claim to return, such as `email`. Optional.',
+    'TEXT'
+);
@@ -0,0 +1,82 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'cache',
+    'Keeps the rendered page in memory for a given number of seconds, and serves it to the next visitors of the same url
+without running the queries of the page again.
+
+This drastically reduces the load on your database for public pages that are read much more often than they change,
+such as a blog post or a product catalog.
+The url includes its query string: `product.sql?id=1` and `product.sql?id=2` are cached separately.
+
+Only cache pages that are the same for every visitor: a cached page is shown to all users, whatever their cookies or session variables.
+Pages that set cookies, that contain a form (which embeds a per-user protection token), that return an error status,
+or that display an error are never cached.
+
+Like the cookie component, this component must be placed at the top of the page, before any component that generates output.
+Pages can also be cached without changing their code, with the `page_cache` [configuration option](https://github.com/lovasoa/SQLpage/blob/main/configuration.md#page-cache).
+Remove pages from the cache before they expire with [`sqlpage.purge_cache`](functions.sql?function=purge_cache#function).',
+    'clock-bolt',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES (
+    'cache',
+    'ttl',
+    'Number of seconds during which the rendered page is served from the cache.',
+    'NUMBER',
+    TRUE,
+    FALSE
+);
+
+INSERT INTO example (component, description)
+VALUES (
+    'cache',
+    'Cache a blog post for 5 minutes:
+
+```sql
+SELECT ''cache'' AS component, 300 AS ttl;
+SELECT ''text'' AS component, title, contents_md FROM posts WHERE id = $id;
+```
+'
+);
+
+INSERT INTO sqlpage_functions (
+    "name",
+    "introduced_in_version",
+    "icon",
+    "description_md"
+)
+VALUES (
+    'purge_cache',
+    '0.18.0',
+    'clock-x',
+    'Removes pages from the cache filled by the [`cache` component](documentation.sql?component=cache#component),
+so that the next visitors see the latest version of your data without waiting for the cached pages to expire.
+
+Returns the number of pages removed from the cache.
+
+## Example
+
+After editing a blog post, remove all the cached versions of the blog:
+
+```sql
+UPDATE posts SET contents_md = :contents WHERE id = $id;
+SELECT ''redirect'' AS component, ''post.sql?id='' || $id || ''&purged='' || sqlpage.purge_cache(''/blog/'') AS link;
+```
+');
+
+INSERT INTO sqlpage_function_parameters (
+    "function",
+    "index",
+    "name",
+    "description_md",
+    "type"
+)
+VALUES (
+    'purge_cache',
+    1,
+    'path_prefix',
+    'Only the pages whose path starts with this prefix are removed, as a literal string. Optional: all the pages are removed by default.',
+    'TEXT'
+);
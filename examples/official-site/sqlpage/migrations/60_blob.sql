@@ -0,0 +1,51 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'blob',
+    'Sends a file stored in the database, such as an image, an avatar or an attachment, as the whole response to the request,
+instead of rendering an html page.
+
+Binary columns (`BLOB` in SQLite and MySQL, `BYTEA` in PostgreSQL, `VARBINARY` in SQL Server) are returned by SQLPage as
+[data urls](https://developer.mozilla.org/en-US/docs/Web/HTTP/Basics_of_HTTP/Data_URLs), and this component decodes them back to the original bytes.
+Text columns are sent as is.
+
+If the body is `NULL`, the response is an empty `404 Not Found` error.
+
+This component must be the first component of the page, and all the following queries are ignored.
+The page that serves the file can then be used as the source of an image or as a download link:
+
+```sql
+SELECT ''image'' AS component, ''avatar.sql?id='' || $id AS src;
+```',
+    'file-database',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES (
+    'blob',
+    'body',
+    'The contents of the file, from a binary or text column.',
+    'TEXT',
+    TRUE,
+    FALSE
+),
+(
+    'blob',
+    'content_type',
+    'The media type of the file, such as `image/png` or `application/pdf`. Defaults to the type of the data url, or to `application/octet-stream`.',
+    'TEXT',
+    TRUE,
+    TRUE
+);
+
+INSERT INTO example (component, description)
+VALUES (
+    'blob',
+    'Serve the avatars stored in a `users` table, in a file named `avatar.sql`:
+
+```sql
+SELECT ''blob'' AS component, avatar_mime_type AS content_type, avatar AS body
+FROM users WHERE id = $id;
+```
+'
+);
@@ -0,0 +1,61 @@
+INSERT INTO component (name, description, icon, introduced_in_version)
+VALUES (
+    'content_type',
+    'Sets the type of the whole response, and optionally makes the browser download it as a file,
+whatever the components used to render it.
+
+This lets a single `.sql` file generate a calendar event (`.ics`), a contact card (`.vcf`), an `.xml` document, or any other text format,
+usually with the `shell-empty` and `html` components, which output their contents without any surrounding html.
+It also lets you choose the name of the file downloaded from a `json`, `csv` or `blob` response.
+
+Like the cookie component, this component must be placed at the top of the page, before any component that generates output.',
+    'file-settings',
+    '0.18.0'
+);
+
+INSERT INTO parameter (component, name, description, type, top_level, optional)
+VALUES (
+    'content_type',
+    'type',
+    'The media type of the response, such as `text/calendar` or `application/xml`. Text types are sent with a `charset=utf-8` parameter.',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'content_type',
+    'filename',
+    'Name of the file saved by the browser. The response is downloaded instead of being displayed, unless `download` is false.',
+    'TEXT',
+    TRUE,
+    TRUE
+),
+(
+    'content_type',
+    'download',
+    'Whether the browser should save the response as a file instead of displaying it. Defaults to true when a filename is given.',
+    'BOOLEAN',
+    TRUE,
+    TRUE
+);
+
+INSERT INTO example (component, description)
+VALUES (
+    'content_type',
+    'Let users add an event to their calendar:
+
+```sql
+SELECT ''content_type'' AS component, ''text/calendar'' AS type, ''event.ics'' AS filename;
+SELECT ''shell-empty'' AS component;
+SELECT ''html'' AS component,
+    ''BEGIN:VCALENDAR
+VERSION:2.0
+BEGIN:VEVENT
+SUMMARY:'' || title || ''
+DTSTART:'' || strftime(''%Y%m%dT%H%M%SZ'', starts_at) || ''
+END:VEVENT
+END:VCALENDAR'' AS html
+FROM events WHERE id = $id;
+```
+'
+);
@@ -0,0 +1,98 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'form', * FROM (VALUES
+    ('chunked', 'For inputs of type file. Sends the file to the server in several parts while the user fills the rest of the form, with a progress bar, resuming automatically after a connection failure. The field then contains the id of the upload, to use with `sqlpage.chunked_upload_path`. Requires the `max_chunked_upload_size` configuration option. Introduced in v0.18.0.', 'BOOLEAN', FALSE, TRUE)
+);
+
+INSERT INTO sqlpage_functions (
+    "name",
+    "introduced_in_version",
+    "icon",
+    "description_md"
+)
+VALUES (
+    'chunked_upload_path',
+    '0.18.0',
+    'cloud-upload',
+    'Returns the path of a file uploaded in several parts, from the id of its upload,
+or NULL if the upload does not exist or is not complete yet.
+
+Files uploaded with a regular form field are limited by `max_uploaded_file_size`, and a connection failure means starting again from the beginning.
+For very large files, such as videos or database dumps, set `max_chunked_upload_size` in the configuration, and add `true as chunked` to the file field of your form.
+The browser then sends the file in parts of a few megabytes while the user fills the rest of the form, displays the progress of the upload,
+and resumes where it stopped after a connection failure. The field contains the id of the upload when the form is submitted.
+
+Like with [`sqlpage.uploaded_file_path`](?function=uploaded_file_path#function), the file is stored in a temporary directory:
+move it somewhere else, or copy its contents to the database, to keep it. Uploads are removed a day after their last change,
+and incomplete uploads an hour after they received their last part.
+An upload created by a user who has a [session](documentation.sql?component=session#component) can only be used in the same session.
+The total size of the uploads stored at the same time is limited by `max_chunked_uploads_total_size`.
+
+## Example
+
+`upload_video.sql`:
+```sql
+select ''form'' as component, ''save_video.sql'' as action;
+select ''title'' as name;
+select ''video'' as name, ''file'' as type, ''video/*'' as accept, true as chunked, true as required;
+```
+
+`save_video.sql`:
+```sql
+set video_path = sqlpage.chunked_upload_path(:video);
+set moved = sqlpage.exec(''mv'', $video_path, ''/srv/videos/'');
+insert into videos (title, file_path)
+values (:title, ''/srv/videos/'' || :video)
+returning ''redirect'' as component, ''videos.sql'' as link;
+```
+
+## Protocol
+
+Other clients, such as scripts or mobile applications, can upload files the same way:
+
+ - `POST /sqlpage/uploads` with the size of the file in bytes in the `Upload-Length` header, and optionally its percent-encoded name in the `Upload-Filename` header,
+   creates an upload and returns its `id` in a JSON object.
+ - `PATCH /sqlpage/uploads/<id>` with a part of the file as the request body, and the position of its first byte in the `Upload-Offset` header, adds it to the upload.
+   Parts must be sent in order: a part that does not start where the previous one ended is refused with a `409 Conflict` status.
+ - `GET /sqlpage/uploads/<id>` returns the progress of the upload, as a JSON object with `offset`, `length`, `complete` and `file_name` properties.
+   After a connection failure, the upload resumes at `offset`.
+ - `DELETE /sqlpage/uploads/<id>` cancels the upload.
+'),
+(
+    'chunked_upload_status',
+    '0.18.0',
+    'progress',
+    'Returns the progress of a file uploaded in several parts, from the id of its upload,
+as a JSON object such as `{"id": "...", "file_name": "video.mp4", "length": 1048576000, "offset": 524288000, "complete": false}`,
+or NULL if the upload does not exist.
+
+See [`sqlpage.chunked_upload_path`](?function=chunked_upload_path#function) to learn how to upload large files in several parts.
+
+## Example
+
+```sql
+set status = sqlpage.chunked_upload_status($upload_id);
+set received = sqlpage.json_param($status, ''$.offset'');
+select ''text'' as component, ''Received '' || (CAST($received AS INTEGER) / 1000000) || '' MB'' as contents;
+```
+');
+
+INSERT INTO sqlpage_function_parameters (
+    "function",
+    "index",
+    "name",
+    "description_md",
+    "type"
+)
+VALUES (
+    'chunked_upload_path',
+    1,
+    'upload_id',
+    'The id of the upload, contained in the file field of a form with `chunked` set to true.',
+    'TEXT'
+),
+(
+    'chunked_upload_status',
+    1,
+    'upload_id',
+    'The id of the upload, contained in the file field of a form with `chunked` set to true.',
+    'TEXT'
+);
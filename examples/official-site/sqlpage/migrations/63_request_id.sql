@@ -0,0 +1,27 @@
+INSERT INTO sqlpage_functions (
+        "name",
+        "introduced_in_version",
+        "icon",
+        "description_md"
+    )
+VALUES (
+        'request_id',
+        '0.18.0',
+        'fingerprint',
+        'Returns the id of the current request.
+
+Every request receives an id, that is sent back to the browser in the `X-Request-Id` response header,
+written in the access log, and displayed in error pages.
+When a user reports an error, the id they see lets you find the corresponding lines in the logs.
+If the request already has an `X-Request-Id` header, set for instance by a reverse proxy or a load balancer, its value is used.
+
+### Example
+
+Store the id of the request that changed a row, to find out later which request it was:
+
+```sql
+insert into audit_log (user_id, action, request_id)
+values ($user_id, ''delete_item'', sqlpage.request_id());
+```
+'
+    );
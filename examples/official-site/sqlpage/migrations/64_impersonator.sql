@@ -0,0 +1,29 @@
+INSERT INTO sqlpage_functions (
+        "name",
+        "introduced_in_version",
+        "icon",
+        "description_md"
+    )
+VALUES (
+        'impersonator',
+        '0.18.0',
+        'spy',
+        'Returns the identifier of the administrator who is [impersonating](documentation.sql?component=impersonate#component) the current user,
+or NULL when no impersonation is in progress.
+
+During an impersonation, [`sqlpage.current_user()`](?function=current_user#function) returns the impersonated user,
+and this function returns the identity of the person who is actually using the application.
+
+### Example
+
+Display a banner while an impersonation is in progress:
+
+```sql
+select ''alert'' as component,
+    ''You are viewing the application as '' || sqlpage.current_user() as title,
+    ''Stop impersonating'' as link_text,
+    ''stop_impersonation.sql'' as link
+where sqlpage.impersonator() is not null;
+```
+'
+    );
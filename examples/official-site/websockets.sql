@@ -0,0 +1,62 @@
+select 'dynamic' as component, properties FROM example WHERE component = 'shell' LIMIT 1;
+
+select 'text' as component, '
+
+# Live pages with WebSockets
+
+SQLPage files can also be used as [WebSocket](https://developer.mozilla.org/en-US/docs/Web/API/WebSockets_API) endpoints,
+to build pages that update without being reloaded, such as chats, notifications, or forms that are validated as you type.
+
+WebSocket connections are refused by default. List the files that accept them in the `websocket_paths`
+[configuration option](https://github.com/lovasoa/SQLpage/blob/main/configuration.md), like `["/chat.sql"]`.
+Only the pages of your website can open connections, which prevents other websites from using the cookies of your visitors.
+Pages of other websites can be allowed with `websocket_allowed_origins`.
+
+When a browser opens a WebSocket connection to a `.sql` file, the connection stays open,
+and the file is executed again each time the browser sends a message:
+
+ - when the message is a JSON object, each of its fields is available as a variable in the SQL file: `{"name": "Ann"}` sets `:name`, just like the field of a form,
+ - any other message is available as `:message`,
+ - the URL parameters of the connection are available as usual, with `$name`.
+
+Each row returned by the queries of the file is sent back to the browser as a JSON message, as it is produced.
+Components are not rendered: the rows are sent as they are, like with the [`json`](documentation.sql?component=json#component) component.
+Nothing is executed when the connection is opened, only when a message is received.
+
+Components that control access to the page, such as [`authentication`](documentation.sql?component=authentication#component)
+and [`redirect`](documentation.sql?component=redirect#component), are not sent:
+when they deny access, the connection is closed before the next statements of the file are executed.
+
+## Example: a chat
+
+`chat.sql`:
+
+```sql
+insert into messages (room, author, text)
+select $room, :author, :text where :text is not null;
+
+select author, text, created_at from messages
+where room = $room
+order by created_at desc
+limit 20;
+```
+
+The page that displays the chat opens the connection with a few lines of JavaScript,
+in a [custom script](documentation.sql?component=shell#component):
+
+```js
+const socket = new WebSocket(`ws://${location.host}/chat.sql?room=general`);
+socket.onopen = () => socket.send("{}");
+socket.onmessage = (event) => {
+  const { author, text } = JSON.parse(event.data);
+  // display the message in the page
+};
+document.querySelector("form").onsubmit = (event) => {
+  event.preventDefault();
+  socket.send(JSON.stringify({ author: "Ann", text: event.target.text.value }));
+};
+```
+
+When a query fails, a message with an `error` field is sent instead of the rows.
+Its details are only included when [`verbose_errors`](https://github.com/lovasoa/SQLpage/blob/main/configuration.md) is enabled.
+' as contents_md;
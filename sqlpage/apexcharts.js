@@ -32,6 +32,51 @@ function sqlpage_chart() {
         return new_series;
     }
 
+    /** Parses dates, including the 'YYYY-MM-DD HH:MM:SS' format returned by many databases */
+    function parse_date(x) {
+        if (typeof x === "string" && /^\d{4}-\d{2}-\d{2} \d/.test(x)) x = x.replace(" ", "T");
+        return new Date(x);
+    }
+
+    /**
+     * Adds, for each series, a series with the average of its last `window` values
+     * @param {Series} series
+     * @param {number} window
+     * @returns {Series} */
+    function moving_averages(series, window) {
+        return series.flatMap(s => {
+            let sum = 0;
+            const data = s.data.map(({ x, y }, i) => {
+                sum += +y - (i >= window ? +s.data[i - window].y : 0);
+                return { x, y: i >= window - 1 ? sum / window : null };
+            });
+            return [s, { name: `${s.name} (${window} points average)`, type: 'line', data }];
+        });
+    }
+
+    /**
+     * Counts the values of each series in bins of equal width, shared by all series
+     * @param {Series} series
+     * @param {number | undefined} bins
+     * @returns {Series} */
+    function histogram(series, bins) {
+        const values = series.flatMap(s => s.data.map(p => +p.y)).filter(Number.isFinite);
+        const min = Math.min(...values), max = Math.max(...values);
+        const bin_count = bins || Math.max(1, Math.ceil(Math.sqrt(values.length)));
+        const width = (max - min) / bin_count || 1;
+        const fmt = new Intl.NumberFormat(undefined, { maximumSignificantDigits: 3 });
+        const categories = Array.from({ length: bin_count },
+            (_, i) => `${fmt.format(min + i * width)} – ${fmt.format(min + (i + 1) * width)}`);
+        return series.map(({ name, data }) => {
+            const counts = new Array(bin_count).fill(0);
+            for (const { y } of data) {
+                if (!Number.isFinite(+y)) continue;
+                counts[Math.min(bin_count - 1, Math.floor((y - min) / width))]++;
+            }
+            return { name, data: counts.map((y, i) => ({ x: categories[i], y })) };
+        });
+    }
+
     for (const c of document.getElementsByClassName("chart")) {
         try {
             const data = JSON.parse(c.querySelector("data").innerText);
@@ -40,7 +85,7 @@ function sqlpage_chart() {
             const series_map = {};
             data.points.forEach(([name, x, y, z]) => {
                 series_map[name] = series_map[name] || { name, data: [] }
-                if (is_timeseries) x = new Date(x);
+                if (is_timeseries) x = parse_date(x);
                 series_map[name].data.push({ x, y, z });
             })
             if (data.xmin == null) data.xmin = undefined;
@@ -55,36 +100,51 @@ function sqlpage_chart() {
 
             let series = Object.values(series_map);
 
+            const is_histogram = data.type === "histogram";
+            if (is_histogram) {
+                series = histogram(series, data.bins);
+                data.type = "bar";
+            }
+
             // tickamount is the number of intervals, not the number of ticks
             const tickAmount = data.xticks ||
                 Math.min(30, Math.max(...series.map(s => s.data.length - 1)));
 
             let labels;
-            const categories = typeof data.points[0][1] === "string";
+            if (data.moving_average > 1 && !["pie", "heatmap"].includes(data.type)) {
+                series = moving_averages(series, +data.moving_average);
+            }
+            const categories = is_histogram || (!is_timeseries && typeof data.points[0][1] === "string");
             if (data.type === "pie") {
                 labels = data.points.map(([name, x, y]) => x || name);
                 series = data.points.map(([name, x, y]) => y);
-            } else if (categories && data.type !== 'line') series = align_categories(series);
+            } else if (categories && data.type !== 'line' && !is_histogram) series = align_categories(series);
 
             const options = {
                 chart: {
                     type: data.type || 'line',
                     fontFamily: 'inherit',
+                    background: 'transparent',
                     parentHeightOffset: 0,
                     height: c.style.height,
                     stacked: !!data.stacked,
                     toolbar: {
-                        show: !!data.toolbar,
+                        show: !!data.toolbar || !!data.zoom,
+                        tools: {
+                            download: !!data.toolbar,
+                        },
                     },
                     animations: {
                         enabled: false
                     },
                     zoom: {
-                        enabled: false
+                        enabled: !!data.zoom,
+                        type: 'x',
                     }
                 },
                 theme: {
                     palette: 'palette4',
+                    mode: is_dark_theme() ? 'dark' : 'light',
                 },
                 dataLabels: {
                     enabled: !!data.labels,
@@ -108,6 +168,9 @@ function sqlpage_chart() {
                         text: data.xtitle || undefined,
                     },
                     type: is_timeseries ? 'datetime' : categories ? 'category' : undefined,
+                    labels: {
+                        datetimeUTC: false,
+                    },
                 },
                 yaxis: {
                     logarithmic: !!data.logarithmic,
@@ -135,7 +198,7 @@ function sqlpage_chart() {
                     custom: (data.type === 'bubble' || data.type === 'scatter') ? bubbleTooltip : undefined,
                 },
                 plotOptions: {
-                    bar: { horizontal: !!data.horizontal },
+                    bar: { horizontal: !!data.horizontal, columnWidth: is_histogram ? '95%' : undefined },
                     bubble: { minBubbleRadius: 5, },
                 },
                 colors,
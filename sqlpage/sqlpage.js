@@ -14,10 +14,20 @@ function sqlpage_chart() {
 function sqlpage_table(){
     // Tables
     for (const r of document.getElementsByClassName("data-list")) {
+        const page_size = +r.dataset.page_size;
         new List(r, {
             valueNames: [...r.getElementsByTagName("th")].map(t => t.textContent),
             searchDelay: 100,
-            indexAsync: true
+            indexAsync: true,
+            // Printed tables show all their rows
+            ...(page_size > 0 && !is_print_mode() && {
+                page: page_size,
+                pagination: {
+                    item: '<li class="page-item"><a class="page page-link" href="#"></a></li>',
+                    innerWindow: 2,
+                    outerWindow: 1,
+                },
+            }),
         });
     }
 }
@@ -44,15 +54,26 @@ function sqlpage_map() {
         const tile_source = m.dataset.tile_source;
         const maxZoom = +m.dataset.max_zoom;
         const attribution = m.dataset.attribution;
-        const center = m.dataset.center.split(",").map(c => parseFloat(c));
         const map = L.map(m, { attributionControl: !!attribution });
-        map.setView(center, +m.dataset.zoom);
         L.tileLayer(tile_source, { attribution, maxZoom }).addTo(map);
+        const markers = L.featureGroup().addTo(map);
         for (const marker_elem of m.getElementsByClassName("marker")) {
-          setTimeout(addMarker, 0, marker_elem, map);
+          setTimeout(addMarker, 0, marker_elem, markers);
+        }
+        if (m.dataset.center) {
+          const center = m.dataset.center.split(",").map(c => parseFloat(c));
+          map.setView(center, +(m.dataset.zoom || 5));
+        } else {
+          // Show all the markers, once they have been added
+          map.setView([48, 3], 5);
+          setTimeout(fitMarkers, 0, map, markers, +(m.dataset.zoom || 15));
         }
       }
     }
+    function fitMarkers(map, markers, maxZoom) {
+      const bounds = markers.getBounds();
+      if (bounds.isValid()) map.fitBounds(bounds, { maxZoom, padding: [20, 20] });
+    }
     function addMarker(marker_elem, map) {
       const { dataset } = marker_elem;
       const options = {
@@ -97,6 +118,349 @@ function sqlpage_map() {
     }
 }
 
+function sqlpage_tabs() {
+    // Tabs without a custom link keep the other query parameters of the page,
+    // and the tab selected in the URL is active unless the page chose one explicitly
+    const params = new URLSearchParams(window.location.search);
+    for (const nav of document.getElementsByClassName("sqlpage-tabs")) {
+        const parameter = nav.dataset.parameter;
+        const tabs = [...nav.querySelectorAll("a[data-tab]")];
+        for (const tab of tabs) {
+            const tab_params = new URLSearchParams(params);
+            tab_params.set(parameter, tab.dataset.tab);
+            tab.href = "?" + tab_params.toString();
+        }
+        if (nav.querySelector(".nav-link.active") || tabs.length === 0) continue;
+        const selected = params.get(parameter);
+        const active = selected == null ? tabs[0] : tabs.find(t => t.dataset.tab === selected);
+        if (active) active.classList.add("active");
+    }
+}
+
+function sqlpage_modal() {
+    // Modals are opened by their trigger button, by a link to "#modal_id", or when the page loads
+    if (!window.bootstrap) return;
+    function show(modal) {
+        if (modal && modal.classList.contains("sqlpage-modal")) bootstrap.Modal.getOrCreateInstance(modal).show();
+    }
+    for (const modal of document.querySelectorAll(".sqlpage-modal[data-open]")) show(modal);
+    const open_from_hash = () => show(window.location.hash && document.getElementById(window.location.hash.slice(1)));
+    open_from_hash();
+    window.addEventListener("hashchange", open_from_hash);
+}
+
+function sqlpage_board() {
+    for (const board of document.getElementsByClassName("sqlpage-board")) {
+        const columns = board.querySelector(".board-columns");
+        const column_cards = (status) => {
+            let column = [...columns.children].find(c => c.dataset.status === status);
+            if (!column) {
+                // Statuses that are not listed in the columns property get a column at the end
+                column = columns.appendChild(document.createElement("div"));
+                column.className = "col-10 col-md-5 col-lg-3 board-column";
+                column.dataset.status = status;
+                column.appendChild(document.createElement("h3")).textContent = status;
+                column.lastChild.className = "mb-2";
+                column.appendChild(document.createElement("div")).className = "board-cards d-flex flex-column gap-2 pb-5";
+            }
+            return column.querySelector(".board-cards");
+        };
+        for (const item of [...board.querySelectorAll(".board-items > .board-item")]) {
+            column_cards(item.dataset.status).appendChild(item);
+        }
+        const action = board.dataset.action;
+        if (!action) continue;
+        let dragged = null;
+        board.addEventListener("dragstart", e => { dragged = e.target.closest(".board-item"); });
+        board.addEventListener("dragend", () => { dragged = null; });
+        board.addEventListener("dragover", e => { if (dragged && e.target.closest(".board-column")) e.preventDefault(); });
+        board.addEventListener("drop", async e => {
+            const column = e.target.closest(".board-column");
+            if (!dragged || !column || column.dataset.status === dragged.dataset.status) return;
+            e.preventDefault();
+            const item = dragged, previous = item.parentElement;
+            column.querySelector(".board-cards").appendChild(item);
+            const body = new URLSearchParams({ id: item.dataset.id, status: column.dataset.status });
+            if (board.dataset.csrf) body.append("_sqlpage_csrf", board.dataset.csrf);
+            const response = await fetch(action, { method: "POST", body }).catch(() => null);
+            if (response && response.ok) item.dataset.status = column.dataset.status;
+            else previous.appendChild(item);
+        });
+    }
+}
+
+function sqlpage_chunked_upload() {
+    const CHUNK_SIZE = 8 * 1024 * 1024;
+    const wait = ms => new Promise(resolve => setTimeout(resolve, ms));
+    for (const input of document.querySelectorAll("input[data-chunked-upload]")) {
+        const id_input = input.nextElementSibling;
+        const progress = id_input.nextElementSibling;
+        let pending = null;
+        // Sends the file in parts, and resumes where the server stopped after a failure
+        async function upload(file) {
+            progress.hidden = false;
+            progress.max = file.size;
+            progress.value = 0;
+            const created = await fetch("/sqlpage/uploads", {
+                method: "POST",
+                headers: { "Upload-Length": file.size, "Upload-Filename": encodeURIComponent(file.name) },
+            });
+            if (!created.ok) throw new Error(`Unable to upload ${file.name}: ${created.statusText}`);
+            const { id } = await created.json();
+            let offset = 0, failures = 0;
+            while (offset < file.size) {
+                try {
+                    const response = await fetch(`/sqlpage/uploads/${id}`, {
+                        method: "PATCH",
+                        headers: { "Upload-Offset": offset },
+                        body: file.slice(offset, offset + CHUNK_SIZE),
+                    });
+                    if (!response.ok && response.status !== 409) throw new Error(response.statusText);
+                    offset = (await response.json()).offset;
+                    failures = 0;
+                } catch (err) {
+                    if (++failures > 10) throw err;
+                    await wait(1000 * failures);
+                    const status = await fetch(`/sqlpage/uploads/${id}`).then(r => r.json()).catch(() => null);
+                    if (status) offset = status.offset;
+                }
+                progress.value = offset;
+            }
+            return id;
+        }
+        input.addEventListener("change", () => {
+            id_input.value = "";
+            input.setCustomValidity("");
+            const file = input.files[0];
+            if (!file) return;
+            const current = pending = upload(file)
+                .then(id => { if (pending === current) id_input.value = id; })
+                .catch(err => { if (pending === current) input.setCustomValidity(err.message); })
+                .finally(() => { if (pending === current) pending = null; });
+        });
+        input.form.addEventListener("submit", async event => {
+            if (!pending) return;
+            // The form is sent once the file is uploaded
+            event.preventDefault();
+            await pending;
+            if (input.reportValidity()) input.form.requestSubmit(event.submitter);
+        });
+    }
+}
+
+function sqlpage_carousel() {
+    for (const carousel of document.getElementsByClassName("sqlpage-carousel")) {
+        const images = [...carousel.querySelectorAll(".carousel-item img")];
+        if (carousel.hasAttribute("data-thumbnails")) {
+            const indicators = document.createElement("div");
+            indicators.className = "carousel-indicators carousel-indicators-thumb";
+            images.forEach((img, i) => {
+                const thumb = indicators.appendChild(document.createElement("button"));
+                thumb.type = "button";
+                thumb.className = "ratio ratio-4x3" + (i === 0 ? " active" : "");
+                thumb.style.backgroundImage = `url("${img.src}")`;
+                thumb.dataset.bsTarget = "#" + carousel.id;
+                thumb.dataset.bsSlideTo = i;
+                thumb.setAttribute("aria-label", img.alt);
+            });
+            carousel.appendChild(indicators);
+        }
+        if (carousel.hasAttribute("data-lightbox")) {
+            // Images without a link open in full screen when clicked
+            for (const img of images) {
+                if (img.closest("a")) continue;
+                img.style.cursor = "zoom-in";
+                img.addEventListener("click", () => show_lightbox(img));
+            }
+        }
+    }
+    function show_lightbox(img) {
+        const dialog = document.createElement("dialog");
+        dialog.className = "p-0 border-0 bg-transparent mw-100 mh-100";
+        const full = dialog.appendChild(document.createElement("img"));
+        full.src = img.src;
+        full.alt = img.alt;
+        full.style.cssText = "max-width: 95vw; max-height: 95vh; cursor: zoom-out";
+        dialog.addEventListener("click", () => dialog.close());
+        dialog.addEventListener("close", () => dialog.remove());
+        document.body.appendChild(dialog);
+        dialog.showModal();
+    }
+}
+
+function sqlpage_alert() {
+    // Toasts and alerts with a delay disappear automatically
+    for (const alert of document.querySelectorAll(".sqlpage-alert[data-delay]")) {
+        const delay = +alert.dataset.delay;
+        if (delay > 0) setTimeout(() => {
+            alert.classList.remove("show");
+            setTimeout(() => alert.remove(), 300);
+        }, delay);
+    }
+}
+
+function sqlpage_code() {
+    for (const button of document.getElementsByClassName("sqlpage-copy")) {
+        button.addEventListener("click", () => {
+            const code = button.parentElement.querySelector("code");
+            navigator.clipboard.writeText(code.textContent).then(() => {
+                button.classList.add("text-green");
+                setTimeout(() => button.classList.remove("text-green"), 1000);
+            });
+        });
+    }
+    // Syntax highlighting, with Prism. Grammars are loaded on demand by the autoloader plugin.
+    const first_code = document.querySelector("code[data-highlight-js]");
+    if (!first_code) return;
+    const base = first_code.dataset.highlightJs;
+    window.Prism = { manual: true };
+    const theme = document.createElement("link");
+    theme.rel = "stylesheet";
+    theme.href = base + (is_dark_theme() ? "themes/prism-tomorrow.min.css" : "themes/prism.min.css");
+    document.head.appendChild(theme);
+    for (const path of ["components/prism-core.min.js", "plugins/autoloader/prism-autoloader.min.js"]) {
+        const script = document.createElement("script");
+        script.src = base + path;
+        script.async = false;
+        script.onload = () => {
+            if (path.includes("autoloader")) {
+                for (const code of document.querySelectorAll("code[data-highlight-js]")) Prism.highlightElement(code);
+            }
+        };
+        document.head.appendChild(script);
+    }
+}
+
+function sqlpage_tree() {
+    // Rows are rendered as a flat list, and nested here under their parent
+    for (const tree of document.getElementsByClassName("sqlpage-tree")) {
+        const separator = tree.dataset.separator;
+        const items = [...tree.children];
+        const by_id = new Map();
+        for (const item of items) {
+            const path = item.dataset.path;
+            if (path != null) {
+                const parts = path.split(separator).filter(p => p);
+                item.dataset.id = parts.join(separator);
+                if (parts.length > 1) item.dataset.parent = parts.slice(0, -1).join(separator);
+                const title = item.querySelector(".sqlpage-tree-title");
+                if (!title.textContent) title.textContent = parts[parts.length - 1];
+            }
+            if (item.dataset.id != null) by_id.set(item.dataset.id, item);
+        }
+        for (const item of items) {
+            const parent = by_id.get(item.dataset.parent);
+            if (!parent || item.contains(parent)) continue;
+            let children = parent.querySelector(":scope > details > ul");
+            if (!children) {
+                const details = document.createElement("details");
+                details.open = tree.hasAttribute("data-expanded");
+                const summary = details.appendChild(document.createElement("summary"));
+                summary.append(...parent.childNodes);
+                children = details.appendChild(document.createElement("ul"));
+                children.className = "list-unstyled ps-4";
+                parent.appendChild(details);
+            }
+            children.appendChild(item);
+        }
+        // Show the active item
+        for (const active of tree.querySelectorAll("[data-active]")) {
+            for (let d = active.parentElement.closest("details"); d; d = d.parentElement.closest("details")) d.open = true;
+        }
+    }
+}
+
+function sqlpage_apply_theme() {
+    // Applies the theme saved by the theme toggle, or the theme of the operating system.
+    // This runs as soon as the script is loaded, to display the page directly in the right theme.
+    const root = document.documentElement;
+    const default_theme = root.dataset.sqlpageTheme;
+    if (!default_theme) return;
+    const saved = document.cookie.match(/(?:^|; )sqlpage_theme=(dark|light)/);
+    const theme = saved ? saved[1] : default_theme;
+    if (theme !== "auto") return root.setAttribute("data-bs-theme", theme);
+    const dark = matchMedia("(prefers-color-scheme: dark)");
+    root.setAttribute("data-bs-theme", dark.matches ? "dark" : "light");
+    dark.addEventListener("change", e => root.setAttribute("data-bs-theme", e.matches ? "dark" : "light"));
+}
+sqlpage_apply_theme();
+
+function sqlpage_theme_toggle() {
+    for (const button of document.querySelectorAll("[data-sqlpage-theme-toggle]")) {
+        button.addEventListener("click", () => {
+            const root = document.documentElement;
+            const theme = root.getAttribute("data-bs-theme") === "dark" ? "light" : "dark";
+            root.setAttribute("data-bs-theme", theme);
+            document.cookie = `sqlpage_theme=${theme}; path=/; max-age=31536000; SameSite=Lax`;
+        });
+    }
+}
+
+function sqlpage_sse() {
+    // Live updates sent by the sse component: each row updates the element with the same id
+    for (const elem of document.getElementsByClassName("sqlpage-sse")) {
+        const source = new EventSource(elem.dataset.url || window.location.href);
+        source.addEventListener("message", event => {
+            const row = JSON.parse(event.data);
+            const target = row.id != null && document.getElementById(row.id);
+            if (target) {
+                if (row.html != null) target.innerHTML = row.html;
+                else if (row.contents != null) target.textContent = row.contents;
+                if (row.value != null) target.value = row.value;
+            }
+            elem.dispatchEvent(new CustomEvent("sqlpage:update", { detail: row, bubbles: true }));
+        });
+        source.addEventListener("error", event => {
+            if (event.data) console.error("Error in the sse component:", JSON.parse(event.data).error);
+        });
+        source.addEventListener("denied", () => source.close());
+    }
+}
+
+function sqlpage_print() {
+    // Expand the collapsed sections, so that the printed page contains all the content
+    if (!is_print_mode()) return;
+    for (const details of document.querySelectorAll("details")) details.open = true;
+    for (const collapsed of document.querySelectorAll(".collapse:not(.navbar-collapse)")) collapsed.classList.add("show");
+}
+
+async function sqlpage_accessibility_audit() {
+    // In development, with the accessibility_audit configuration option, list the accessibility issues of the page
+    if (!document.body.hasAttribute("data-sqlpage-accessibility-audit") || !window.axe) return;
+    const { violations } = await axe.run(document, {
+        runOnly: ["wcag2a", "wcag2aa", "wcag21a", "wcag21aa", "best-practice"],
+    });
+    const overlay = document.createElement("details");
+    overlay.className = "sqlpage-accessibility-audit card position-fixed bottom-0 end-0 m-3 shadow";
+    const summary = overlay.appendChild(document.createElement("summary"));
+    summary.className = "card-header fw-bold " + (violations.length ? "text-danger" : "text-success");
+    summary.textContent = `Accessibility audit: ${violations.length} issue${violations.length === 1 ? "" : "s"}`;
+    const list = overlay.appendChild(document.createElement("ul"));
+    list.className = "card-body overflow-auto m-0";
+    for (const violation of violations) {
+        const item = list.appendChild(document.createElement("li"));
+        const link = item.appendChild(document.createElement("a"));
+        link.href = violation.helpUrl;
+        link.target = "_blank";
+        link.textContent = violation.help;
+        item.append(` (${violation.impact}, ${violation.nodes.length} element${violation.nodes.length === 1 ? "" : "s"})`);
+        for (const node of violation.nodes) {
+            const element = document.querySelector(node.target[0]);
+            if (element) element.classList.add("sqlpage-accessibility-issue");
+        }
+        console.warn("Accessibility issue:", violation.help, violation.nodes.map(n => n.target));
+    }
+    document.body.appendChild(overlay);
+}
+
+function is_print_mode() {
+    return document.body.classList.contains("sqlpage-print");
+}
+
+function is_dark_theme() {
+    return document.documentElement.getAttribute("data-bs-theme") === "dark";
+}
+
 function get_tabler_color(name) {
     return getComputedStyle(document.documentElement).getPropertyValue('--tblr-' + name);
 }
@@ -105,4 +469,16 @@ document.addEventListener('DOMContentLoaded', function () {
     sqlpage_table();
     sqlpage_chart();
     sqlpage_map();
+    sqlpage_tabs();
+    sqlpage_modal();
+    sqlpage_board();
+    sqlpage_chunked_upload();
+    sqlpage_carousel();
+    sqlpage_alert();
+    sqlpage_code();
+    sqlpage_tree();
+    sqlpage_theme_toggle();
+    sqlpage_sse();
+    sqlpage_print();
+    sqlpage_accessibility_audit();
 })
\ No newline at end of file
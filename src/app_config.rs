@@ -2,9 +2,20 @@ use anyhow::Context;
 use config::Config;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::path::PathBuf;
 
+use crate::webserver::access_log::AccessLogFormat;
+use crate::webserver::basic_auth::BasicAuth;
+use crate::webserver::forwarded::IpNetwork;
+use crate::webserver::ip_restrictions::IpRestriction;
+use crate::webserver::maintenance::Maintenance;
+use crate::webserver::page_cache::PageCacheRule;
+use crate::webserver::proxy::ProxyRoute;
+use crate::webserver::rate_limit::RateLimit;
+use crate::webserver::well_known::{RobotsTxt, SecurityTxt};
+
 #[cfg(not(feature = "lambda-web"))]
 const DEFAULT_DATABASE_DIR: &str = "sqlpage";
 #[cfg(not(feature = "lambda-web"))]
@@ -21,9 +32,20 @@ pub struct AppConfig {
     #[serde(default)]
     pub sqlite_extensions: Vec<String>,
 
-    #[serde(default, deserialize_with = "deserialize_socket_addr")]
-    pub listen_on: Option<SocketAddr>,
+    /// Addresses on which the web server listens: a single address, or a list of addresses,
+    /// such as a private HTTP port and a public HTTPS port.
+    #[serde(default, deserialize_with = "deserialize_socket_addrs")]
+    pub listen_on: Vec<SocketAddr>,
+    /// Overrides the port of the first address of `listen_on`
     pub port: Option<u16>,
+    /// Number of threads that accept connections and handle requests. Defaults to the number of CPU cores.
+    pub http_workers: Option<usize>,
+    /// Number of seconds an idle connection is kept open, waiting for the next request of the client.
+    /// 0 disables keep-alive. Defaults to 5 seconds.
+    pub keep_alive_seconds: Option<f64>,
+    /// Maximum number of connections handled at the same time, shared between the workers.
+    /// Defaults to 25000 per worker.
+    pub max_connections: Option<usize>,
 
     /// Number of times to retry connecting to the database after a failure when the server starts
     /// up. Retries will happen every 5 seconds. The default is 6 retries, which means the server
@@ -31,6 +53,11 @@ pub struct AppConfig {
     #[serde(default = "default_database_connection_retries")]
     pub database_connection_retries: u32,
 
+    /// Maximum number of seconds that a page can take to render. Slower pages are interrupted,
+    /// their queries are cancelled, and a `503 Service Unavailable` page is displayed if the page
+    /// had not started to be sent yet. There is no limit by default.
+    pub request_timeout_seconds: Option<f64>,
+
     /// Maximum number of seconds to wait before giving up when acquiring a database connection from the
     /// pool. The default is 10 seconds.
     #[serde(default = "default_database_connection_acquire_timeout_seconds")]
@@ -39,6 +66,26 @@ pub struct AppConfig {
     #[serde(default = "default_web_root")]
     pub web_root: PathBuf,
 
+    /// Directories containing component templates that override the built-in ones, such as a shared theme.
+    /// Relative paths are resolved from the web root. When several directories contain a template
+    /// with the same name, the first one wins. Templates in `sqlpage/templates` always take precedence.
+    #[serde(default)]
+    pub template_directories: Vec<PathBuf>,
+
+    /// Files served for the urls that end with a slash, in order of preference, such as
+    /// `["index.sql", "index.html"]`. Defaults to `["index.sql"]`.
+    #[serde(default = "default_index_files")]
+    pub index_files: Vec<String>,
+
+    /// What to do with the urls of directories that do not end with a slash: `add` redirects `/dir` to `/dir/`
+    /// (the default), `remove` redirects `/dir/` to `/dir`, and `ignore` serves the index file at both urls.
+    #[serde(default)]
+    pub trailing_slash: TrailingSlash,
+
+    /// Serve `page.sql` at the url `/page`, and redirect the urls ending with `.sql` to the url without extension.
+    #[serde(default)]
+    pub clean_urls: bool,
+
     /// Set to true to allow the `sqlpage.exec` function to be used in SQL queries.
     /// This should be enabled only if you trust the users writing SQL queries, since it gives
     /// them the ability to execute arbitrary shell commands on the server.
@@ -49,6 +96,15 @@ pub struct AppConfig {
     #[serde(default = "default_max_file_size")]
     pub max_uploaded_file_size: usize,
 
+    /// Maximum size in bytes of the files uploaded in several parts to `/sqlpage/uploads`,
+    /// which lets forms accept very large files over unreliable connections.
+    /// Chunked uploads are disabled when this is not set.
+    pub max_chunked_upload_size: Option<u64>,
+
+    /// Maximum total size in bytes of the chunked uploads stored at the same time.
+    /// Defaults to ten times `max_chunked_upload_size`.
+    pub max_chunked_uploads_total_size: Option<u64>,
+
     /// A domain name to use for the HTTPS server. If this is set, the server will perform all the necessary
     /// steps to set up an HTTPS server automatically. All you need to do is point your domain name to the
     /// server's IP address.
@@ -56,6 +112,8 @@ pub struct AppConfig {
     /// It will listen on port 443 for HTTPS connections,
     /// and will automatically request a certificate from Let's Encrypt
     /// using the ACME protocol (requesting a TLS-ALPN-01 challenge).
+    /// The certificate is renewed automatically before it expires.
+    /// HTTP-01 challenges are not supported, so port 443 has to be reachable from the internet.
     pub https_domain: Option<String>,
 
     /// The email address to use when requesting a certificate from Let's Encrypt.
@@ -70,6 +128,192 @@ pub struct AppConfig {
     #[serde(default = "default_https_acme_directory_url")]
     pub https_acme_directory_url: String,
 
+    /// Path to a PEM file containing the TLS certificate chain to serve HTTPS traffic with,
+    /// for when the certificate is not obtained automatically through `https_domain`.
+    /// Must be set together with `tls_key`.
+    pub tls_certificate: Option<PathBuf>,
+
+    /// Path to a PEM file containing the private key of `tls_certificate`.
+    pub tls_key: Option<PathBuf>,
+
+    /// Addresses of the reverse proxies allowed to set the client address and scheme of requests
+    /// in `X-Forwarded-For`, `X-Forwarded-Proto` and `Forwarded` headers. Each entry is an ip address
+    /// or a network in CIDR notation. Defaults to the loopback addresses.
+    #[serde(default = "default_trusted_proxies")]
+    pub trusted_proxies: Vec<IpNetwork>,
+
+    /// Limits on the number of requests that each client address can make to sql files,
+    /// for all the paths that start with a given prefix.
+    #[serde(default)]
+    pub rate_limits: Vec<RateLimit>,
+
+    /// Parts of the website that can only be accessed with a user name and password,
+    /// checked with HTTP basic authentication before any sql file is executed.
+    #[serde(default)]
+    pub basic_auth: Vec<BasicAuth>,
+
+    /// Parts of the website that can only be accessed from some client addresses
+    #[serde(default)]
+    pub ip_restrictions: Vec<IpRestriction>,
+
+    /// Answers all requests with a maintenance page, except the ones from some addresses and to some paths
+    #[serde(default)]
+    pub maintenance: Maintenance,
+
+    /// Path prefixes whose requests are forwarded to another web server, such as an api written in another language.
+    #[serde(default)]
+    pub proxy: Vec<ProxyRoute>,
+
+    /// Generates `/robots.txt`, with the paths crawlers may and may not visit and a link to the sitemap
+    pub robots_txt: Option<RobotsTxt>,
+
+    /// Generates `/.well-known/security.txt`, telling security researchers how to report vulnerabilities
+    pub security_txt: Option<SecurityTxt>,
+
+    /// Url of an OpenID Connect provider, such as `https://accounts.google.com`, used to log users in.
+    /// Its configuration is read from `<oidc_issuer_url>/.well-known/openid-configuration`.
+    pub oidc_issuer_url: Option<String>,
+
+    /// Identifier of the website at the OpenID Connect provider.
+    pub oidc_client_id: Option<String>,
+
+    /// Secret shared with the OpenID Connect provider.
+    pub oidc_client_secret: Option<String>,
+
+    /// Scopes requested from the OpenID Connect provider. Defaults to `openid email profile`.
+    #[serde(default = "default_oidc_scopes")]
+    pub oidc_scopes: String,
+
+    /// Public url of the website, like `https://example.com`, used to build the url to which the OpenID Connect
+    /// provider sends users back. Defaults to `https://<https_domain>`.
+    pub oidc_public_url: Option<String>,
+
+    /// Path prefixes that can only be accessed by users logged in with OpenID Connect. Defaults to
+    /// the whole website.
+    #[serde(default = "default_oidc_protected_paths")]
+    pub oidc_protected_paths: Vec<String>,
+
+    /// Pages that are kept in memory after they are rendered, and served to the next visitors without running
+    /// their queries again. Pages can also be cached with the `cache` component.
+    #[serde(default)]
+    pub page_cache: Vec<PageCacheRule>,
+
+    /// Path prefixes of the directories whose files are listed in a page when they have no index file,
+    /// such as `/files/`. Directory listings are disabled by default.
+    #[serde(default)]
+    pub directory_listing_paths: Vec<String>,
+
+    /// Path prefixes of the sql pages that are rendered completely before being sent, to compute their `ETag`.
+    /// Clients that already have the latest version of such a page get an empty `304 Not Modified` response.
+    #[serde(default)]
+    pub etag_paths: Vec<String>,
+
+    /// Path prefixes of the sql files that can be used as WebSocket endpoints, such as `/chat.sql`.
+    /// WebSocket connections are refused by default.
+    #[serde(default)]
+    pub websocket_paths: Vec<String>,
+
+    /// Origins of the other websites whose pages can open WebSocket connections, such as `https://example.com`.
+    /// Pages of the website itself always can.
+    #[serde(default)]
+    pub websocket_allowed_origins: Vec<String>,
+
+    /// Where to write the access log: `stdout`, `stderr`, or the path to a file. The file is opened again
+    /// when the configuration is reloaded, which allows rotating it. When this is not set, requests are
+    /// logged in the application log.
+    pub access_log: Option<String>,
+
+    /// Format of the lines of the access log: `common`, `combined` (the default), or `json`.
+    #[serde(default)]
+    pub access_log_format: AccessLogFormat,
+
+    /// Whether to compress the responses with gzip, brotli or zstd, when the browser supports it.
+    /// Defaults to true.
+    #[serde(default = "default_compress_responses")]
+    pub compress_responses: bool,
+
+    /// Value of the `Cache-Control` header of the static files of the website, except the ones with
+    /// a hash of their contents in their name, which are cached for a year. Defaults to `no-cache`,
+    /// which lets browsers keep the files, but check that they did not change before using them.
+    #[serde(default = "default_static_files_cache_control")]
+    pub static_files_cache_control: String,
+
+    /// Value of the `Content-Security-Policy` header. `{NONCE}` is replaced by a random value
+    /// generated for each page, that the shell adds to its scripts and styles. An empty string
+    /// disables the header.
+    #[serde(default = "default_content_security_policy")]
+    pub content_security_policy: String,
+
+    /// Websites served by the same process, for requests with a given `Host` header. Each one has
+    /// its own web root, and optionally its own database. Requests for other hosts are served from `web_root`.
+    #[serde(default)]
+    pub virtual_hosts: Vec<VirtualHost>,
+
+    /// Base URL of an S3-compatible object storage service, used by `sqlpage.render_to_storage`.
+    /// For instance `https://s3.eu-west-3.amazonaws.com`. Objects are addressed as `<endpoint>/<bucket>/<key>`.
+    pub storage_endpoint: Option<String>,
+
+    /// Region used to sign object storage requests. Defaults to `us-east-1`.
+    #[serde(default = "default_storage_region")]
+    pub storage_region: String,
+
+    /// Access key id used to authenticate to the object storage service.
+    pub storage_access_key_id: Option<String>,
+
+    /// Secret access key used to authenticate to the object storage service.
+    pub storage_secret_access_key: Option<String>,
+
+    /// Tables whose rows are soft-deleted. Queries that read from these tables automatically
+    /// ignore the rows where `soft_delete_column` is not null.
+    #[serde(default)]
+    pub soft_delete_tables: Vec<String>,
+
+    /// Name of the column that marks a row as deleted in `soft_delete_tables`. Defaults to `deleted_at`.
+    #[serde(default = "default_soft_delete_column")]
+    pub soft_delete_column: String,
+
+    /// Default values of the feature flags returned by `sqlpage.feature`, used when a flag is not
+    /// defined in the `sqlpage_feature_flags` table.
+    #[serde(default)]
+    pub feature_flags: HashMap<String, bool>,
+
+    /// Number of seconds during which the feature flags from the database are cached. Defaults to 5.
+    #[serde(default = "default_feature_flags_cache_seconds")]
+    pub feature_flags_cache_seconds: f64,
+
+    /// Secret used to sign session variables. If it is not set, a random secret is generated
+    /// when the server starts, and session variables do not survive restarts.
+    pub session_secret: Option<String>,
+
+    /// Where session variables are stored. `cookie` (the default) stores each variable in its own signed
+    /// cookie. `memory`, `database` (in the `sqlpage_sessions` table) and `redis://host:port` store them
+    /// on the server, and only send a signed session id to the browser.
+    #[serde(default = "default_session_store")]
+    pub session_store: String,
+
+    /// Number of seconds after which a session variable stored in a cookie, or an unused server-side session,
+    /// expires. Defaults to one day.
+    #[serde(default = "default_session_max_age_seconds")]
+    pub session_max_age_seconds: u64,
+
+    /// Whether to reject form submissions that do not contain the CSRF token of the user.
+    /// Defaults to true.
+    #[serde(default = "default_csrf_protection")]
+    pub csrf_protection: bool,
+
+    /// Command used by the `pdf` component to convert html pages to pdf, such as `wkhtmltopdf - -`.
+    /// It receives the html page on its standard input, and must write the pdf to its standard output.
+    pub pdf_converter: Option<String>,
+
+    /// Whether to show the details of errors to the users: the failing SQL query, the database error
+    /// message, and the backtrace. Defaults to true in development and false in production.
+    pub verbose_errors: Option<bool>,
+
+    /// Whether to check the accessibility of every page in the browser, and list the issues found
+    /// in an overlay. Only used in development.
+    #[serde(default)]
+    pub accessibility_audit: bool,
+
     /// Whether SQLPage is running in development or production mode. This is used to determine
     /// whether to show error messages to the user.
     #[serde(default)]
@@ -78,21 +322,78 @@ pub struct AppConfig {
 
 impl AppConfig {
     #[must_use]
-    pub fn listen_on(&self) -> SocketAddr {
-        let mut addr = self.listen_on.unwrap_or_else(|| {
-            if self.https_domain.is_some() {
-                SocketAddr::from(([0, 0, 0, 0], 443))
-            } else {
-                SocketAddr::from(([0, 0, 0, 0], 8080))
-            }
-        });
+    pub fn listen_on(&self) -> Vec<SocketAddr> {
+        let mut addrs = self.listen_on.clone();
+        if addrs.is_empty() {
+            addrs.push(
+                if self.https_domain.is_some() || self.tls_certificate.is_some() {
+                    SocketAddr::from(([0, 0, 0, 0], 443))
+                } else {
+                    SocketAddr::from(([0, 0, 0, 0], 8080))
+                },
+            );
+        }
         if let Some(port) = self.port {
-            addr.set_port(port);
+            addrs[0].set_port(port);
+        }
+        addrs
+    }
+
+    /// Whether HTTPS is served on this address of `listen_on()`. When HTTPS is configured,
+    /// it is served on the addresses with port 443, or on all the addresses if none of them uses port 443.
+    #[must_use]
+    pub fn is_https_address(&self, addr: &SocketAddr) -> bool {
+        if self.https_domain.is_none() && self.tls_certificate.is_none() {
+            return false;
+        }
+        addr.port() == 443 || self.listen_on().iter().all(|a| a.port() != 443)
+    }
+
+    /// The configuration of a virtual host: the same as this one, with the web root
+    /// and the database of the virtual host. The template directories are shared with the main website.
+    #[must_use]
+    pub fn for_virtual_host(&self, virtual_host: &VirtualHost) -> AppConfig {
+        let web_root = std::env::current_dir()
+            .unwrap_or_default()
+            .join(&self.web_root);
+        AppConfig {
+            web_root: virtual_host.web_root.clone(),
+            template_directories: self
+                .template_directories
+                .iter()
+                .map(|dir| web_root.join(dir))
+                .collect(),
+            database_url: virtual_host
+                .database_url
+                .clone()
+                .unwrap_or_else(|| self.database_url.clone()),
+            virtual_hosts: Vec::new(),
+            ..self.clone()
         }
-        addr
+    }
+
+    #[must_use]
+    pub fn verbose_errors(&self) -> bool {
+        self.verbose_errors
+            .unwrap_or_else(|| !self.environment.is_prod())
+    }
+
+    #[must_use]
+    pub fn accessibility_audit(&self) -> bool {
+        self.accessibility_audit && !self.environment.is_prod()
     }
 }
 
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct VirtualHost {
+    /// Host name of the requests served by this website, without the port, like `blog.example.com`
+    pub host: String,
+    /// Directory containing the files of this website
+    pub web_root: PathBuf,
+    /// Database of this website. Defaults to the main `database_url`, with a shared connection pool.
+    pub database_url: Option<String>,
+}
+
 pub fn load() -> anyhow::Result<AppConfig> {
     Config::builder()
         .add_source(config::File::with_name("sqlpage/sqlpage").required(false))
@@ -108,15 +409,29 @@ fn env_config() -> config::Environment {
         .try_parsing(true)
         .list_separator(" ")
         .with_list_parse_key("sqlite_extensions")
+        .with_list_parse_key("soft_delete_tables")
+        .with_list_parse_key("trusted_proxies")
+        .with_list_parse_key("template_directories")
 }
 
-fn deserialize_socket_addr<'de, D: Deserializer<'de>>(
+/// Accepts a single address, a list of addresses, or addresses separated by spaces
+fn deserialize_socket_addrs<'de, D: Deserializer<'de>>(
     deserializer: D,
-) -> Result<Option<SocketAddr>, D::Error> {
-    let host_str: Option<String> = Deserialize::deserialize(deserializer)?;
-    host_str
-        .map(|h| parse_socket_addr(&h).map_err(D::Error::custom))
-        .transpose()
+) -> Result<Vec<SocketAddr>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    let host_strs = match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(hosts) => hosts.split_whitespace().map(String::from).collect(),
+        OneOrMany::Many(hosts) => hosts,
+    };
+    host_strs
+        .iter()
+        .map(|h| parse_socket_addr(h).map_err(D::Error::custom))
+        .collect()
 }
 
 fn parse_socket_addr(host_str: &str) -> anyhow::Result<SocketAddr> {
@@ -126,6 +441,22 @@ fn parse_socket_addr(host_str: &str) -> anyhow::Result<SocketAddr> {
         .with_context(|| format!("host '{host_str}' does not resolve to an IP"))
 }
 
+fn default_session_store() -> String {
+    "cookie".to_string()
+}
+
+fn default_session_max_age_seconds() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_oidc_scopes() -> String {
+    "openid email profile".to_string()
+}
+
+fn default_oidc_protected_paths() -> Vec<String> {
+    vec!["/".to_string()]
+}
+
 fn default_database_url() -> String {
     let prefix = "sqlite://".to_owned();
 
@@ -182,10 +513,49 @@ fn default_https_certificate_cache_dir() -> PathBuf {
     default_web_root().join("sqlpage").join("https")
 }
 
+fn default_trusted_proxies() -> Vec<IpNetwork> {
+    ["127.0.0.0/8", "::1"]
+        .iter()
+        .map(|network| network.parse().expect("valid network"))
+        .collect()
+}
+
+fn default_compress_responses() -> bool {
+    true
+}
+
+fn default_csrf_protection() -> bool {
+    true
+}
+
+fn default_index_files() -> Vec<String> {
+    vec!["index.sql".to_string()]
+}
+
+fn default_static_files_cache_control() -> String {
+    "no-cache".to_string()
+}
+
+fn default_content_security_policy() -> String {
+    "script-src 'self' 'nonce-{NONCE}' https://cdn.jsdelivr.net".to_string()
+}
+
 fn default_https_acme_directory_url() -> String {
     "https://acme-v02.api.letsencrypt.org/directory".to_string()
 }
 
+fn default_storage_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_feature_flags_cache_seconds() -> f64 {
+    5.
+}
+
+fn default_soft_delete_column() -> String {
+    "deleted_at".to_string()
+}
+
 #[derive(Debug, Deserialize, PartialEq, Clone, Copy, Eq, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum DevOrProd {
@@ -199,6 +569,15 @@ impl DevOrProd {
     }
 }
 
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TrailingSlash {
+    #[default]
+    Add,
+    Remove,
+    Ignore,
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::AppConfig;
@@ -213,4 +592,25 @@ pub mod tests {
         )
         .unwrap()
     }
+
+    #[test]
+    fn test_listen_on_list() {
+        let config: AppConfig = serde_json::from_str(
+            r#"{"listen_on": ["127.0.0.1:8080", "0.0.0.0:443"], "tls_certificate": "cert.pem"}"#,
+        )
+        .unwrap();
+        let addrs = config.listen_on();
+        assert_eq!(addrs.len(), 2);
+        assert!(!config.is_https_address(&addrs[0]));
+        assert!(config.is_https_address(&addrs[1]));
+        let config: AppConfig =
+            serde_json::from_str(r#"{"listen_on": "127.0.0.1:8080 127.0.0.1:8081", "port": 9000}"#)
+                .unwrap();
+        let ports: Vec<u16> = config
+            .listen_on()
+            .iter()
+            .map(std::net::SocketAddr::port)
+            .collect();
+        assert_eq!(ports, [9000, 8081]);
+    }
 }
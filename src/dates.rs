@@ -0,0 +1,154 @@
+//! Date parsing and time zone conversions, used by the `format_date` template helper.
+//!
+//! Named time zones (such as `Europe/Paris`) come from the IANA time zone database embedded
+//! by `chrono-tz`, so they do not depend on the time zone files installed on the server.
+
+use anyhow::anyhow;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
+use serde_json::Value as JsonValue;
+
+/// Parses a date returned by the database: an RFC 3339 string, a `YYYY-MM-DD HH:MM:SS` string
+/// (interpreted as UTC), a `YYYY-MM-DD` date, or a number of seconds since the unix epoch.
+#[must_use]
+pub fn parse_date(date: &JsonValue) -> Option<DateTime<Utc>> {
+    match date {
+        JsonValue::Number(n) => n.as_i64().and_then(|ts| Utc.timestamp_opt(ts, 0).single()),
+        JsonValue::String(s) => {
+            let s = s.trim();
+            DateTime::parse_from_rfc3339(s)
+                .map(|d| d.with_timezone(&Utc))
+                .ok()
+                .or_else(|| {
+                    [
+                        "%Y-%m-%d %H:%M:%S%.f",
+                        "%Y-%m-%dT%H:%M:%S%.f",
+                        "%Y-%m-%d %H:%M",
+                    ]
+                    .iter()
+                    .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+                    .map(|d| d.and_utc())
+                })
+                .or_else(|| {
+                    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                        .ok()
+                        .and_then(|d| d.and_hms_opt(0, 0, 0))
+                        .map(|d| d.and_utc())
+                })
+        }
+        _ => None,
+    }
+}
+
+/// A time zone in which dates can be displayed
+#[derive(Debug, Clone, Copy)]
+pub enum Tz {
+    Fixed(FixedOffset),
+    Local,
+    Named(chrono_tz::Tz),
+}
+
+impl Tz {
+    /// Accepts `UTC`, `local` (the time zone of the server), a fixed offset such as `+02:00`,
+    /// or the name of a time zone from the IANA database, such as `Europe/Paris`.
+    pub fn from_name(name: &str) -> anyhow::Result<Self> {
+        if name.eq_ignore_ascii_case("utc") || name == "Z" {
+            return Ok(Self::Fixed(FixedOffset::east_opt(0).unwrap()));
+        }
+        if name.eq_ignore_ascii_case("local") {
+            return Ok(Self::Local);
+        }
+        if let Some(offset) = parse_fixed_offset(name) {
+            return Ok(Self::Fixed(offset));
+        }
+        name.parse()
+            .map(Self::Named)
+            .map_err(|e| anyhow!("Invalid time zone {name:?}: {e}"))
+    }
+
+    #[must_use]
+    pub fn offset_at(&self, date: &DateTime<Utc>) -> FixedOffset {
+        match self {
+            Self::Fixed(offset) => *offset,
+            Self::Local => chrono::Local.offset_from_utc_datetime(&date.naive_utc()),
+            Self::Named(zone) => zone.offset_from_utc_datetime(&date.naive_utc()).fix(),
+        }
+    }
+}
+
+/// `+02:00`, `-0530` or `+01`
+fn parse_fixed_offset(s: &str) -> Option<FixedOffset> {
+    let sign = match s.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let digits = s[1..].replace(':', "");
+    if !digits.bytes().all(|b| b.is_ascii_digit()) || !matches!(digits.len(), 2 | 4) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = if digits.len() == 4 {
+        digits[2..].parse().ok()?
+    } else {
+        0
+    };
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+#[test]
+fn test_parse_date() {
+    let expected = "2024-01-15T10:30:00+00:00";
+    for input in [
+        serde_json::json!("2024-01-15T10:30:00Z"),
+        serde_json::json!("2024-01-15T12:30:00+02:00"),
+        serde_json::json!("2024-01-15 10:30:00"),
+        serde_json::json!(1_705_314_600),
+    ] {
+        assert_eq!(parse_date(&input).unwrap().to_rfc3339(), expected);
+    }
+    assert!(parse_date(&serde_json::json!("not a date")).is_none());
+}
+
+#[test]
+fn test_named_time_zones() {
+    let offset_at = |tz: &str, date: &str| {
+        let date = DateTime::parse_from_rfc3339(date)
+            .unwrap()
+            .with_timezone(&Utc);
+        Tz::from_name(tz)
+            .unwrap()
+            .offset_at(&date)
+            .local_minus_utc()
+    };
+    assert_eq!(offset_at("Europe/Paris", "2024-01-15T12:00:00Z"), 3600);
+    assert_eq!(offset_at("Europe/Paris", "2024-07-15T12:00:00Z"), 7200);
+    // DST starts on the last sunday of March at 01:00 UTC, and ends on the last sunday of October
+    assert_eq!(offset_at("Europe/Paris", "2024-03-31T00:59:59Z"), 3600);
+    assert_eq!(offset_at("Europe/Paris", "2024-03-31T01:00:00Z"), 7200);
+    assert_eq!(offset_at("Europe/Paris", "2024-10-27T00:59:59Z"), 7200);
+    assert_eq!(offset_at("Europe/Paris", "2024-10-27T01:00:00Z"), 3600);
+    assert_eq!(
+        offset_at("Australia/Sydney", "2024-01-15T12:00:00Z"),
+        11 * 3600
+    );
+    assert_eq!(
+        offset_at("Australia/Sydney", "2024-07-15T12:00:00Z"),
+        10 * 3600
+    );
+    assert_eq!(offset_at("Asia/Kolkata", "2024-07-15T12:00:00Z"), 19800);
+}
+
+#[test]
+fn test_fixed_offsets() {
+    assert_eq!(
+        parse_fixed_offset("+02:00").unwrap().local_minus_utc(),
+        7200
+    );
+    assert_eq!(
+        parse_fixed_offset("-0530").unwrap().local_minus_utc(),
+        -19800
+    );
+    assert_eq!(parse_fixed_offset("+01").unwrap().local_minus_utc(), 3600);
+    assert!(parse_fixed_offset("Europe/Paris").is_none());
+    assert!(Tz::from_name("../../etc/passwd").is_err());
+}
@@ -91,6 +91,53 @@ impl FileSystem {
         }
     }
 
+    /// Returns the metadata of a local file in the web root, or `None` if it does not exist.
+    /// Files stored in the database are not taken into account.
+    pub async fn local_metadata(&self, path: &Path) -> anyhow::Result<Option<std::fs::Metadata>> {
+        let local_path = self.safe_local_path(path, false)?;
+        match tokio::fs::metadata(&local_path).await {
+            Ok(metadata) => Ok(Some(metadata)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Unable to read file metadata for {path:?}")),
+        }
+    }
+
+    /// Reads `length` bytes of a local file in the web root, starting at `start`,
+    /// without reading the rest of the file
+    pub async fn read_local_file_range(
+        &self,
+        path: &Path,
+        start: u64,
+        length: u64,
+    ) -> anyhow::Result<Vec<u8>> {
+        use tokio::io::{AsyncReadExt, AsyncSeekExt};
+        let local_path = self.safe_local_path(path, false)?;
+        let mut file = tokio::fs::File::open(&local_path)
+            .await
+            .with_context(|| format!("Unable to read local file {path:?}"))?;
+        file.seek(std::io::SeekFrom::Start(start)).await?;
+        let mut contents = Vec::with_capacity(usize::try_from(length)?);
+        file.take(length).read_to_end(&mut contents).await?;
+        Ok(contents)
+    }
+
+    /// Lists the names of the entries of a local directory in the web root,
+    /// with whether each of them is a directory.
+    pub async fn local_dir_entries(&self, path: &Path) -> anyhow::Result<Vec<(String, bool)>> {
+        let local_path = self.safe_local_path(path, false)?;
+        let mut read_dir = tokio::fs::read_dir(&local_path)
+            .await
+            .with_context(|| format!("Unable to list the files in {path:?}"))?;
+        let mut entries = Vec::new();
+        while let Some(entry) = read_dir.next_entry().await? {
+            if let Ok(name) = entry.file_name().into_string() {
+                entries.push((name, entry.file_type().await?.is_dir()));
+            }
+        }
+        entries.sort();
+        Ok(entries)
+    }
+
     fn safe_local_path(&self, path: &Path, priviledged: bool) -> anyhow::Result<PathBuf> {
         for (i, component) in path.components().enumerate() {
             if let Component::Normal(c) = component {
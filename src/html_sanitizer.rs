@@ -0,0 +1,370 @@
+//! An allow-list html sanitizer, used by the `html` component to display rich text
+//! that does not come from a trusted source.
+//!
+//! Tags and attributes that are not explicitly allowed are removed, and the contents of
+//! tags like `<script>` or `<style>` are dropped. Links can only point to http, https, mailto
+//! and tel URLs, or to relative URLs. Attribute values are always re-escaped.
+
+use std::fmt::Write;
+
+/// How much html is allowed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanitizeLevel {
+    /// The html is trusted, and returned unchanged
+    None,
+    /// Only text formatting: paragraphs, lists, titles, emphasis, code...
+    Strict,
+    /// Text formatting, links, images and tables
+    Basic,
+}
+
+impl SanitizeLevel {
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Some(Self::None),
+            "strict" => Some(Self::Strict),
+            "basic" => Some(Self::Basic),
+            _ => None,
+        }
+    }
+
+    fn allows_tag(self, tag: &str) -> bool {
+        const STRICT: &[&str] = &[
+            "abbr",
+            "b",
+            "blockquote",
+            "br",
+            "code",
+            "del",
+            "em",
+            "h1",
+            "h2",
+            "h3",
+            "h4",
+            "h5",
+            "h6",
+            "hr",
+            "i",
+            "ins",
+            "kbd",
+            "li",
+            "mark",
+            "ol",
+            "p",
+            "pre",
+            "q",
+            "s",
+            "small",
+            "span",
+            "strong",
+            "sub",
+            "sup",
+            "u",
+            "ul",
+        ];
+        const BASIC: &[&str] = &[
+            "a",
+            "caption",
+            "dd",
+            "div",
+            "dl",
+            "dt",
+            "figcaption",
+            "figure",
+            "img",
+            "table",
+            "tbody",
+            "td",
+            "tfoot",
+            "th",
+            "thead",
+            "tr",
+        ];
+        match self {
+            Self::None => true,
+            Self::Strict => STRICT.contains(&tag),
+            Self::Basic => STRICT.contains(&tag) || BASIC.contains(&tag),
+        }
+    }
+
+    /// `class` and `style` are never allowed, because the classes of the page could be used
+    /// to make sanitized html cover the rest of the page, or look like its trusted parts
+    fn allows_attribute(self, tag: &str, attribute: &str) -> bool {
+        matches!(attribute, "title" | "lang" | "dir")
+            || (self == Self::Basic
+                && matches!(
+                    (tag, attribute),
+                    ("a", "href")
+                        | ("img", "src" | "alt" | "width" | "height")
+                        | ("td" | "th", "colspan" | "rowspan")
+                ))
+            || matches!((tag, attribute), ("ol", "start"))
+    }
+}
+
+/// Elements whose contents are removed with them
+const DROPPED_CONTENTS: &[&str] = &[
+    "script", "style", "iframe", "object", "embed", "template", "noscript", "textarea", "title",
+    "xmp", "svg", "math", "select",
+];
+
+const VOID_ELEMENTS: &[&str] = &["br", "hr", "img", "wbr", "input", "meta", "link", "source"];
+
+#[must_use]
+pub fn sanitize_html(html: &str, level: SanitizeLevel) -> String {
+    if level == SanitizeLevel::None {
+        return html.to_string();
+    }
+    let mut out = String::with_capacity(html.len());
+    let mut open_tags: Vec<String> = Vec::new();
+    let mut rest = html;
+    while let Some(lt) = rest.find('<') {
+        out.push_str(&rest[..lt]);
+        rest = &rest[lt..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(tag) = parse_tag(rest) else {
+            out.push_str("&lt;");
+            rest = &rest[1..];
+            continue;
+        };
+        rest = &rest[tag.len..];
+        if tag.closing {
+            if let Some(pos) = open_tags.iter().rposition(|t| *t == tag.name) {
+                for name in open_tags.drain(pos..).rev() {
+                    let _ = write!(out, "</{name}>");
+                }
+            }
+        } else if DROPPED_CONTENTS.contains(&tag.name.as_str()) {
+            if !tag.self_closing {
+                rest = skip_until_closing(rest, &tag.name);
+            }
+        } else if level.allows_tag(&tag.name) {
+            out.push('<');
+            out.push_str(&tag.name);
+            for (name, value) in &tag.attributes {
+                if !level.allows_attribute(&tag.name, name)
+                    || (matches!(name.as_str(), "href" | "src") && !is_safe_url(value))
+                {
+                    continue;
+                }
+                let _ = write!(out, " {name}=\"{}\"", escape_attribute(value));
+            }
+            out.push('>');
+            if !VOID_ELEMENTS.contains(&tag.name.as_str()) && !tag.self_closing {
+                open_tags.push(tag.name);
+            }
+        }
+    }
+    out.push_str(rest);
+    for name in open_tags.iter().rev() {
+        let _ = write!(out, "</{name}>");
+    }
+    out
+}
+
+struct Tag {
+    name: String,
+    attributes: Vec<(String, String)>,
+    closing: bool,
+    self_closing: bool,
+    /// Length of the tag in the source, including the angle brackets
+    len: usize,
+}
+
+/// Parses the tag at the start of `s`, that starts with '<'
+fn parse_tag(s: &str) -> Option<Tag> {
+    let bytes = s.as_bytes();
+    let mut i = 1;
+    let closing = bytes.get(i) == Some(&b'/');
+    if closing {
+        i += 1;
+    }
+    if !bytes.get(i)?.is_ascii_alphabetic() {
+        return None;
+    }
+    let name_start = i;
+    while bytes.get(i).is_some_and(u8::is_ascii_alphanumeric) {
+        i += 1;
+    }
+    let name = s[name_start..i].to_ascii_lowercase();
+    let mut attributes = Vec::new();
+    let mut self_closing = false;
+    loop {
+        while bytes
+            .get(i)
+            .is_some_and(|b| b.is_ascii_whitespace() || *b == b'/')
+        {
+            self_closing = bytes[i] == b'/';
+            i += 1;
+        }
+        if *bytes.get(i)? == b'>' {
+            break;
+        }
+        self_closing = false;
+        let attr_start = i;
+        while bytes
+            .get(i)
+            .is_some_and(|b| !b.is_ascii_whitespace() && !b"/>=".contains(b))
+        {
+            i += 1;
+        }
+        let attr_name = s[attr_start..i].to_ascii_lowercase();
+        while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+            i += 1;
+        }
+        let mut value = String::new();
+        if bytes.get(i) == Some(&b'=') {
+            i += 1;
+            while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+                i += 1;
+            }
+            let quote = *bytes.get(i)?;
+            if quote == b'"' || quote == b'\'' {
+                let value_end = i + 1 + s[i + 1..].find(char::from(quote))?;
+                value = decode_entities(&s[i + 1..value_end]);
+                i = value_end + 1;
+            } else {
+                let start = i;
+                while bytes
+                    .get(i)
+                    .is_some_and(|b| !b.is_ascii_whitespace() && *b != b'>')
+                {
+                    i += 1;
+                }
+                value = decode_entities(&s[start..i]);
+            }
+        }
+        if !attr_name.is_empty() && !closing {
+            attributes.push((attr_name, value));
+        }
+    }
+    Some(Tag {
+        name,
+        attributes,
+        closing,
+        self_closing,
+        len: i + 1,
+    })
+}
+
+/// The rest of the source after the closing tag of an element whose contents are dropped
+fn skip_until_closing<'a>(s: &'a str, name: &str) -> &'a str {
+    let lower = s.to_ascii_lowercase();
+    let closing = format!("</{name}");
+    match lower.find(&closing) {
+        Some(pos) => s[pos..].find('>').map_or("", |end| &s[pos + end + 1..]),
+        None => "",
+    }
+}
+
+fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp + 1..];
+        let end = rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '#')
+            .unwrap_or(rest.len());
+        let entity = &rest[..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "colon" => Some(':'),
+            "Tab" => Some('\t'),
+            "NewLine" => Some('\n'),
+            _ => entity.strip_prefix('#').and_then(|num| {
+                let code = match num.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => num.parse().ok(),
+                };
+                code.and_then(char::from_u32)
+            }),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = rest[end..].strip_prefix(';').unwrap_or(&rest[end..]);
+            }
+            None => out.push('&'),
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn is_safe_url(url: &str) -> bool {
+    // Browsers ignore whitespace and control characters in URL schemes
+    let cleaned: String = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match cleaned.find([':', '/', '?', '#']) {
+        Some(pos) if cleaned.as_bytes()[pos] == b':' => {
+            matches!(&cleaned[..pos], "http" | "https" | "mailto" | "tel")
+        }
+        _ => true,
+    }
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[test]
+fn test_sanitize_html() {
+    let basic = |html| sanitize_html(html, SanitizeLevel::Basic);
+    assert_eq!(basic("<b>bold</b> <i>it</i>"), "<b>bold</b> <i>it</i>");
+    assert_eq!(basic("a<script>alert(1)</script>b"), "ab");
+    assert_eq!(basic("<SCRIPT src=x></SCRIPT >ok"), "ok");
+    assert_eq!(
+        basic(r#"<a href="javascript:alert(1)" onclick="x()">link</a>"#),
+        "<a>link</a>"
+    );
+    assert_eq!(
+        basic(r#"<a href="jav&#x09;ascript&colon;alert(1)">x</a>"#),
+        "<a>x</a>"
+    );
+    assert_eq!(
+        basic("<a href='/page?a=1&amp;b=2'>x</a>"),
+        r#"<a href="/page?a=1&amp;b=2">x</a>"#
+    );
+    assert_eq!(
+        basic(r#"<img src=https://example.com/a.png alt="a &quot;b&quot;" onerror=alert(1)>"#),
+        r#"<img src="https://example.com/a.png" alt="a &quot;b&quot;">"#
+    );
+    assert_eq!(
+        basic("<p>unclosed <em>tags"),
+        "<p>unclosed <em>tags</em></p>"
+    );
+    assert_eq!(basic("<div><p>a</div>b"), "<div><p>a</p></div>b");
+    assert_eq!(
+        basic("1 < 2 <!-- comment --> <unknown>x</unknown>"),
+        "1 &lt; 2  x"
+    );
+    assert_eq!(basic("<p style=\"position:fixed\">x</p>"), "<p>x</p>");
+    assert_eq!(
+        basic("<div class=\"modal-backdrop show\" title=\"t\">x</div>"),
+        "<div title=\"t\">x</div>"
+    );
+    assert_eq!(
+        sanitize_html("<a href=\"/x\">x</a><img src=x>", SanitizeLevel::Strict),
+        "x"
+    );
+    assert_eq!(
+        sanitize_html("<script>ok</script>", SanitizeLevel::None),
+        "<script>ok</script>"
+    );
+}
@@ -4,8 +4,11 @@
 extern crate core;
 
 pub mod app_config;
+pub mod dates;
 pub mod file_cache;
 pub mod filesystem;
+pub mod html_sanitizer;
+pub mod qr_code;
 pub mod render;
 pub mod templates;
 pub mod utils;
@@ -13,9 +16,21 @@ pub mod webserver;
 
 use crate::app_config::AppConfig;
 use crate::filesystem::FileSystem;
+use crate::webserver::access_log::AccessLog;
+use crate::webserver::basic_auth::BasicAuthCache;
+use crate::webserver::database::custom_functions::{CustomFunctions, SqlPageFunction};
+use crate::webserver::database::feature_flags::FeatureFlags;
 use crate::webserver::database::ParsedSqlFile;
+use crate::webserver::maintenance::MaintenanceSwitch;
+use crate::webserver::oidc::OidcProvider;
+use crate::webserver::page_cache::PageCache;
+use crate::webserver::rate_limit::RateLimiter;
+use crate::webserver::server_sent_events::Notifications;
+use crate::webserver::session_store::SessionStore;
+use actix_web::web;
 use file_cache::FileCache;
 use std::path::PathBuf;
+use std::sync::Arc;
 use templates::AllTemplates;
 use webserver::Database;
 
@@ -29,18 +44,127 @@ pub struct AppState {
     sql_file_cache: FileCache<ParsedSqlFile>,
     file_system: FileSystem,
     config: AppConfig,
+    session_key: Vec<u8>,
+    /// Where session variables are stored, when they are not stored in cookies
+    session_store: Option<Arc<SessionStore>>,
+    feature_flags: FeatureFlags,
+    rate_limiter: RateLimiter,
+    basic_auth_cache: BasicAuthCache,
+    oidc_provider: OidcProvider,
+    page_cache: PageCache,
+    maintenance_switch: MaintenanceSwitch,
+    /// Postgres notifications received for the `sse` component
+    notifications: Notifications,
+    /// Functions registered by the program that embeds SQLPage, shared by the virtual hosts
+    custom_functions: Arc<CustomFunctions>,
+    /// Shared by the virtual hosts, which do not have their own
+    access_log: Option<Arc<AccessLog>>,
+    /// Websites served for other `Host` headers, with their own state
+    pub virtual_hosts: Vec<(String, web::Data<AppState>)>,
 }
 
 impl AppState {
     pub async fn init(config: &AppConfig) -> anyhow::Result<Self> {
+        Self::builder(config).build().await
+    }
+
+    /// Creates the state of a website served by a program that embeds SQLPage,
+    /// with functions written in rust that sql files can call.
+    #[must_use]
+    pub fn builder(config: &AppConfig) -> AppStateBuilder<'_> {
+        AppStateBuilder {
+            config,
+            custom_functions: CustomFunctions::new(),
+        }
+    }
+
+    /// Creates a new state for a new configuration, with empty caches. The database connection
+    /// pools of the current state are kept when the database urls did not change.
+    pub async fn reload(&self, config: &AppConfig) -> anyhow::Result<Self> {
+        Self::init_reusing(config, Some(self), Arc::clone(&self.custom_functions)).await
+    }
+
+    async fn init_reusing(
+        config: &AppConfig,
+        previous: Option<&AppState>,
+        custom_functions: Arc<CustomFunctions>,
+    ) -> anyhow::Result<Self> {
         // Connect to the database
-        let db = Database::init(config).await?;
-        let all_templates = AllTemplates::init()?;
+        let db = connect(config, previous).await?;
+        let mut virtual_hosts = Vec::with_capacity(config.virtual_hosts.len());
+        for virtual_host in &config.virtual_hosts {
+            let host = virtual_host.host.to_ascii_lowercase();
+            let host_config = config.for_virtual_host(virtual_host);
+            let host_db = if virtual_host.database_url.is_some() {
+                let previous_host = previous.and_then(|p| p.virtual_host(&host));
+                connect(&host_config, previous_host).await?
+            } else {
+                db.clone()
+            };
+            let mut state = Self::init_site(
+                &host_config,
+                host_db,
+                Vec::new(),
+                Arc::clone(&custom_functions),
+            )
+            .await?;
+            state.keep_sessions(previous.and_then(|p| p.virtual_host(&host)));
+            virtual_hosts.push((host, web::Data::new(state)));
+        }
+        let mut state = Self::init_site(config, db, virtual_hosts, custom_functions).await?;
+        state.keep_sessions(previous);
+        state.access_log = AccessLog::from_config(config)?.map(Arc::new);
+        Ok(state)
+    }
+
+    /// Randomly generated session keys and in-memory sessions are kept across reloads,
+    /// so that sessions are not lost
+    fn keep_sessions(&mut self, previous: Option<&AppState>) {
+        let Some(previous) = previous else {
+            return;
+        };
+        if previous.config.session_secret.is_none() && self.config.session_secret.is_none() {
+            self.session_key.clone_from(&previous.session_key);
+        }
+        let is_memory =
+            |state: &AppState| state.session_store.as_ref().is_some_and(|s| s.is_memory());
+        if is_memory(self) && is_memory(previous) {
+            self.session_store.clone_from(&previous.session_store);
+        }
+    }
+
+    fn virtual_host(&self, host: &str) -> Option<&AppState> {
+        self.virtual_hosts
+            .iter()
+            .find_map(|(h, state)| (h == host).then_some(state.as_ref()))
+    }
+
+    /// Applies the migrations to the database, and to the databases of the virtual hosts
+    pub async fn apply_migrations(&self) -> anyhow::Result<()> {
+        webserver::database::migrations::apply(&self.db).await?;
+        let virtual_hosts = self.config.virtual_hosts.iter().zip(&self.virtual_hosts);
+        for (virtual_host, (_, host_state)) in virtual_hosts {
+            // Virtual hosts without their own database share the main one, which is already migrated
+            if virtual_host.database_url.is_some() {
+                webserver::database::migrations::apply(&host_state.db).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn init_site(
+        config: &AppConfig,
+        db: Database,
+        virtual_hosts: Vec<(String, web::Data<AppState>)>,
+        custom_functions: Arc<CustomFunctions>,
+    ) -> anyhow::Result<Self> {
+        let all_templates = AllTemplates::init(config)?;
         let mut sql_file_cache = FileCache::new();
         let file_system = FileSystem::init(&config.web_root, &db).await;
+        let session_store = SessionStore::from_config(config, &db).await?.map(Arc::new);
         sql_file_cache.add_static(
             PathBuf::from("index.sql"),
-            ParsedSqlFile::new(&db, include_str!("../index.sql")),
+            ParsedSqlFile::new(&db, include_str!("../index.sql"), config),
         );
         Ok(AppState {
             db,
@@ -48,10 +172,54 @@ impl AppState {
             sql_file_cache,
             file_system,
             config: config.clone(),
+            session_key: webserver::session::session_key(config),
+            session_store,
+            feature_flags: FeatureFlags::default(),
+            rate_limiter: RateLimiter::default(),
+            basic_auth_cache: BasicAuthCache::default(),
+            oidc_provider: OidcProvider::default(),
+            page_cache: PageCache::default(),
+            maintenance_switch: MaintenanceSwitch::default(),
+            notifications: Notifications::default(),
+            custom_functions,
+            access_log: None,
+            virtual_hosts,
         })
     }
 }
 
+/// Builds an [`AppState`] with custom functions, created with [`AppState::builder`]
+pub struct AppStateBuilder<'a> {
+    config: &'a AppConfig,
+    custom_functions: CustomFunctions,
+}
+
+impl AppStateBuilder<'_> {
+    /// Makes `sqlpage.<name>(...)` call the given function in sql files.
+    /// Built-in functions cannot be replaced.
+    #[must_use]
+    pub fn function(
+        mut self,
+        name: impl Into<String>,
+        function: impl SqlPageFunction + 'static,
+    ) -> Self {
+        self.custom_functions
+            .insert(name.into(), Arc::new(function));
+        self
+    }
+
+    pub async fn build(self) -> anyhow::Result<AppState> {
+        AppState::init_reusing(self.config, None, Arc::new(self.custom_functions)).await
+    }
+}
+
+async fn connect(config: &AppConfig, previous: Option<&AppState>) -> anyhow::Result<Database> {
+    match previous.filter(|p| p.config.database_url == config.database_url) {
+        Some(previous) => Ok(previous.db.clone()),
+        None => Database::init(config).await,
+    }
+}
+
 impl std::fmt::Debug for AppState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AppState").finish()
@@ -16,7 +16,7 @@ async fn start() -> anyhow::Result<()> {
     let app_config = app_config::load()?;
     log::debug!("Starting with the following configuration: {app_config:#?}");
     let state = AppState::init(&app_config).await?;
-    webserver::database::migrations::apply(&state.db).await?;
+    state.apply_migrations().await?;
     log::debug!("Starting server...");
     let (r, _) = tokio::join!(
         webserver::http::run_server(&app_config, state),
@@ -26,23 +26,34 @@ async fn start() -> anyhow::Result<()> {
 }
 
 async fn log_welcome_message(config: &AppConfig) {
-    // Don't show 0.0.0.0 as the host, show the actual IP address
-    let http_addr = config.listen_on().to_string().replace(
-        "0.0.0.0",
-        std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST)
-            .to_string()
-            .as_str(),
-    );
+    let addresses = config
+        .listen_on()
+        .iter()
+        .map(|addr| {
+            if let (Some(domain), true) = (&config.https_domain, config.is_https_address(addr)) {
+                return format!("https://{domain}");
+            }
+            let scheme = if config.is_https_address(addr) {
+                "https"
+            } else {
+                "http"
+            };
+            // Don't show 0.0.0.0 as the host, show the actual IP address
+            let addr = addr.to_string().replace(
+                "0.0.0.0",
+                std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST)
+                    .to_string()
+                    .as_str(),
+            );
+            format!("{scheme}://{addr}")
+        })
+        .collect::<Vec<_>>();
 
     log::info!(
         "Server started successfully.
     SQLPage is now running on {}
     You can write your website's code in .sql files in {}.",
-        if let Some(domain) = &config.https_domain {
-            format!("https://{}", domain)
-        } else {
-            format!("http://{}", http_addr)
-        },
+        addresses.join(" and "),
         config.web_root.display()
     );
 }
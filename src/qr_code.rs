@@ -0,0 +1,500 @@
+//! A minimal QR code encoder, used by the `qr_code` component to render QR codes as inline SVG.
+//!
+//! Only the byte mode is supported, which can encode any text.
+//! The implementation follows the ISO/IEC 18004 standard,
+//! and is adapted from Project Nayuki's QR code generator (MIT license).
+
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ErrorCorrection {
+    Low,
+    Medium,
+    Quartile,
+    High,
+}
+
+impl ErrorCorrection {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "L" | "LOW" => Some(Self::Low),
+            "M" | "MEDIUM" => Some(Self::Medium),
+            "Q" | "QUARTILE" => Some(Self::Quartile),
+            "H" | "HIGH" => Some(Self::High),
+            _ => None,
+        }
+    }
+
+    fn ordinal(self) -> usize {
+        self as usize
+    }
+
+    fn format_bits(self) -> u32 {
+        match self {
+            Self::Low => 1,
+            Self::Medium => 0,
+            Self::Quartile => 3,
+            Self::High => 2,
+        }
+    }
+}
+
+const MAX_VERSION: usize = 40;
+
+#[rustfmt::skip]
+const ECC_CODEWORDS_PER_BLOCK: [[u8; MAX_VERSION + 1]; 4] = [
+    [0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28, 28, 30, 30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30],
+    [0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28],
+    [0, 13, 22, 18, 26, 18, 24, 18, 22, 20, 24, 28, 26, 24, 20, 30, 24, 28, 28, 26, 30, 28, 30, 30, 30, 30, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30],
+    [0, 17, 28, 22, 16, 22, 28, 26, 26, 24, 28, 24, 28, 22, 24, 24, 30, 28, 28, 26, 28, 30, 24, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30],
+];
+
+#[rustfmt::skip]
+const NUM_ERROR_CORRECTION_BLOCKS: [[u8; MAX_VERSION + 1]; 4] = [
+    [0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12, 13, 14, 15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25],
+    [0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23, 25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49],
+    [0, 1, 1, 2, 2, 4, 4, 6, 6, 8, 8, 8, 10, 12, 16, 12, 17, 16, 18, 21, 20, 23, 23, 25, 27, 29, 34, 34, 35, 38, 40, 43, 45, 48, 51, 53, 56, 59, 62, 65, 68],
+    [0, 1, 1, 2, 4, 4, 4, 5, 6, 8, 8, 11, 11, 16, 16, 18, 16, 19, 21, 25, 25, 25, 34, 30, 32, 35, 37, 40, 42, 45, 48, 51, 54, 57, 60, 63, 66, 70, 74, 77, 81],
+];
+
+/// A square grid of dark and light modules
+pub(crate) struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+    is_function: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encodes the given text in the smallest QR code that can hold it
+    pub(crate) fn encode(text: &str, ecl: ErrorCorrection) -> anyhow::Result<Self> {
+        let data = text.as_bytes();
+        let version = (1..=MAX_VERSION)
+            .find(|&v| data_bits_needed(v, data.len()) <= num_data_codewords(v, ecl) * 8)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "The text is too long to be encoded in a QR code ({} bytes)",
+                    data.len()
+                )
+            })?;
+        let mut bits = BitBuffer::default();
+        bits.append(0b0100, 4);
+        bits.append(data.len(), char_count_bits(version));
+        for &b in data {
+            bits.append(b.into(), 8);
+        }
+        let capacity = num_data_codewords(version, ecl) * 8;
+        bits.append(0, (capacity - bits.0.len()).min(4));
+        bits.append(0, (8 - bits.0.len() % 8) % 8);
+        for &pad in [0xEC, 0x11].iter().cycle() {
+            if bits.0.len() >= capacity {
+                break;
+            }
+            bits.append(pad, 8);
+        }
+        let codewords: Vec<u8> = bits
+            .0
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, &b| (acc << 1) | u8::from(b)))
+            .collect();
+
+        let size = version * 4 + 17;
+        let mut qr = Self {
+            size,
+            modules: vec![false; size * size],
+            is_function: vec![false; size * size],
+        };
+        qr.draw_function_patterns(version, ecl);
+        qr.draw_codewords(&add_ecc_and_interleave(&codewords, version, ecl));
+        let best_mask = (0..8)
+            .min_by_key(|&mask| {
+                qr.apply_mask(mask);
+                qr.draw_format_bits(ecl, mask);
+                let penalty = qr.penalty_score();
+                qr.apply_mask(mask);
+                penalty
+            })
+            .unwrap_or_default();
+        qr.apply_mask(best_mask);
+        qr.draw_format_bits(ecl, best_mask);
+        Ok(qr)
+    }
+
+    /// Renders the QR code as a standalone SVG image, with a quiet zone of 4 modules around it
+    pub(crate) fn to_svg(&self) -> String {
+        const BORDER: usize = 4;
+        let dimension = self.size + 2 * BORDER;
+        let mut path = String::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if self.get(x, y) {
+                    let _ = write!(path, "M{},{}h1v1h-1z", x + BORDER, y + BORDER);
+                }
+            }
+        }
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {dimension} {dimension}\" shape-rendering=\"crispEdges\">\
+            <rect width=\"100%\" height=\"100%\" fill=\"#fff\"/><path d=\"{path}\" fill=\"#000\"/></svg>"
+        )
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.is_function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self, version: usize, ecl: ErrorCorrection) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+        for (x, y) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            self.draw_finder_pattern(x, y);
+        }
+        let positions = alignment_pattern_positions(version);
+        let last = positions.len().saturating_sub(1);
+        for (i, &x) in positions.iter().enumerate() {
+            for (j, &y) in positions.iter().enumerate() {
+                // The corners that overlap with finder patterns have no alignment pattern
+                let is_corner =
+                    matches!((i, j), (0, 0)) || (i, j) == (0, last) || (i, j) == (last, 0);
+                if !is_corner {
+                    self.draw_alignment_pattern(x, y);
+                }
+            }
+        }
+        // Reserve the format area, it is drawn again once the mask is known
+        self.draw_format_bits(ecl, 0);
+        self.draw_version(version);
+    }
+
+    fn draw_finder_pattern(&mut self, x: usize, y: usize) {
+        for dy in -4_isize..=4 {
+            for dx in -4_isize..=4 {
+                let dist = dx.abs().max(dy.abs());
+                let (Some(xx), Some(yy)) = (x.checked_add_signed(dx), y.checked_add_signed(dy))
+                else {
+                    continue;
+                };
+                if xx < self.size && yy < self.size {
+                    self.set_function(xx, yy, dist != 2 && dist != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment_pattern(&mut self, x: usize, y: usize) {
+        for dy in 0_usize..5 {
+            for dx in 0_usize..5 {
+                let dist = dx.abs_diff(2).max(dy.abs_diff(2));
+                self.set_function(x + dx - 2, y + dy - 2, dist != 1);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, ecl: ErrorCorrection, mask: u32) {
+        let data = ecl.format_bits() << 3 | mask;
+        let mut rem = data;
+        for _ in 0..10 {
+            rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+        }
+        let bits = (data << 10 | rem) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 != 0;
+        let size = self.size;
+        for i in 0..=5 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    fn draw_version(&mut self, version: usize) {
+        if version < 7 {
+            return;
+        }
+        let mut rem = version;
+        for _ in 0..12 {
+            rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+        }
+        let bits = version << 12 | rem;
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 != 0;
+            let a = self.size - 11 + i % 3;
+            let b = i / 3;
+            self.set_function(a, b, dark);
+            self.set_function(b, a, dark);
+        }
+    }
+
+    /// Places the data in a zigzag pattern, in pairs of columns from the bottom right corner
+    fn draw_codewords(&mut self, data: &[u8]) {
+        let size = self.size;
+        let mut i = 0;
+        let mut right = size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vert in 0..size {
+                for j in 0..2 {
+                    let x = right - j;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward { size - 1 - vert } else { vert };
+                    if !self.is_function[y * size + x] && i < data.len() * 8 {
+                        self.modules[y * size + x] = (data[i >> 3] >> (7 - (i & 7))) & 1 != 0;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    /// XORs the data modules with the given mask. Applying the same mask twice undoes it.
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let idx = y * self.size + x;
+                self.modules[idx] ^= invert && !self.is_function[idx];
+            }
+        }
+    }
+
+    /// A simplified version of the penalty score of the standard, that penalizes long runs
+    /// of modules of the same color, 2x2 blocks, and unbalanced proportions of dark modules
+    fn penalty_score(&self) -> usize {
+        let size = self.size;
+        let mut penalty = 0;
+        for horizontal in [true, false] {
+            for a in 0..size {
+                let mut run = 0;
+                let mut previous = None;
+                for b in 0..size {
+                    let dark = if horizontal {
+                        self.get(b, a)
+                    } else {
+                        self.get(a, b)
+                    };
+                    if previous == Some(dark) {
+                        run += 1;
+                        match run {
+                            5 => penalty += 3,
+                            6.. => penalty += 1,
+                            _ => {}
+                        }
+                    } else {
+                        run = 1;
+                        previous = Some(dark);
+                    }
+                }
+            }
+        }
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let color = self.get(x, y);
+                if color == self.get(x + 1, y)
+                    && color == self.get(x, y + 1)
+                    && color == self.get(x + 1, y + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+        let dark = self.modules.iter().filter(|&&m| m).count();
+        let total = size * size;
+        let deviation = (dark * 20).abs_diff(total * 10);
+        penalty + deviation.div_ceil(total).saturating_sub(1) * 10
+    }
+}
+
+#[derive(Default)]
+struct BitBuffer(Vec<bool>);
+
+impl BitBuffer {
+    fn append(&mut self, value: usize, len: usize) {
+        self.0.extend((0..len).rev().map(|i| (value >> i) & 1 != 0));
+    }
+}
+
+fn char_count_bits(version: usize) -> usize {
+    if version <= 9 {
+        8
+    } else {
+        16
+    }
+}
+
+fn data_bits_needed(version: usize, len: usize) -> usize {
+    4 + char_count_bits(version) + 8 * len
+}
+
+fn num_raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let num_align = version / 7 + 2;
+        result -= (25 * num_align - 10) * num_align - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn num_data_codewords(version: usize, ecl: ErrorCorrection) -> usize {
+    num_raw_data_modules(version) / 8
+        - usize::from(ECC_CODEWORDS_PER_BLOCK[ecl.ordinal()][version])
+            * usize::from(NUM_ERROR_CORRECTION_BLOCKS[ecl.ordinal()][version])
+}
+
+fn alignment_pattern_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return vec![];
+    }
+    let num_align = version / 7 + 2;
+    let step = (version * 8 + num_align * 3 + 5) / (num_align * 4 - 4) * 2;
+    let size = version * 4 + 17;
+    let mut result: Vec<usize> = (0..num_align - 1).map(|i| size - 7 - i * step).collect();
+    result.push(6);
+    result.reverse();
+    result
+}
+
+/// Splits the data into blocks, adds error correction codewords to each block, and interleaves them
+fn add_ecc_and_interleave(data: &[u8], version: usize, ecl: ErrorCorrection) -> Vec<u8> {
+    let num_blocks = usize::from(NUM_ERROR_CORRECTION_BLOCKS[ecl.ordinal()][version]);
+    let block_ecc_len = usize::from(ECC_CODEWORDS_PER_BLOCK[ecl.ordinal()][version]);
+    let raw_codewords = num_raw_data_modules(version) / 8;
+    let num_short_blocks = num_blocks - raw_codewords % num_blocks;
+    let short_block_len = raw_codewords / num_blocks;
+    let divisor = reed_solomon_divisor(block_ecc_len);
+    let mut blocks = Vec::with_capacity(num_blocks);
+    let mut k = 0;
+    for i in 0..num_blocks {
+        let len = short_block_len - block_ecc_len + usize::from(i >= num_short_blocks);
+        let mut block = data[k..k + len].to_vec();
+        k += len;
+        let block_ecc = reed_solomon_remainder(&block, &divisor);
+        if i < num_short_blocks {
+            block.push(0);
+        }
+        block.extend(block_ecc);
+        blocks.push(block);
+    }
+    let mut result = Vec::with_capacity(raw_codewords);
+    for i in 0..blocks[0].len() {
+        for (j, block) in blocks.iter().enumerate() {
+            // Skip the padding byte of short blocks
+            if i != short_block_len - block_ecc_len || j >= num_short_blocks {
+                result.push(block[i]);
+            }
+        }
+    }
+    result
+}
+
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0; degree];
+    result[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = reed_solomon_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = reed_solomon_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0; divisor.len()];
+    for &b in data {
+        let factor = b ^ result.remove(0);
+        result.push(0);
+        for (x, &y) in result.iter_mut().zip(divisor) {
+            *x ^= reed_solomon_multiply(y, factor);
+        }
+    }
+    result
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1
+fn reed_solomon_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u16 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= u16::from((y >> i) & 1) * u16::from(x);
+    }
+    u8::try_from(z).expect("the product is reduced modulo a degree 8 polynomial")
+}
+
+#[test]
+fn test_capacity_tables() {
+    use ErrorCorrection::{High, Low, Medium, Quartile};
+    // Byte mode capacities from the standard
+    let byte_capacity = |v, ecl| (num_data_codewords(v, ecl) * 8 - 4 - char_count_bits(v)) / 8;
+    assert_eq!(byte_capacity(1, Low), 17);
+    assert_eq!(byte_capacity(1, Medium), 14);
+    assert_eq!(byte_capacity(1, Quartile), 11);
+    assert_eq!(byte_capacity(1, High), 7);
+    assert_eq!(byte_capacity(10, Medium), 213);
+    assert_eq!(byte_capacity(40, Low), 2953);
+    assert_eq!(byte_capacity(40, High), 1273);
+    assert_eq!(alignment_pattern_positions(7), [6, 22, 38]);
+    assert_eq!(alignment_pattern_positions(32), [6, 34, 60, 86, 112, 138]);
+}
+
+#[test]
+fn test_reed_solomon() {
+    // Error correction codewords of the "01234567" example of the standard, version 1-M
+    let data = [
+        0x10, 0x20, 0x0C, 0x56, 0x61, 0x80, 0xEC, 0x11, 0xEC, 0x11, 0xEC, 0x11, 0xEC, 0x11, 0xEC,
+        0x11,
+    ];
+    assert_eq!(
+        reed_solomon_remainder(&data, &reed_solomon_divisor(10)),
+        [0xA5, 0x24, 0xD4, 0xC1, 0xED, 0x36, 0xC7, 0x87, 0x2C, 0x55]
+    );
+}
+
+#[test]
+fn test_encode() {
+    let qr = QrCode::encode("https://sql.ophir.dev", ErrorCorrection::Medium).unwrap();
+    assert_eq!(qr.size, 25, "21 bytes need a version 2 code");
+    // The dark module, next to the bottom left finder pattern
+    assert!(qr.get(8, qr.size - 8));
+    // Timing patterns
+    assert!((8..qr.size - 8).all(|i| qr.get(i, 6) == (i % 2 == 0)));
+    assert!(qr.to_svg().starts_with("<svg"));
+    assert!(QrCode::encode(&"x".repeat(3000), ErrorCorrection::Low).is_err());
+}
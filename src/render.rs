@@ -1,4 +1,11 @@
-use crate::templates::SplitTemplate;
+use crate::templates::{PageLocals, SplitTemplate, CSRF_TOKEN, CURRENT_PATH, LOCALE, TRANSLATIONS};
+use crate::utils::is_truthy;
+use crate::webserver::content_security_policy;
+use crate::webserver::csrf::CsrfToken;
+use crate::webserver::page_cache::CacheFor;
+use crate::webserver::session::{
+    destroy_session, set_session_variable, Session, SESSION_IMPERSONATOR, SESSION_USER,
+};
 use crate::AppState;
 use actix_web::cookie::time::format_description::well_known::Rfc3339;
 use actix_web::cookie::time::OffsetDateTime;
@@ -6,11 +13,13 @@ use actix_web::http::{header, StatusCode};
 use actix_web::{HttpResponse, HttpResponseBuilder};
 use anyhow::{bail, format_err, Context as AnyhowContext};
 use async_recursion::async_recursion;
+use base64::Engine;
 use handlebars::{BlockContext, Context, JsonValue, RenderError, Renderable};
 use serde::Serialize;
 use serde_json::{json, Value};
 use std::borrow::Cow;
 use std::sync::Arc;
+use std::time::Duration;
 
 pub enum PageContext<W: std::io::Write> {
     /// Indicates that we should stay in the header context
@@ -19,11 +28,38 @@ pub enum PageContext<W: std::io::Write> {
     /// Indicates that we should start rendering the body
     Body {
         http_response: HttpResponseBuilder,
-        renderer: RenderContext<W>,
+        renderer: AnyRenderBodyContext<W>,
     },
 
     /// The response is ready, and should be sent as is. No further statements should be executed
     Close(HttpResponse),
+
+    /// The page is rendered in memory, and converted to pdf once it is complete, before the headers are
+    /// sent: when the conversion fails, the client gets an error instead of an empty document
+    Pdf {
+        http_response: HttpResponseBuilder,
+        renderer: PdfBodyRenderer,
+    },
+}
+
+/// Components that only change the headers of the response, or replace it with a redirection.
+/// WebSockets and server-sent events, whose headers are already sent, still use them to check access.
+const HEADER_COMPONENTS: [&str; 10] = [
+    "status_code",
+    "http_header",
+    "redirect",
+    "content_type",
+    "flush",
+    "cache",
+    "cookie",
+    "session",
+    "impersonate",
+    "authentication",
+];
+
+/// Whether a row is handled by [`HeaderContext`] without starting the body of the page
+pub(crate) fn is_header_component(data: &JsonValue) -> bool {
+    get_object_str(data, "component").is_some_and(|c| HEADER_COMPONENTS.contains(&c))
 }
 
 /// Handles the first SQL statements, before the headers have been sent to
@@ -32,17 +68,65 @@ pub struct HeaderContext<W: std::io::Write> {
     pub writer: W,
     response: HttpResponseBuilder,
     has_status: bool,
+    /// The session variables that were sent with the request, updated by the `session` component
+    session: Session,
+    /// Set by the pdf component, to convert the html page to pdf
+    pdf: Option<PdfOptions>,
+    /// Set by the `content_type` component, and applied after the headers of the component that renders the body
+    content_headers: Vec<(header::HeaderName, header::HeaderValue)>,
+    options: PageOptions,
+}
+
+/// Properties of the request that change how the html page is rendered
+#[derive(Default)]
+pub struct PageOptions {
+    /// Render only the components, without the shell, to be inserted in an existing page
+    pub fragment: bool,
+    /// Directory of the sql file, relative to the web root, in which errors can be displayed
+    /// with a custom error component
+    pub directory: String,
+    /// Languages accepted by the user, by order of preference, used to translate the components
+    pub languages: Vec<String>,
+    /// Path of the requested page, used to highlight the current page in menus
+    pub path: String,
+    /// Render a print-friendly version of the page, without navigation
+    pub print: bool,
+    /// The CSRF token embedded in forms, when CSRF protection is enabled
+    pub csrf: Option<CsrfToken>,
+    /// Random value that allows the inline scripts and styles of the page in the `Content-Security-Policy`
+    pub csp_nonce: Option<String>,
+    /// Id of the request, displayed in error messages to find the corresponding logs
+    pub request_id: Option<String>,
 }
 
 impl<W: std::io::Write> HeaderContext<W> {
-    pub fn new(app_state: Arc<AppState>, writer: W) -> Self {
+    pub fn new(
+        app_state: Arc<AppState>,
+        session: Session,
+        writer: W,
+        mut options: PageOptions,
+    ) -> Self {
         let mut response = HttpResponseBuilder::new(StatusCode::OK);
         response.content_type("text/html; charset=utf-8");
+        if let Some(cookie) = options.csrf.as_mut().and_then(|csrf| csrf.cookie.take()) {
+            response.append_header((header::SET_COOKIE, cookie.encoded().to_string()));
+        }
+        if let Some(nonce) = &options.csp_nonce {
+            let policy = &app_state.config.content_security_policy;
+            response.insert_header((
+                header::CONTENT_SECURITY_POLICY,
+                content_security_policy::with_nonce(policy, nonce),
+            ));
+        }
         Self {
             app_state,
             writer,
             response,
             has_status: false,
+            session,
+            pdf: None,
+            content_headers: Vec::new(),
+            options,
         }
     }
     pub async fn handle_row(self, data: JsonValue) -> anyhow::Result<PageContext<W>> {
@@ -51,36 +135,76 @@ impl<W: std::io::Write> HeaderContext<W> {
             Some("status_code") => self.status_code(&data).map(PageContext::Header),
             Some("http_header") => self.add_http_header(&data).map(PageContext::Header),
             Some("redirect") => self.redirect(&data).map(PageContext::Close),
-            Some("json") => self.json(&data).map(PageContext::Close),
+            Some("json") => self.json(&data),
+            Some("csv") => self.csv(&data),
+            Some("feed") => self.feed(&data),
+            Some("pdf") => self.pdf(&data).map(PageContext::Header),
+            Some("blob") => self.blob(&data).map(PageContext::Close),
+            Some("content_type") => self.content_type(&data).map(PageContext::Header),
+            Some("flush") => Ok(PageContext::Header(self.flush(&data))),
+            Some("cache") => self.cache(&data).map(PageContext::Header),
             Some("cookie") => self.add_cookie(&data).map(PageContext::Header),
+            Some("session") => self
+                .set_session_variable(&data)
+                .await
+                .map(PageContext::Header),
+            Some("impersonate") => self.impersonate(&data).await.map(PageContext::Header),
             Some("authentication") => self.authentication(data).await,
             _ => self.start_body(data).await,
         }
     }
 
-    pub async fn handle_error(self, err: anyhow::Error) -> anyhow::Result<PageContext<W>> {
-        if self.app_state.config.environment.is_prod() {
-            return Err(err);
+    /// Displays an error that happened before the body of the page was started,
+    /// with the same error component as the errors in the body
+    pub async fn handle_error(mut self, err: anyhow::Error) -> anyhow::Result<PageContext<W>> {
+        let request_id = self.options.request_id.clone();
+        log::error!(
+            "SQL error in request {}: {err:?}",
+            request_id.as_deref().unwrap_or("-")
+        );
+        if !self.has_status {
+            self.response.status(StatusCode::INTERNAL_SERVER_ERROR);
+            self.has_status = true;
         }
-        log::debug!("Handling header error: {err}");
+        let mut data = error_properties(&self.app_state, &err, request_id.as_deref());
+        data["component"] = error_component(&self.app_state, &self.options.directory)
+            .await
+            .into();
+        self.start_body(data).await
+    }
+
+    /// Renders a page that tells the user that the server took too long to answer
+    pub async fn timeout(mut self) -> anyhow::Result<PageContext<W>> {
+        self.response.status(StatusCode::SERVICE_UNAVAILABLE);
+        self.has_status = true;
         let data = json!({
-            "component": "error",
-            "description": err.to_string(),
-            "backtrace": get_backtrace(&err),
+            "component": "alert",
+            "title": "This page took too long to load",
+            "description": "The server is busy or the page requires too much work. Please try again later.",
+            "icon": "clock-exclamation",
+            "color": "red",
         });
         self.start_body(data).await
     }
 
     fn status_code(mut self, data: &JsonValue) -> anyhow::Result<Self> {
-        let status_code = data
-            .as_object()
-            .and_then(|m| m.get("status"))
-            .with_context(|| "status_code component requires a status")?
-            .as_u64()
-            .with_context(|| "status must be a number")?;
+        let status = data
+            .get("status")
+            .with_context(|| "status_code component requires a status")?;
+        // Some databases return numbers as strings
+        let status_code = match status {
+            JsonValue::Number(n) => n.as_u64(),
+            JsonValue::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+        .with_context(|| format!("status must be a number, got {status}"))?;
         let code = u16::try_from(status_code)
-            .with_context(|| format!("status must be a number between 0 and {}", u16::MAX))?;
-        self.response.status(StatusCode::from_u16(code)?);
+            .ok()
+            .and_then(|code| StatusCode::from_u16(code).ok())
+            .with_context(|| {
+                format!("Invalid HTTP status code {status_code}: it must be between 100 and 999")
+            })?;
+        self.response.status(code);
         self.has_status = true;
         Ok(self)
     }
@@ -111,38 +235,38 @@ impl<W: std::io::Write> HeaderContext<W> {
             .with_context(|| "cookie name must be a string")?;
         let mut cookie = actix_web::cookie::Cookie::named(name);
 
-        let remove = obj.get("remove");
-        if remove == Some(&json!(true)) || remove == Some(&json!(1)) {
+        // The path and domain must match the ones of the cookie to remove
+        let path = obj.get("path").and_then(JsonValue::as_str);
+        if let Some(path) = path {
+            cookie.set_path(path);
+        }
+        let domain = obj.get("domain").and_then(JsonValue::as_str);
+        if let Some(domain) = domain {
+            cookie.set_domain(domain);
+        }
+
+        if obj.get("remove").is_some_and(is_truthy) {
             cookie.make_removal();
             self.response.cookie(cookie);
             log::trace!("Removing cookie {}", name);
             return Ok(self);
         }
 
-        let value = obj
-            .get("value")
-            .and_then(JsonValue::as_str)
-            .with_context(|| "The 'value' property of the cookie component is required (unless 'remove' is set) and must be a string.")?;
+        let value = match obj.get("value") {
+            Some(JsonValue::String(s)) => Cow::Borrowed(s.as_str()),
+            Some(n @ JsonValue::Number(_)) => Cow::Owned(n.to_string()),
+            _ => bail!("The 'value' property of the cookie component is required (unless 'remove' is set) and must be a string."),
+        };
         cookie.set_value(value);
-        let http_only = obj.get("http_only");
-        cookie.set_http_only(http_only != Some(&json!(false)) && http_only != Some(&json!(0)));
+        cookie.set_http_only(enabled_by_default(obj.get("http_only")));
         let same_site = obj.get("same_site").and_then(Value::as_str);
-        cookie.set_same_site(match same_site {
+        cookie.set_same_site(match same_site.map(str::to_ascii_lowercase).as_deref() {
             Some("none") => actix_web::cookie::SameSite::None,
             Some("lax") => actix_web::cookie::SameSite::Lax,
             None | Some("strict") => actix_web::cookie::SameSite::Strict, // strict by default
             Some(other) => bail!("Cookie: invalid value for same_site: {}", other),
         });
-        let secure = obj.get("secure");
-        cookie.set_secure(secure != Some(&json!(false)) && secure != Some(&json!(0)));
-        let path = obj.get("path").and_then(JsonValue::as_str);
-        if let Some(path) = path {
-            cookie.set_path(path);
-        }
-        let domain = obj.get("domain").and_then(JsonValue::as_str);
-        if let Some(domain) = domain {
-            cookie.set_domain(domain);
-        }
+        cookie.set_secure(enabled_by_default(obj.get("secure")));
         let expires = obj.get("expires");
         if let Some(expires) = expires {
             cookie.set_expires(actix_web::cookie::Expiration::DateTime(match expires {
@@ -153,14 +277,139 @@ impl<W: std::io::Write> HeaderContext<W> {
                 _ => bail!("expires must be a string or a number"),
             }));
         }
+        if let Some(max_age) = obj.get("max_age") {
+            let seconds = match max_age {
+                JsonValue::Number(n) => n.as_i64(),
+                JsonValue::String(s) => s.trim().parse().ok(),
+                _ => None,
+            }
+            .with_context(|| format!("max_age must be a number of seconds, got {max_age}"))?;
+            cookie.set_max_age(actix_web::cookie::time::Duration::seconds(seconds));
+        }
         log::trace!("Setting cookie {}", cookie);
         self.response
             .append_header((header::SET_COOKIE, cookie.encoded().to_string()));
         Ok(self)
     }
 
+    /// Keeps the rendered page in memory, to serve it to the next visitors without running the queries again
+    fn cache(mut self, data: &JsonValue) -> anyhow::Result<Self> {
+        let ttl = match data.get("ttl") {
+            Some(JsonValue::Number(n)) => n.as_f64(),
+            Some(JsonValue::String(s)) => s.trim().parse().ok(),
+            _ => None,
+        }
+        .with_context(|| "The cache component requires a 'ttl' property: a number of seconds")?;
+        let ttl = Duration::try_from_secs_f64(ttl)
+            .with_context(|| format!("Invalid cache ttl: {ttl}"))?;
+        self.response.extensions_mut().insert(CacheFor(ttl));
+        Ok(self)
+    }
+
+    async fn set_session_variable(mut self, data: &JsonValue) -> anyhow::Result<Self> {
+        if data.get("destroy").is_some_and(is_truthy) {
+            let cookies =
+                destroy_session(&self.app_state, &mut self.session, is_secure(data)).await?;
+            for cookie in cookies {
+                self.response
+                    .append_header((header::SET_COOKIE, cookie.encoded().to_string()));
+            }
+            return Ok(self);
+        }
+        let name = get_object_str(data, "name")
+            .with_context(|| "The session component requires a 'name' property")?;
+        let value = json_to_session_value(data.get("value"));
+        self.set_session_value(data, name, value.as_deref()).await?;
+        Ok(self)
+    }
+
+    /// Makes the current user assume the identity of another user, or drop back to their own identity.
+    /// Starting an impersonation requires a truthy `allowed` property, computed by the page from the
+    /// permissions of the current user. All the attempts are logged as audit events.
+    async fn impersonate(mut self, data: &JsonValue) -> anyhow::Result<Self> {
+        let current_user = self
+            .session
+            .variables
+            .get(SESSION_USER)
+            .and_then(Value::as_str);
+        let impersonator = self
+            .session
+            .variables
+            .get(SESSION_IMPERSONATOR)
+            .and_then(Value::as_str);
+        let reason = get_object_str(data, "reason").unwrap_or_default();
+        let stop = data.get("stop");
+        if stop == Some(&json!(true)) || stop == Some(&json!(1)) {
+            let Some(admin) = impersonator.map(str::to_string) else {
+                log::debug!("impersonate: the current user is not impersonating anyone");
+                return Ok(self);
+            };
+            log::warn!(
+                target: "sqlpage::audit",
+                "{admin} stopped impersonating {}. {reason}",
+                current_user.unwrap_or_default()
+            );
+            self.set_session_value(data, SESSION_USER, Some(&admin))
+                .await?;
+            self.set_session_value(data, SESSION_IMPERSONATOR, None)
+                .await?;
+            return Ok(self);
+        }
+        let user = json_to_session_value(data.get("user")).with_context(|| {
+            "The impersonate component requires a 'user' property (unless 'stop' is set)"
+        })?;
+        // When switching from an impersonated user to another, the original identity is kept
+        let admin = impersonator
+            .or(current_user)
+            .with_context(|| {
+                format!("Only a logged-in user can impersonate another user, but the '{SESSION_USER}' session variable is not set")
+            })?
+            .to_string();
+        if !data.get("allowed").is_some_and(is_truthy) {
+            log::warn!(target: "sqlpage::audit", "{admin} was not allowed to impersonate {user}. {reason}");
+            bail!(
+                "The impersonate component requires an 'allowed' property that is true, such as \
+                EXISTS (SELECT 1 FROM users WHERE id = $session.user AND is_admin) AS allowed"
+            );
+        }
+        log::warn!(target: "sqlpage::audit", "{admin} started impersonating {user}. {reason}");
+        self.set_session_value(data, SESSION_USER, Some(&user))
+            .await?;
+        self.set_session_value(data, SESSION_IMPERSONATOR, Some(&admin))
+            .await?;
+        Ok(self)
+    }
+
+    async fn set_session_value(
+        &mut self,
+        data: &JsonValue,
+        name: &str,
+        value: Option<&str>,
+    ) -> anyhow::Result<()> {
+        log::trace!("Setting session variable {name}: {value:?}");
+        let cookies = set_session_variable(
+            &self.app_state,
+            &mut self.session,
+            name,
+            value,
+            is_secure(data),
+        )
+        .await?;
+        for cookie in cookies {
+            self.response
+                .append_header((header::SET_COOKIE, cookie.encoded().to_string()));
+        }
+        Ok(())
+    }
+
     fn redirect(mut self, data: &JsonValue) -> anyhow::Result<HttpResponse> {
-        self.response.status(StatusCode::FOUND);
+        let status = match data.get("status") {
+            None | Some(JsonValue::Null) => StatusCode::FOUND,
+            Some(JsonValue::Number(n)) => redirect_status(n.as_u64())?,
+            Some(JsonValue::String(s)) => redirect_status(s.trim().parse().ok())?,
+            Some(other) => bail!("Invalid redirect status: {other}"),
+        };
+        self.response.status(status);
         self.has_status = true;
         let link = get_object_str(data, "link")
             .with_context(|| "The redirect component requires a 'link' property")?;
@@ -169,30 +418,220 @@ impl<W: std::io::Write> HeaderContext<W> {
         Ok(response)
     }
 
-    /// Answers to the HTTP request with a single json object
-    fn json(mut self, data: &JsonValue) -> anyhow::Result<HttpResponse> {
-        let contents = data
-            .get("contents")
-            .with_context(|| "Missing 'contents' property for the json component")?;
-        let json_response = if let Some(s) = contents.as_str() {
-            s.as_bytes().to_owned()
-        } else {
-            serde_json::to_vec(contents)?
+    /// Answers to the HTTP request with json.
+    /// If the `contents` property is set, it is sent as is. Otherwise, all the following rows
+    /// are streamed to the client as a json array or as json lines, without rendering the shell.
+    fn json(mut self, data: &JsonValue) -> anyhow::Result<PageContext<W>> {
+        if let Some(contents) = data.get("contents") {
+            let json_response = if let Some(s) = contents.as_str() {
+                s.as_bytes().to_owned()
+            } else {
+                serde_json::to_vec(contents)?
+            };
+            self.response
+                .insert_header((header::CONTENT_TYPE, "application/json"));
+            self.apply_content_headers();
+            return Ok(PageContext::Close(self.response.body(json_response)));
+        }
+        let output_type = match get_object_str(data, "type") {
+            None | Some("array") => JsonOutputType::Array,
+            Some("jsonlines") => JsonOutputType::JsonLines,
+            Some(other) => bail!(
+                "Invalid type {other:?} for the json component. Expected 'array' or 'jsonlines'."
+            ),
         };
         self.response
-            .insert_header((header::CONTENT_TYPE, "application/json"));
-        Ok(self.response.body(json_response))
+            .insert_header((header::CONTENT_TYPE, output_type.content_type()));
+        self.apply_content_headers();
+        let renderer = JsonBodyRenderer::new(self.app_state, self.writer, output_type)?;
+        Ok(PageContext::Body {
+            http_response: self.response,
+            renderer: AnyRenderBodyContext::Json(renderer),
+        })
+    }
+
+    /// Answers to the HTTP request with a csv file download, containing all the following rows
+    fn csv(mut self, data: &JsonValue) -> anyhow::Result<PageContext<W>> {
+        let mut filename = get_object_str(data, "filename")
+            .or_else(|| get_object_str(data, "title"))
+            .unwrap_or("download")
+            .to_string();
+        if !filename.to_ascii_lowercase().ends_with(".csv") {
+            filename.push_str(".csv");
+        }
+        self.response
+            .insert_header((header::CONTENT_TYPE, "text/csv; charset=utf-8"));
+        self.response
+            .insert_header(header::ContentDisposition::attachment(filename));
+        self.apply_content_headers();
+        let renderer = CsvBodyRenderer::new(self.writer, data)?;
+        Ok(PageContext::Body {
+            http_response: self.response,
+            renderer: AnyRenderBodyContext::Csv(renderer),
+        })
+    }
+
+    /// Answers to the HTTP request with an RSS or Atom feed, where each of the following rows is an entry
+    fn feed(mut self, data: &JsonValue) -> anyhow::Result<PageContext<W>> {
+        let renderer = FeedBodyRenderer::new(self.writer, data)?;
+        self.response
+            .insert_header((header::CONTENT_TYPE, renderer.feed_type.content_type()));
+        self.apply_content_headers();
+        Ok(PageContext::Body {
+            http_response: self.response,
+            renderer: AnyRenderBodyContext::Feed(renderer),
+        })
+    }
+
+    /// Answers to the HTTP request with the contents of a file stored in the database.
+    /// Binary columns are returned by the database as data urls, that are decoded back to bytes.
+    fn blob(mut self, data: &JsonValue) -> anyhow::Result<HttpResponse> {
+        let Some(body) = get_object_str(data, "body") else {
+            if data.get("body").map_or(true, JsonValue::is_null) {
+                log::debug!("The blob component received a null body, responding with 404");
+                self.response.status(StatusCode::NOT_FOUND);
+                return Ok(self.response.body(()));
+            }
+            bail!("The 'body' property of the blob component must be a binary or text value");
+        };
+        let (data_url_type, bytes) = match parse_data_url(body) {
+            Some((content_type, bytes)) => (Some(content_type), bytes?),
+            None => (None, body.as_bytes().to_vec()),
+        };
+        let content_type = get_object_str(data, "content_type")
+            .or(data_url_type)
+            .unwrap_or("application/octet-stream");
+        self.response
+            .insert_header((header::CONTENT_TYPE, content_type));
+        // The contents of the file must not be interpreted as html in the context of the site
+        self.response
+            .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"));
+        self.apply_content_headers();
+        Ok(self.response.body(bytes))
+    }
+
+    /// Sets the type of the whole response, and optionally makes it a file download,
+    /// whatever the component used to render its body
+    fn content_type(mut self, data: &JsonValue) -> anyhow::Result<Self> {
+        self.content_headers.clear();
+        if let Some(content_type) = get_object_str(data, "type") {
+            let content_type: mime_guess::mime::Mime = content_type
+                .parse()
+                .with_context(|| format!("Invalid content type: {content_type:?}"))?;
+            // Everything SQLPage renders is encoded in utf-8
+            let content_type = if content_type.type_() == mime_guess::mime::TEXT
+                && content_type.get_param(mime_guess::mime::CHARSET).is_none()
+            {
+                format!("{content_type}; charset=utf-8")
+            } else {
+                content_type.to_string()
+            };
+            self.content_headers.push((
+                header::CONTENT_TYPE,
+                header::HeaderValue::try_from(content_type)?,
+            ));
+        }
+        let filename = get_object_str(data, "filename");
+        let download = data.get("download").map(is_truthy);
+        if filename.is_some() || download.is_some() {
+            let disposition = if download.unwrap_or(true) {
+                header::DispositionType::Attachment
+            } else {
+                header::DispositionType::Inline
+            };
+            let parameters = filename
+                .map(|f| header::DispositionParam::Filename(f.to_string()))
+                .into_iter()
+                .collect();
+            let disposition = header::ContentDisposition {
+                disposition,
+                parameters,
+            };
+            self.content_headers.push((
+                header::CONTENT_DISPOSITION,
+                header::HeaderValue::try_from(disposition.to_string())?,
+            ));
+        }
+        Ok(self)
+    }
+
+    fn apply_content_headers(&mut self) {
+        for header in self.content_headers.drain(..) {
+            self.response.insert_header(header);
+        }
+    }
+
+    /// Nothing has been rendered yet, so there is nothing to flush, but buffering can still be disabled
+    fn flush(mut self, data: &JsonValue) -> Self {
+        if data.get("buffering").is_some_and(|b| !is_truthy(b)) {
+            // Compressed responses are only sent when the compressor's buffer is full.
+            // The compression middleware leaves responses that already have an encoding untouched.
+            self.response
+                .insert_header(header::ContentEncoding::Identity);
+            // Ask reverse proxies such as nginx not to buffer the response either
+            self.response.insert_header(("X-Accel-Buffering", "no"));
+        }
+        self
+    }
+
+    /// Renders the rest of the page as html, and converts it to a pdf document once it is complete
+    fn pdf(mut self, data: &JsonValue) -> anyhow::Result<Self> {
+        let converter = self
+            .app_state
+            .config
+            .pdf_converter
+            .clone()
+            .with_context(|| {
+                "The pdf component requires the pdf_converter configuration option to be set, \
+            for instance to 'weasyprint - -'"
+            })?;
+        let filename = get_object_str(data, "filename")
+            .or_else(|| get_object_str(data, "title"))
+            .unwrap_or("document");
+        self.response
+            .insert_header((header::CONTENT_TYPE, "application/pdf"));
+        let disposition = if data.get("download").is_some_and(is_truthy) {
+            header::DispositionType::Attachment
+        } else {
+            header::DispositionType::Inline
+        };
+        self.response.insert_header(header::ContentDisposition {
+            disposition,
+            parameters: vec![header::DispositionParam::Filename(format!(
+                "{filename}.pdf"
+            ))],
+        });
+        let print_setup = pdf_print_setup(data, &self.app_state.config)?;
+        self.pdf = Some(PdfOptions {
+            converter,
+            print_setup,
+        });
+        Ok(self)
     }
 
     async fn authentication(mut self, mut data: JsonValue) -> anyhow::Result<PageContext<W>> {
         let password_hash = take_object_str(&mut data, "password_hash");
         let password = take_object_str(&mut data, "password");
-        if let (Some(password), Some(password_hash)) = (password, password_hash) {
-            log::debug!("Authentication with password_hash = {:?}", password_hash);
-            match verify_password_async(password_hash, password).await? {
-                Ok(()) => return Ok(PageContext::Header(self)),
-                Err(e) => log::info!("Password didn't match: {}", e),
+        let condition = data.get("condition").map(is_truthy);
+        let password_ok = match (password, password_hash) {
+            (Some(password), Some(password_hash)) => {
+                log::debug!("Authentication with password_hash = {:?}", password_hash);
+                match verify_password_async(password_hash, password).await? {
+                    Ok(()) => Some(true),
+                    Err(e) => {
+                        log::info!("Password didn't match: {}", e);
+                        Some(false)
+                    }
+                }
             }
+            // A password_hash is never matched by a missing password, even when there is a condition
+            (None, Some(_)) => Some(false),
+            // Without a password_hash, a condition alone is enough to grant access
+            (_, None) if condition.is_some() => None,
+            (_, None) => Some(false),
+        };
+        if password_ok != Some(false) && condition != Some(false) {
+            return Ok(PageContext::Header(self));
         }
         log::debug!("Authentication failed");
         // The authentication failed
@@ -211,23 +650,594 @@ impl<W: std::io::Write> HeaderContext<W> {
         Ok(PageContext::Close(http_response))
     }
 
-    async fn start_body(self, data: JsonValue) -> anyhow::Result<PageContext<W>> {
-        let renderer = RenderContext::new(self.app_state, self.writer, data)
+    async fn start_body(mut self, data: JsonValue) -> anyhow::Result<PageContext<W>> {
+        self.apply_content_headers();
+        let http_response = self.response;
+        if let Some(options) = self.pdf {
+            let html = RenderContext::new(self.app_state, Vec::new(), data, self.options)
+                .await
+                .with_context(|| "Failed to create a render context from the header context.")?;
+            return Ok(PageContext::Pdf {
+                renderer: PdfBodyRenderer { html, options },
+                http_response,
+            });
+        }
+        let renderer = RenderContext::new(self.app_state, self.writer, data, self.options)
             .await
             .with_context(|| "Failed to create a render context from the header context.")?;
-        let http_response = self.response;
         Ok(PageContext::Body {
-            renderer,
+            renderer: AnyRenderBodyContext::Html(renderer),
             http_response,
         })
     }
 
     pub fn close(mut self) -> HttpResponse {
+        self.apply_content_headers();
         self.response.finish()
     }
 }
 
-async fn verify_password_async(
+/// The renderer for the body of the response, once all the headers have been sent
+pub enum AnyRenderBodyContext<W: std::io::Write> {
+    /// Renders the page with handlebars templates, inside the shell
+    Html(RenderContext<W>),
+    /// Renders the rows as raw json, for the json component
+    Json(JsonBodyRenderer<W>),
+    /// Renders the rows as a csv file, for the csv component
+    Csv(CsvBodyRenderer<W>),
+    /// Renders the rows as an RSS or Atom feed, for the feed component
+    Feed(FeedBodyRenderer<W>),
+}
+
+impl<W: std::io::Write> AnyRenderBodyContext<W> {
+    pub async fn handle_row(&mut self, data: &JsonValue) -> anyhow::Result<()> {
+        match self {
+            Self::Html(renderer) => renderer.handle_row(data).await,
+            Self::Json(renderer) => renderer.handle_row(data),
+            Self::Csv(renderer) => renderer.handle_row(data),
+            Self::Feed(renderer) => renderer.handle_row(data),
+        }
+    }
+
+    pub async fn handle_error(&mut self, error: &anyhow::Error) -> anyhow::Result<()> {
+        match self {
+            Self::Html(renderer) => renderer.handle_error(error).await,
+            Self::Json(renderer) => renderer.handle_error(error),
+            // The headers have already been sent, so the download is interrupted
+            // rather than silently producing an incomplete file
+            Self::Csv(_) => Err(format_err!("Unable to generate the csv file: {error:#}")),
+            Self::Feed(_) => Err(format_err!("Unable to generate the feed: {error:#}")),
+        }
+    }
+
+    pub async fn finish_query(&mut self) -> anyhow::Result<()> {
+        match self {
+            Self::Html(renderer) => renderer.finish_query().await,
+            Self::Json(_) | Self::Csv(_) | Self::Feed(_) => Ok(()),
+        }
+    }
+
+    pub fn writer_mut(&mut self) -> &mut W {
+        match self {
+            Self::Html(renderer) => &mut renderer.writer,
+            Self::Json(renderer) => &mut renderer.writer,
+            Self::Csv(renderer) => &mut renderer.writer,
+            Self::Feed(renderer) => &mut renderer.writer,
+        }
+    }
+
+    pub async fn close(self) -> W {
+        match self {
+            Self::Html(renderer) => renderer.close().await,
+            Self::Json(renderer) => renderer.close(),
+            Self::Csv(renderer) => renderer.writer,
+            Self::Feed(renderer) => renderer.close(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum JsonOutputType {
+    /// A single json array containing all the rows
+    Array,
+    /// One json object per line, see <https://jsonlines.org>
+    JsonLines,
+}
+
+impl JsonOutputType {
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Array => "application/json",
+            Self::JsonLines => "application/x-ndjson",
+        }
+    }
+}
+
+/// Streams database rows as json, without buffering the whole response
+pub struct JsonBodyRenderer<W: std::io::Write> {
+    app_state: Arc<AppState>,
+    writer: W,
+    output_type: JsonOutputType,
+    is_first: bool,
+}
+
+impl<W: std::io::Write> JsonBodyRenderer<W> {
+    fn new(
+        app_state: Arc<AppState>,
+        mut writer: W,
+        output_type: JsonOutputType,
+    ) -> anyhow::Result<Self> {
+        if output_type == JsonOutputType::Array {
+            writer.write_all(b"[")?;
+        }
+        Ok(Self {
+            app_state,
+            writer,
+            output_type,
+            is_first: true,
+        })
+    }
+
+    fn handle_row(&mut self, data: &JsonValue) -> anyhow::Result<()> {
+        match self.output_type {
+            JsonOutputType::Array => {
+                self.writer
+                    .write_all(if self.is_first { b"\n" } else { b",\n" })?;
+                serde_json::to_writer(&mut self.writer, data)?;
+            }
+            JsonOutputType::JsonLines => {
+                serde_json::to_writer(&mut self.writer, data)?;
+                self.writer.write_all(b"\n")?;
+            }
+        }
+        self.is_first = false;
+        Ok(())
+    }
+
+    /// Errors are sent to the client as a json object with an `error` property
+    fn handle_error(&mut self, error: &anyhow::Error) -> anyhow::Result<()> {
+        log::error!("SQL error: {:?}", error);
+        let description = if self.app_state.config.verbose_errors() {
+            format!("{error:#}")
+        } else {
+            "Please contact the administrator for more information. The error has been logged."
+                .to_string()
+        };
+        self.handle_row(&json!({ "error": description }))
+    }
+
+    fn close(mut self) -> W {
+        if self.output_type == JsonOutputType::Array {
+            let end: &[u8] = if self.is_first { b"]" } else { b"\n]" };
+            if let Err(e) = self.writer.write_all(end) {
+                log::error!("Unable to write the end of the json array: {e}");
+            }
+        }
+        self.writer
+    }
+}
+
+/// Streams database rows as a csv file, without buffering the whole response.
+/// The columns of the first row determine the header of the file.
+pub struct CsvBodyRenderer<W: std::io::Write> {
+    writer: W,
+    separator: String,
+    columns: Option<Vec<String>>,
+}
+
+impl<W: std::io::Write> CsvBodyRenderer<W> {
+    fn new(mut writer: W, options: &JsonValue) -> anyhow::Result<Self> {
+        let separator = get_object_str(options, "separator").unwrap_or(",");
+        anyhow::ensure!(
+            !separator.is_empty() && !separator.contains(['"', '\r', '\n']),
+            "Invalid csv separator: {separator:?}"
+        );
+        if options.get("bom").is_some_and(is_truthy) {
+            // Lets Excel know that the file is encoded in UTF-8
+            writer.write_all("\u{feff}".as_bytes())?;
+        }
+        Ok(Self {
+            writer,
+            separator: separator.to_string(),
+            columns: None,
+        })
+    }
+
+    fn handle_row(&mut self, data: &JsonValue) -> anyhow::Result<()> {
+        let JsonValue::Object(row) = data else {
+            bail!("The csv component expects rows to be objects, got {data}");
+        };
+        let columns = match &self.columns {
+            Some(columns) => columns,
+            None => {
+                let columns: Vec<String> = row.keys().cloned().collect();
+                self.write_record(columns.iter().map(|c| Cow::Borrowed(c.as_str())))?;
+                self.columns.insert(columns)
+            }
+        };
+        let values: Vec<Cow<str>> = columns
+            .iter()
+            .map(|column| match row.get(column) {
+                None | Some(JsonValue::Null) => Cow::Borrowed(""),
+                Some(JsonValue::String(s)) => Cow::Borrowed(s.as_str()),
+                Some(other) => Cow::Owned(other.to_string()),
+            })
+            .collect();
+        self.write_record(values.into_iter())?;
+        Ok(())
+    }
+
+    fn write_record<'a>(
+        &mut self,
+        fields: impl Iterator<Item = Cow<'a, str>>,
+    ) -> std::io::Result<()> {
+        for (i, field) in fields.enumerate() {
+            if i > 0 {
+                self.writer.write_all(self.separator.as_bytes())?;
+            }
+            if field.contains(['"', '\r', '\n']) || field.contains(self.separator.as_str()) {
+                write!(self.writer, "\"{}\"", field.replace('"', "\"\""))?;
+            } else {
+                self.writer.write_all(field.as_bytes())?;
+            }
+        }
+        self.writer.write_all(b"\r\n")
+    }
+}
+
+pub struct PdfOptions {
+    converter: String,
+    /// Html inserted at the end of the page head, to set up the printed pages
+    print_setup: String,
+}
+
+/// Buffers the html page, since the pdf can only be generated once the page is complete
+pub struct PdfBodyRenderer {
+    html: RenderContext<Vec<u8>>,
+    options: PdfOptions,
+}
+
+impl PdfBodyRenderer {
+    pub async fn handle_row(&mut self, data: &JsonValue) -> anyhow::Result<()> {
+        self.html.handle_row(data).await
+    }
+
+    pub async fn handle_error(&mut self, error: &anyhow::Error) -> anyhow::Result<()> {
+        self.html.handle_error(error).await
+    }
+
+    pub async fn finish_query(&mut self) -> anyhow::Result<()> {
+        self.html.finish_query().await
+    }
+
+    /// Converts the complete page to pdf
+    pub async fn into_pdf(self) -> anyhow::Result<Vec<u8>> {
+        let mut html = self.html.close().await;
+        let head_end = html
+            .windows(b"</head>".len())
+            .position(|w| w == b"</head>")
+            .unwrap_or(0);
+        html.splice(head_end..head_end, self.options.print_setup.bytes());
+        html_to_pdf(&self.options.converter, html)
+            .await
+            .with_context(|| "Unable to convert the page to pdf")
+    }
+}
+
+/// Pipes the html page through the configured converter, and returns its output
+async fn html_to_pdf(converter: &str, html: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+    use std::process::Stdio;
+    use tokio::io::AsyncWriteExt;
+    let mut args = converter.split_whitespace();
+    let program = args
+        .next()
+        .with_context(|| "The pdf_converter configuration option is empty")?;
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Unable to execute the pdf converter: {converter}"))?;
+    let mut stdin = child.stdin.take().with_context(|| "No stdin")?;
+    let write_html = async move {
+        let res = stdin.write_all(&html).await;
+        drop(stdin);
+        res
+    };
+    let (written, output) = tokio::join!(write_html, child.wait_with_output());
+    let output = output?;
+    if !output.status.success() {
+        bail!(
+            "'{converter}' failed with exit code {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    written.with_context(|| format!("Unable to send the page to '{converter}'"))?;
+    Ok(output.stdout)
+}
+
+/// Builds the `<base>` and `<style>` tags that set up the printed pages: the converter reads the page
+/// from its standard input, so it needs the address of the server to load stylesheets and images
+fn pdf_print_setup(
+    data: &JsonValue,
+    config: &crate::app_config::AppConfig,
+) -> anyhow::Result<String> {
+    let base_url = if let Some(domain) = &config.https_domain {
+        format!("https://{domain}/")
+    } else {
+        let mut addr = *config.listen_on().first().with_context(|| {
+            "The pdf component needs https_domain, or a TCP address in listen_on, to load the resources of the page"
+        })?;
+        let scheme = if config.is_https_address(&addr) {
+            "https"
+        } else {
+            "http"
+        };
+        if addr.ip().is_unspecified() {
+            addr.set_ip(std::net::Ipv4Addr::LOCALHOST.into());
+        }
+        format!("{scheme}://{addr}/")
+    };
+    let mut size = get_object_str(data, "size").unwrap_or("A4").to_string();
+    if let Some(orientation) = get_object_str(data, "orientation") {
+        size.push(' ');
+        size.push_str(orientation);
+    }
+    let margin = get_object_str(data, "margin").unwrap_or("1.5cm");
+    for value in [&*size, margin] {
+        if value.contains([';', '{', '}', '<', '>', '"', '\\']) {
+            bail!("Invalid page size or margin in the pdf component: {value:?}");
+        }
+    }
+    let mut margin_boxes = String::new();
+    for (property, position) in [("header", "top-center"), ("footer", "bottom-center")] {
+        if let Some(text) = get_object_str(data, property) {
+            margin_boxes.push_str(&format!(
+                "@{position}{{content:{};}}",
+                css_page_string(text)
+            ));
+        }
+    }
+    Ok(format!(
+        "<base href=\"{base_url}\"><style>@page{{size:{size};margin:{margin};{margin_boxes}}}</style>"
+    ))
+}
+
+/// Escapes a text as a css string, where `{page}` and `{pages}` are replaced by page counters
+fn css_page_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\A "),
+            '<' => escaped.push_str("\\3C "),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+        .replace("{page}", "\" counter(page) \"")
+        .replace("{pages}", "\" counter(pages) \"")
+}
+
+#[test]
+fn test_css_page_string() {
+    assert_eq!(
+        css_page_string("Page {page}/{pages} of \"a\" </style>"),
+        r#""Page " counter(page) "/" counter(pages) " of \"a\" \3C /style>""#
+    );
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FeedType {
+    Rss,
+    Atom,
+}
+
+impl FeedType {
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Rss => "application/rss+xml; charset=utf-8",
+            Self::Atom => "application/atom+xml; charset=utf-8",
+        }
+    }
+}
+
+/// Streams database rows as the entries of an RSS 2.0 or Atom feed
+pub struct FeedBodyRenderer<W: std::io::Write> {
+    writer: W,
+    feed_type: FeedType,
+}
+
+impl<W: std::io::Write> FeedBodyRenderer<W> {
+    fn new(mut writer: W, options: &JsonValue) -> anyhow::Result<Self> {
+        let feed_type = match get_object_str(options, "type") {
+            None | Some("rss") => FeedType::Rss,
+            Some("atom") => FeedType::Atom,
+            Some(other) => {
+                bail!("Invalid type {other:?} for the feed component. Expected 'rss' or 'atom'.")
+            }
+        };
+        let title = get_object_str(options, "title")
+            .with_context(|| "The feed component requires a 'title' property")?;
+        let link = get_object_str(options, "link")
+            .with_context(|| "The feed component requires a 'link' property")?;
+        let description = get_object_str(options, "description").unwrap_or_default();
+        let language = get_object_str(options, "language");
+        let updated = match options.get("updated") {
+            Some(date) if !date.is_null() => Some(parse_feed_date(date)?),
+            _ => None,
+        };
+        writer.write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+        match feed_type {
+            FeedType::Rss => {
+                writer.write_all(b"<rss version=\"2.0\"><channel>")?;
+                write_xml_element(&mut writer, "title", title)?;
+                write_xml_element(&mut writer, "link", link)?;
+                write_xml_element(&mut writer, "description", description)?;
+                if let Some(language) = language {
+                    write_xml_element(&mut writer, "language", language)?;
+                }
+                if let Some(updated) = updated {
+                    write_xml_element(&mut writer, "lastBuildDate", &updated.to_rfc2822())?;
+                }
+            }
+            FeedType::Atom => {
+                let lang = language.map(|l| format!(" xml:lang=\"{}\"", xml_escape(l)));
+                write!(
+                    writer,
+                    "<feed xmlns=\"http://www.w3.org/2005/Atom\"{}>",
+                    lang.unwrap_or_default()
+                )?;
+                write_xml_element(&mut writer, "title", title)?;
+                write!(writer, "<link href=\"{}\"/>", xml_escape(link))?;
+                write_xml_element(&mut writer, "id", link)?;
+                if !description.is_empty() {
+                    write_xml_element(&mut writer, "subtitle", description)?;
+                }
+                let updated = updated.unwrap_or_else(chrono::Utc::now);
+                write_xml_element(&mut writer, "updated", &updated.to_rfc3339())?;
+            }
+        }
+        Ok(Self { writer, feed_type })
+    }
+
+    fn handle_row(&mut self, data: &JsonValue) -> anyhow::Result<()> {
+        let title = get_object_str(data, "title").unwrap_or_default();
+        let link = get_object_str(data, "link");
+        let description = get_object_str(data, "description");
+        let author = get_object_str(data, "author");
+        let id = match data.get("id") {
+            Some(JsonValue::String(s)) => Some(Cow::Borrowed(s.as_str())),
+            Some(n @ JsonValue::Number(_)) => Some(Cow::Owned(n.to_string())),
+            _ => None,
+        };
+        let published = match data.get("date") {
+            Some(d) if !d.is_null() => Some(parse_feed_date(d)?),
+            _ => None,
+        };
+        let w = &mut self.writer;
+        match self.feed_type {
+            FeedType::Rss => {
+                w.write_all(b"\n<item>")?;
+                write_xml_element(w, "title", title)?;
+                if let Some(link) = link {
+                    write_xml_element(w, "link", link)?;
+                }
+                if let Some(description) = description {
+                    write_xml_element(w, "description", description)?;
+                }
+                if let Some(author) = author {
+                    write_xml_element(w, "author", author)?;
+                }
+                if let Some(guid) = id.as_deref().or(link) {
+                    let permalink = id.is_none();
+                    write!(
+                        w,
+                        "<guid isPermaLink=\"{permalink}\">{}</guid>",
+                        xml_escape(guid)
+                    )?;
+                }
+                if let Some(published) = published {
+                    write_xml_element(w, "pubDate", &published.to_rfc2822())?;
+                }
+                w.write_all(b"</item>")?;
+            }
+            FeedType::Atom => {
+                w.write_all(b"\n<entry>")?;
+                write_xml_element(w, "title", title)?;
+                if let Some(link) = link {
+                    write!(w, "<link href=\"{}\"/>", xml_escape(link))?;
+                }
+                let entry_id = id
+                    .as_deref()
+                    .or(link)
+                    .with_context(|| "Atom feed entries require an 'id' or a 'link'")?;
+                write_xml_element(w, "id", entry_id)?;
+                let published = published.with_context(|| "Atom feed entries require a 'date'")?;
+                write_xml_element(w, "updated", &published.to_rfc3339())?;
+                if let Some(description) = description {
+                    write_xml_element(w, "summary", description)?;
+                }
+                if let Some(author) = author {
+                    w.write_all(b"<author>")?;
+                    write_xml_element(w, "name", author)?;
+                    w.write_all(b"</author>")?;
+                }
+                w.write_all(b"</entry>")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn close(mut self) -> W {
+        let end: &[u8] = match self.feed_type {
+            FeedType::Rss => b"\n</channel></rss>\n",
+            FeedType::Atom => b"\n</feed>\n",
+        };
+        if let Err(e) = self.writer.write_all(end) {
+            log::error!("Unable to write the end of the feed: {e}");
+        }
+        self.writer
+    }
+}
+
+fn xml_escape(s: &str) -> Cow<'_, str> {
+    if !s.contains(['<', '>', '&', '"', '\'']) {
+        return Cow::Borrowed(s);
+    }
+    let mut escaped = String::with_capacity(s.len() + 16);
+    for c in s.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+fn write_xml_element(w: &mut impl std::io::Write, name: &str, text: &str) -> std::io::Result<()> {
+    write!(w, "<{name}>{}</{name}>", xml_escape(text))
+}
+
+/// Dates can be given as RFC 3339 strings, as `YYYY-MM-DD HH:MM:SS` or `YYYY-MM-DD` in UTC,
+/// or as unix timestamps
+fn parse_feed_date(date: &JsonValue) -> anyhow::Result<chrono::DateTime<chrono::Utc>> {
+    crate::dates::parse_date(date)
+        .with_context(|| format!("Invalid date in the feed component: {date}"))
+}
+
+#[test]
+fn test_parse_feed_date() {
+    let expected = "2024-01-15T10:30:00+00:00";
+    for date in [
+        json!("2024-01-15T10:30:00Z"),
+        json!("2024-01-15T11:30:00+01:00"),
+        json!("2024-01-15 10:30:00"),
+        json!(1_705_314_600),
+    ] {
+        assert_eq!(parse_feed_date(&date).unwrap().to_rfc3339(), expected);
+    }
+    assert_eq!(
+        parse_feed_date(&json!("2024-01-15")).unwrap().to_rfc2822(),
+        "Mon, 15 Jan 2024 00:00:00 +0000"
+    );
+    assert!(parse_feed_date(&json!("yesterday")).is_err());
+    assert_eq!(xml_escape("<a & 'b'>"), "&lt;a &amp; &apos;b&apos;&gt;");
+}
+
+pub(crate) async fn verify_password_async(
     password_hash: String,
     password: String,
 ) -> Result<Result<(), password_hash::Error>, anyhow::Error> {
@@ -240,6 +1250,52 @@ async fn verify_password_async(
     .await?
 }
 
+fn redirect_status(code: Option<u64>) -> anyhow::Result<StatusCode> {
+    match code {
+        Some(301) => Ok(StatusCode::MOVED_PERMANENTLY),
+        Some(302) => Ok(StatusCode::FOUND),
+        Some(303) => Ok(StatusCode::SEE_OTHER),
+        Some(307) => Ok(StatusCode::TEMPORARY_REDIRECT),
+        Some(308) => Ok(StatusCode::PERMANENT_REDIRECT),
+        _ => bail!("Invalid redirect status {code:?}. Expected one of 301, 302, 303, 307 or 308."),
+    }
+}
+
+/// Sites can customize how errors are displayed in a directory and its subdirectories
+/// by creating a `sqlpage/templates/<directory>/error.handlebars` file
+async fn error_component(app_state: &AppState, mut directory: &str) -> String {
+    while !directory.is_empty() {
+        let component = format!("{directory}/{ERROR_COMPONENT}");
+        let template = app_state.all_templates.get_template(app_state, &component);
+        if template.await.is_ok() {
+            return component;
+        }
+        directory = directory.rsplit_once('/').map_or("", |(parent, _)| parent);
+    }
+    ERROR_COMPONENT.to_string()
+}
+
+/// The properties of the error component, with the details of the error only when they can be shown
+fn error_properties(
+    app_state: &AppState,
+    error: &anyhow::Error,
+    request_id: Option<&str>,
+) -> JsonValue {
+    if app_state.config.verbose_errors() {
+        json!({
+            "description": error.to_string(),
+            "backtrace": get_backtrace(error),
+            "request_id": request_id,
+            "note": "You can hide error messages like this one from your users by setting the 'environment' configuration option to 'production'."
+        })
+    } else {
+        json!({
+            "description": "Please contact the administrator for more information. The error has been logged.",
+            "request_id": request_id,
+        })
+    }
+}
+
 fn get_backtrace(error: &anyhow::Error) -> Vec<String> {
     let mut backtrace = vec![];
     let mut source = error.source();
@@ -250,12 +1306,45 @@ fn get_backtrace(error: &anyhow::Error) -> Vec<String> {
     backtrace
 }
 
+/// Options that are on unless they are explicitly turned off: a missing or null value keeps them on
+fn enabled_by_default(value: Option<&JsonValue>) -> bool {
+    value.filter(|v| !v.is_null()).map_or(true, is_truthy)
+}
+
+/// Session cookies are secure unless the `secure` property is false
+fn is_secure(data: &JsonValue) -> bool {
+    let secure = data.get("secure");
+    secure != Some(&json!(false)) && secure != Some(&json!(0))
+}
+
+fn json_to_session_value(value: Option<&JsonValue>) -> Option<Cow<'_, str>> {
+    match value? {
+        JsonValue::Null => None,
+        JsonValue::String(s) => Some(Cow::Borrowed(s)),
+        other => Some(Cow::Owned(other.to_string())),
+    }
+}
+
 fn get_object_str<'a>(json: &'a JsonValue, key: &str) -> Option<&'a str> {
     json.as_object()
         .and_then(|obj| obj.get(key))
         .and_then(JsonValue::as_str)
 }
 
+/// Splits a `data:` url into its content type and its decoded contents
+fn parse_data_url(url: &str) -> Option<(&str, anyhow::Result<Vec<u8>>)> {
+    let (metadata, contents) = url.strip_prefix("data:")?.split_once(',')?;
+    if let Some(content_type) = metadata.strip_suffix(";base64") {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(contents)
+            .with_context(|| "Invalid base64 data url");
+        Some((content_type, bytes))
+    } else {
+        let bytes = percent_encoding::percent_decode_str(contents).collect();
+        Some((metadata, Ok(bytes)))
+    }
+}
+
 fn take_object_str(json: &mut JsonValue, key: &str) -> Option<String> {
     match json.get_mut(key)?.take() {
         JsonValue::String(s) => Some(s),
@@ -271,11 +1360,20 @@ pub struct RenderContext<W: std::io::Write> {
     shell_renderer: SplitTemplateRenderer,
     recursion_depth: usize,
     current_statement: usize,
+    /// Directory of the sql file, where a custom error component is looked up
+    directory: String,
+    request_id: Option<String>,
+    locals: PageLocals,
 }
 
 const DEFAULT_COMPONENT: &str = "debug";
 const SHELL_COMPONENT: &str = "shell";
+const ERROR_COMPONENT: &str = "error";
+/// Replaces the shell when only the components of the page are rendered
+const FRAGMENT_SHELL_COMPONENT: &str = "shell-empty";
 const DYNAMIC_COMPONENT: &str = "dynamic";
+/// Row property containing components to render inside the row
+const CHILDREN_PROPERTY: &str = "_sqlpage_children";
 const MAX_RECURSION_DEPTH: usize = 256;
 
 impl<W: std::io::Write> RenderContext<W> {
@@ -283,11 +1381,28 @@ impl<W: std::io::Write> RenderContext<W> {
         app_state: Arc<AppState>,
         mut writer: W,
         mut initial_row: JsonValue,
+        options: PageOptions,
     ) -> anyhow::Result<RenderContext<W>> {
         log::debug!("Creating the shell component for the page");
-        let mut shell_renderer = Self::create_renderer(SHELL_COMPONENT, Arc::clone(&app_state))
-            .await
-            .with_context(|| "The shell component should always exist")?;
+        let shell_component = if options.fragment {
+            FRAGMENT_SHELL_COMPONENT
+        } else {
+            SHELL_COMPONENT
+        };
+        let locals = PageLocals {
+            language: options.languages.first().cloned(),
+            translations: app_state
+                .all_templates
+                .get_translations(&app_state, &options.languages)
+                .await,
+            path: Some(options.path).filter(|p| !p.is_empty()),
+            csrf_token: options.csrf.map(|csrf| csrf.token),
+            csp_nonce: options.csp_nonce,
+        };
+        let mut shell_renderer =
+            Self::create_renderer(shell_component, Arc::clone(&app_state), locals.clone())
+                .await
+                .with_context(|| "The shell component should always exist")?;
 
         let mut initial_component =
             Some(get_object_str(&initial_row, "component").unwrap_or(DEFAULT_COMPONENT));
@@ -312,6 +1427,18 @@ impl<W: std::io::Write> RenderContext<W> {
             _ => log::trace!("The first row is not a shell component, so we will render a shell with default properties"),
         }
 
+        if options.print || app_state.config.accessibility_audit() {
+            if !shell_properties.is_object() {
+                shell_properties = JsonValue::Object(serde_json::Map::new());
+            }
+            if options.print {
+                shell_properties["print"] = JsonValue::Bool(true);
+            }
+            if app_state.config.accessibility_audit() {
+                shell_properties["_sqlpage_accessibility_audit"] = JsonValue::Bool(true);
+            }
+        }
+
         log::debug!("Rendering the shell with properties: {shell_properties}");
         shell_renderer.render_start(&mut writer, shell_properties)?;
 
@@ -322,6 +1449,9 @@ impl<W: std::io::Write> RenderContext<W> {
             shell_renderer,
             recursion_depth: 0,
             current_statement: 1,
+            directory: options.directory,
+            request_id: options.request_id,
+            locals,
         };
 
         if let Some(component) = initial_component {
@@ -350,6 +1480,15 @@ impl<W: std::io::Write> RenderContext<W> {
         let new_component = get_object_str(data, "component");
         let current_component = self.current_component().await?.name();
         match (current_component, new_component) {
+            (_current_component, Some("flush")) => {
+                anyhow::ensure!(
+                    data.get("buffering").map_or(true, is_truthy),
+                    "Buffering can only be disabled before any data has been sent to the client's browser. \
+                    Move the flush component to the top of the SQL file."
+                );
+                // The output is flushed after each row anyway, so it will be sent right away
+                log::trace!("Flushing the output");
+            }
             (_current_component, Some(DYNAMIC_COMPONENT)) => {
                 self.render_dynamic(data).await.with_context(|| {
                     format!("Unable to render dynamic component with properties {data}")
@@ -359,7 +1498,7 @@ impl<W: std::io::Write> RenderContext<W> {
                 _,
                 Some(
                     component_name @ ("status_code" | "http_header" | "redirect" | "json"
-                    | "cookie" | "authentication"),
+                    | "cookie" | "authentication" | "pdf"),
                 ),
             ) => {
                 bail!("The {component_name} component cannot be used after data has already been sent to the client's browser. \
@@ -370,12 +1509,56 @@ impl<W: std::io::Write> RenderContext<W> {
                 self.open_component_with_data(new_component, &data).await?;
             }
             (_, _) => {
+                let data = self.render_children(data).await?;
                 self.render_current_template_with_data(&data).await?;
             }
         }
         Ok(())
     }
 
+    /// Replaces the child components of a row, given as a json list of rows in its
+    /// `_sqlpage_children` property, by their html rendering
+    async fn render_children<'a>(&self, data: &'a JsonValue) -> anyhow::Result<Cow<'a, JsonValue>> {
+        let Some(children) = data.get(CHILDREN_PROPERTY) else {
+            return Ok(Cow::Borrowed(data));
+        };
+        anyhow::ensure!(
+            self.recursion_depth <= MAX_RECURSION_DEPTH,
+            "Maximum recursion depth exceeded in nested components."
+        );
+        let rows = Self::extract_dynamic_properties(&json!({ "properties": children }))
+            .with_context(|| format!("Invalid child components in {CHILDREN_PROPERTY}"))?
+            .into_iter()
+            .map(Cow::into_owned)
+            .collect::<Vec<_>>();
+        let mut shell_renderer = Self::create_renderer(
+            FRAGMENT_SHELL_COMPONENT,
+            Arc::clone(&self.app_state),
+            self.locals.clone(),
+        )
+        .await?;
+        let mut writer = Vec::new();
+        shell_renderer.render_start(&mut writer, JsonValue::Null)?;
+        let mut child_context = RenderContext {
+            app_state: Arc::clone(&self.app_state),
+            writer,
+            current_component: None,
+            shell_renderer,
+            recursion_depth: self.recursion_depth + 1,
+            current_statement: self.current_statement,
+            directory: self.directory.clone(),
+            request_id: self.request_id.clone(),
+            locals: self.locals.clone(),
+        };
+        for row in &rows {
+            child_context.handle_row(row).await?;
+        }
+        let html = String::from_utf8(child_context.close().await)?;
+        let mut data = data.clone();
+        data[CHILDREN_PROPERTY] = JsonValue::String(html);
+        Ok(Cow::Owned(data))
+    }
+
     fn extract_dynamic_properties(data: &Value) -> anyhow::Result<Vec<Cow<'_, JsonValue>>> {
         let properties_key = "properties";
         let properties_obj = data
@@ -421,21 +1604,17 @@ impl<W: std::io::Write> RenderContext<W> {
     /// Handles the rendering of an error.
     /// Returns whether the error is irrecoverable and the rendering must stop
     pub async fn handle_error(&mut self, error: &anyhow::Error) -> anyhow::Result<()> {
-        log::error!("SQL error: {:?}", error);
+        let request_id = self.request_id.as_deref().unwrap_or("-");
+        log::error!("SQL error in request {request_id}: {error:?}");
         self.close_component()?;
-        let data = if self.app_state.config.environment.is_prod() {
-            json!({
-                "description": format!("Please contact the administrator for more information. The error has been logged."),
-            })
-        } else {
-            json!({
-                "query_number": self.current_statement,
-                "description": error.to_string(),
-                "backtrace": get_backtrace(error),
-                "note": "You can hide error messages like this one from your users by setting the 'environment' configuration option to 'production'."
-            })
-        };
-        let saved_component = self.open_component_with_data("error", &data).await?;
+        let mut data = error_properties(&self.app_state, error, self.request_id.as_deref());
+        if self.app_state.config.verbose_errors() {
+            data["query_number"] = self.current_statement.into();
+        }
+        let error_component = error_component(&self.app_state, &self.directory).await;
+        let saved_component = self
+            .open_component_with_data(&error_component, &data)
+            .await?;
         self.close_component()?;
         self.current_component = saved_component;
         Ok(())
@@ -474,12 +1653,15 @@ impl<W: std::io::Write> RenderContext<W> {
     async fn create_renderer(
         component: &str,
         app_state: Arc<AppState>,
+        locals: PageLocals,
     ) -> anyhow::Result<SplitTemplateRenderer> {
         let split_template = app_state
             .all_templates
             .get_template(&app_state, component)
             .await?;
-        Ok(SplitTemplateRenderer::new(split_template, app_state))
+        let mut renderer = SplitTemplateRenderer::new(split_template, app_state);
+        renderer.locals = locals;
+        Ok(renderer)
     }
 
     /// Set a new current component and return the old one
@@ -487,7 +1669,9 @@ impl<W: std::io::Write> RenderContext<W> {
         &mut self,
         component: &str,
     ) -> anyhow::Result<Option<SplitTemplateRenderer>> {
-        let new_component = Self::create_renderer(component, Arc::clone(&self.app_state)).await?;
+        let new_component =
+            Self::create_renderer(component, Arc::clone(&self.app_state), self.locals.clone())
+                .await?;
         Ok(self.current_component.replace(new_component))
     }
 
@@ -542,6 +1726,124 @@ pub struct SplitTemplateRenderer {
     ctx: Context,
     app_state: Arc<AppState>,
     row_index: usize,
+    locals: PageLocals,
+    grouping: Option<RowGrouping>,
+}
+
+/// Row property set on the first row of each group, when the component has a `group_by` property
+const GROUP_START_PROPERTY: &str = "_sqlpage_group";
+/// Row property set when a group ends, with the number of rows and the totals of the group.
+/// It is set on the first row of the next group, and on the component itself for the last group.
+const GROUP_END_PROPERTY: &str = "_sqlpage_group_end";
+
+/// Groups consecutive rows that have the same value in the `group_by` column,
+/// and computes the totals of the `group_sum` columns in each group
+struct RowGrouping {
+    column: String,
+    sum_columns: Vec<String>,
+    current: Option<RowGroup>,
+}
+
+struct RowGroup {
+    value: JsonValue,
+    count: usize,
+    sums: Vec<f64>,
+    /// Columns of the rows of the group that are displayed, in order
+    columns: Vec<String>,
+}
+
+impl RowGrouping {
+    fn from_properties(properties: &JsonValue) -> Option<Self> {
+        let column = get_object_str(properties, "group_by")?.to_string();
+        let sum_columns = match properties.get("group_sum") {
+            Some(JsonValue::Array(columns)) => columns
+                .iter()
+                .filter_map(|c| c.as_str().map(String::from))
+                .collect(),
+            Some(JsonValue::String(s)) => {
+                serde_json::from_str(s).unwrap_or_else(|_| vec![s.clone()])
+            }
+            _ => Vec::new(),
+        };
+        Some(Self {
+            column,
+            sum_columns,
+            current: None,
+        })
+    }
+
+    /// Adds the group properties to a row, and updates the totals of its group
+    fn add_row(&mut self, row: &mut JsonValue) {
+        let Some(object) = row.as_object_mut() else {
+            return;
+        };
+        let value = object.get(&self.column).cloned().unwrap_or_default();
+        if self.current.as_ref().map_or(true, |g| g.value != value) {
+            if let Some(ended) = self.current.take() {
+                object.insert(
+                    GROUP_END_PROPERTY.into(),
+                    ended.into_json(&self.sum_columns),
+                );
+            }
+            let columns: Vec<String> = object
+                .keys()
+                .filter(|k| !k.starts_with("_sqlpage_"))
+                .cloned()
+                .collect();
+            object.insert(
+                GROUP_START_PROPERTY.into(),
+                json!({ "value": value, "columns": columns.len() }),
+            );
+            self.current = Some(RowGroup {
+                value,
+                count: 0,
+                sums: vec![0.; self.sum_columns.len()],
+                columns,
+            });
+        }
+        let group = self
+            .current
+            .as_mut()
+            .expect("the current group was just set");
+        group.count += 1;
+        for (sum, column) in group.sums.iter_mut().zip(&self.sum_columns) {
+            *sum += object
+                .get(column)
+                .and_then(crate::templates::as_f64)
+                .unwrap_or_default();
+        }
+    }
+
+    /// Properties of the last group, once all the rows have been rendered
+    fn finish(self) -> Option<JsonValue> {
+        Some(self.current?.into_json(&self.sum_columns))
+    }
+}
+
+impl RowGroup {
+    fn into_json(self, sum_columns: &[String]) -> JsonValue {
+        let sums: serde_json::Map<String, JsonValue> = sum_columns
+            .iter()
+            .cloned()
+            .zip(self.sums.into_iter().map(number_to_json))
+            .collect();
+        let cells: Vec<JsonValue> = self
+            .columns
+            .iter()
+            .map(|c| json!({ "column": c, "total": sums.get(c) }))
+            .collect();
+        json!({ "value": self.value, "count": self.count, "sum": sums, "cells": cells })
+    }
+}
+
+/// Integers are displayed without a decimal part
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn number_to_json(n: f64) -> JsonValue {
+    if n.fract() == 0. && n.abs() < 1e15 {
+        JsonValue::from(n as i64)
+    } else {
+        JsonValue::from(n)
+    }
 }
 
 impl SplitTemplateRenderer {
@@ -552,6 +1854,8 @@ impl SplitTemplateRenderer {
             app_state,
             row_index: 0,
             ctx: Context::null(),
+            locals: PageLocals::default(),
+            grouping: None,
         }
     }
     fn name(&self) -> &str {
@@ -575,6 +1879,24 @@ impl SplitTemplateRenderer {
                 .unwrap_or_default(),
         );
         let mut render_context = handlebars::RenderContext::new(None);
+        if let Some(block) = render_context.block_mut() {
+            if let Some(translations) = &self.locals.translations {
+                block.set_local_var(TRANSLATIONS, translations.0.clone());
+            }
+            if let Some(language) = &self.locals.language {
+                block.set_local_var(LOCALE, JsonValue::String(language.clone()));
+            }
+            if let Some(path) = &self.locals.path {
+                block.set_local_var(CURRENT_PATH, JsonValue::String(path.clone()));
+            }
+            if let Some(token) = &self.locals.csrf_token {
+                block.set_local_var(CSRF_TOKEN, JsonValue::String(token.clone()));
+            }
+            if let Some(nonce) = &self.locals.csp_nonce {
+                block.set_local_var(CSP_NONCE, JsonValue::String(nonce.clone()));
+            }
+        }
+        self.grouping = RowGrouping::from_properties(&data);
         *self.ctx.data_mut() = data;
         let mut output = HandlebarWriterOutput(writer);
         self.split_template.before_list.render(
@@ -593,9 +1915,12 @@ impl SplitTemplateRenderer {
     fn render_item<W: std::io::Write>(
         &mut self,
         writer: W,
-        data: JsonValue,
+        mut data: JsonValue,
     ) -> Result<(), RenderError> {
         log::trace!("Rendering a new item in the page: {data:?}");
+        if let Some(grouping) = &mut self.grouping {
+            grouping.add_row(&mut data);
+        }
         if let Some(local_vars) = self.local_vars.take() {
             let mut render_context = handlebars::RenderContext::new(None);
             let blk = render_context
@@ -630,6 +1955,11 @@ impl SplitTemplateRenderer {
                 .map(|name| format!("('{name}')"))
                 .unwrap_or_default(),
         );
+        if let Some(group_end) = self.grouping.take().and_then(RowGrouping::finish) {
+            if let Some(data) = self.ctx.data_mut().as_object_mut() {
+                data.insert(GROUP_END_PROPERTY.into(), group_end);
+            }
+        }
         if let Some(mut local_vars) = self.local_vars.take() {
             let mut render_context = handlebars::RenderContext::new(None);
             local_vars.put("row_index", self.row_index.into());
@@ -680,6 +2010,32 @@ mod tests {
         Ok(())
     }
 
+    #[actix_web::test]
+    async fn test_group_by() -> anyhow::Result<()> {
+        let template = Template::compile(
+            "{{#each_row}}\
+            {{#if _sqlpage_group_end}}[{{_sqlpage_group_end.count}}: {{_sqlpage_group_end.sum.n}}]{{/if}}\
+            {{#if _sqlpage_group}}{{_sqlpage_group.value}}:{{/if}} {{n}} \
+            {{/each_row}}\
+            [{{_sqlpage_group_end.count}}: {{_sqlpage_group_end.sum.n}}]",
+        )?;
+        let split = split_template(template);
+        let mut output = Vec::new();
+        let config = app_config::tests::test_config();
+        let app_state = Arc::new(AppState::init(&config).await.unwrap());
+        let mut rdr = SplitTemplateRenderer::new(Arc::new(split), app_state);
+        rdr.render_start(&mut output, json!({"group_by": "g", "group_sum": "n"}))?;
+        rdr.render_item(&mut output, json!({"g": "a", "n": 1}))?;
+        rdr.render_item(&mut output, json!({"g": "a", "n": "2.5"}))?;
+        rdr.render_item(&mut output, json!({"g": "b", "n": 3}))?;
+        rdr.render_end(&mut output)?;
+        assert_eq!(
+            String::from_utf8_lossy(&output),
+            "a: 1  2.5 [2: 3.5]b: 3 [1: 3]"
+        );
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn test_delayed() -> anyhow::Result<()> {
         let template = Template::compile(
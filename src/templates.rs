@@ -1,6 +1,8 @@
+use crate::app_config::AppConfig;
 use crate::file_cache::AsyncFromStrWithState;
 use crate::utils::static_filename;
 use crate::{AppState, FileCache, TEMPLATES_DIR};
+use anyhow::Context as _;
 use async_trait::async_trait;
 use handlebars::{
     handlebars_helper, template::TemplateElement, Context, Handlebars, JsonValue, RenderError,
@@ -9,10 +11,25 @@ use handlebars::{
 use handlebars::{PathAndJson, RenderErrorReason};
 use include_dir::{include_dir, Dir};
 use std::borrow::Cow;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 pub(crate) const DELAYED_CONTENTS: &str = "_delayed_contents";
+/// Local variable holding the translations of the user's language, used by the `t` helper
+pub(crate) const TRANSLATIONS: &str = "_translations";
+/// Local variable holding the preferred language of the user, used to format numbers
+pub(crate) const LOCALE: &str = "_locale";
+/// Local variable holding the path of the requested page, used by the `is_current_path` helper
+pub(crate) const CURRENT_PATH: &str = "_current_path";
+/// Local variable holding the CSRF token of the user, used by the `csrf_token` helper
+pub(crate) const CSRF_TOKEN: &str = "_csrf_token";
+/// Local variable holding the nonce of the `Content-Security-Policy` of the page, used by the `csp_nonce` helper
+pub(crate) const CSP_NONCE: &str = "_csp_nonce";
+const TRANSLATIONS_DIR: &str = "sqlpage/translations";
+/// Handlebars templates in this directory are registered as helpers, named after their file
+const HELPERS_DIR: &str = "sqlpage/helpers";
+/// Handlebars templates in this directory can be included in components with `{{> name}}`
+const PARTIALS_DIR: &str = "sqlpage/partials";
 
 pub struct SplitTemplate {
     pub before_list: Template,
@@ -73,10 +90,41 @@ fn is_template_list_item(element: &TemplateElement) -> bool {
                         if matches!(&tpl.name, Name(name) if name == "each_row"))
 }
 
+/// The translations of the texts of the components in a language, as a json object
+pub struct Translations(pub JsonValue);
+
+/// Information about the current request that the helpers of all the templates of a page can use
+#[derive(Clone, Default)]
+pub struct PageLocals {
+    /// Preferred language of the user
+    pub language: Option<String>,
+    /// Translations of the components in the language of the user
+    pub translations: Option<Arc<Translations>>,
+    /// Path of the requested page
+    pub path: Option<String>,
+    /// Token that forms must contain to be accepted, when CSRF protection is enabled
+    pub csrf_token: Option<String>,
+    /// Nonce that allows inline scripts and styles in the `Content-Security-Policy` of the page
+    pub csp_nonce: Option<String>,
+}
+
+#[async_trait(? Send)]
+impl AsyncFromStrWithState for Translations {
+    async fn from_str_with_state(_app_state: &AppState, source: &str) -> anyhow::Result<Self> {
+        let translations: serde_json::Map<String, JsonValue> = serde_json::from_str(source)
+            .with_context(|| "Translation files must contain a json object")?;
+        if let Some((key, _)) = translations.iter().find(|(_, v)| !v.is_string()) {
+            anyhow::bail!("The translation of {key:?} must be a string");
+        }
+        Ok(Self(JsonValue::Object(translations)))
+    }
+}
+
 #[allow(clippy::module_name_repetitions)]
 pub struct AllTemplates {
     pub handlebars: Handlebars<'static>,
     split_templates: FileCache<SplitTemplate>,
+    translations: FileCache<Translations>,
 }
 
 fn with_each_block<'a, 'reg, 'rc>(
@@ -161,157 +209,865 @@ fn sum_helper<'reg, 'rc>(
     Ok(())
 }
 
-fn icon_img_helper<'reg, 'rc>(
+/// percentage: the ratio between a value and a maximum (100 by default), between 0 and 100
+fn percentage_helper<'reg, 'rc>(
     helper: &handlebars::Helper<'rc>,
     _r: &'reg Handlebars<'reg>,
     _ctx: &'rc Context,
     _rc: &mut handlebars::RenderContext<'reg, 'rc>,
     writer: &mut dyn handlebars::Output,
 ) -> handlebars::HelperResult {
-    let null = handlebars::JsonValue::Null;
-    let params = [0, 1].map(|i| helper.params().get(i).map_or(&null, PathAndJson::value));
-    let name = match params[0] {
-        JsonValue::String(s) => s,
-        other => {
-            log::debug!("icon_img: {other:?} is not an icon name, not rendering anything");
+    let null = JsonValue::Null;
+    let value = helper
+        .param(0)
+        .map_or(&null, handlebars::PathAndJson::value);
+    let max = helper
+        .param(1)
+        .map_or(&null, handlebars::PathAndJson::value);
+    write!(writer, "{}", percentage(value, max))?;
+    Ok(())
+}
+
+/// `t`: the translation of a text in the language of the user, or the text itself when it is not translated
+struct TranslateHelper;
+
+impl handlebars::HelperDef for TranslateHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        helper: &handlebars::Helper<'rc>,
+        _r: &'reg Handlebars<'reg>,
+        _ctx: &'rc Context,
+        rc: &mut handlebars::RenderContext<'reg, 'rc>,
+    ) -> Result<handlebars::ScopedJson<'rc>, RenderError> {
+        let key = helper
+            .param(0)
+            .and_then(|p| p.value().as_str())
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("t", 0))?;
+        let mut translation = None;
+        with_each_block(rc, |block, _is_last| {
+            if translation.is_none() {
+                translation = block
+                    .get_local_var(TRANSLATIONS)
+                    .and_then(|t| t.get(key))
+                    .cloned();
+            }
+            Ok(())
+        })?;
+        let text = translation.unwrap_or_else(|| JsonValue::String(key.to_string()));
+        Ok(handlebars::ScopedJson::Derived(text))
+    }
+}
+
+/// Returns the value of a local variable of the page, such as the CSRF token, or null when it is not set:
+/// - `csrf_token`: the token that forms must contain in their `_sqlpage_csrf` field to be accepted
+/// - `csp_nonce`: the nonce that allows an inline script or style in the `Content-Security-Policy`
+struct PageLocalHelper(&'static str);
+
+impl handlebars::HelperDef for PageLocalHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        _helper: &handlebars::Helper<'rc>,
+        _r: &'reg Handlebars<'reg>,
+        _ctx: &'rc Context,
+        rc: &mut handlebars::RenderContext<'reg, 'rc>,
+    ) -> Result<handlebars::ScopedJson<'rc>, RenderError> {
+        let mut value = None;
+        with_each_block(rc, |block, _is_last| {
+            if value.is_none() {
+                value = block.get_local_var(self.0).cloned();
+            }
+            Ok(())
+        })?;
+        Ok(handlebars::ScopedJson::Derived(
+            value.unwrap_or(JsonValue::Null),
+        ))
+    }
+}
+
+/// `markdown`: the html rendering of a markdown text.
+/// As a block (`{{#markdown}}...{{/markdown}}`), renders the markdown written inside the block.
+struct MarkdownHelper;
+
+fn markdown_to_html(markdown_text: &str) -> String {
+    markdown::to_html_with_options(markdown_text, &markdown::Options::gfm()).unwrap_or_else(|s| s)
+}
+
+impl handlebars::HelperDef for MarkdownHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        helper: &handlebars::Helper<'rc>,
+        _r: &'reg Handlebars<'reg>,
+        _ctx: &'rc Context,
+        _rc: &mut handlebars::RenderContext<'reg, 'rc>,
+    ) -> Result<handlebars::ScopedJson<'rc>, RenderError> {
+        let as_str = match helper.param(0).map(PathAndJson::value) {
+            Some(JsonValue::String(s)) => Cow::Borrowed(s.as_str()),
+            Some(JsonValue::Array(arr)) => Cow::Owned(
+                arr.iter()
+                    .map(|v| v.as_str().unwrap_or_default())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            Some(JsonValue::Null) | None => Cow::Borrowed(""),
+            Some(other) => Cow::Owned(other.to_string()),
+        };
+        Ok(handlebars::ScopedJson::Derived(JsonValue::String(
+            markdown_to_html(&as_str),
+        )))
+    }
+
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        helper: &handlebars::Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut handlebars::RenderContext<'reg, 'rc>,
+        out: &mut dyn handlebars::Output,
+    ) -> handlebars::HelperResult {
+        if let Some(template) = helper.template() {
+            let mut contents = handlebars::StringOutput::new();
+            template.render(r, ctx, rc, &mut contents)?;
+            out.write(&markdown_to_html(&contents.into_string()?))?;
             return Ok(());
         }
+        let html = self.call_inner(helper, r, ctx, rc)?.render();
+        if rc.is_disable_escape() {
+            out.write(&html)?;
+        } else {
+            out.write(&r.get_escape_fn()(&html))?;
+        }
+        Ok(())
+    }
+}
+
+/// A helper defined by the user as a handlebars template.
+/// The template is rendered with the positional parameters of the helper in `args`,
+/// and its named parameters as top-level variables.
+struct TemplateHelper(Template);
+
+impl handlebars::HelperDef for TemplateHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        helper: &handlebars::Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        _ctx: &'rc Context,
+        rc: &mut handlebars::RenderContext<'reg, 'rc>,
+        out: &mut dyn handlebars::Output,
+    ) -> handlebars::HelperResult {
+        let mut data: serde_json::Map<String, JsonValue> = helper
+            .hash()
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), v.value().clone()))
+            .collect();
+        let args = helper.params().iter().map(|p| p.value().clone()).collect();
+        data.insert("args".into(), JsonValue::Array(args));
+        let ctx = Context::wraps(data)?;
+        let mut helper_rc = handlebars::RenderContext::new(None);
+        // Keep the language of the user, for the helpers that depend on it
+        for var in [TRANSLATIONS, LOCALE, CURRENT_PATH, CSRF_TOKEN, CSP_NONCE] {
+            let mut value = None;
+            with_each_block(rc, |block, _is_last| {
+                if value.is_none() {
+                    value = block.get_local_var(var).cloned();
+                }
+                Ok(())
+            })?;
+            if let (Some(value), Some(block)) = (value, helper_rc.block_mut()) {
+                block.set_local_var(var, value);
+            }
+        }
+        self.0.render(r, &ctx, &mut helper_rc, out)
+    }
+}
+
+/// The handlebars templates of a directory of the site, with their names.
+/// A missing directory contains no templates.
+fn read_template_files(dir: &Path) -> anyhow::Result<Vec<(String, PathBuf, String)>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e).with_context(|| format!("Unable to read {dir:?}")),
+    };
+    let mut files = vec![];
+    for entry in entries {
+        let file_path = entry?.path();
+        if file_path.extension() != Some("handlebars".as_ref()) {
+            continue;
+        }
+        let Some(name) = file_path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let source = std::fs::read_to_string(&file_path)
+            .with_context(|| format!("Unable to read {file_path:?}"))?;
+        files.push((name.to_string(), file_path, source));
+    }
+    Ok(files)
+}
+
+/// Registers the helpers defined in `sqlpage/helpers/<name>.handlebars`.
+/// They can replace the built-in helpers with the same name.
+fn register_template_helpers(handlebars: &mut Handlebars<'_>, dir: &Path) -> anyhow::Result<()> {
+    for (name, file_path, source) in read_template_files(dir)? {
+        log::info!("Registering the {name} helper from {file_path:?}");
+        let tpl = Template::compile_with_name(&source, name.clone())
+            .with_context(|| format!("Invalid helper template in {file_path:?}"))?;
+        handlebars.register_helper(&name, Box::new(TemplateHelper(tpl)));
+    }
+    Ok(())
+}
+
+/// Registers the built-in partials, and then the ones defined in `sqlpage/partials/<name>.handlebars`,
+/// that replace the built-in partials with the same name.
+fn register_partials(handlebars: &mut Handlebars<'_>, dir: &Path) -> anyhow::Result<()> {
+    for file in STATIC_PARTIALS.files() {
+        let name = file.path().file_stem().unwrap().to_string_lossy();
+        let source = String::from_utf8_lossy(file.contents());
+        handlebars
+            .register_partial(&name, source)
+            .with_context(|| format!("Invalid built-in partial {name}"))?;
+    }
+    for (name, file_path, source) in read_template_files(dir)? {
+        log::info!("Registering the {name} partial from {file_path:?}");
+        handlebars
+            .register_partial(&name, source)
+            .with_context(|| format!("Invalid partial in {file_path:?}"))?;
+    }
+    Ok(())
+}
+
+fn sanitize_html_helper<'reg, 'rc>(
+    helper: &handlebars::Helper<'rc>,
+    _r: &'reg Handlebars<'reg>,
+    _ctx: &'rc Context,
+    _rc: &mut handlebars::RenderContext<'reg, 'rc>,
+    writer: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    use crate::html_sanitizer::{sanitize_html, SanitizeLevel};
+    let html = match helper.param(0).map(PathAndJson::value) {
+        Some(JsonValue::String(s)) => Cow::Borrowed(s.as_str()),
+        Some(JsonValue::Null) | None => return Ok(()),
+        Some(other) => Cow::Owned(other.to_string()),
+    };
+    let level = match helper.param(1).map(PathAndJson::value) {
+        Some(JsonValue::String(name)) => SanitizeLevel::from_name(name).ok_or_else(|| {
+            RenderErrorReason::Other(format!(
+                "Invalid html sanitization level {name:?}. Expected none, strict, or basic."
+            ))
+        })?,
+        _ => SanitizeLevel::None,
+    };
+    writer.write(&sanitize_html(&html, level))?;
+    Ok(())
+}
+
+/// `qr_code`: an inline SVG image of a QR code that encodes the given text,
+/// with an optional error correction level (L, M, Q, or H)
+fn qr_code_helper<'reg, 'rc>(
+    helper: &handlebars::Helper<'rc>,
+    _r: &'reg Handlebars<'reg>,
+    _ctx: &'rc Context,
+    _rc: &mut handlebars::RenderContext<'reg, 'rc>,
+    writer: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    use crate::qr_code::{ErrorCorrection, QrCode};
+    let text = match helper.param(0).map(PathAndJson::value) {
+        Some(JsonValue::String(s)) => Cow::Borrowed(s.as_str()),
+        Some(JsonValue::Null) | None => return Ok(()),
+        Some(other) => Cow::Owned(other.to_string()),
+    };
+    let ecl = match helper.param(1).map(PathAndJson::value) {
+        Some(JsonValue::String(name)) => ErrorCorrection::from_name(name).ok_or_else(|| {
+            RenderErrorReason::Other(format!(
+                "Invalid QR code error correction level {name:?}. Expected L, M, Q, or H."
+            ))
+        })?,
+        _ => ErrorCorrection::Medium,
     };
-    let size = params[1].as_u64().unwrap_or(24);
+    let qr = QrCode::encode(&text, ecl).map_err(|e| RenderErrorReason::Other(e.to_string()))?;
+    writer.write(&qr.to_svg())?;
+    Ok(())
+}
+
+/// `format_date`: a date formatted with a strftime-like pattern (`%Y-%m-%d %H:%M` by default),
+/// in the time zone given by the `tz` hash parameter (UTC by default)
+fn format_date_helper<'reg, 'rc>(
+    helper: &handlebars::Helper<'rc>,
+    _r: &'reg Handlebars<'reg>,
+    _ctx: &'rc Context,
+    _rc: &mut handlebars::RenderContext<'reg, 'rc>,
+    writer: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    use crate::dates::{parse_date, Tz};
+    let date = match helper.param(0).map(PathAndJson::value) {
+        Some(JsonValue::Null) | None => return Ok(()),
+        Some(value) => parse_date(value).ok_or_else(|| {
+            RenderErrorReason::Other(format!("format_date: invalid date {value}"))
+        })?,
+    };
+    let format = helper
+        .param(1)
+        .and_then(|p| p.value().as_str())
+        .unwrap_or("%Y-%m-%d %H:%M");
+    let offset = match helper.hash_get("tz").map(PathAndJson::value) {
+        Some(JsonValue::String(name)) => Tz::from_name(name)
+            .map_err(|e| RenderErrorReason::Other(format!("format_date: {e:#}")))?
+            .offset_at(&date),
+        _ => chrono::FixedOffset::east_opt(0).unwrap(),
+    };
+    let items = chrono::format::StrftimeItems::new(format).collect::<Vec<_>>();
+    if items.contains(&chrono::format::Item::Error) {
+        return Err(RenderErrorReason::Other(format!(
+            "format_date: invalid date format {format:?}"
+        ))
+        .into());
+    }
     write!(
         writer,
-        "<svg width={size} height={size}><use href=\"/{}#tabler-{name}\" /></svg>",
-        static_filename!("tabler-icons.svg")
+        "{}",
+        date.with_timezone(&offset)
+            .format_with_items(items.into_iter())
     )?;
     Ok(())
 }
 
-const STATIC_TEMPLATES: Dir = include_dir!("$CARGO_MANIFEST_DIR/sqlpage/templates");
-
-impl AllTemplates {
-    pub fn init() -> anyhow::Result<Self> {
-        let mut handlebars = Handlebars::new();
+/// `is_current_path`: whether a link points to the requested page.
+/// The parameters are concatenated to form the link: `(is_current_path name ".sql")`.
+/// A menu item object is current when its `link` or any of its `submenu` items is.
+/// In partials, which do not see the variables of the page, the path can be given with `path=`.
+struct IsCurrentPathHelper;
 
-        handlebars_helper!(stringify: |v: Json| v.to_string());
-        handlebars.register_helper("stringify", Box::new(stringify));
-
-        handlebars_helper!(parse_json: |v: Json| match v {
-            obj @ serde_json::value::Value::String(s) =>
-                serde_json::from_str(s)
-                .unwrap_or_else(|_| {
-                    log::warn!("Failed to parse JSON string: {}", s);
-                    obj.clone()
-                }),
-            other => other.clone()
-        });
-        handlebars.register_helper("parse_json", Box::new(parse_json));
-
-        handlebars_helper!(default: |a: Json, b:Json| if a.is_null() {b} else {a}.clone());
-        handlebars.register_helper("default", Box::new(default));
-
-        handlebars_helper!(entries: |v: Json | match v {
-            serde_json::value::Value::Object(map) =>
-                map.into_iter()
-                    .map(|(k, v)| serde_json::json!({"key": k, "value": v}))
-                    .collect(),
-            serde_json::value::Value::Array(values) =>
-                values.iter()
-                    .enumerate()
-                    .map(|(k, v)| serde_json::json!({"key": k, "value": v}))
-                    .collect(),
-            _ => vec![]
+impl handlebars::HelperDef for IsCurrentPathHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        helper: &handlebars::Helper<'rc>,
+        _r: &'reg Handlebars<'reg>,
+        _ctx: &'rc Context,
+        rc: &mut handlebars::RenderContext<'reg, 'rc>,
+    ) -> Result<handlebars::ScopedJson<'rc>, RenderError> {
+        let mut current_path = helper
+            .hash_get("path")
+            .and_then(|p| p.value().as_str())
+            .map(str::to_string);
+        with_each_block(rc, |block, _is_last| {
+            if current_path.is_none() {
+                current_path = block
+                    .get_local_var(CURRENT_PATH)
+                    .and_then(JsonValue::as_str)
+                    .map(str::to_string);
+            }
+            Ok(())
+        })?;
+        let is_current = current_path.is_some_and(|current| match helper.params().as_slice() {
+            [item] => is_current_menu_item(item.value(), &current),
+            params => {
+                let link: String = params
+                    .iter()
+                    .map(|p| match p.value() {
+                        JsonValue::String(s) => Cow::Borrowed(s.as_str()),
+                        other => Cow::Owned(other.to_string()),
+                    })
+                    .collect();
+                is_current_link(&link, &current)
+            }
         });
+        Ok(handlebars::ScopedJson::Derived(JsonValue::Bool(is_current)))
+    }
+}
+
+fn is_current_menu_item(item: &JsonValue, current_path: &str) -> bool {
+    match item {
+        JsonValue::String(link) => is_current_link(link, current_path),
+        JsonValue::Object(obj) => {
+            obj.get("link")
+                .and_then(JsonValue::as_str)
+                .is_some_and(|link| is_current_link(link, current_path))
+                || match obj.get("submenu") {
+                    Some(JsonValue::Array(items)) => items
+                        .iter()
+                        .any(|item| is_current_menu_item(item, current_path)),
+                    Some(JsonValue::String(s)) => serde_json::from_str::<Vec<JsonValue>>(s)
+                        .is_ok_and(|items| {
+                            items
+                                .iter()
+                                .any(|item| is_current_menu_item(item, current_path))
+                        }),
+                    _ => false,
+                }
+        }
+        _ => false,
+    }
+}
+
+/// Whether a link, absolute or relative to the current page, points to the current page
+fn is_current_link(link: &str, current_path: &str) -> bool {
+    let link = link.split(['?', '#']).next().unwrap_or_default();
+    let link = match link.split_once("://") {
+        Some((_scheme, rest)) => rest.find('/').map_or("/", |i| &rest[i..]),
+        None => link,
+    };
+    if link.is_empty() {
+        return false;
+    }
+    let mut segments: Vec<&str> = if link.starts_with('/') {
+        vec![]
+    } else {
+        let directory = current_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+        directory.split('/').collect()
+    };
+    for segment in link.split('/') {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            s => segments.push(s),
+        }
+    }
+    let normalize = |path: String| {
+        let path = path.trim_start_matches('/').to_string();
+        match path.strip_suffix("index.sql") {
+            Some(dir) => dir.to_string(),
+            None => path,
+        }
+    };
+    normalize(segments.join("/")) == normalize(current_path.to_string())
+}
 
-        handlebars.register_helper("entries", Box::new(entries));
+/// The locale given in the `locale` hash parameter of a helper, or else the language of the user
+fn helper_locale(
+    helper: &handlebars::Helper<'_>,
+    rc: &mut handlebars::RenderContext<'_, '_>,
+) -> Result<String, RenderError> {
+    if let Some(locale) = helper.hash_get("locale").and_then(|l| l.value().as_str()) {
+        return Ok(locale.to_string());
+    }
+    let mut locale = None;
+    with_each_block(rc, |block, _is_last| {
+        if locale.is_none() {
+            locale = block
+                .get_local_var(LOCALE)
+                .and_then(JsonValue::as_str)
+                .map(str::to_string);
+        }
+        Ok(())
+    })?;
+    Ok(locale.unwrap_or_else(|| "en".into()))
+}
 
-        // delay helper: store a piece of information in memory that can be output later with flush_delayed
-        handlebars.register_helper("delay", Box::new(delay_helper));
-        handlebars.register_helper("flush_delayed", Box::new(flush_delayed_helper));
+fn number_param(helper: &handlebars::Helper<'_>) -> Result<Option<f64>, RenderError> {
+    match helper.param(0).map(PathAndJson::value) {
+        Some(JsonValue::Null) | None => Ok(None),
+        Some(value) => as_f64(value).map(Some).ok_or_else(|| {
+            RenderErrorReason::Other(format!("{}: {value} is not a number", helper.name())).into()
+        }),
+    }
+}
 
-        handlebars_helper!(plus: |a: Json, b:Json| a.as_i64().unwrap_or_default() + b.as_i64().unwrap_or_default());
-        handlebars.register_helper("plus", Box::new(plus));
+fn decimals_param(helper: &handlebars::Helper<'_>) -> Option<usize> {
+    helper
+        .hash_get("decimals")
+        .and_then(|d| d.value().as_u64())
+        .and_then(|d| usize::try_from(d.min(20)).ok())
+}
 
-        handlebars_helper!(minus: |a: Json, b:Json| a.as_i64().unwrap_or_default() - b.as_i64().unwrap_or_default());
-        handlebars.register_helper("minus", Box::new(minus));
+/// `format_number`: a number with the decimal and thousands separators of the user's language.
+/// The number of decimals can be fixed with `decimals=2`, and the language with `locale="fr"`.
+fn format_number_helper<'reg, 'rc>(
+    helper: &handlebars::Helper<'rc>,
+    _r: &'reg Handlebars<'reg>,
+    _ctx: &'rc Context,
+    rc: &mut handlebars::RenderContext<'reg, 'rc>,
+    writer: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let Some(value) = number_param(helper)? else {
+        return Ok(());
+    };
+    let format = NumberFormat::for_locale(&helper_locale(helper, rc)?);
+    writer.write(&format.format(value, decimals_param(helper)))?;
+    Ok(())
+}
 
-        handlebars.register_helper("sum", Box::new(sum_helper));
+/// `currency`: an amount of money in the given currency (an ISO 4217 code such as `EUR`),
+/// formatted for the user's language
+fn currency_helper<'reg, 'rc>(
+    helper: &handlebars::Helper<'rc>,
+    _r: &'reg Handlebars<'reg>,
+    _ctx: &'rc Context,
+    rc: &mut handlebars::RenderContext<'reg, 'rc>,
+    writer: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let Some(value) = number_param(helper)? else {
+        return Ok(());
+    };
+    let code = helper
+        .param(1)
+        .and_then(|c| c.value().as_str())
+        .ok_or(RenderErrorReason::ParamNotFoundForIndex("currency", 1))?;
+    let format = NumberFormat::for_locale(&helper_locale(helper, rc)?);
+    writer.write(&format.currency(value, code, decimals_param(helper)))?;
+    Ok(())
+}
 
-        handlebars_helper!(starts_with: |s: str, prefix:str| s.starts_with(prefix));
-        handlebars.register_helper("starts_with", Box::new(starts_with));
+/// How numbers are written in a language
+#[derive(Debug, PartialEq)]
+struct NumberFormat {
+    decimal: char,
+    group: &'static str,
+    /// Whether the currency symbol is written before the amount
+    currency_first: bool,
+}
 
-        // to_array: convert a value to a single-element array. If the value is already an array, return it as-is.
-        handlebars_helper!(to_array: |x: Json| match x {
-            JsonValue::Array(arr) => arr.clone(),
-            JsonValue::Null => vec![],
-            JsonValue::String(s) if s.starts_with('[')  => {
-                if let Ok(JsonValue::Array(r)) = serde_json::from_str(s) {
-                    r
-                } else {
-                    vec![JsonValue::String(s.clone())]
-                }
-            }
-            other => vec![other.clone()]
-        });
-        handlebars.register_helper("to_array", Box::new(to_array));
+impl NumberFormat {
+    fn for_locale(locale: &str) -> Self {
+        let locale = locale.to_ascii_lowercase().replace('_', "-");
+        let (language, region) = locale.split_once('-').unwrap_or((&locale, ""));
+        let (decimal, group, currency_first) = match (language, region) {
+            ("de", "ch" | "li") => ('.', "\u{2019}", true),
+            ("es", "mx" | "us") => ('.', ",", true),
+            ("pt", "br") | ("nl" | "id", _) => (',', ".", true),
+            ("fr" | "pt", _) => (',', "\u{202f}", false),
+            (
+                "ru" | "pl" | "cs" | "sk" | "uk" | "fi" | "sv" | "nb" | "no" | "nn" | "hu" | "bg"
+                | "lt" | "lv" | "et",
+                _,
+            ) => (',', "\u{a0}", false),
+            (
+                "de" | "es" | "it" | "da" | "tr" | "el" | "ro" | "hr" | "sl" | "sr" | "vi" | "ca"
+                | "is",
+                _,
+            ) => (',', ".", false),
+            _ => ('.', ",", true),
+        };
+        Self {
+            decimal,
+            group,
+            currency_first,
+        }
+    }
 
-        // array_contains: check if an array contains an element. If the first argument is not an array, it is compared to the second argument.
-        handlebars_helper!(array_contains: |array: Json, element: Json| match array {
-            JsonValue::Array(arr) => arr.contains(element),
-            other => other == element
-        });
-        handlebars.register_helper("array_contains", Box::new(array_contains));
-
-        // static_path helper: generate a path to a static file. Replaces sqpage.js by sqlpage.<hash>.js
-        handlebars_helper!(static_path: |x: str| match x {
-            "sqlpage.js" => static_filename!("sqlpage.js"),
-            "sqlpage.css" => static_filename!("sqlpage.css"),
-            "apexcharts.js" => static_filename!("apexcharts.js"),
-            unknown => {
-                log::error!("Unknown static path: {}", unknown);
-                "!!unknown static path!!"
+    fn format(&self, value: f64, decimals: Option<usize>) -> String {
+        let digits = match decimals {
+            Some(decimals) => format!("{:.*}", decimals, value.abs()),
+            None => value.abs().to_string(),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+        let mut formatted = String::with_capacity(digits.len() + 8);
+        if value < 0. && digits.bytes().any(|b| b.is_ascii_digit() && b != b'0') {
+            formatted.push('-');
+        }
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                formatted.push_str(self.group);
             }
+            formatted.push(digit);
+        }
+        if !fraction.is_empty() {
+            formatted.push(self.decimal);
+            formatted.push_str(fraction);
+        }
+        formatted
+    }
+
+    fn currency(&self, value: f64, code: &str, decimals: Option<usize>) -> String {
+        let code = code.to_ascii_uppercase();
+        let symbol = match code.as_str() {
+            "EUR" => "€",
+            "USD" => "$",
+            "GBP" => "£",
+            "JPY" | "CNY" => "¥",
+            "INR" => "₹",
+            "KRW" => "₩",
+            "RUB" => "₽",
+            "BRL" => "R$",
+            "TRY" => "₺",
+            "UAH" => "₴",
+            "ILS" => "₪",
+            "NGN" => "₦",
+            "VND" => "₫",
+            "PLN" => "zł",
+            other => other,
+        };
+        let decimals = decimals.unwrap_or(match code.as_str() {
+            "JPY" | "KRW" | "VND" | "CLP" | "ISK" | "UGX" | "XOF" | "XAF" => 0,
+            _ => 2,
         });
-        handlebars.register_helper("static_path", Box::new(static_path));
+        let amount = self.format(value, Some(decimals));
+        // Multi-letter symbols such as "CHF" or "R$" are separated from the amount
+        let spaced = symbol.chars().count() > 1;
+        match (self.currency_first, amount.strip_prefix('-')) {
+            (true, Some(abs)) if !spaced => format!("-{symbol}{abs}"),
+            (true, None) if !spaced => format!("{symbol}{amount}"),
+            (true, _) => format!("{symbol}\u{a0}{amount}"),
+            (false, _) => format!("{amount}\u{a0}{symbol}"),
+        }
+    }
+}
 
-        // icon helper: generate an image with the specified icon
-        handlebars.register_helper("icon_img", Box::new(icon_img_helper));
+/// Numbers can be returned as strings by some databases
+pub(crate) fn as_f64(v: &JsonValue) -> Option<f64> {
+    match v {
+        JsonValue::String(s) => s.trim().parse().ok(),
+        v => v.as_f64(),
+    }
+}
 
-        handlebars_helper!(markdown_helper: |x: Json| {
-            let as_str = match x {
-                JsonValue::String(s) => Cow::Borrowed(s),
-                JsonValue::Array(arr) => Cow::Owned(arr.iter().map(|v|v.as_str().unwrap_or_default()).collect::<Vec<_>>().join("\n")),
-                JsonValue::Null => Cow::Owned(String::new()),
-                other => Cow::Owned(other.to_string())
-            };
-            markdown::to_html_with_options(&as_str, &markdown::Options::gfm())
-            .unwrap_or_else(|s|s)
+#[allow(clippy::cast_possible_truncation)]
+fn as_i64(v: &JsonValue) -> i64 {
+    v.as_i64()
+        .or_else(|| as_f64(v).map(|v| v as i64))
+        .unwrap_or_default()
+}
+
+fn sparkline_points(values: &JsonValue) -> String {
+    let values: Vec<f64> = match values {
+        JsonValue::Array(arr) => arr.iter().filter_map(as_f64).collect(),
+        JsonValue::String(s) => serde_json::from_str::<Vec<JsonValue>>(s)
+            .map(|arr| arr.iter().filter_map(as_f64).collect())
+            .unwrap_or_default(),
+        _ => vec![],
+    };
+    let (min, max) = values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
         });
-        handlebars.register_helper("markdown", Box::new(markdown_helper));
+    let range = if max > min { max - min } else { 1. };
+    #[allow(clippy::cast_precision_loss)]
+    let step = 100. / (values.len().max(2) - 1) as f64;
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            #[allow(clippy::cast_precision_loss)]
+            let x = i as f64 * step;
+            // Leave a margin so that the line is not cut at the top and bottom
+            let y = 28. - (v - min) / range * 26.;
+            format!("{x:.1},{y:.1}")
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn percentage(value: &JsonValue, max: &JsonValue) -> f64 {
+    let value = as_f64(value).unwrap_or_default();
+    let max = as_f64(max).unwrap_or(100.);
+    if max == 0. || !value.is_finite() || !max.is_finite() {
+        return 0.;
+    }
+    ((value / max * 1000.).round() / 10.).clamp(0., 100.)
+}
+
+/// `{{icon name size color}}`: an svg icon from the tabler icon set.
+/// The size and color can also be given as `size=` and `color=` hash parameters.
+fn icon_helper<'reg, 'rc>(
+    helper: &handlebars::Helper<'rc>,
+    _r: &'reg Handlebars<'reg>,
+    _ctx: &'rc Context,
+    _rc: &mut handlebars::RenderContext<'reg, 'rc>,
+    writer: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let param = |i: usize, name: &str| {
+        helper
+            .params()
+            .get(i)
+            .or_else(|| helper.hash_get(name))
+            .map_or(&JsonValue::Null, PathAndJson::value)
+    };
+    let name = match param(0, "name") {
+        JsonValue::String(s) if !s.is_empty() => s,
+        other => {
+            log::debug!("icon: {other:?} is not an icon name, not rendering anything");
+            return Ok(());
+        }
+    };
+    let size = match param(1, "size") {
+        JsonValue::String(s) => s.parse().ok(),
+        other => other.as_u64(),
+    }
+    .unwrap_or(24);
+    write!(writer, "<svg width={size} height={size}")?;
+    if let Some(color) = param(2, "color").as_str().filter(|c| !c.is_empty()) {
+        write!(writer, " class=\"text-{}\"", handlebars::html_escape(color))?;
+    }
+    write!(
+        writer,
+        "><use href=\"/{}#tabler-{}\" /></svg>",
+        static_filename!("tabler-icons.svg"),
+        handlebars::html_escape(name)
+    )?;
+    Ok(())
+}
+
+const STATIC_TEMPLATES: Dir = include_dir!("$CARGO_MANIFEST_DIR/sqlpage/templates");
+const STATIC_TRANSLATIONS: Dir = include_dir!("$CARGO_MANIFEST_DIR/sqlpage/translations");
+const STATIC_PARTIALS: Dir = include_dir!("$CARGO_MANIFEST_DIR/sqlpage/partials");
+
+/// Helpers that do arithmetic, and display numbers
+fn register_number_helpers(handlebars: &mut Handlebars<'_>) {
+    handlebars_helper!(plus: |a: Json, b:Json| as_i64(a) + as_i64(b));
+    handlebars.register_helper("plus", Box::new(plus));
+
+    handlebars_helper!(minus: |a: Json, b:Json| as_i64(a) - as_i64(b));
+    handlebars.register_helper("minus", Box::new(minus));
+
+    handlebars.register_helper("sum", Box::new(sum_helper));
+    handlebars.register_helper("percentage", Box::new(percentage_helper));
+    // sign: 1, -1, or 0 depending on the sign of a number
+    handlebars_helper!(sign: |x: Json| as_f64(x).map_or(0, |x| i8::from(x > 0.) - i8::from(x < 0.)));
+    handlebars.register_helper("sign", Box::new(sign));
+    // sparkline: the points of an SVG polyline that represents a series of numbers, in a 100x30 box
+    handlebars_helper!(sparkline: |values: Json| sparkline_points(values));
+    handlebars.register_helper("sparkline", Box::new(sparkline));
+    // threshold: "danger" or "warning" when a value reaches the corresponding threshold
+    handlebars_helper!(threshold: |value: Json, warning: Json, danger: Json| {
+        let reaches = |t: &JsonValue| as_f64(t).zip(as_f64(value)).is_some_and(|(t, v)| v >= t);
+        if reaches(danger) { "danger" } else if reaches(warning) { "warning" } else { "" }
+    });
+    handlebars.register_helper("threshold", Box::new(threshold));
+    handlebars.register_helper("format_number", Box::new(format_number_helper));
+    handlebars.register_helper("currency", Box::new(currency_helper));
+}
+
+fn register_helpers(handlebars: &mut Handlebars<'_>) {
+    handlebars_helper!(stringify: |v: Json| v.to_string());
+    handlebars.register_helper("stringify", Box::new(stringify));
+
+    handlebars_helper!(parse_json: |v: Json| match v {
+        obj @ serde_json::value::Value::String(s) =>
+            serde_json::from_str(s)
+            .unwrap_or_else(|_| {
+                log::warn!("Failed to parse JSON string: {}", s);
+                obj.clone()
+            }),
+        other => other.clone()
+    });
+    handlebars.register_helper("parse_json", Box::new(parse_json));
+
+    handlebars_helper!(default: |a: Json, b:Json| if a.is_null() {b} else {a}.clone());
+    handlebars.register_helper("default", Box::new(default));
 
-        handlebars_helper!(buildinfo_helper: |x: str|
-            match x {
-                "CARGO_PKG_NAME" => env!("CARGO_PKG_NAME"),
-                "CARGO_PKG_VERSION" => env!("CARGO_PKG_VERSION"),
-                _ => "!!unknown buildinfo key!!"
+    handlebars_helper!(entries: |v: Json | match v {
+        serde_json::value::Value::Object(map) =>
+            map.into_iter()
+                .map(|(k, v)| serde_json::json!({"key": k, "value": v}))
+                .collect(),
+        serde_json::value::Value::Array(values) =>
+            values.iter()
+                .enumerate()
+                .map(|(k, v)| serde_json::json!({"key": k, "value": v}))
+                .collect(),
+        _ => vec![]
+    });
+
+    handlebars.register_helper("entries", Box::new(entries));
+
+    // delay helper: store a piece of information in memory that can be output later with flush_delayed
+    handlebars.register_helper("delay", Box::new(delay_helper));
+    handlebars.register_helper("flush_delayed", Box::new(flush_delayed_helper));
+
+    handlebars.register_helper("t", Box::new(TranslateHelper));
+    handlebars.register_helper("is_current_path", Box::new(IsCurrentPathHelper));
+    handlebars.register_helper("csrf_token", Box::new(PageLocalHelper(CSRF_TOKEN)));
+    handlebars.register_helper("csp_nonce", Box::new(PageLocalHelper(CSP_NONCE)));
+
+    register_number_helpers(handlebars);
+
+    handlebars_helper!(starts_with: |s: str, prefix:str| s.starts_with(prefix));
+    handlebars.register_helper("starts_with", Box::new(starts_with));
+
+    // is_rtl_language: whether a language tag like "ar" or "he-IL" is written from right to left
+    handlebars_helper!(is_rtl_language: |lang: Json| lang.as_str().is_some_and(|lang| {
+        let primary = lang.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase();
+        matches!(primary.as_str(), "ar" | "he" | "fa" | "ur" | "ps" | "yi" | "dv" | "ckb" | "sd" | "ug" | "syr")
+    }));
+    handlebars.register_helper("is_rtl_language", Box::new(is_rtl_language));
+
+    // to_array: convert a value to a single-element array. If the value is already an array, return it as-is.
+    handlebars_helper!(to_array: |x: Json| match x {
+        JsonValue::Array(arr) => arr.clone(),
+        JsonValue::Null => vec![],
+        JsonValue::String(s) if s.starts_with('[')  => {
+            if let Ok(JsonValue::Array(r)) = serde_json::from_str(s) {
+                r
+            } else {
+                vec![JsonValue::String(s.clone())]
             }
-        );
-        handlebars.register_helper("buildinfo", Box::new(buildinfo_helper));
-
-        handlebars_helper!(typeof_helper: |x: Json| match x {
-            JsonValue::Null => "null",
-            JsonValue::Bool(_) => "boolean",
-            JsonValue::Number(_) => "number",
-            JsonValue::String(_) => "string",
-            JsonValue::Array(_) => "array",
-            JsonValue::Object(_) => "object",
-        });
-        handlebars.register_helper("typeof", Box::new(typeof_helper));
+        }
+        other => vec![other.clone()]
+    });
+    handlebars.register_helper("to_array", Box::new(to_array));
+
+    // array_contains: check if an array contains an element. If the first argument is not an array, it is compared to the second argument.
+    handlebars_helper!(array_contains: |array: Json, element: Json| match array {
+        JsonValue::Array(arr) => arr.contains(element),
+        other => other == element
+    });
+    handlebars.register_helper("array_contains", Box::new(array_contains));
+
+    // static_path helper: generate a path to a static file. Replaces sqpage.js by sqlpage.<hash>.js
+    handlebars_helper!(static_path: |x: str| match x {
+        "sqlpage.js" => static_filename!("sqlpage.js"),
+        "sqlpage.css" => static_filename!("sqlpage.css"),
+        "sqlpage.rtl.css" => static_filename!("sqlpage.rtl.css"),
+        "apexcharts.js" => static_filename!("apexcharts.js"),
+        unknown => {
+            log::error!("Unknown static path: {}", unknown);
+            "!!unknown static path!!"
+        }
+    });
+    handlebars.register_helper("static_path", Box::new(static_path));
+
+    // icon helper: generate an image with the specified icon
+    handlebars.register_helper("icon", Box::new(icon_helper));
+    // the previous name of the icon helper, kept for custom components that use it
+    handlebars.register_helper("icon_img", Box::new(icon_helper));
+    handlebars.register_helper("qr_code", Box::new(qr_code_helper));
+    handlebars.register_helper("format_date", Box::new(format_date_helper));
+
+    handlebars.register_helper("markdown", Box::new(MarkdownHelper));
+
+    // sanitize_html: html from which the tags and attributes that are not allowed at the given level are removed
+    handlebars.register_helper("sanitize_html", Box::new(sanitize_html_helper));
+
+    handlebars_helper!(buildinfo_helper: |x: str|
+        match x {
+            "CARGO_PKG_NAME" => env!("CARGO_PKG_NAME"),
+            "CARGO_PKG_VERSION" => env!("CARGO_PKG_VERSION"),
+            _ => "!!unknown buildinfo key!!"
+        }
+    );
+    handlebars.register_helper("buildinfo", Box::new(buildinfo_helper));
+
+    handlebars_helper!(typeof_helper: |x: Json| match x {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    });
+    handlebars.register_helper("typeof", Box::new(typeof_helper));
+}
+
+impl AllTemplates {
+    pub fn init(config: &AppConfig) -> anyhow::Result<Self> {
+        let mut handlebars = Handlebars::new();
+
+        register_helpers(&mut handlebars);
+        register_template_helpers(&mut handlebars, &config.web_root.join(HELPERS_DIR))?;
+        register_partials(&mut handlebars, &config.web_root.join(PARTIALS_DIR))?;
 
         let mut this = Self {
             handlebars,
             split_templates: FileCache::new(),
+            translations: FileCache::new(),
         };
         this.preregister_static_templates()?;
+        this.preregister_static_translations()?;
+        // The first theme has the highest precedence, so it is registered last
+        for theme_dir in config.template_directories.iter().rev() {
+            this.preregister_theme_templates(&config.web_root.join(theme_dir))?;
+        }
         Ok(this)
     }
 
@@ -334,6 +1090,74 @@ impl AllTemplates {
         Ok(())
     }
 
+    fn preregister_static_translations(&mut self) -> anyhow::Result<()> {
+        for file in STATIC_TRANSLATIONS.files() {
+            let mut path = PathBuf::from(TRANSLATIONS_DIR);
+            path.push(file.path());
+            let translations = serde_json::from_slice(file.contents())
+                .with_context(|| format!("Invalid built-in translation file {path:?}"))?;
+            self.translations
+                .add_static(path, Translations(translations));
+        }
+        Ok(())
+    }
+
+    /// Finds the translations for the first of the user's preferred languages that has a
+    /// `sqlpage/translations/<language>.json` file, trying `fr` when `fr-CA` is not available.
+    pub async fn get_translations(
+        &self,
+        app_state: &AppState,
+        languages: &[String],
+    ) -> Option<Arc<Translations>> {
+        for language in languages {
+            let mut candidates = vec![language.as_str()];
+            if let Some((primary, _region)) = language.split_once('-') {
+                candidates.push(primary);
+            }
+            for candidate in candidates {
+                if !candidate
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-')
+                {
+                    continue;
+                }
+                let mut path = PathBuf::from(TRANSLATIONS_DIR);
+                path.push(candidate);
+                path.set_extension("json");
+                match self.translations.get(app_state, &path).await {
+                    Ok(translations) => return Some(translations),
+                    Err(e) => log::trace!("No translations in {path:?}: {e:#}"),
+                }
+            }
+        }
+        None
+    }
+
+    /// Loads the templates of a theme, that replace the built-in ones with the same name.
+    /// Templates in the site's own `sqlpage/templates` directory still take precedence over them.
+    fn preregister_theme_templates(&mut self, theme_dir: &Path) -> anyhow::Result<()> {
+        let entries = std::fs::read_dir(theme_dir)
+            .with_context(|| format!("Unable to read the template directory {theme_dir:?}"))?;
+        for entry in entries {
+            let file_path = entry?.path();
+            if file_path.extension() != Some("handlebars".as_ref()) {
+                continue;
+            }
+            let Some(name) = file_path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            log::debug!("Loading the {name} component from {file_path:?}");
+            let source = std::fs::read_to_string(&file_path)
+                .with_context(|| format!("Unable to read {file_path:?}"))?;
+            let tpl = Template::compile_with_name(&source, name.to_string())
+                .with_context(|| format!("Invalid template in {file_path:?}"))?;
+            let mut path = PathBuf::from(TEMPLATES_DIR);
+            path.push(file_path.file_name().unwrap_or_default());
+            self.split_templates.add_static(path, split_template(tpl));
+        }
+        Ok(())
+    }
+
     pub async fn get_template(
         &self,
         app_state: &AppState,
@@ -374,3 +1198,184 @@ fn test_split_template() {
         Template::compile("end").unwrap().elements
     );
 }
+
+#[test]
+fn test_percentage() {
+    use serde_json::json;
+    assert!((percentage(&json!(1), &json!(3)) - 33.3).abs() < f64::EPSILON);
+    assert!((percentage(&json!("42"), &JsonValue::Null) - 42.).abs() < f64::EPSILON);
+    assert!((percentage(&json!(150), &json!(100)) - 100.).abs() < f64::EPSILON);
+    assert!(percentage(&json!(5), &json!(0)).abs() < f64::EPSILON);
+}
+
+#[test]
+fn test_sparkline() {
+    use serde_json::json;
+    assert_eq!(
+        sparkline_points(&json!([1, 3, 2])),
+        "0.0,28.0 50.0,2.0 100.0,15.0"
+    );
+    assert_eq!(sparkline_points(&json!("[5, 5]")), "0.0,28.0 100.0,28.0");
+    assert_eq!(sparkline_points(&JsonValue::Null), "");
+}
+
+#[test]
+fn test_format_date() {
+    use serde_json::json;
+    let mut handlebars = Handlebars::new();
+    register_helpers(&mut handlebars);
+    let render = |template: &str, date: JsonValue| {
+        handlebars
+            .render_template(template, &json!({ "date": date }))
+            .unwrap()
+    };
+    assert_eq!(
+        render("{{format_date date}}", json!("2024-07-15 10:30:00")),
+        "2024-07-15 10:30"
+    );
+    assert_eq!(
+        render(
+            r#"{{format_date date "%d/%m/%Y %H:%M" tz="+02:00"}}"#,
+            json!(1_705_314_600)
+        ),
+        "15/01/2024 12:30"
+    );
+    assert_eq!(render("{{format_date date}}", JsonValue::Null), "");
+    assert_eq!(
+        render(
+            r#"{{format_date date "%H:%M %:z" tz="Europe/Paris"}}"#,
+            json!("2024-07-15T10:30:00Z")
+        ),
+        "12:30 +02:00"
+    );
+    assert!(handlebars
+        .render_template(r#"{{format_date "2024-01-01" tz="Nowhere/Atlantis"}}"#, &())
+        .is_err());
+}
+
+#[test]
+fn test_number_format() {
+    let en = NumberFormat::for_locale("en-US");
+    assert_eq!(en.format(1_234_567.891, None), "1,234,567.891");
+    assert_eq!(en.format(-1234.5, Some(2)), "-1,234.50");
+    assert_eq!(en.format(-0.001, Some(2)), "0.00");
+    assert_eq!(en.format(999., None), "999");
+    assert_eq!(en.currency(-1234.5, "usd", None), "-$1,234.50");
+    assert_eq!(en.currency(1234.5, "CHF", None), "CHF\u{a0}1,234.50");
+    assert_eq!(en.currency(1234.6, "JPY", None), "¥1,235");
+    let fr = NumberFormat::for_locale("fr_FR");
+    assert_eq!(fr.format(1234.5, Some(2)), "1\u{202f}234,50");
+    assert_eq!(fr.currency(1234.5, "EUR", None), "1\u{202f}234,50\u{a0}€");
+    let de = NumberFormat::for_locale("de");
+    assert_eq!(de.format(1_234_567.5, None), "1.234.567,5");
+    assert_eq!(
+        NumberFormat::for_locale("de-CH").format(1234.5, None),
+        "1\u{2019}234.5"
+    );
+}
+
+#[test]
+fn test_format_number_helper() {
+    let mut handlebars = Handlebars::new();
+    register_helpers(&mut handlebars);
+    let data = serde_json::json!({ "n": "1234.5" });
+    let render = |template: &str| handlebars.render_template(template, &data).unwrap();
+    assert_eq!(render("{{format_number n}}"), "1,234.5");
+    assert_eq!(
+        render(r#"{{format_number n decimals=2 locale="de"}}"#),
+        "1.234,50"
+    );
+    assert_eq!(
+        render(r#"{{currency n "EUR" locale="es"}}"#),
+        "1.234,50\u{a0}€"
+    );
+    assert_eq!(render("{{format_number missing}}"), "");
+}
+
+#[test]
+fn test_icon_helper() {
+    let mut handlebars = Handlebars::new();
+    register_helpers(&mut handlebars);
+    let data = serde_json::json!({ "icon": "user", "size": "16" });
+    let render = |template: &str| handlebars.render_template(template, &data).unwrap();
+    let href = static_filename!("tabler-icons.svg");
+    assert_eq!(
+        render("{{#if icon}}{{icon icon}}{{/if}}"),
+        format!("<svg width=24 height=24><use href=\"/{href}#tabler-user\" /></svg>")
+    );
+    assert_eq!(
+        render("{{icon icon size 'red'}}"),
+        format!(
+            "<svg width=16 height=16 class=\"text-red\"><use href=\"/{href}#tabler-user\" /></svg>"
+        )
+    );
+    assert_eq!(
+        render("{{icon_img icon size=16}}"),
+        render("{{icon icon 16}}")
+    );
+    assert_eq!(render("{{icon missing}}"), "");
+}
+
+#[test]
+fn test_markdown_helper() {
+    let mut handlebars = Handlebars::new();
+    register_helpers(&mut handlebars);
+    let data = serde_json::json!({ "text": "**bold**", "name": "<World>" });
+    let render = |template: &str| handlebars.render_template(template, &data).unwrap();
+    assert_eq!(
+        render("{{{markdown text}}}"),
+        "<p><strong>bold</strong></p>"
+    );
+    assert_eq!(
+        render("{{markdown text}}"),
+        "&lt;p&gt;&lt;strong&gt;bold&lt;/strong&gt;&lt;/p&gt;"
+    );
+    assert_eq!(
+        render("{{#markdown}}# Hello {{name}}{{/markdown}}"),
+        "<h1>Hello &lt;World&gt;</h1>"
+    );
+}
+
+#[test]
+fn test_template_helpers() {
+    let dir = std::env::temp_dir().join("sqlpage_test_template_helpers");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("badge.handlebars"),
+        r#"<span class="bg-{{default color 'blue'}}">{{args.[0]}}</span>"#,
+    )
+    .unwrap();
+    let mut handlebars = Handlebars::new();
+    register_helpers(&mut handlebars);
+    register_template_helpers(&mut handlebars, &dir).unwrap();
+    let data = serde_json::json!({ "rows": [{ "status": "<ok>" }] });
+    assert_eq!(
+        handlebars
+            .render_template(
+                r#"{{#each rows}}{{badge status color="green"}}{{badge "x"}}{{/each}}"#,
+                &data
+            )
+            .unwrap(),
+        r#"<span class="bg-green">&lt;ok&gt;</span><span class="bg-blue">x</span>"#
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_is_current_link() {
+    assert!(is_current_link("/blog/post.sql", "/blog/post.sql"));
+    assert!(is_current_link("post.sql?id=1", "/blog/post.sql"));
+    assert!(is_current_link("../blog/post.sql", "/blog/post.sql"));
+    assert!(is_current_link(
+        "https://example.com/blog/",
+        "/blog/index.sql"
+    ));
+    assert!(is_current_link("/", "/"));
+    assert!(!is_current_link("/", "/blog/"));
+    assert!(!is_current_link("other.sql", "/blog/post.sql"));
+    let menu = serde_json::json!({"title": "Blog", "submenu": [
+        {"title": "Posts", "submenu": [{"link": "/blog/post.sql"}]}
+    ]});
+    assert!(is_current_menu_item(&menu, "/blog/post.sql"));
+    assert!(!is_current_menu_item(&menu, "/about.sql"));
+}
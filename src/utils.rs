@@ -25,6 +25,19 @@ pub fn add_value_to_map(
     map
 }
 
+/// Interprets a boolean returned by the database, whose type depends on the database
+pub(crate) fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.),
+        Value::String(s) => matches!(
+            s.trim().to_ascii_lowercase().as_str(),
+            "1" | "t" | "true" | "y" | "yes" | "on"
+        ),
+        _ => false,
+    }
+}
+
 macro_rules! static_filename {
     ($filename:expr) => {
         include_str!(concat!(env!("OUT_DIR"), "/", $filename, ".filename.txt"))
@@ -32,3 +45,26 @@ macro_rules! static_filename {
 }
 
 pub(crate) use static_filename;
+
+#[test]
+fn test_is_truthy() {
+    use serde_json::json;
+    for truthy in [
+        json!(true),
+        json!(1),
+        json!("TRUE"),
+        json!("t"),
+        json!(" yes "),
+    ] {
+        assert!(is_truthy(&truthy), "{truthy}");
+    }
+    for falsy in [
+        json!(false),
+        json!(0),
+        json!("false"),
+        json!("0"),
+        json!(null),
+    ] {
+        assert!(!is_truthy(&falsy), "{falsy}");
+    }
+}
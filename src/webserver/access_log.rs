@@ -0,0 +1,226 @@
+//! Access log, separate from the application log, written to a file or to the standard output
+//! for ingestion by existing log pipelines.
+//!
+//! Requests are logged in the Common Log Format, in the Combined Log Format (the default),
+//! or as JSON objects that also contain the duration of the request, the sql file that handled it,
+//! and the id of the request.
+//! A line is written once the whole response body has been sent, when its size is known.
+
+use super::forwarded;
+use super::request_id;
+use crate::app_config::AppConfig;
+use actix_web::body::{BodySize, BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, Header};
+use actix_web::web::Bytes;
+use actix_web_httpauth::headers::authorization::{Authorization, Basic};
+use anyhow::Context;
+use chrono::{DateTime, Local, SecondsFormat};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context as TaskContext, Poll};
+use std::time::Instant;
+
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogFormat {
+    Common,
+    #[default]
+    Combined,
+    Json,
+}
+
+enum Output {
+    Stdout,
+    Stderr,
+    File(File),
+}
+
+pub(crate) struct AccessLog {
+    format: AccessLogFormat,
+    output: Mutex<Output>,
+}
+
+/// The sql file that handled a request, stored in the request extensions
+#[derive(Debug, Clone)]
+pub(crate) struct SqlFile(pub PathBuf);
+
+impl AccessLog {
+    /// Opens the access log file, when `access_log` is set in the configuration
+    pub(crate) fn from_config(config: &AppConfig) -> anyhow::Result<Option<Self>> {
+        let Some(destination) = &config.access_log else {
+            return Ok(None);
+        };
+        let output = match destination.as_str() {
+            "stdout" => Output::Stdout,
+            "stderr" => Output::Stderr,
+            path => Output::File(
+                File::options()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("Unable to open the access log file {path:?}"))?,
+            ),
+        };
+        Ok(Some(Self {
+            format: config.access_log_format,
+            output: Mutex::new(output),
+        }))
+    }
+
+    fn write(&self, mut line: String) {
+        line.push('\n');
+        let mut output = self.output.lock().expect("access log lock poisoned");
+        // Each line is written at once, so that lines are not mixed when the file is shared
+        let result = match &mut *output {
+            Output::Stdout => std::io::stdout().lock().write_all(line.as_bytes()),
+            Output::Stderr => std::io::stderr().lock().write_all(line.as_bytes()),
+            Output::File(file) => file.write_all(line.as_bytes()),
+        };
+        if let Err(e) = result {
+            log::error!("Unable to write to the access log: {e}");
+        }
+    }
+}
+
+/// What is known about a request before its response is sent
+pub(crate) struct AccessLogEntry {
+    log: Arc<AccessLog>,
+    start: Instant,
+    time: DateTime<Local>,
+    client_ip: String,
+    user: Option<String>,
+    method: String,
+    uri: String,
+    version: String,
+    referer: Option<String>,
+    user_agent: Option<String>,
+    status: u16,
+    sql_file: Option<String>,
+    request_id: Option<String>,
+    size: u64,
+}
+
+impl AccessLogEntry {
+    pub(crate) fn new(log: &Arc<AccessLog>, req: &ServiceRequest, config: &AppConfig) -> Self {
+        let header = |name| {
+            req.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(ToString::to_string)
+        };
+        Self {
+            log: Arc::clone(log),
+            start: Instant::now(),
+            time: Local::now(),
+            client_ip: forwarded::log_client_ip(req, &config.trusted_proxies),
+            user: Authorization::<Basic>::parse(req)
+                .ok()
+                .map(|auth| auth.into_scheme().user_id().to_string()),
+            method: req.method().to_string(),
+            uri: req.uri().to_string(),
+            version: format!("{:?}", req.version()),
+            referer: header(header::REFERER),
+            user_agent: header(header::USER_AGENT),
+            status: 0,
+            sql_file: None,
+            request_id: request_id::get(req.request()),
+            size: 0,
+        }
+    }
+
+    fn line(&self) -> String {
+        let duration = self.start.elapsed();
+        let quoted =
+            |value: Option<&str>| value.map_or_else(|| "\"-\"".to_string(), |v| format!("{v:?}"));
+        let common = format!(
+            "{} - {} [{}] \"{} {} {}\" {} {}",
+            self.client_ip,
+            self.user.as_deref().unwrap_or("-"),
+            self.time.format("%d/%b/%Y:%H:%M:%S %z"),
+            self.method,
+            self.uri,
+            self.version,
+            self.status,
+            self.size
+        );
+        match self.log.format {
+            AccessLogFormat::Common => common,
+            AccessLogFormat::Combined => format!(
+                "{common} {} {}",
+                quoted(self.referer.as_deref()),
+                quoted(self.user_agent.as_deref())
+            ),
+            AccessLogFormat::Json => serde_json::json!({
+                "time": self.time.to_rfc3339_opts(SecondsFormat::Millis, true),
+                "client_ip": self.client_ip,
+                "user": self.user,
+                "method": self.method,
+                "uri": self.uri,
+                "version": self.version,
+                "status": self.status,
+                "size": self.size,
+                "duration_ms": duration.as_secs_f64() * 1000.,
+                "sql_file": self.sql_file,
+                "request_id": self.request_id,
+                "referer": self.referer,
+                "user_agent": self.user_agent,
+            })
+            .to_string(),
+        }
+    }
+}
+
+/// Wraps the body of a response to log the request once the body has been sent
+pub(crate) fn log_response(
+    response: ServiceResponse<BoxBody>,
+    entry: Option<AccessLogEntry>,
+) -> ServiceResponse<LoggedBody> {
+    let entry = entry.map(|mut entry| {
+        entry.status = response.status().as_u16();
+        entry.sql_file = response
+            .request()
+            .extensions()
+            .get::<SqlFile>()
+            .map(|SqlFile(path)| path.to_string_lossy().into_owned());
+        entry
+    });
+    response.map_body(|_, body| LoggedBody { body, entry })
+}
+
+pub(crate) struct LoggedBody {
+    body: BoxBody,
+    entry: Option<AccessLogEntry>,
+}
+
+impl MessageBody for LoggedBody {
+    type Error = <BoxBody as MessageBody>::Error;
+
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.get_mut();
+        let chunk = Pin::new(&mut this.body).poll_next(cx);
+        if let (Some(entry), Poll::Ready(Some(Ok(bytes)))) = (&mut this.entry, &chunk) {
+            entry.size += bytes.len() as u64;
+        }
+        chunk
+    }
+}
+
+impl Drop for LoggedBody {
+    fn drop(&mut self) {
+        if let Some(entry) = &self.entry {
+            entry.log.write(entry.line());
+        }
+    }
+}
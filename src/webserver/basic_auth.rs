@@ -0,0 +1,176 @@
+//! Protects whole parts of the website with HTTP basic authentication, configured in `basic_auth`.
+//!
+//! Every rule whose `path_prefix` matches the path of a request applies to it, before any sql file
+//! is executed or any static file is served. The password of a user is checked against a hash
+//! from the configuration file, or returned by a query on the database.
+//! Successful verifications are cached for a short time, because hashing passwords is slow
+//! and browsers send the credentials again with every request.
+
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header;
+use actix_web::{HttpMessage, HttpResponse};
+use actix_web_httpauth::headers::authorization::{Authorization, Basic};
+use anyhow::Context;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::make_placeholder;
+use crate::AppState;
+
+const CACHE_DURATION: Duration = Duration::from_secs(60);
+/// Above this number of cached credentials, the expired ones are removed
+const MAX_CACHED_CREDENTIALS: usize = 10_000;
+/// Parameter of the `query` of a rule that is replaced by the name of the user
+const USERNAME_PARAMETER: &str = "$username";
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct BasicAuth {
+    /// The rule applies to the requests whose path starts with this prefix. Defaults to `/`.
+    #[serde(default = "default_path_prefix")]
+    pub path_prefix: String,
+    /// Names of the users that are allowed in, with their password hashed by `sqlpage.hash_password`
+    #[serde(default)]
+    pub users: HashMap<String, String>,
+    /// Query that returns the password hash of the user given by the `$username` parameter,
+    /// for users that are not listed in `users`
+    pub query: Option<String>,
+    /// Name of the protected area, shown by browsers in their login prompt. Defaults to `SQLPage`.
+    pub realm: Option<String>,
+}
+
+/// Name of the user whose password was checked by the rules of the request, stored in its extensions
+#[derive(Clone)]
+pub(crate) struct VerifiedUser(pub String);
+
+fn default_path_prefix() -> String {
+    "/".to_string()
+}
+
+#[derive(Default)]
+pub(crate) struct BasicAuthCache {
+    verified_at: Mutex<HashMap<[u8; 32], Instant>>,
+}
+
+impl BasicAuthCache {
+    fn key(rule_index: usize, credentials: &Basic) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(rule_index.to_le_bytes());
+        hasher.update(credentials.user_id().as_bytes());
+        hasher.update([0]);
+        hasher.update(credentials.password().unwrap_or_default().as_bytes());
+        hasher.finalize().into()
+    }
+
+    fn contains(&self, key: &[u8; 32]) -> bool {
+        let verified_at = self.verified_at.lock().expect("basic auth cache lock");
+        verified_at
+            .get(key)
+            .is_some_and(|at| at.elapsed() < CACHE_DURATION)
+    }
+
+    fn insert(&self, key: [u8; 32]) {
+        let mut verified_at = self.verified_at.lock().expect("basic auth cache lock");
+        if verified_at.len() >= MAX_CACHED_CREDENTIALS {
+            verified_at.retain(|_, at| at.elapsed() < CACHE_DURATION);
+        }
+        verified_at.insert(key, Instant::now());
+    }
+}
+
+/// Checks the credentials of the request against all the rules that apply to its path.
+/// Returns a `401 Unauthorized` response when they are missing or invalid.
+pub(crate) async fn check(
+    req: &ServiceRequest,
+    app_state: &AppState,
+    path: &str,
+) -> anyhow::Result<Option<HttpResponse>> {
+    let rules = &app_state.config.basic_auth;
+    let credentials = Authorization::<Basic>::parse(req)
+        .ok()
+        .map(Authorization::into_scheme);
+    let mut verified = None;
+    for (rule_index, rule) in rules.iter().enumerate() {
+        if !path.starts_with(&rule.path_prefix) {
+            continue;
+        }
+        let allowed = match &credentials {
+            Some(credentials) => is_allowed(app_state, rule_index, rule, credentials).await?,
+            None => false,
+        };
+        if !allowed {
+            log::debug!("Basic authentication required for {path}");
+            return Ok(Some(unauthorized(rule)));
+        }
+        verified = credentials.as_ref().map(|c| c.user_id().to_string());
+    }
+    if let Some(user) = verified {
+        req.extensions_mut().insert(VerifiedUser(user));
+    }
+    Ok(None)
+}
+
+async fn is_allowed(
+    app_state: &AppState,
+    rule_index: usize,
+    rule: &BasicAuth,
+    credentials: &Basic,
+) -> anyhow::Result<bool> {
+    let cache_key = BasicAuthCache::key(rule_index, credentials);
+    if app_state.basic_auth_cache.contains(&cache_key) {
+        return Ok(true);
+    }
+    let user = credentials.user_id();
+    let password_hash = match (rule.users.get(user), &rule.query) {
+        (Some(password_hash), _) => Some(password_hash.clone()),
+        (None, Some(query)) => query_password_hash(app_state, query, user).await?,
+        (None, None) => None,
+    };
+    let Some(password_hash) = password_hash else {
+        log::info!("Basic authentication failed: unknown user {user:?}");
+        return Ok(false);
+    };
+    let password = credentials.password().unwrap_or_default().to_string();
+    match crate::render::verify_password_async(password_hash, password).await? {
+        Ok(()) => {
+            app_state.basic_auth_cache.insert(cache_key);
+            Ok(true)
+        }
+        Err(e) => {
+            log::info!("Basic authentication failed for {user:?}: {e}");
+            Ok(false)
+        }
+    }
+}
+
+async fn query_password_hash(
+    app_state: &AppState,
+    query: &str,
+    user: &str,
+) -> anyhow::Result<Option<String>> {
+    let db = &app_state.db.connection;
+    let sql = query.replace(USERNAME_PARAMETER, &make_placeholder(db.any_kind(), 1));
+    let row = sqlx::query(&sql)
+        .bind(user)
+        .fetch_optional(db)
+        .await
+        .with_context(|| format!("Unable to run the basic_auth query: {query}"))?;
+    row.map(|row| row.try_get::<Option<String>, _>(0))
+        .transpose()
+        .with_context(|| "The basic_auth query must return the password hash as a string")
+        .map(Option::flatten)
+}
+
+fn unauthorized(rule: &BasicAuth) -> HttpResponse {
+    let realm = rule.realm.as_deref().unwrap_or("SQLPage").replace('"', "");
+    HttpResponse::Unauthorized()
+        .insert_header((
+            header::WWW_AUTHENTICATE,
+            format!("Basic realm=\"{realm}\", charset=\"UTF-8\""),
+        ))
+        .content_type("text/plain")
+        .body("Sorry, but you are not authorized to access this page.")
+}
@@ -0,0 +1,384 @@
+//! Uploads of large files in several parts, that can be resumed after a connection failure.
+//! They are enabled by setting `max_chunked_upload_size`.
+//!
+//! A client creates an upload with `POST /sqlpage/uploads`, giving the size of the file in the
+//! `Upload-Length` header and optionally its name in `Upload-Filename`, and gets the id of the upload.
+//! It then sends the parts of the file in order with `PATCH /sqlpage/uploads/<id>`, each with the
+//! position of its first byte in the `Upload-Offset` header. `GET /sqlpage/uploads/<id>` tells how
+//! much of the file was received, to resume an interrupted upload or to display its progress.
+//!
+//! Complete uploads are available to sql files with `sqlpage.chunked_upload_path(<id>)`.
+//! Uploads are stored in a temporary directory, and removed a day after their last change, or an hour
+//! after their last part when they are not complete. When the client that creates an upload has a
+//! session, the upload can only be continued and used in this session.
+//!
+//! The number of incomplete uploads and the total size of the stored uploads are limited, so that
+//! anonymous clients cannot fill the disk of the server.
+
+use crate::webserver::session::session_id;
+use crate::webserver::ErrorWithStatus;
+use crate::AppState;
+use actix_web::dev::ServiceRequest;
+use actix_web::http::{header, Method, StatusCode};
+use actix_web::HttpResponse;
+use anyhow::{bail, Context};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tokio::io::AsyncWriteExt;
+
+const UPLOADS_PATH: &str = "/sqlpage/uploads";
+const ID_LENGTH: usize = 32;
+/// Uploads that did not change for this long are removed
+const UPLOAD_EXPIRATION: Duration = Duration::from_secs(24 * 3600);
+/// Incomplete uploads that did not receive a part for this long are abandoned, and removed
+const ABANDONED_UPLOAD_EXPIRATION: Duration = Duration::from_secs(3600);
+/// Maximum number of incomplete uploads at the same time
+const MAX_PENDING_UPLOADS: usize = 100;
+
+/// Ids of the uploads that are receiving a part, so that two parts are never written at the same time
+static WRITING: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Stored next to the contents of the upload, in `<id>.json`
+#[derive(Serialize, Deserialize)]
+struct UploadInfo {
+    length: u64,
+    file_name: Option<String>,
+    /// Hash of the id of the session that created the upload
+    #[serde(default)]
+    session: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct UploadStatus {
+    id: String,
+    file_name: Option<String>,
+    length: u64,
+    offset: u64,
+    complete: bool,
+    #[serde(skip)]
+    session: Option<String>,
+}
+
+/// Answers the requests to `/sqlpage/uploads`, when chunked uploads are enabled
+pub(crate) async fn handle(
+    req: &mut ServiceRequest,
+    app_state: &AppState,
+    path: &str,
+) -> anyhow::Result<Option<HttpResponse>> {
+    let Some(id) = path.strip_prefix(UPLOADS_PATH) else {
+        return Ok(None);
+    };
+    let Some(max_size) = app_state.config.max_chunked_upload_size else {
+        return Ok(None);
+    };
+    let max_total_size = app_state
+        .config
+        .max_chunked_uploads_total_size
+        .unwrap_or(max_size.saturating_mul(10));
+    let session = session_hash(session_id(req.request(), app_state).as_deref());
+    let session = session.as_deref();
+    let id = id.trim_start_matches('/');
+    let response = match req.method().clone() {
+        Method::POST if id.is_empty() => create(req, max_size, max_total_size, session).await?,
+        _ if !is_valid_id(id) => bail!(ErrorWithStatus {
+            status: StatusCode::NOT_FOUND
+        }),
+        Method::PATCH => append(req, id, session).await?,
+        Method::GET | Method::HEAD => {
+            status_response(StatusCode::OK, &owned_status(id, session).await?)
+        }
+        Method::DELETE => {
+            owned_status(id, session).await?;
+            remove(id).await;
+            HttpResponse::NoContent().finish()
+        }
+        _ => bail!(ErrorWithStatus {
+            status: StatusCode::METHOD_NOT_ALLOWED
+        }),
+    };
+    Ok(Some(response))
+}
+
+/// The path of the file of a complete upload, or `None` when the upload does not exist, is not complete,
+/// or was created in another session
+pub(crate) async fn completed_upload_path(
+    id: &str,
+    session_id: Option<&str>,
+) -> anyhow::Result<Option<PathBuf>> {
+    Ok(upload_status(id, session_id)
+        .await?
+        .filter(|status| status.complete)
+        .map(|status| upload_directory().join(status.id)))
+}
+
+/// The progress of an upload, or `None` when it does not exist or was created in another session
+pub(crate) async fn upload_status(
+    id: &str,
+    session_id: Option<&str>,
+) -> anyhow::Result<Option<UploadStatus>> {
+    if !is_valid_id(id) {
+        return Ok(None);
+    }
+    match owned_status(id, session_hash(session_id).as_deref()).await {
+        Ok(status) => Ok(Some(status)),
+        Err(e) if e.downcast_ref::<ErrorWithStatus>().is_some() => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+async fn create(
+    req: &ServiceRequest,
+    max_size: u64,
+    max_total_size: u64,
+    session: Option<&str>,
+) -> anyhow::Result<HttpResponse> {
+    let length = upload_header(req, "upload-length")?;
+    if length > max_size {
+        bail!(ErrorWithStatus {
+            status: StatusCode::PAYLOAD_TOO_LARGE
+        });
+    }
+    let file_name = req
+        .headers()
+        .get("upload-filename")
+        .and_then(|v| v.to_str().ok())
+        .map(|name| percent_encoding::percent_decode_str(name).decode_utf8_lossy())
+        .map(|name| {
+            name.rsplit(['/', '\\'])
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        });
+    let dir = upload_directory();
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("Unable to create the upload directory {dir:?}"))?;
+    let (pending, total_size) = remove_expired_uploads().await;
+    if pending >= MAX_PENDING_UPLOADS {
+        log::warn!("Refusing a chunked upload: there are already {pending} incomplete uploads");
+        bail!(ErrorWithStatus {
+            status: StatusCode::TOO_MANY_REQUESTS
+        });
+    }
+    if total_size.saturating_add(length) > max_total_size {
+        log::warn!(
+            "Refusing a chunked upload of {length} bytes: the stored uploads already take \
+            {total_size} bytes, and max_chunked_uploads_total_size is {max_total_size}"
+        );
+        bail!(ErrorWithStatus {
+            status: StatusCode::INSUFFICIENT_STORAGE
+        });
+    }
+    let id = random_id();
+    tokio::fs::File::create(dir.join(&id))
+        .await
+        .context("Unable to create the uploaded file")?;
+    let info = serde_json::to_vec(&UploadInfo {
+        length,
+        file_name,
+        session: session.map(str::to_string),
+    })?;
+    tokio::fs::write(dir.join(format!("{id}.json")), info).await?;
+    log::debug!("Created the chunked upload {id} of {length} bytes");
+    let status = status(&id).await?;
+    let mut response = status_response(StatusCode::CREATED, &status);
+    response.headers_mut().insert(
+        header::LOCATION,
+        header::HeaderValue::from_str(&format!("{UPLOADS_PATH}/{id}"))?,
+    );
+    Ok(response)
+}
+
+/// Writes a part of the file at the end of the upload. A part that does not start where the
+/// previous one ended is rejected with `409 Conflict`, and the response tells where to resume.
+async fn append(
+    req: &mut ServiceRequest,
+    id: &str,
+    session: Option<&str>,
+) -> anyhow::Result<HttpResponse> {
+    let Some(_lock) = WriteLock::acquire(id) else {
+        bail!(ErrorWithStatus {
+            status: StatusCode::CONFLICT
+        });
+    };
+    let offset = upload_header(req, "upload-offset")?;
+    let status = owned_status(id, session).await?;
+    if offset != status.offset {
+        return Ok(status_response(StatusCode::CONFLICT, &status));
+    }
+    let mut remaining = status.length - status.offset;
+    let mut file = tokio::fs::OpenOptions::new()
+        .append(true)
+        .open(upload_directory().join(id))
+        .await
+        .with_context(|| format!("Unable to open the upload {id}"))?;
+    let mut payload = req.take_payload();
+    while let Some(chunk) = payload.next().await {
+        let chunk =
+            chunk.with_context(|| format!("Unable to receive a part of the upload {id}"))?;
+        let Some(left) = remaining.checked_sub(chunk.len() as u64) else {
+            bail!(ErrorWithStatus {
+                status: StatusCode::PAYLOAD_TOO_LARGE
+            });
+        };
+        file.write_all(&chunk).await?;
+        remaining = left;
+    }
+    file.flush().await?;
+    Ok(status_response(StatusCode::OK, &self::status(id).await?))
+}
+
+async fn status(id: &str) -> anyhow::Result<UploadStatus> {
+    let dir = upload_directory();
+    let info = match tokio::fs::read(dir.join(format!("{id}.json"))).await {
+        Ok(info) => info,
+        Err(e) if e.kind() == ErrorKind::NotFound => bail!(ErrorWithStatus {
+            status: StatusCode::NOT_FOUND
+        }),
+        Err(e) => return Err(e).with_context(|| format!("Unable to read the upload {id}")),
+    };
+    let UploadInfo {
+        length,
+        file_name,
+        session,
+    } = serde_json::from_slice(&info)?;
+    let offset = tokio::fs::metadata(dir.join(id))
+        .await
+        .with_context(|| format!("Unable to read the upload {id}"))?
+        .len();
+    Ok(UploadStatus {
+        id: id.to_string(),
+        file_name,
+        length,
+        offset,
+        complete: offset == length,
+        session,
+    })
+}
+
+/// The status of an upload, that only the session that created it can see
+async fn owned_status(id: &str, session: Option<&str>) -> anyhow::Result<UploadStatus> {
+    let status = status(id).await?;
+    if status.session.is_some() && status.session.as_deref() != session {
+        log::debug!("The upload {id} was created in another session");
+        bail!(ErrorWithStatus {
+            status: StatusCode::NOT_FOUND
+        });
+    }
+    Ok(status)
+}
+
+/// Session ids are secret, so only their hash is stored next to the uploads
+fn session_hash(session_id: Option<&str>) -> Option<String> {
+    session_id.map(|id| hex::encode(Sha256::digest(id.as_bytes())))
+}
+
+fn status_response(status_code: StatusCode, status: &UploadStatus) -> HttpResponse {
+    HttpResponse::build(status_code)
+        .insert_header((header::CACHE_CONTROL, "no-store"))
+        .insert_header(("Upload-Offset", status.offset))
+        .insert_header(("Upload-Length", status.length))
+        .json(status)
+}
+
+fn upload_header(req: &ServiceRequest, name: &str) -> anyhow::Result<u64> {
+    let value = req
+        .headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok()?.parse().ok());
+    let Some(value) = value else {
+        log::debug!("Missing or invalid {name} header in a chunked upload request");
+        bail!(ErrorWithStatus {
+            status: StatusCode::BAD_REQUEST
+        });
+    };
+    Ok(value)
+}
+
+async fn remove(id: &str) {
+    let dir = upload_directory();
+    for path in [dir.join(format!("{id}.json")), dir.join(id)] {
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            log::warn!("Unable to remove {path:?}: {e}");
+        }
+    }
+}
+
+/// Removes the expired and abandoned uploads. Returns the number of incomplete uploads that remain,
+/// and the total size of the remaining uploads.
+async fn remove_expired_uploads() -> (usize, u64) {
+    let (mut pending, mut total_size) = (0, 0);
+    let Ok(mut entries) = tokio::fs::read_dir(upload_directory()).await else {
+        return (pending, total_size);
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let file_name = entry.file_name();
+        // Each upload is a file named after its id, that changes with every part it receives
+        let Some(id) = file_name.to_str().filter(|name| is_valid_id(name)) else {
+            continue;
+        };
+        let status = status(id).await.ok();
+        let expiration = match &status {
+            Some(status) if status.complete => UPLOAD_EXPIRATION,
+            _ => ABANDONED_UPLOAD_EXPIRATION,
+        };
+        let expired = entry
+            .metadata()
+            .await
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age > expiration);
+        if expired {
+            log::debug!("Removing the expired upload {id}");
+            remove(id).await;
+        } else if let Some(status) = status {
+            pending += usize::from(!status.complete);
+            total_size += status.length;
+        }
+    }
+    (pending, total_size)
+}
+
+fn upload_directory() -> PathBuf {
+    std::env::temp_dir().join("sqlpage-uploads")
+}
+
+fn random_id() -> String {
+    use rand::{distributions::Alphanumeric, Rng};
+    password_hash::rand_core::OsRng
+        .sample_iter(&Alphanumeric)
+        .take(ID_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+/// Ids are generated by the server: anything else cannot be an upload, and cannot escape the upload directory
+fn is_valid_id(id: &str) -> bool {
+    id.len() == ID_LENGTH && id.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+struct WriteLock(String);
+
+impl WriteLock {
+    fn acquire(id: &str) -> Option<Self> {
+        let mut writing = WRITING.lock().expect("upload lock poisoned");
+        if writing.iter().any(|w| w == id) {
+            return None;
+        }
+        writing.push(id.to_string());
+        Some(Self(id.to_string()))
+    }
+}
+
+impl Drop for WriteLock {
+    fn drop(&mut self) {
+        let mut writing = WRITING.lock().expect("upload lock poisoned");
+        writing.retain(|w| *w != self.0);
+    }
+}
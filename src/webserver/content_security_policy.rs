@@ -0,0 +1,51 @@
+//! The `Content-Security-Policy` header, with a nonce generated for each page.
+//!
+//! The `{NONCE}` placeholder of the `content_security_policy` configuration option is replaced
+//! by a random value, that the shell adds to its inline scripts and styles with the `csp_nonce` helper.
+//! Responses that are not rendered from templates, such as static files, have no nonce at all.
+
+use base64::Engine;
+
+const NONCE_PLACEHOLDER: &str = "{NONCE}";
+
+#[must_use]
+pub fn new_nonce() -> String {
+    let mut nonce = [0; 18];
+    rand::RngCore::fill_bytes(&mut password_hash::rand_core::OsRng, &mut nonce);
+    base64::engine::general_purpose::STANDARD.encode(nonce)
+}
+
+/// The policy of a page rendered with the given nonce
+#[must_use]
+pub fn with_nonce(policy: &str, nonce: &str) -> String {
+    policy.replace(NONCE_PLACEHOLDER, nonce)
+}
+
+/// The policy of the responses that have no nonce: the sources that contain the placeholder are removed
+#[must_use]
+pub fn without_nonce(policy: &str) -> String {
+    policy
+        .split(';')
+        .map(|directive| {
+            directive
+                .split_ascii_whitespace()
+                .filter(|source| !source.contains(NONCE_PLACEHOLDER))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[test]
+fn test_content_security_policy() {
+    let policy = "script-src 'self' 'nonce-{NONCE}'; style-src 'nonce-{NONCE}' https://cdn.net";
+    assert_eq!(
+        with_nonce(policy, "abc"),
+        "script-src 'self' 'nonce-abc'; style-src 'nonce-abc' https://cdn.net"
+    );
+    assert_eq!(
+        without_nonce(policy),
+        "script-src 'self'; style-src https://cdn.net"
+    );
+}
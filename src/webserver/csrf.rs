@@ -0,0 +1,90 @@
+//! Protection against cross-site request forgery.
+//!
+//! Each browser receives a random identifier in the `sqlpage_csrf` cookie. The CSRF token of the browser
+//! is the signature of this identifier with the session key, and is embedded in the forms of the site.
+//! Other websites can make the browser send its cookies, but they cannot read the token.
+
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::http::Method;
+use base64::Engine;
+
+use super::http::SingleOrVec;
+use super::http_request_info::RequestInfo;
+use super::session::{constant_time_eq, signature};
+
+const CSRF_COOKIE: &str = "sqlpage_csrf";
+/// Name of the form field that contains the CSRF token
+const CSRF_FIELD: &str = "_sqlpage_csrf";
+/// Content types of the requests that other websites can make browsers send without asking them
+const FORM_CONTENT_TYPES: [&str; 3] = [
+    "application/x-www-form-urlencoded",
+    "multipart/form-data",
+    "text/plain",
+];
+
+/// The CSRF token of a browser, and the cookie to send to it when it does not have an identifier yet
+#[derive(Debug)]
+pub struct CsrfToken {
+    pub token: String,
+    pub cookie: Option<Cookie<'static>>,
+}
+
+fn browser_id(request: &RequestInfo) -> Option<&str> {
+    match request.cookies.get(CSRF_COOKIE) {
+        Some(SingleOrVec::Single(id)) if !id.is_empty() => Some(id),
+        _ => None,
+    }
+}
+
+pub(crate) fn csrf_token(request: &RequestInfo) -> CsrfToken {
+    let key = &request.app_state.session_key;
+    if let Some(id) = browser_id(request) {
+        return CsrfToken {
+            token: signature(key, CSRF_COOKIE, id),
+            cookie: None,
+        };
+    }
+    let mut id = [0; 24];
+    rand::RngCore::fill_bytes(&mut password_hash::rand_core::OsRng, &mut id);
+    let id = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(id);
+    let token = signature(key, CSRF_COOKIE, &id);
+    let mut cookie = Cookie::new(CSRF_COOKIE, id);
+    cookie.set_path("/");
+    cookie.set_http_only(true);
+    cookie.set_secure(request.protocol == "https");
+    cookie.set_same_site(SameSite::Lax);
+    CsrfToken {
+        token,
+        cookie: Some(cookie),
+    }
+}
+
+/// Whether a request is a form submission that does not contain the CSRF token of the browser.
+/// The token is removed from the posted variables, so that it does not end up in the database.
+pub(crate) fn is_forged(request: &mut RequestInfo) -> bool {
+    let submitted = request.post_variables.remove(CSRF_FIELD);
+    if !is_form_submission(request) {
+        return false;
+    }
+    let (Some(id), Some(SingleOrVec::Single(submitted))) = (browser_id(request), submitted) else {
+        return true;
+    };
+    let expected = signature(&request.app_state.session_key, CSRF_COOKIE, id);
+    !constant_time_eq(&expected, &submitted)
+}
+
+/// Only POST requests with a form body can be sent by other websites without a CORS preflight request
+fn is_form_submission(request: &RequestInfo) -> bool {
+    if request.method != Method::POST {
+        return false;
+    }
+    match request.headers.get("content-type") {
+        Some(SingleOrVec::Single(content_type)) => {
+            let content_type = content_type.trim_start().to_ascii_lowercase();
+            FORM_CONTENT_TYPES
+                .iter()
+                .any(|form_type| content_type.starts_with(form_type))
+        }
+        _ => true,
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::{anyhow, Context};
+use password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use rand::Rng;
+
+/// Characters used in backup codes. Visually ambiguous characters (0, o, 1, l, i) are excluded.
+const BACKUP_CODE_ALPHABET: &[u8] = b"23456789abcdefghjkmnpqrstuvwxyz";
+const BACKUP_CODE_GROUP_LEN: usize = 5;
+const MAX_BACKUP_CODES: usize = 100;
+
+/// Generates `count` random backup codes, and returns them as a JSON array
+/// of objects with a `code` (to show to the user once) and a `hash` (to store in the database).
+/// This is a CPU-intensive blocking operation.
+pub(super) fn generate_backup_codes(count: usize) -> anyhow::Result<String> {
+    anyhow::ensure!(
+        (1..=MAX_BACKUP_CODES).contains(&count),
+        "generate_backup_codes: the number of codes must be between 1 and {MAX_BACKUP_CODES}, got {count}"
+    );
+    let argon2 = argon2::Argon2::default();
+    let codes = (0..count)
+        .map(|_| {
+            let code = random_backup_code();
+            let salt = SaltString::generate(&mut password_hash::rand_core::OsRng);
+            let hash = argon2
+                .hash_password(normalize_backup_code(&code).as_bytes(), &salt)
+                .map_err(|e| anyhow!("Unable to hash backup code: {e}"))?;
+            Ok(serde_json::json!({ "code": code, "hash": hash.to_string() }))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(serde_json::Value::Array(codes).to_string())
+}
+
+/// Returns the hash that matches the given backup code, if any.
+/// `hashes` is either a JSON array of hashes, or a single hash.
+/// This is a CPU-intensive blocking operation.
+pub(super) fn verify_backup_code(code: &str, hashes: &str) -> anyhow::Result<Option<String>> {
+    let hashes: Vec<String> = if hashes.trim_start().starts_with('[') {
+        serde_json::from_str(hashes)
+            .with_context(|| "verify_backup_code: the hashes must be a JSON array of strings")?
+    } else {
+        vec![hashes.to_string()]
+    };
+    let code = normalize_backup_code(code);
+    let argon2 = argon2::Argon2::default();
+    for hash in hashes {
+        let parsed = PasswordHash::new(&hash)
+            .map_err(|e| anyhow!("verify_backup_code: invalid hash {hash:?}: {e}"))?;
+        if argon2.verify_password(code.as_bytes(), &parsed).is_ok() {
+            return Ok(Some(hash));
+        }
+    }
+    Ok(None)
+}
+
+fn random_backup_code() -> String {
+    let mut rng = password_hash::rand_core::OsRng;
+    let mut code = String::with_capacity(2 * BACKUP_CODE_GROUP_LEN + 1);
+    for i in 0..2 * BACKUP_CODE_GROUP_LEN {
+        if i == BACKUP_CODE_GROUP_LEN {
+            code.push('-');
+        }
+        let idx = rng.gen_range(0..BACKUP_CODE_ALPHABET.len());
+        code.push(char::from(BACKUP_CODE_ALPHABET[idx]));
+    }
+    code
+}
+
+/// Users may type backup codes in uppercase, or without the separator.
+fn normalize_backup_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[test]
+fn test_backup_codes() {
+    let codes: serde_json::Value =
+        serde_json::from_str(&generate_backup_codes(2).unwrap()).unwrap();
+    let code = codes[1]["code"].as_str().unwrap();
+    assert_eq!(code.len(), 2 * BACKUP_CODE_GROUP_LEN + 1);
+    let hashes = serde_json::to_string(&[&codes[0]["hash"], &codes[1]["hash"]]).unwrap();
+    let matched = verify_backup_code(&code.to_uppercase().replace('-', " "), &hashes).unwrap();
+    assert_eq!(matched.as_deref(), codes[1]["hash"].as_str());
+    assert_eq!(verify_backup_code("aaaaa-aaaaa", &hashes).unwrap(), None);
+    assert!(generate_backup_codes(0).is_err());
+}
@@ -289,6 +289,33 @@ fn make_csv_reader<R: AsyncRead + Unpin + Send>(
         .create_reader(file)
 }
 
+/// Parses a CSV file with a header row into a JSON array of objects, one per record,
+/// where the keys are the column names from the header.
+pub(super) async fn csv_to_json_array(
+    file: impl AsyncRead + Unpin + Send,
+) -> anyhow::Result<String> {
+    let mut reader = csv_async::AsyncReaderBuilder::new()
+        .has_headers(true)
+        .create_reader(file);
+    let headers = reader
+        .headers()
+        .await
+        .with_context(|| "reading csv header")?
+        .clone();
+    let mut rows = Vec::new();
+    let mut records = reader.into_records();
+    while let Some(record) = records.next().await {
+        let record = record.with_context(|| "reading csv record")?;
+        let row: serde_json::Map<String, serde_json::Value> = headers
+            .iter()
+            .zip(record.iter())
+            .map(|(k, v)| (k.to_string(), serde_json::Value::String(v.to_string())))
+            .collect();
+        rows.push(serde_json::Value::Object(row));
+    }
+    Ok(serde_json::to_string(&rows)?)
+}
+
 #[test]
 fn test_make_statement() {
     let csv_import = CsvImport {
@@ -0,0 +1,24 @@
+//! Functions written in rust by the applications that embed SQLPage, called from sql files
+//! like the built-in functions: `select sqlpage.my_function($x, 'y') as contents`.
+//!
+//! They are registered with [`AppState::builder`](crate::AppState::builder).
+//! Built-in functions always take precedence over custom functions with the same name.
+
+use crate::webserver::http_request_info::RequestInfo;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[async_trait(? Send)]
+pub trait SqlPageFunction: Send + Sync {
+    /// Computes the value of a call, from the values of its arguments, which are `None` when they are NULL.
+    /// Returning `None` gives NULL. An error stops the execution of the sql file, like errors of built-in functions.
+    async fn call(
+        &self,
+        request: &RequestInfo,
+        arguments: Vec<Option<String>>,
+    ) -> anyhow::Result<Option<String>>;
+}
+
+/// Custom functions by name, without the `sqlpage.` prefix
+pub type CustomFunctions = HashMap<String, Arc<dyn SqlPageFunction>>;
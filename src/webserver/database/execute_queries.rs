@@ -1,19 +1,21 @@
 use anyhow::anyhow;
 use futures_util::stream::Stream;
-use futures_util::StreamExt;
+use futures_util::{StreamExt, TryStreamExt};
 use std::borrow::Cow;
 use std::collections::HashMap;
 
 use super::csv_import::run_csv_import;
 use super::sql::{ParsedSqlFile, ParsedStatement, StmtWithParams};
 use crate::webserver::database::sql_pseudofunctions::extract_req_param;
-use crate::webserver::database::sql_to_json::row_to_string;
+use crate::webserver::database::sql_to_json::{row_to_json, row_to_string};
 use crate::webserver::http::SingleOrVec;
 use crate::webserver::http_request_info::RequestInfo;
+use crate::webserver::session::set_request_session_variable;
+use serde_json::json;
 
 use sqlx::any::{AnyArguments, AnyQueryResult, AnyRow, AnyStatement, AnyTypeInfo};
 use sqlx::pool::PoolConnection;
-use sqlx::{Any, AnyConnection, Arguments, Either, Executor, Statement};
+use sqlx::{Any, AnyConnection, Arguments, Either, Executor, Row, Statement};
 
 use super::sql_pseudofunctions::StmtParam;
 use super::{highlight_sql_error, Database, DbItem};
@@ -63,7 +65,14 @@ pub fn stream_query_results<'a>(
                     let query = bind_parameters(value, request).await?;
                     let connection = take_connection(db, &mut connection_opt).await?;
                     log::debug!("Executing query to set the {variable:?} variable: {:?}", query.sql);
-                    let value: Option<String> = connection.fetch_optional(query).await?.as_ref().and_then(row_to_string);
+                    let value = fetch_variable_value(connection, query, variable).await?;
+                    if let StmtParam::Session(Some(name)) = variable {
+                        log::debug!("Setting session variable {name} to {value:?}");
+                        set_request_session_variable(request, name, value.as_deref());
+                        // The session component stores the new value in the session of the user
+                        yield DbItem::Row(json!({ "component": "session", "name": name, "value": value }));
+                        continue;
+                    }
                     let (vars, name) = vars_and_name(request, variable)?;
                     if let Some(value) = value {
                         log::debug!("Setting variable {name} to {value:?}");
@@ -83,6 +92,36 @@ pub fn stream_query_results<'a>(
     .map(|res| res.unwrap_or_else(DbItem::Error))
 }
 
+/// Runs the query of a `SET` statement. It must not return more than one row,
+/// because a variable cannot hold several values.
+async fn fetch_variable_value(
+    connection: &mut AnyConnection,
+    query: StatementWithParams<'_>,
+    variable: &StmtParam,
+) -> anyhow::Result<Option<String>> {
+    let mut rows = connection.fetch(query);
+    let Some(row) = rows.try_next().await? else {
+        return Ok(None);
+    };
+    if rows.try_next().await?.is_some() {
+        return Err(anyhow!(
+            "The query that sets the variable {variable:?} returned more than one row. \
+            Add a WHERE or LIMIT clause to select a single row, \
+            or aggregate the rows into a single value"
+        ));
+    }
+    Ok(row_to_variable_value(&row))
+}
+
+/// A single column is stored as is, and a row with several columns is stored as a JSON object
+fn row_to_variable_value(row: &AnyRow) -> Option<String> {
+    if row.columns().len() > 1 {
+        Some(row_to_json(row).to_string())
+    } else {
+        row_to_string(row)
+    }
+}
+
 fn vars_and_name<'a>(
     request: &'a mut RequestInfo,
     variable: &StmtParam,
@@ -125,7 +164,7 @@ async fn take_connection<'a, 'b>(
 #[inline]
 fn parse_single_sql_result(sql: &str, res: sqlx::Result<Either<AnyQueryResult, AnyRow>>) -> DbItem {
     match res {
-        Ok(Either::Right(r)) => DbItem::Row(super::sql_to_json::row_to_json(&r)),
+        Ok(Either::Right(r)) => DbItem::Row(row_to_json(&r)),
         Ok(Either::Left(res)) => {
             log::debug!("Finished query with result: {:?}", res);
             DbItem::FinishedQuery
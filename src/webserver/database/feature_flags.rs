@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use super::sql_to_json::row_to_json;
+use crate::utils::is_truthy;
+use crate::AppState;
+
+const FEATURE_FLAGS_QUERY: &str = "SELECT name, enabled FROM sqlpage_feature_flags";
+
+/// Feature flags read from the `sqlpage_feature_flags` table, and cached for a short time
+/// to avoid querying the database on every request.
+#[derive(Default)]
+pub(crate) struct FeatureFlags {
+    cache: RwLock<Option<CachedFlags>>,
+}
+
+struct CachedFlags {
+    flags: Arc<HashMap<String, bool>>,
+    fetched_at: Instant,
+}
+
+impl FeatureFlags {
+    /// Returns whether the given feature is enabled.
+    /// Flags from the database take precedence over the ones from the configuration file.
+    pub(crate) async fn is_enabled(&self, app_state: &AppState, name: &str) -> bool {
+        let flags = self.flags(app_state).await;
+        flags
+            .get(name)
+            .or_else(|| app_state.config.feature_flags.get(name))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    async fn flags(&self, app_state: &AppState) -> Arc<HashMap<String, bool>> {
+        // Invalid durations, such as negative ones, disable the cache
+        let ttl = Duration::try_from_secs_f64(app_state.config.feature_flags_cache_seconds)
+            .unwrap_or(Duration::ZERO);
+        if let Some(cached) = &*self.cache.read().expect("feature flags lock poisoned") {
+            if cached.fetched_at.elapsed() < ttl {
+                return Arc::clone(&cached.flags);
+            }
+        }
+        self.refresh(app_state).await
+    }
+
+    /// Reads the flags from the database again, without waiting for the cached ones to expire
+    pub(crate) async fn refresh(&self, app_state: &AppState) -> Arc<HashMap<String, bool>> {
+        let flags = Arc::new(fetch_flags(app_state).await);
+        *self.cache.write().expect("feature flags lock poisoned") = Some(CachedFlags {
+            flags: Arc::clone(&flags),
+            fetched_at: Instant::now(),
+        });
+        flags
+    }
+}
+
+async fn fetch_flags(app_state: &AppState) -> HashMap<String, bool> {
+    log::debug!("Refreshing feature flags from the database");
+    match sqlx::query(FEATURE_FLAGS_QUERY)
+        .fetch_all(&app_state.db.connection)
+        .await
+    {
+        Ok(rows) => rows
+            .iter()
+            .filter_map(|row| {
+                let row = row_to_json(row);
+                let name = row.get("name")?.as_str()?.to_string();
+                Some((name, is_truthy(row.get("enabled")?)))
+            })
+            .collect(),
+        Err(e) => {
+            log::debug!(
+                "Unable to read feature flags from the database, using only the ones from the configuration. \
+                You can store feature flags in the database by creating the following table: \
+                CREATE TABLE sqlpage_feature_flags(name VARCHAR(255) NOT NULL PRIMARY KEY, enabled BOOLEAN NOT NULL); \
+                The error was: {e:#}"
+            );
+            HashMap::new()
+        }
+    }
+}
+
+#[actix_web::test]
+async fn test_feature_flags_from_database() -> anyhow::Result<()> {
+    use sqlx::Executor;
+    let mut config = crate::app_config::tests::test_config();
+    config.feature_flags = HashMap::from([
+        ("from_config".to_string(), true),
+        ("overridden".to_string(), true),
+    ]);
+    let state = AppState::init(&config).await?;
+    state
+        .db
+        .connection
+        .execute(
+            "CREATE TABLE sqlpage_feature_flags(name VARCHAR(255) NOT NULL PRIMARY KEY, enabled BOOLEAN NOT NULL);
+            INSERT INTO sqlpage_feature_flags(name, enabled) VALUES ('new_dashboard', TRUE), ('overridden', FALSE);",
+        )
+        .await?;
+    let flags = FeatureFlags::default();
+    assert!(flags.is_enabled(&state, "new_dashboard").await);
+    assert!(flags.is_enabled(&state, "from_config").await);
+    assert!(!flags.is_enabled(&state, "overridden").await);
+    assert!(!flags.is_enabled(&state, "unknown").await);
+
+    state
+        .db
+        .connection
+        .execute("UPDATE sqlpage_feature_flags SET enabled = TRUE WHERE name = 'overridden'")
+        .await?;
+    assert!(!flags.is_enabled(&state, "overridden").await, "cached");
+    assert_eq!(flags.refresh(&state).await.len(), 2);
+    assert!(flags.is_enabled(&state, "overridden").await);
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_invalid_feature_flags_cache_duration() -> anyhow::Result<()> {
+    let mut config = crate::app_config::tests::test_config();
+    config.feature_flags = HashMap::from([("from_config".to_string(), true)]);
+    for seconds in [-1., f64::NAN, f64::INFINITY] {
+        config.feature_flags_cache_seconds = seconds;
+        let state = AppState::init(&config).await?;
+        assert!(
+            FeatureFlags::default()
+                .is_enabled(&state, "from_config")
+                .await
+        );
+    }
+    Ok(())
+}
@@ -1,13 +1,22 @@
+mod backup_codes;
 mod connect;
 mod csv_import;
+pub mod custom_functions;
 pub mod execute_queries;
+pub(crate) mod feature_flags;
 pub mod migrations;
+mod render_to_storage;
+mod soft_delete;
 mod sql;
 mod sql_pseudofunctions;
 mod sql_to_json;
+mod validation;
+mod xml_to_json;
 
 pub use sql::{make_placeholder, ParsedSqlFile};
 
+/// Cloning a database shares its connection pool
+#[derive(Clone)]
 pub struct Database {
     pub(crate) connection: sqlx::AnyPool,
 }
@@ -0,0 +1,276 @@
+use std::ops::ControlFlow;
+
+use sqlparser::ast::{
+    visit_expressions, BinaryOperator, Expr, Function, Ident, JoinConstraint, JoinOperator,
+    ObjectName, Query, SetExpr, Statement, TableFactor, VisitMut, VisitorMut,
+};
+
+use crate::app_config::AppConfig;
+
+/// Adds a `deleted_at IS NULL` condition to all the queries that read from soft-deleted tables.
+///
+/// Queries that already mention the soft-delete column in their `WHERE` clause (or in the `ON`
+/// clause of a join) are left untouched, so that pages like a recycle bin can list deleted rows.
+/// A query can also include all the deleted rows explicitly, with `sqlpage.include_deleted()` in its `WHERE` clause.
+pub(super) struct SoftDeleteFilter {
+    column: String,
+    /// Lowercase names of the tables that use soft deletes
+    tables: Vec<String>,
+}
+
+impl SoftDeleteFilter {
+    pub(super) fn from_config(config: &AppConfig) -> Option<Self> {
+        if config.soft_delete_tables.is_empty() {
+            return None;
+        }
+        Some(Self {
+            column: config.soft_delete_column.clone(),
+            tables: config
+                .soft_delete_tables
+                .iter()
+                .map(|t| t.to_lowercase())
+                .collect(),
+        })
+    }
+
+    pub(super) fn apply(&self, stmt: &mut Statement) {
+        stmt.visit(&mut SoftDeleteVisitor(self));
+    }
+
+    /// Returns the name under which the table is visible in the query, if it is a soft-deleted table
+    fn table_qualifier(&self, relation: &TableFactor) -> Option<Vec<Ident>> {
+        let TableFactor::Table { name, alias, .. } = relation else {
+            return None;
+        };
+        let table_name = name.0.last()?.value.to_lowercase();
+        if !self.tables.contains(&table_name) {
+            return None;
+        }
+        Some(match alias {
+            Some(alias) => vec![alias.name.clone()],
+            None => name.0.clone(),
+        })
+    }
+
+    /// Whether the expression refers to the soft-delete column, with or without a table qualifier
+    fn mentions_column(&self, expr: Option<&Expr>) -> bool {
+        let Some(expr) = expr else {
+            return false;
+        };
+        let is_column = |ident: &Ident| ident.value.eq_ignore_ascii_case(&self.column);
+        visit_expressions(expr, |e| match e {
+            Expr::Identifier(ident) if is_column(ident) => ControlFlow::Break(()),
+            Expr::CompoundIdentifier(idents) if idents.last().is_some_and(is_column) => {
+                ControlFlow::Break(())
+            }
+            _ => ControlFlow::Continue(()),
+        })
+        .is_break()
+    }
+
+    fn not_deleted(&self, mut qualifier: Vec<Ident>) -> Expr {
+        qualifier.push(Ident::new(&self.column));
+        Expr::IsNull(Box::new(Expr::CompoundIdentifier(qualifier)))
+    }
+
+    fn filter_set_expr(&self, body: &mut SetExpr) {
+        match body {
+            SetExpr::Select(select) => {
+                if take_include_deleted(&mut select.selection) {
+                    return;
+                }
+                let skip_where = self.mentions_column(select.selection.as_ref());
+                let mut conditions = Vec::new();
+                for table in &mut select.from {
+                    if !skip_where {
+                        conditions.extend(
+                            self.table_qualifier(&table.relation)
+                                .map(|q| self.not_deleted(q)),
+                        );
+                    }
+                    for join in &mut table.joins {
+                        let Some(qualifier) = self.table_qualifier(&join.relation) else {
+                            continue;
+                        };
+                        let condition = self.not_deleted(qualifier);
+                        // Filtering in the join condition preserves the semantics of outer joins
+                        match &mut join.join_operator {
+                            JoinOperator::Inner(JoinConstraint::On(on))
+                            | JoinOperator::LeftOuter(JoinConstraint::On(on))
+                            | JoinOperator::RightOuter(JoinConstraint::On(on))
+                            | JoinOperator::FullOuter(JoinConstraint::On(on)) => {
+                                if !self.mentions_column(Some(on)) {
+                                    and_condition(on, condition);
+                                }
+                            }
+                            JoinOperator::Inner(_) | JoinOperator::CrossJoin if !skip_where => {
+                                conditions.push(condition);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                for condition in conditions {
+                    match &mut select.selection {
+                        Some(selection) => and_condition(selection, condition),
+                        None => select.selection = Some(condition),
+                    }
+                }
+            }
+            SetExpr::SetOperation { left, right, .. } => {
+                self.filter_set_expr(left);
+                self.filter_set_expr(right);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Removes `sqlpage.include_deleted()` from a `WHERE` clause, where it can be combined with other
+/// conditions with `AND`. Returns whether it was found.
+fn take_include_deleted(selection: &mut Option<Expr>) -> bool {
+    match selection {
+        Some(expr) if is_include_deleted(expr) => {
+            *selection = None;
+            true
+        }
+        Some(expr) => take_include_deleted_from_conjunction(expr),
+        None => false,
+    }
+}
+
+fn take_include_deleted_from_conjunction(expr: &mut Expr) -> bool {
+    match expr {
+        Expr::Nested(inner) => take_include_deleted_from_conjunction(inner),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => {
+            let remaining = if is_include_deleted(right) {
+                left
+            } else if is_include_deleted(left) {
+                right
+            } else {
+                return take_include_deleted_from_conjunction(left)
+                    || take_include_deleted_from_conjunction(right);
+            };
+            *expr = std::mem::replace(remaining.as_mut(), Expr::Value(sqlparser::ast::Value::Null));
+            true
+        }
+        _ => false,
+    }
+}
+
+fn is_include_deleted(expr: &Expr) -> bool {
+    match expr {
+        Expr::Nested(inner) => is_include_deleted(inner),
+        Expr::Function(Function {
+            name: ObjectName(name),
+            args,
+            ..
+        }) => {
+            args.is_empty()
+                && matches!(name.as_slice(), [namespace, function]
+                    if namespace.value == "sqlpage" && function.value == "include_deleted")
+        }
+        _ => false,
+    }
+}
+
+fn and_condition(expr: &mut Expr, condition: Expr) {
+    let existing = std::mem::replace(expr, Expr::Value(sqlparser::ast::Value::Null));
+    *expr = Expr::BinaryOp {
+        left: Box::new(Expr::Nested(Box::new(existing))),
+        op: BinaryOperator::And,
+        right: Box::new(condition),
+    };
+}
+
+struct SoftDeleteVisitor<'a>(&'a SoftDeleteFilter);
+
+impl VisitorMut for SoftDeleteVisitor<'_> {
+    type Break = ();
+    fn pre_visit_query(&mut self, query: &mut Query) -> ControlFlow<Self::Break> {
+        self.0.filter_set_expr(&mut query.body);
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sqlparser::dialect::PostgreSqlDialect;
+    use sqlparser::parser::Parser;
+
+    fn filtered(sql: &str) -> String {
+        let filter = SoftDeleteFilter {
+            column: "deleted_at".to_string(),
+            tables: vec!["users".to_string(), "orders".to_string()],
+        };
+        let mut stmt = Parser::parse_sql(&PostgreSqlDialect {}, sql)
+            .unwrap()
+            .pop()
+            .unwrap();
+        filter.apply(&mut stmt);
+        stmt.to_string()
+    }
+
+    #[test]
+    fn test_soft_delete_filter() {
+        assert_eq!(
+            filtered("select * from users"),
+            "SELECT * FROM users WHERE users.deleted_at IS NULL"
+        );
+        assert_eq!(
+            filtered("select * from users u where u.id = 1"),
+            "SELECT * FROM users AS u WHERE (u.id = 1) AND u.deleted_at IS NULL"
+        );
+        assert_eq!(
+            filtered("select * from users u left join orders o on o.user_id = u.id"),
+            "SELECT * FROM users AS u LEFT JOIN orders AS o ON (o.user_id = u.id) AND o.deleted_at IS NULL WHERE u.deleted_at IS NULL"
+        );
+        assert_eq!(
+            filtered("select * from products where id in (select product_id from orders)"),
+            "SELECT * FROM products WHERE id IN (SELECT product_id FROM orders WHERE orders.deleted_at IS NULL)"
+        );
+    }
+
+    #[test]
+    fn test_soft_delete_opt_out() {
+        assert_eq!(
+            filtered("select * from users where deleted_at is not null"),
+            "SELECT * FROM users WHERE deleted_at IS NOT NULL"
+        );
+        assert_eq!(
+            filtered("update users set deleted_at = null where id = 1"),
+            "UPDATE users SET deleted_at = NULL WHERE id = 1"
+        );
+        assert_eq!(
+            filtered("select * from users u where u.deleted_at < now()"),
+            "SELECT * FROM users AS u WHERE u.deleted_at < now()"
+        );
+        // Only the column itself turns the filter off, not a string or another column that contains its name
+        assert_eq!(
+            filtered("select * from users where note = 'deleted_at' or was_deleted_at_once"),
+            "SELECT * FROM users WHERE (note = 'deleted_at' OR was_deleted_at_once) AND users.deleted_at IS NULL"
+        );
+    }
+
+    #[test]
+    fn test_soft_delete_include_deleted() {
+        assert_eq!(
+            filtered("select * from users where sqlpage.include_deleted()"),
+            "SELECT * FROM users"
+        );
+        assert_eq!(
+            filtered("select * from users u join orders o on o.user_id = u.id where u.id = 1 and sqlpage.include_deleted()"),
+            "SELECT * FROM users AS u JOIN orders AS o ON o.user_id = u.id WHERE u.id = 1"
+        );
+        // The other queries of the statement are still filtered
+        assert_eq!(
+            filtered("select * from users where sqlpage.include_deleted() and id in (select user_id from orders)"),
+            "SELECT * FROM users WHERE id IN (SELECT user_id FROM orders WHERE orders.deleted_at IS NULL)"
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use super::csv_import::{extract_csv_copy_statement, CsvImport};
+use super::soft_delete::SoftDeleteFilter;
 use super::sql_pseudofunctions::{func_call_to_param, StmtParam};
+use crate::app_config::AppConfig;
 use crate::file_cache::AsyncFromStrWithState;
 use crate::utils::add_value_to_map;
 use crate::{AppState, Database};
@@ -11,8 +13,8 @@ use sqlparser::ast::{
 };
 use sqlparser::dialect::{Dialect, MsSqlDialect, MySqlDialect, PostgreSqlDialect, SQLiteDialect};
 use sqlparser::parser::{Parser, ParserError};
-use sqlparser::tokenizer::Token::{SemiColon, EOF};
-use sqlparser::tokenizer::Tokenizer;
+use sqlparser::tokenizer::Token::{Period, SemiColon, EOF};
+use sqlparser::tokenizer::{Token, TokenWithLocation, Tokenizer, Word};
 use sqlx::any::AnyKind;
 use std::fmt::Write;
 use std::ops::ControlFlow;
@@ -24,9 +26,10 @@ pub struct ParsedSqlFile {
 
 impl ParsedSqlFile {
     #[must_use]
-    pub fn new(db: &Database, sql: &str) -> ParsedSqlFile {
+    pub fn new(db: &Database, sql: &str, config: &AppConfig) -> ParsedSqlFile {
         let dialect = dialect_for_db(db.connection.any_kind());
-        let parsed_statements = match parse_sql(dialect.as_ref(), sql) {
+        let soft_delete = SoftDeleteFilter::from_config(config);
+        let parsed_statements = match parse_sql(dialect.as_ref(), sql, soft_delete) {
             Ok(parsed) => parsed,
             Err(err) => return Self::from_err(err),
         };
@@ -46,7 +49,7 @@ impl ParsedSqlFile {
 #[async_trait(? Send)]
 impl AsyncFromStrWithState for ParsedSqlFile {
     async fn from_str_with_state(app_state: &AppState, source: &str) -> anyhow::Result<Self> {
-        Ok(ParsedSqlFile::new(&app_state.db, source))
+        Ok(ParsedSqlFile::new(&app_state.db, source, &app_state.config))
     }
 }
 
@@ -71,18 +74,53 @@ pub(super) enum ParsedStatement {
 fn parse_sql<'a>(
     dialect: &'a dyn Dialect,
     sql: &'a str,
+    soft_delete: Option<SoftDeleteFilter>,
 ) -> anyhow::Result<impl Iterator<Item = ParsedStatement> + 'a> {
     let tokens = Tokenizer::new(dialect, sql)
         .tokenize_with_location()
         .with_context(|| "SQLPage's SQL parser could not tokenize the sql file")?;
+    let tokens = merge_session_variables(tokens);
     let mut parser = Parser::new(dialect).with_tokens_with_locations(tokens);
     let db_kind = kind_of_dialect(dialect);
     Ok(std::iter::from_fn(move || {
-        parse_single_statement(&mut parser, db_kind)
+        parse_single_statement_with(&mut parser, db_kind, soft_delete.as_ref())
     }))
 }
 
+/// Prefix of the session variables, such as `$session.user`
+const SESSION_VARIABLE_PREFIX: &str = "$session";
+
+/// Turns `$session.name` into a single identifier, which no database dialect parses on its own
+fn merge_session_variables(mut tokens: Vec<TokenWithLocation>) -> Vec<TokenWithLocation> {
+    let mut i = 0;
+    while i + 2 < tokens.len() {
+        let is_session = matches!(
+            &tokens[i].token,
+            Token::Placeholder(s) | Token::Word(Word { value: s, quote_style: None, .. })
+                if s == SESSION_VARIABLE_PREFIX
+        );
+        if is_session && tokens[i + 1].token == Period {
+            if let Token::Word(Word { value: name, .. }) = &tokens[i + 2].token {
+                tokens[i].token =
+                    Token::make_word(&format!("{SESSION_VARIABLE_PREFIX}.{name}"), None);
+                tokens.drain(i + 1..=i + 2);
+            }
+        }
+        i += 1;
+    }
+    tokens
+}
+
+#[cfg(test)]
 fn parse_single_statement(parser: &mut Parser<'_>, db_kind: AnyKind) -> Option<ParsedStatement> {
+    parse_single_statement_with(parser, db_kind, None)
+}
+
+fn parse_single_statement_with(
+    parser: &mut Parser<'_>,
+    db_kind: AnyKind,
+    soft_delete: Option<&SoftDeleteFilter>,
+) -> Option<ParsedStatement> {
     if parser.peek_token() == EOF {
         return None;
     }
@@ -91,6 +129,9 @@ fn parse_single_statement(parser: &mut Parser<'_>, db_kind: AnyKind) -> Option<P
         Err(err) => return Some(syntax_error(err, parser)),
     };
     while parser.consume_token(&SemiColon) {}
+    if let Some(soft_delete) = soft_delete {
+        soft_delete.apply(&mut stmt);
+    }
     if let Some(static_statement) = extract_static_simple_select(&stmt) {
         log::debug!("Optimised a static simple select to avoid a trivial database query: {stmt} optimized to {static_statement:?}");
         return Some(ParsedStatement::StaticSimpleSelect(static_statement));
@@ -156,10 +197,16 @@ fn kind_of_dialect(dialect: &dyn Dialect) -> AnyKind {
     }
 }
 
-fn map_param(mut name: String) -> StmtParam {
+pub(super) fn map_param(mut name: String) -> StmtParam {
     if name.is_empty() {
         return StmtParam::GetOrPost(name);
     }
+    if let Some(session_var) = name
+        .strip_prefix(SESSION_VARIABLE_PREFIX)
+        .and_then(|n| n.strip_prefix('.'))
+    {
+        return StmtParam::Session(Some(session_var.to_string()));
+    }
     let prefix = name.remove(0);
     match prefix {
         '$' => StmtParam::GetOrPost(name),
@@ -236,7 +283,12 @@ fn extract_set_variable(stmt: &mut Statement) -> Option<(StmtParam, String)> {
             } else {
                 StmtParam::GetOrPost(std::mem::take(&mut ident.value))
             };
-            return Some((variable, format!("SELECT {value}")));
+            // `SET x = (SELECT a, b FROM t)` runs the subquery itself, so that it can return several columns
+            let query = match value {
+                Expr::Subquery(subquery) => subquery.to_string(),
+                value => format!("SELECT {value}"),
+            };
+            return Some((variable, query));
         }
     }
     None
@@ -619,6 +671,44 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_set_variable_from_subquery() {
+        let sql = "set user = (select id, name from users where id = $id)";
+        for &(dialect, db_kind) in ALL_DIALECTS {
+            let mut parser = Parser::new(dialect).try_with_sql(sql).unwrap();
+            let stmt = parse_single_statement(&mut parser, db_kind);
+            if let Some(ParsedStatement::SetVariable {
+                value: StmtWithParams { query, params },
+                ..
+            }) = stmt
+            {
+                assert!(query.starts_with("SELECT id, name FROM users"), "{query}");
+                assert_eq!(params, [StmtParam::GetOrPost("id".to_string())]);
+            } else {
+                panic!("Failed for dialect {dialect:?}: {stmt:#?}",);
+            }
+        }
+    }
+
+    #[test]
+    fn test_session_variables() {
+        let sql = "set $session.step = $session.step + 1";
+        for &(dialect, _) in ALL_DIALECTS {
+            let stmt = parse_sql(dialect, sql, None).unwrap().next();
+            if let Some(ParsedStatement::SetVariable {
+                variable,
+                value: StmtWithParams { params, .. },
+            }) = stmt
+            {
+                let step = StmtParam::Session(Some("step".to_string()));
+                assert_eq!(variable, step, "{dialect:?}");
+                assert_eq!(params, [step]);
+            } else {
+                panic!("Failed for dialect {dialect:?}: {stmt:#?}",);
+            }
+        }
+    }
+
     #[test]
     fn is_own_placeholder() {
         assert!(ParameterExtractor {
@@ -6,13 +6,23 @@ use base64::Engine;
 use mime_guess::{mime::APPLICATION_OCTET_STREAM, Mime};
 use sqlparser::ast::FunctionArg;
 
+use crate::webserver::chunked_upload;
+use crate::webserver::oidc::user_info;
+use crate::webserver::session::{
+    get_session_variable, get_session_variables, SESSION_IMPERSONATOR, SESSION_USER,
+};
 use crate::webserver::{http::SingleOrVec, http_request_info::RequestInfo, ErrorWithStatus};
 
+use super::backup_codes::{generate_backup_codes, verify_backup_code};
+use super::csv_import::csv_to_json_array;
+use super::render_to_storage::render_to_storage;
 use super::sql::{
     extract_integer, extract_single_quoted_string, extract_single_quoted_string_optional,
-    extract_variable_argument, function_arg_to_stmt_param, stmt_param_error_invalid_arguments,
-    FormatArguments,
+    extract_variable_argument, function_arg_to_stmt_param, map_param,
+    stmt_param_error_invalid_arguments, FormatArguments,
 };
+use super::validation::Validator;
+use super::xml_to_json::xml_to_json;
 use anyhow::{anyhow, bail, Context};
 
 #[derive(Debug, PartialEq, Eq)]
@@ -22,10 +32,17 @@ pub(super) enum StmtParam {
     Post(String),
     GetOrPost(String),
     Cookie(String),
+    Session(Option<String>),
     Header(String),
     Error(String),
     BasicAuthPassword,
     BasicAuthUsername,
+    CurrentUser,
+    Impersonator,
+    UserInfo(Option<String>),
+    PurgeCache(Option<String>),
+    Feature(String),
+    RefreshFeatureFlags,
     HashPassword(Box<StmtParam>),
     UrlEncode(Box<StmtParam>),
     Exec(Vec<StmtParam>),
@@ -34,11 +51,30 @@ pub(super) enum StmtParam {
     EnvironmentVariable(String),
     SqlPageVersion,
     Literal(String),
-    UploadedFilePath(String),
+    UploadedFilePath(String, Option<String>),
+    UploadedFileMimeType(String),
+    UploadedFileName(String),
     ReadFileAsText(Box<StmtParam>),
     ReadFileAsDataUrl(Box<StmtParam>),
+    ReadUploadedCsv(String),
+    ChunkedUploadPath(Box<StmtParam>),
+    ChunkedUploadStatus(Box<StmtParam>),
+    FileExists(Box<StmtParam>),
+    FileSize(Box<StmtParam>),
+    FileModified(Box<StmtParam>),
+    JsonParam(Box<StmtParam>, String),
+    XmlToJson(Box<StmtParam>),
+    Validate(Box<StmtParam>, Validator),
+    GenerateBackupCodes(usize),
+    VerifyBackupCode(Box<StmtParam>, Box<StmtParam>),
+    RenderToStorage(Vec<StmtParam>),
     Path,
     Protocol,
+    ClientIp,
+    RequestMethod,
+    RequestId,
+    /// A function registered by an application that embeds SQLPage
+    Custom(String, Vec<StmtParam>),
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -63,14 +99,117 @@ fn parse_get_or_post(arg: Option<String>) -> StmtParam {
     }
 }
 
+fn parse_json_param(arguments: &mut [FunctionArg]) -> StmtParam {
+    let variable = match arguments.first_mut().and_then(function_arg_to_stmt_param) {
+        Some(StmtParam::Literal(name)) if name.starts_with('$') || name.starts_with(':') => {
+            map_param(name)
+        }
+        Some(param) => param,
+        None => return stmt_param_error_invalid_arguments("json_param", arguments),
+    };
+    match arguments.get_mut(1..) {
+        Some(rest) => extract_single_quoted_string("json_param", rest)
+            .map_or_else(StmtParam::Error, |path| {
+                StmtParam::JsonParam(Box::new(variable), path)
+            }),
+        None => StmtParam::Error(
+            "json_param(variable, path) requires a JSONPath as its second argument".to_string(),
+        ),
+    }
+}
+
+/// Parses `validate(value, 'rule')` and `matches(value, 'regex')`
+fn parse_validate(
+    func_name: &'static str,
+    arguments: &mut [FunctionArg],
+    build_validator: fn(&str) -> anyhow::Result<Validator>,
+) -> StmtParam {
+    let Some(value) = arguments.first_mut().and_then(function_arg_to_stmt_param) else {
+        return stmt_param_error_invalid_arguments(func_name, arguments);
+    };
+    let rule = match arguments.get_mut(1..) {
+        Some(rest) if !rest.is_empty() => extract_single_quoted_string(func_name, rest),
+        _ => Err(format!(
+            "{func_name}({}) requires a literal string as its second argument",
+            FormatArguments(arguments)
+        )),
+    };
+    match rule.and_then(|rule| build_validator(&rule).map_err(|e| format!("{func_name}: {e:#}"))) {
+        Ok(validator) => StmtParam::Validate(Box::new(value), validator),
+        Err(e) => StmtParam::Error(e),
+    }
+}
+
+fn parse_uploaded_file_path(arguments: &mut [FunctionArg]) -> StmtParam {
+    let name = match extract_single_quoted_string("uploaded_file_path", arguments) {
+        Ok(name) => name,
+        Err(e) => return StmtParam::Error(e),
+    };
+    let allowed_mime_type = match arguments.get_mut(1..) {
+        Some(rest) if !rest.is_empty() => {
+            match extract_single_quoted_string("uploaded_file_path", rest) {
+                Ok(mime) => Some(mime),
+                Err(e) => return StmtParam::Error(e),
+            }
+        }
+        _ => None,
+    };
+    StmtParam::UploadedFilePath(name, allowed_mime_type)
+}
+
+fn parse_verify_backup_code(arguments: &mut [FunctionArg]) -> StmtParam {
+    match arguments
+        .iter_mut()
+        .map(function_arg_to_stmt_param)
+        .collect::<Option<Vec<_>>>()
+        .map(<[StmtParam; 2]>::try_from)
+    {
+        Some(Ok([code, hashes])) => StmtParam::VerifyBackupCode(Box::new(code), Box::new(hashes)),
+        _ => StmtParam::Error(format!(
+            "verify_backup_code({}) is not a valid call. \
+            Expected two arguments: the code entered by the user, and the stored hashes.",
+            FormatArguments(arguments)
+        )),
+    }
+}
+
+fn parse_render_to_storage(arguments: &mut [FunctionArg]) -> StmtParam {
+    match arguments
+        .iter_mut()
+        .map(function_arg_to_stmt_param)
+        .collect::<Option<Vec<_>>>()
+    {
+        Some(params) if params.len() == 3 => StmtParam::RenderToStorage(params),
+        _ => StmtParam::Error(format!(
+            "render_to_storage({}) is not a valid call. \
+            Expected three arguments: the sql file to render, its parameters as a json object, and the destination.",
+            FormatArguments(arguments)
+        )),
+    }
+}
+
 pub(super) fn func_call_to_param(func_name: &str, arguments: &mut [FunctionArg]) -> StmtParam {
     match func_name {
         "cookie" => extract_single_quoted_string("cookie", arguments)
             .map_or_else(StmtParam::Error, StmtParam::Cookie),
+        "session" => StmtParam::Session(extract_single_quoted_string_optional(arguments)),
         "header" => extract_single_quoted_string("header", arguments)
             .map_or_else(StmtParam::Error, StmtParam::Header),
         "basic_auth_username" => StmtParam::BasicAuthUsername,
         "basic_auth_password" => StmtParam::BasicAuthPassword,
+        "current_user" => StmtParam::CurrentUser,
+        "impersonator" => StmtParam::Impersonator,
+        "user_info" => StmtParam::UserInfo(extract_single_quoted_string_optional(arguments)),
+        "purge_cache" => StmtParam::PurgeCache(extract_single_quoted_string_optional(arguments)),
+        "feature" => extract_single_quoted_string("feature", arguments)
+            .map_or_else(StmtParam::Error, StmtParam::Feature),
+        "refresh_feature_flags" => StmtParam::RefreshFeatureFlags,
+        // Removed from the queries by the soft delete filter, when it is used where it is allowed
+        "include_deleted" => StmtParam::Error(
+            "sqlpage.include_deleted() can only be used in the WHERE clause of a query, \
+            combined with other conditions with AND, when the soft_delete_tables configuration option is set"
+                .to_string(),
+        ),
         "hash_password" => StmtParam::HashPassword(Box::new(extract_variable_argument(
             "hash_password",
             arguments,
@@ -93,8 +232,16 @@ pub(super) fn func_call_to_param(func_name: &str, arguments: &mut [FunctionArg])
         "variables" => parse_get_or_post(extract_single_quoted_string_optional(arguments)),
         "path" => StmtParam::Path,
         "protocol" => StmtParam::Protocol,
-        "uploaded_file_path" => extract_single_quoted_string("uploaded_file_path", arguments)
-            .map_or_else(StmtParam::Error, StmtParam::UploadedFilePath),
+        "client_ip" => StmtParam::ClientIp,
+        "request_method" => StmtParam::RequestMethod,
+        "request_id" => StmtParam::RequestId,
+        "uploaded_file_path" => parse_uploaded_file_path(arguments),
+        "uploaded_file_mime_type" => {
+            extract_single_quoted_string("uploaded_file_mime_type", arguments)
+                .map_or_else(StmtParam::Error, StmtParam::UploadedFileMimeType)
+        }
+        "uploaded_file_name" => extract_single_quoted_string("uploaded_file_name", arguments)
+            .map_or_else(StmtParam::Error, StmtParam::UploadedFileName),
         "read_file_as_text" => StmtParam::ReadFileAsText(Box::new(extract_variable_argument(
             "read_file_as_text",
             arguments,
@@ -102,10 +249,51 @@ pub(super) fn func_call_to_param(func_name: &str, arguments: &mut [FunctionArg])
         "read_file_as_data_url" => StmtParam::ReadFileAsDataUrl(Box::new(
             extract_variable_argument("read_file_as_data_url", arguments),
         )),
-        unknown_name => StmtParam::Error(format!(
-            "Unknown function {unknown_name}({})",
-            FormatArguments(arguments)
+        "file_exists" => StmtParam::FileExists(Box::new(extract_variable_argument(
+            "file_exists",
+            arguments,
+        ))),
+        "file_size" => {
+            StmtParam::FileSize(Box::new(extract_variable_argument("file_size", arguments)))
+        }
+        "file_modified" => StmtParam::FileModified(Box::new(extract_variable_argument(
+            "file_modified",
+            arguments,
+        ))),
+        "read_uploaded_csv" => extract_single_quoted_string("read_uploaded_csv", arguments)
+            .map_or_else(StmtParam::Error, StmtParam::ReadUploadedCsv),
+        "chunked_upload_path" => StmtParam::ChunkedUploadPath(Box::new(extract_variable_argument(
+            "chunked_upload_path",
+            arguments,
+        ))),
+        "chunked_upload_status" => StmtParam::ChunkedUploadStatus(Box::new(
+            extract_variable_argument("chunked_upload_status", arguments),
         )),
+        "json_param" => parse_json_param(arguments),
+        "xml_to_json" => StmtParam::XmlToJson(Box::new(extract_variable_argument(
+            "xml_to_json",
+            arguments,
+        ))),
+        "validate" => parse_validate("validate", arguments, Validator::from_rule),
+        "matches" => parse_validate("matches", arguments, Validator::from_regex),
+        "render_to_storage" => parse_render_to_storage(arguments),
+        "generate_backup_codes" => extract_integer("generate_backup_codes", arguments)
+            .map_or_else(StmtParam::Error, StmtParam::GenerateBackupCodes),
+        "verify_backup_code" => parse_verify_backup_code(arguments),
+        // Custom functions are only known when the file is executed
+        unknown_name => arguments
+            .iter_mut()
+            .map(function_arg_to_stmt_param)
+            .collect::<Option<Vec<_>>>()
+            .map_or_else(
+                || {
+                    StmtParam::Error(format!(
+                        "Unknown function {unknown_name}({})",
+                        FormatArguments(arguments)
+                    ))
+                },
+                |params| StmtParam::Custom(unknown_name.to_string(), params),
+            ),
     }
 }
 
@@ -121,6 +309,66 @@ pub(super) async fn extract_req_param<'a>(
         StmtParam::UrlEncode(inner) => url_encode(inner, request)?,
         StmtParam::ReadFileAsText(inner) => read_file_as_text(inner, request).await?,
         StmtParam::ReadFileAsDataUrl(inner) => read_file_as_data_url(inner, request).await?,
+        StmtParam::ReadUploadedCsv(name) => read_uploaded_csv(name, request).await?,
+        StmtParam::ChunkedUploadPath(inner) => {
+            let Some(id) = extract_req_param_non_nested(inner, request)? else {
+                return Ok(None);
+            };
+            chunked_upload::completed_upload_path(&id, request.session.id.as_deref())
+                .await?
+                .and_then(|path| path.to_str().map(|p| Cow::Owned(p.to_string())))
+        }
+        StmtParam::ChunkedUploadStatus(inner) => {
+            let Some(id) = extract_req_param_non_nested(inner, request)? else {
+                return Ok(None);
+            };
+            match chunked_upload::upload_status(&id, request.session.id.as_deref()).await? {
+                Some(status) => Some(Cow::Owned(serde_json::to_string(&status)?)),
+                None => None,
+            }
+        }
+        StmtParam::Feature(name) => {
+            let app_state = &request.app_state;
+            let enabled = app_state.feature_flags.is_enabled(app_state, name).await;
+            Some(Cow::Borrowed(if enabled { "true" } else { "false" }))
+        }
+        StmtParam::RefreshFeatureFlags => {
+            let app_state = &request.app_state;
+            let flags = app_state.feature_flags.refresh(app_state).await;
+            Some(Cow::Owned(flags.len().to_string()))
+        }
+        StmtParam::FileExists(inner) => {
+            file_metadata(inner, request, |m| Some(m.is_some().to_string()))
+                .await?
+                .map(Cow::Owned)
+        }
+        StmtParam::FileSize(inner) => file_metadata(inner, request, |m| {
+            m.filter(|m| m.is_file()).map(|m| m.len().to_string())
+        })
+        .await?
+        .map(Cow::Owned),
+        StmtParam::FileModified(inner) => file_metadata(inner, request, |m| {
+            let modified = m?.modified().ok()?;
+            Some(chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339())
+        })
+        .await?
+        .map(Cow::Owned),
+        StmtParam::JsonParam(inner, path) => json_param(inner, path, request)?,
+        StmtParam::XmlToJson(inner) => xml_to_json_param(inner, request)?,
+        StmtParam::Validate(inner, validator) => {
+            extract_req_param_non_nested(inner, request)?.filter(|value| validator.is_valid(value))
+        }
+        StmtParam::GenerateBackupCodes(count) => {
+            let count = *count;
+            let codes =
+                actix_web::rt::task::spawn_blocking(move || generate_backup_codes(count)).await??;
+            Some(Cow::Owned(codes))
+        }
+        StmtParam::VerifyBackupCode(code, hashes) => {
+            verify_backup_code_param(code, hashes, request).await?
+        }
+        StmtParam::RenderToStorage(args) => render_to_storage_param(args, request).await?,
+        StmtParam::Custom(name, args) => custom_function(name, args, request).await?,
         _ => extract_req_param_non_nested(param, request)?,
     })
 }
@@ -149,6 +397,130 @@ fn url_encode<'a>(
     }
 }
 
+fn json_param<'a>(
+    inner: &StmtParam,
+    path: &str,
+    request: &'a RequestInfo,
+) -> anyhow::Result<Option<Cow<'a, str>>> {
+    let Some(json_str) = extract_req_param_non_nested(inner, request)? else {
+        return Ok(None);
+    };
+    let json: serde_json::Value = serde_json::from_str(&json_str)
+        .with_context(|| format!("json_param: {inner:?} does not contain valid JSON"))?;
+    Ok(match json_path_lookup(&json, path)? {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(s)) => Some(Cow::Owned(s.clone())),
+        Some(other) => Some(Cow::Owned(other.to_string())),
+    })
+}
+
+fn xml_to_json_param<'a>(
+    inner: &StmtParam,
+    request: &'a RequestInfo,
+) -> anyhow::Result<Option<Cow<'a, str>>> {
+    let Some(xml) = extract_req_param_non_nested(inner, request)? else {
+        return Ok(None);
+    };
+    let json = xml_to_json(&xml)
+        .with_context(|| format!("xml_to_json: {inner:?} does not contain valid XML"))?;
+    Ok(Some(Cow::Owned(json.to_string())))
+}
+
+async fn verify_backup_code_param<'a>(
+    code: &StmtParam,
+    hashes: &StmtParam,
+    request: &'a RequestInfo,
+) -> anyhow::Result<Option<Cow<'a, str>>> {
+    let (Some(code), Some(hashes)) = (
+        extract_req_param_non_nested(code, request)?,
+        extract_req_param_non_nested(hashes, request)?,
+    ) else {
+        return Ok(None);
+    };
+    let (code, hashes) = (code.into_owned(), hashes.into_owned());
+    let matched =
+        actix_web::rt::task::spawn_blocking(move || verify_backup_code(&code, &hashes)).await??;
+    Ok(matched.map(Cow::Owned))
+}
+
+/// Evaluates a simple `JSONPath` expression such as `$.items[0].sku` or `$['a key'][2]`.
+/// Returns `Ok(None)` when the path does not match anything in the given value.
+fn json_path_lookup<'v>(
+    value: &'v serde_json::Value,
+    path: &str,
+) -> anyhow::Result<Option<&'v serde_json::Value>> {
+    let mut rest = path
+        .trim()
+        .strip_prefix('$')
+        .with_context(|| format!("Invalid JSONPath {path:?}: it must start with '$'"))?;
+    let mut current = value;
+    while !rest.is_empty() {
+        let (next, remaining) = if let Some(r) = rest.strip_prefix('.') {
+            let end = r.find(['.', '[']).unwrap_or(r.len());
+            (current.get(&r[..end]), &r[end..])
+        } else if let Some(r) = rest.strip_prefix("['") {
+            let end = r
+                .find("']")
+                .with_context(|| format!("Invalid JSONPath {path:?}: unclosed ['"))?;
+            (current.get(&r[..end]), &r[end + 2..])
+        } else if let Some(r) = rest.strip_prefix('[') {
+            let end = r
+                .find(']')
+                .with_context(|| format!("Invalid JSONPath {path:?}: unclosed ["))?;
+            let index: usize = r[..end]
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid JSONPath {path:?}: bad array index"))?;
+            (current.get(index), &r[end + 1..])
+        } else {
+            bail!("Invalid JSONPath {path:?}: unexpected {rest:?}");
+        };
+        let Some(next) = next else {
+            return Ok(None);
+        };
+        current = next;
+        rest = remaining;
+    }
+    Ok(Some(current))
+}
+
+async fn render_to_storage_param<'a>(
+    args: &[StmtParam],
+    request: &'a RequestInfo,
+) -> anyhow::Result<Option<Cow<'a, str>>> {
+    let [sql_file, parameters, destination] = args else {
+        bail!("render_to_storage() requires exactly three arguments");
+    };
+    let Some(sql_file) = extract_req_param_non_nested(sql_file, request)? else {
+        return Ok(None);
+    };
+    let parameters = extract_req_param_non_nested(parameters, request)?;
+    let Some(destination) = extract_req_param_non_nested(destination, request)? else {
+        return Ok(None);
+    };
+    let url = render_to_storage(&sql_file, parameters.as_deref(), &destination, request).await?;
+    Ok(Some(Cow::Owned(url)))
+}
+
+async fn custom_function<'a>(
+    name: &str,
+    args: &[StmtParam],
+    request: &'a RequestInfo,
+) -> anyhow::Result<Option<Cow<'a, str>>> {
+    let Some(function) = request.app_state.custom_functions.get(name) else {
+        bail!("Unknown function sqlpage.{name}()");
+    };
+    let mut arguments = Vec::with_capacity(args.len());
+    for arg in args {
+        arguments.push(extract_req_param_non_nested(arg, request)?.map(Cow::into_owned));
+    }
+    let value = function
+        .call(request, arguments)
+        .await
+        .with_context(|| format!("Error in sqlpage.{name}()"))?;
+    Ok(value.map(Cow::Owned))
+}
+
 async fn exec_external_command<'a>(
     args_params: &[StmtParam],
     request: &'a RequestInfo,
@@ -211,6 +583,26 @@ async fn read_file_bytes<'a>(
     }
 }
 
+/// Reads the metadata of a file in the web root, and extracts a value from it.
+/// Returns NULL if the path is NULL.
+async fn file_metadata(
+    param0: &StmtParam,
+    request: &RequestInfo,
+    extract: impl FnOnce(Option<&std::fs::Metadata>) -> Option<String>,
+) -> anyhow::Result<Option<String>> {
+    let Some(path_str) = extract_req_param_non_nested(param0, request)? else {
+        return Ok(None);
+    };
+    let path = std::path::Path::new(path_str.trim_start_matches('/'));
+    let metadata = request
+        .app_state
+        .file_system
+        .local_metadata(path)
+        .await
+        .with_context(|| format!("Unable to read the metadata of {path_str:?}"))?;
+    Ok(extract(metadata.as_ref()))
+}
+
 async fn read_file_as_text<'a>(
     param0: &StmtParam,
     request: &'a RequestInfo,
@@ -243,14 +635,41 @@ async fn read_file_as_data_url<'a>(
     Ok(Some(Cow::Owned(data_url)))
 }
 
+async fn read_uploaded_csv<'a>(
+    field_name: &str,
+    request: &'a RequestInfo,
+) -> Result<Option<Cow<'a, str>>, anyhow::Error> {
+    let Some(uploaded_file) = request.uploaded_files.get(field_name) else {
+        log::debug!("read_uploaded_csv: no file uploaded in field {field_name:?}, returning NULL");
+        return Ok(None);
+    };
+    let file = tokio::fs::File::open(uploaded_file.file.path())
+        .await
+        .with_context(|| {
+            format!("read_uploaded_csv: unable to open the file uploaded in {field_name:?}")
+        })?;
+    let json = csv_to_json_array(tokio::io::BufReader::new(file))
+        .await
+        .with_context(|| format!("read_uploaded_csv: {field_name:?} is not a valid CSV file"))?;
+    Ok(Some(Cow::Owned(json)))
+}
+
 fn mime_from_upload<'a>(param0: &StmtParam, request: &'a RequestInfo) -> Option<&'a Mime> {
-    if let StmtParam::UploadedFilePath(name) = param0 {
+    if let StmtParam::UploadedFilePath(name, _) = param0 {
         request.uploaded_files.get(name)?.content_type.as_ref()
     } else {
         None
     }
 }
 
+/// Checks whether a mime type matches a pattern such as `image/png` or `image/*`
+fn mime_matches(mime: &Mime, pattern: &str) -> bool {
+    match pattern.trim().split_once('/') {
+        Some((type_, "*")) => mime.type_().as_str().eq_ignore_ascii_case(type_),
+        _ => mime.essence_str().eq_ignore_ascii_case(pattern.trim()),
+    }
+}
+
 fn mime_guess_from_filename(filename: &str) -> Mime {
     let maybe_mime = mime_guess::from_path(filename).first();
     maybe_mime.unwrap_or(APPLICATION_OCTET_STREAM)
@@ -270,6 +689,10 @@ pub(super) fn extract_req_param_non_nested<'a>(
             .map(SingleOrVec::as_json_str),
         StmtParam::Cookie(x) => request.cookies.get(x).map(SingleOrVec::as_json_str),
         StmtParam::Header(x) => request.headers.get(x).map(SingleOrVec::as_json_str),
+        StmtParam::Session(Some(x)) => get_session_variable(request, x).map(Cow::Borrowed),
+        StmtParam::Session(None) => Some(Cow::Owned(
+            serde_json::Value::Object(get_session_variables(request)).to_string(),
+        )),
         StmtParam::Error(x) => anyhow::bail!("{}", x),
         StmtParam::BasicAuthPassword => extract_basic_auth_password(request)
             .map(Cow::Borrowed)
@@ -277,6 +700,17 @@ pub(super) fn extract_req_param_non_nested<'a>(
         StmtParam::BasicAuthUsername => extract_basic_auth_username(request)
             .map(Cow::Borrowed)
             .map(Some)?,
+        StmtParam::CurrentUser => current_user(request).map(Cow::Borrowed),
+        StmtParam::Impersonator => {
+            get_session_variable(request, SESSION_IMPERSONATOR).map(Cow::Borrowed)
+        }
+        StmtParam::UserInfo(claim) => user_info(request, claim.as_deref()).map(Cow::Owned),
+        StmtParam::PurgeCache(path_prefix) => {
+            let path_prefix = path_prefix.as_deref().unwrap_or("/");
+            let purged = request.app_state.page_cache.purge(path_prefix);
+            log::debug!("Purged {purged} pages starting with {path_prefix} from the page cache");
+            Some(Cow::Owned(purged.to_string()))
+        }
         StmtParam::HashPassword(_) => bail!("Nested hash_password() function not allowed"),
         StmtParam::Exec(_) => bail!("Nested exec() function not allowed"),
         StmtParam::UrlEncode(_) => bail!("Nested url_encode() function not allowed"),
@@ -291,15 +725,62 @@ pub(super) fn extract_req_param_non_nested<'a>(
         StmtParam::AllVariables(get_or_post) => extract_get_or_post(*get_or_post, request),
         StmtParam::Path => Some(Cow::Borrowed(&request.path)),
         StmtParam::Protocol => Some(Cow::Borrowed(&request.protocol)),
-        StmtParam::UploadedFilePath(x) => request
+        StmtParam::ClientIp => request.client_ip.map(|ip| Cow::Owned(ip.to_string())),
+        StmtParam::RequestMethod => Some(Cow::Borrowed(request.method.as_str())),
+        StmtParam::RequestId => Some(Cow::Borrowed(request.request_id.as_str())),
+        StmtParam::UploadedFilePath(x, allowed_mime_type) => request
             .uploaded_files
             .get(x)
+            .filter(|x| {
+                allowed_mime_type.as_deref().map_or(true, |allowed| {
+                    x.content_type
+                        .as_ref()
+                        .is_some_and(|mime| mime_matches(mime, allowed))
+                })
+            })
             .and_then(|x| x.file.path().to_str())
             .map(Cow::Borrowed),
+        StmtParam::UploadedFileMimeType(x) => request
+            .uploaded_files
+            .get(x)
+            .and_then(|x| x.content_type.as_ref())
+            .map(|mime| Cow::Borrowed(mime.essence_str())),
+        StmtParam::UploadedFileName(x) => request
+            .uploaded_files
+            .get(x)
+            .and_then(|x| x.file_name.as_deref())
+            .map(Cow::Borrowed),
         StmtParam::ReadFileAsText(_) => bail!("Nested read_file_as_text() function not allowed",),
         StmtParam::ReadFileAsDataUrl(_) => {
             bail!("Nested read_file_as_data_url() function not allowed",)
         }
+        StmtParam::ReadUploadedCsv(_) => {
+            bail!("Nested read_uploaded_csv() function not allowed",)
+        }
+        StmtParam::ChunkedUploadPath(_) => {
+            bail!("Nested chunked_upload_path() function not allowed")
+        }
+        StmtParam::ChunkedUploadStatus(_) => {
+            bail!("Nested chunked_upload_status() function not allowed")
+        }
+        StmtParam::Feature(_) => bail!("Nested feature() function not allowed"),
+        StmtParam::RefreshFeatureFlags => {
+            bail!("Nested refresh_feature_flags() function not allowed")
+        }
+        StmtParam::FileExists(_) => bail!("Nested file_exists() function not allowed"),
+        StmtParam::FileSize(_) => bail!("Nested file_size() function not allowed"),
+        StmtParam::FileModified(_) => bail!("Nested file_modified() function not allowed"),
+        StmtParam::JsonParam(..) => bail!("Nested json_param() function not allowed"),
+        StmtParam::XmlToJson(_) => bail!("Nested xml_to_json() function not allowed"),
+        StmtParam::Validate(..) => bail!("Nested validate() function not allowed"),
+        StmtParam::GenerateBackupCodes(_) => {
+            bail!("Nested generate_backup_codes() function not allowed")
+        }
+        StmtParam::VerifyBackupCode(..) => {
+            bail!("Nested verify_backup_code() function not allowed")
+        }
+        StmtParam::RenderToStorage(_) => bail!("Nested render_to_storage() function not allowed"),
+        StmtParam::Custom(name, _) => bail!("Nested {name}() function not allowed"),
     })
 }
 
@@ -355,6 +836,13 @@ fn hash_password(password: &str) -> anyhow::Result<String> {
     Ok(password_hash.to_string())
 }
 
+/// The identity of the logged-in user: the `user` session variable, or the basic auth username
+/// when its password was checked by a `basic_auth` rule of the configuration.
+/// Contrary to `basic_auth_username`, anonymous requests are not rejected.
+fn current_user(request: &RequestInfo) -> Option<&str> {
+    get_session_variable(request, SESSION_USER).or(request.verified_user.as_deref())
+}
+
 fn extract_basic_auth_username(request: &RequestInfo) -> anyhow::Result<&str> {
     Ok(extract_basic_auth(request)?.user_id())
 }
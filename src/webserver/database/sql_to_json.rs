@@ -1,4 +1,5 @@
 use crate::utils::add_value_to_map;
+use base64::Engine;
 use chrono::{DateTime, Utc};
 use serde_json::{self, Map, Value};
 use sqlx::any::AnyRow;
@@ -80,6 +81,11 @@ pub fn sql_nonnull_to_json<'r>(mut get_ref: impl FnMut() -> sqlx::any::AnyValueR
                     .map_or_else(ToString::to_string, DateTime::to_rfc3339),
             )
         }
+        "BLOB" | "BYTEA" | "BINARY" | "VARBINARY" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB"
+        | "IMAGE" => <Vec<u8> as Decode<sqlx::any::Any>>::decode(raw_value)
+            .map(|bytes| blob_to_data_url(&bytes))
+            .unwrap_or_default()
+            .into(),
         "JSON" | "JSON[]" | "JSONB" | "JSONB[]" => {
             <Value as Decode<sqlx::any::Any>>::decode(raw_value).unwrap_or_default()
         }
@@ -90,6 +96,14 @@ pub fn sql_nonnull_to_json<'r>(mut get_ref: impl FnMut() -> sqlx::any::AnyValueR
     }
 }
 
+/// Binary values are represented as data urls, that can be used directly as the source of an image,
+/// or sent as is with the `blob` component
+fn blob_to_data_url(bytes: &[u8]) -> String {
+    let mut data_url = String::from("data:application/octet-stream;base64,");
+    base64::engine::general_purpose::STANDARD.encode_string(bytes, &mut data_url);
+    data_url
+}
+
 /// Takes the first column of a row and converts it to a string.
 pub fn row_to_string(row: &AnyRow) -> Option<String> {
     let col = row.columns().first()?;
@@ -111,7 +125,8 @@ async fn test_row_to_json() -> anyhow::Result<()> {
         2 as two_values, \
         'x' as three_values, \
         'y' as three_values, \
-        'z' as three_values \
+        'z' as three_values, \
+        x'53514c' as blob \
     ",
     )
     .fetch_one(&mut c)
@@ -122,6 +137,7 @@ async fn test_row_to_json() -> anyhow::Result<()> {
             "one_value": 123.456,
             "two_values": [1,2],
             "three_values": ["x","y","z"],
+            "blob": "data:application/octet-stream;base64,U1FM",
         })
     );
     Ok(())
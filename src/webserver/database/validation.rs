@@ -0,0 +1,111 @@
+use anyhow::Context;
+use regex::Regex;
+
+/// A rule that user input must respect, used by `sqlpage.validate` and `sqlpage.matches`.
+/// Rules are built when the SQL file is parsed, so that regular expressions are compiled only once.
+#[derive(Debug)]
+pub(super) struct Validator {
+    /// The rule name, or the regular expression, as written by the user
+    source: String,
+    kind: ValidatorKind,
+}
+
+#[derive(Debug)]
+enum ValidatorKind {
+    Regex(Regex),
+    Url,
+    Integer,
+    Number,
+    Date,
+}
+
+impl Validator {
+    /// Builds a validator from one of the named rules accepted by `sqlpage.validate`
+    pub(super) fn from_rule(rule: &str) -> anyhow::Result<Self> {
+        let kind = match rule.to_ascii_lowercase().as_str() {
+            "email" => ValidatorKind::Regex(Regex::new(r"^[^\s@]+@[^\s@.]+(\.[^\s@.]+)+$")?),
+            "uuid" => ValidatorKind::Regex(Regex::new(
+                r"^(?i)[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$",
+            )?),
+            "alphanumeric" => ValidatorKind::Regex(Regex::new(r"^[\p{L}\p{N}]+$")?),
+            "url" => ValidatorKind::Url,
+            "integer" => ValidatorKind::Integer,
+            "number" => ValidatorKind::Number,
+            "date" => ValidatorKind::Date,
+            _ => anyhow::bail!(
+                "Unknown validation rule {rule:?}. \
+                Expected one of: email, url, integer, number, date, uuid, alphanumeric. \
+                Use sqlpage.matches(value, regex) to validate a value against a regular expression."
+            ),
+        };
+        Ok(Self {
+            source: rule.to_string(),
+            kind,
+        })
+    }
+
+    /// Builds a validator that accepts values that match the given regular expression entirely
+    pub(super) fn from_regex(pattern: &str) -> anyhow::Result<Self> {
+        let regex = Regex::new(&format!("^(?:{pattern})$"))
+            .with_context(|| format!("Invalid regular expression: {pattern:?}"))?;
+        Ok(Self {
+            source: pattern.to_string(),
+            kind: ValidatorKind::Regex(regex),
+        })
+    }
+
+    pub(super) fn is_valid(&self, value: &str) -> bool {
+        match &self.kind {
+            ValidatorKind::Regex(regex) => regex.is_match(value),
+            ValidatorKind::Url => url::Url::parse(value)
+                .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.has_host()),
+            ValidatorKind::Integer => value.parse::<i64>().is_ok(),
+            ValidatorKind::Number => value.parse::<f64>().is_ok_and(f64::is_finite),
+            ValidatorKind::Date => chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+        }
+    }
+}
+
+impl PartialEq for Validator {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+            && std::mem::discriminant(&self.kind) == std::mem::discriminant(&other.kind)
+    }
+}
+
+impl Eq for Validator {}
+
+#[test]
+fn test_validation_rules() {
+    let cases = [
+        ("email", "john.doe+test@example.com", true),
+        ("email", "john@localhost", false),
+        ("email", "not an email@example.com", false),
+        ("url", "https://sql.ophir.dev/documentation.sql?x=1", true),
+        ("url", "javascript:alert(1)", false),
+        ("integer", "-42", true),
+        ("integer", "4.2", false),
+        ("number", "4.2e3", true),
+        ("number", "NaN", false),
+        ("date", "2024-02-29", true),
+        ("date", "2023-02-29", false),
+        ("uuid", "123E4567-e89b-12d3-a456-426614174000", true),
+        ("uuid", "123e4567", false),
+        ("alphanumeric", "Élève42", true),
+        ("alphanumeric", "a-b", false),
+    ];
+    for (rule, value, expected) in cases {
+        let validator = Validator::from_rule(rule).unwrap();
+        assert_eq!(validator.is_valid(value), expected, "{rule}: {value}");
+    }
+    assert!(Validator::from_rule("unknown").is_err());
+}
+
+#[test]
+fn test_regex_validator() {
+    let validator = Validator::from_regex("[A-Z]{2}|[0-9]{5}").unwrap();
+    assert!(validator.is_valid("FR"));
+    assert!(validator.is_valid("75001"));
+    assert!(!validator.is_valid("FRA"), "the whole value must match");
+    assert!(Validator::from_regex("(unclosed").is_err());
+}
@@ -0,0 +1,266 @@
+use anyhow::{bail, Context};
+use serde_json::{Map, Value};
+
+use crate::utils::add_value_to_map;
+
+/// Elements nested deeper than this are rejected, so that untrusted documents cannot overflow the stack
+const MAX_DEPTH: usize = 128;
+
+/// Converts an XML document to JSON.
+///
+/// - The document becomes an object with a single key: the name of the root element.
+/// - Attributes are stored with an `@` prefix.
+/// - Child elements are stored under their name. Repeated elements become arrays.
+/// - Text content is stored under `#text`, or directly as a string if the element has
+///   neither attributes nor child elements.
+/// - Empty elements are `null`.
+pub(super) fn xml_to_json(xml: &str) -> anyhow::Result<Value> {
+    let mut parser = XmlParser {
+        rest: xml,
+        depth: 0,
+    };
+    parser.skip_prolog()?;
+    let (name, value) = parser
+        .parse_element()?
+        .with_context(|| "The XML document does not contain any element")?;
+    parser.skip_misc()?;
+    if !parser.rest.is_empty() {
+        bail!(
+            "Unexpected content after the root XML element: {:?}",
+            parser.excerpt()
+        );
+    }
+    let mut root = Map::with_capacity(1);
+    root.insert(name, value);
+    Ok(Value::Object(root))
+}
+
+struct XmlParser<'a> {
+    rest: &'a str,
+    /// Number of elements that are open at the current position
+    depth: usize,
+}
+
+impl<'a> XmlParser<'a> {
+    fn excerpt(&self) -> &'a str {
+        let end = self
+            .rest
+            .char_indices()
+            .nth(32)
+            .map_or(self.rest.len(), |(i, _)| i);
+        &self.rest[..end]
+    }
+
+    fn skip_until(&mut self, end: &str) -> anyhow::Result<&'a str> {
+        let idx = self
+            .rest
+            .find(end)
+            .with_context(|| format!("Unterminated XML construct: expected {end:?}"))?;
+        let skipped = &self.rest[..idx];
+        self.rest = &self.rest[idx + end.len()..];
+        Ok(skipped)
+    }
+
+    /// Skips whitespace, comments and processing instructions
+    fn skip_misc(&mut self) -> anyhow::Result<()> {
+        loop {
+            self.rest = self.rest.trim_start();
+            if let Some(r) = self.rest.strip_prefix("<!--") {
+                self.rest = r;
+                self.skip_until("-->")?;
+            } else if let Some(r) = self.rest.strip_prefix("<?") {
+                self.rest = r;
+                self.skip_until("?>")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn skip_prolog(&mut self) -> anyhow::Result<()> {
+        self.rest = self.rest.trim_start_matches('\u{feff}');
+        self.skip_misc()?;
+        if let Some(r) = self.rest.strip_prefix("<!DOCTYPE") {
+            self.rest = r;
+            // Internal DTD subsets are not supported, but are skipped
+            if self
+                .rest
+                .find('[')
+                .is_some_and(|i| i < self.rest.find('>').unwrap_or(0))
+            {
+                self.skip_until("]")?;
+            }
+            self.skip_until(">")?;
+            self.skip_misc()?;
+        }
+        Ok(())
+    }
+
+    fn parse_name(&mut self) -> anyhow::Result<&'a str> {
+        let end = self
+            .rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '='))
+            .unwrap_or(self.rest.len());
+        if end == 0 {
+            bail!("Expected an XML name, found {:?}", self.excerpt());
+        }
+        let name = &self.rest[..end];
+        self.rest = &self.rest[end..];
+        Ok(name)
+    }
+
+    /// Parses an element starting with `<`. Returns `None` if the next thing is not an element.
+    fn parse_element(&mut self) -> anyhow::Result<Option<(String, Value)>> {
+        let Some(r) = self.rest.strip_prefix('<') else {
+            return Ok(None);
+        };
+        self.rest = r;
+        let name = self.parse_name()?;
+        let mut obj = Map::new();
+        loop {
+            self.rest = self.rest.trim_start();
+            if let Some(r) = self.rest.strip_prefix("/>") {
+                self.rest = r;
+                return Ok(Some((name.to_string(), simplify(obj, ""))));
+            } else if let Some(r) = self.rest.strip_prefix('>') {
+                self.rest = r;
+                break;
+            }
+            let attr_name = self.parse_name()?;
+            self.rest = self
+                .rest
+                .trim_start()
+                .strip_prefix('=')
+                .with_context(|| format!("Expected '=' after XML attribute {attr_name}"))?
+                .trim_start();
+            let quote = self
+                .rest
+                .chars()
+                .next()
+                .filter(|&c| c == '"' || c == '\'')
+                .with_context(|| {
+                    format!("Expected a quoted value for XML attribute {attr_name}")
+                })?;
+            self.rest = &self.rest[1..];
+            let raw_value = self.skip_until(if quote == '"' { "\"" } else { "'" })?;
+            let value = Value::String(decode_entities(raw_value)?);
+            obj = add_value_to_map(obj, (format!("@{attr_name}"), value));
+        }
+        let mut text = String::new();
+        loop {
+            let text_end = self
+                .rest
+                .find('<')
+                .with_context(|| format!("Unterminated XML element: expected </{name}>"))?;
+            text.push_str(&decode_entities(&self.rest[..text_end])?);
+            self.rest = &self.rest[text_end..];
+            if let Some(r) = self.rest.strip_prefix("</") {
+                self.rest = r;
+                let closing = self.skip_until(">")?.trim();
+                if closing != name {
+                    bail!("Mismatched XML tags: <{name}> is closed by </{closing}>");
+                }
+                return Ok(Some((name.to_string(), simplify(obj, &text))));
+            } else if let Some(r) = self.rest.strip_prefix("<![CDATA[") {
+                self.rest = r;
+                text.push_str(self.skip_until("]]>")?);
+            } else if let Some(r) = self.rest.strip_prefix("<!--") {
+                self.rest = r;
+                self.skip_until("-->")?;
+            } else if let Some(r) = self.rest.strip_prefix("<?") {
+                self.rest = r;
+                self.skip_until("?>")?;
+            } else {
+                if self.depth >= MAX_DEPTH {
+                    bail!("XML elements are nested more than {MAX_DEPTH} levels deep");
+                }
+                self.depth += 1;
+                let child = self.parse_element()?;
+                self.depth -= 1;
+                if let Some(child) = child {
+                    obj = add_value_to_map(obj, child);
+                }
+            }
+        }
+    }
+}
+
+fn simplify(mut obj: Map<String, Value>, text: &str) -> Value {
+    let text = text.trim();
+    match (obj.is_empty(), text.is_empty()) {
+        (true, true) => Value::Null,
+        (true, false) => Value::String(text.to_string()),
+        (false, true) => Value::Object(obj),
+        (false, false) => {
+            obj.insert("#text".to_string(), Value::String(text.to_string()));
+            Value::Object(obj)
+        }
+    }
+}
+
+fn decode_entities(s: &str) -> anyhow::Result<String> {
+    let mut decoded = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let end = rest
+            .find(';')
+            .with_context(|| format!("Unterminated XML entity in {s:?}"))?;
+        let entity = &rest[..end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32)
+                .with_context(|| format!("Unknown XML entity: &{entity};"))?,
+        };
+        decoded.push(c);
+        rest = &rest[end + 1..];
+    }
+    decoded.push_str(rest);
+    Ok(decoded)
+}
+
+#[test]
+fn test_xml_to_json() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+    <!-- an order -->
+    <order id="42" status='paid'>
+        <item sku="A1">Tea &amp; biscuits</item>
+        <item sku="B2"><![CDATA[<Coffee>]]></item>
+        <note>Leave at the door &#x1F6AA;</note>
+        <gift/>
+    </order>"#;
+    assert_eq!(
+        xml_to_json(xml).unwrap(),
+        serde_json::json!({
+            "order": {
+                "@id": "42",
+                "@status": "paid",
+                "item": [
+                    {"@sku": "A1", "#text": "Tea & biscuits"},
+                    {"@sku": "B2", "#text": "<Coffee>"}
+                ],
+                "note": "Leave at the door 🚪",
+                "gift": null
+            }
+        })
+    );
+}
+
+#[test]
+fn test_xml_to_json_invalid() {
+    assert!(xml_to_json("<a><b></a>").is_err());
+    assert!(xml_to_json("not xml").is_err());
+    assert!(xml_to_json("<a>&unknown;</a>").is_err());
+    let deep = format!("{}{}", "<a>".repeat(100_000), "</a>".repeat(100_000));
+    assert!(xml_to_json(&deep).is_err());
+}
@@ -0,0 +1,104 @@
+//! Lists the files of the directories that have no index file, for the paths configured in
+//! `directory_listing_paths`.
+//!
+//! The listing is a page made of a `shell` and a `list` component, rendered like the rows
+//! returned by a sql file, so that it uses the templates and the theme of the website.
+
+use crate::filesystem::FileSystem;
+use crate::AppState;
+use chrono::{DateTime, Utc};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::{json, Value as JsonValue};
+use std::path::Path;
+
+/// Characters escaped in the links to the files
+const LINK_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'.')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Whether the directory at the given url path can be listed: it is in one of the
+/// `directory_listing_paths`, and it exists in the local web root
+pub(crate) async fn is_enabled(app_state: &AppState, path: &str, dir: &Path) -> bool {
+    let listing_paths = &app_state.config.directory_listing_paths;
+    listing_paths.iter().any(|prefix| path.starts_with(prefix))
+        && matches!(app_state.file_system.local_metadata(dir).await, Ok(Some(m)) if m.is_dir())
+}
+
+/// The rows of the page listing a local directory of the web root: directories first, then files.
+/// Hidden files, whose name starts with a dot, are not listed.
+pub(crate) async fn listing_rows(
+    file_system: &FileSystem,
+    dir: &Path,
+    url_path: &str,
+) -> anyhow::Result<Vec<JsonValue>> {
+    let entries = file_system.local_dir_entries(dir).await?;
+    let title = format!("Index of {url_path}");
+    let mut rows = vec![
+        json!({"component": "shell", "title": title}),
+        json!({"component": "list", "title": title, "empty_title": "This directory is empty"}),
+    ];
+    // Absolute links, that also work when the url of the directory has no trailing slash
+    let dir_url = url_path.trim_end_matches('/');
+    if let Some((parent, _)) = dir_url.rsplit_once('/') {
+        let link = format!("{}/", utf8_percent_encode(parent, LINK_ENCODE_SET));
+        rows.push(json!({"title": "..", "link": link, "icon": "arrow-up"}));
+    }
+    let link_to =
+        |name: &str| utf8_percent_encode(&format!("{dir_url}/{name}"), LINK_ENCODE_SET).to_string();
+    let is_root = dir.as_os_str().is_empty();
+    let visible = entries.iter().filter(|(name, _)| {
+        !name.starts_with('.') && !(is_root && name.eq_ignore_ascii_case("sqlpage"))
+    });
+    for (name, _) in visible.clone().filter(|(_, is_dir)| *is_dir) {
+        let link = format!("{}/", link_to(name));
+        rows.push(json!({"title": format!("{name}/"), "link": link, "icon": "folder"}));
+    }
+    for (name, _) in visible.filter(|(_, is_dir)| !*is_dir) {
+        let metadata = file_system.local_metadata(&dir.join(name)).await?;
+        let description = metadata.map(|m| {
+            let size = format_size(m.len());
+            match m.modified() {
+                Ok(modified) => {
+                    let modified = DateTime::<Utc>::from(modified).format("%Y-%m-%d %H:%M");
+                    format!("{size}, modified {modified}")
+                }
+                Err(_) => size,
+            }
+        });
+        rows.push(json!({
+            "title": name,
+            "link": link_to(name),
+            "icon": "file",
+            "description": description
+        }));
+    }
+    Ok(rows)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["kB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{bytes} bytes");
+    }
+    let mut size = bytes as f64 / 1000.;
+    let mut unit = UNITS[0];
+    for next_unit in &UNITS[1..] {
+        if size < 1000. {
+            break;
+        }
+        size /= 1000.;
+        unit = next_unit;
+    }
+    format!("{size:.1} {unit}")
+}
+
+#[test]
+fn test_format_size() {
+    assert_eq!(format_size(12), "12 bytes");
+    assert_eq!(format_size(12_345), "12.3 kB");
+    assert_eq!(format_size(4_200_000_000), "4.2 GB");
+}
@@ -0,0 +1,42 @@
+//! Strong `ETag`s for the sql pages listed in `etag_paths`.
+//!
+//! These pages are rendered completely before being sent, and the hash of their body is sent in the `ETag`
+//! header. A client that sends the same hash in `If-None-Match` gets an empty `304 Not Modified` response.
+//! The content security policy nonce changes with every response, so it is not part of the hash.
+
+use base64::Engine;
+use sha2::{Digest, Sha256};
+
+#[must_use]
+pub fn etag(body: &[u8], csp_nonce: Option<&str>) -> String {
+    let mut hasher = Sha256::new();
+    let mut rest = body;
+    if let Some(nonce) = csp_nonce.map(str::as_bytes).filter(|n| !n.is_empty()) {
+        while let Some(pos) = rest.windows(nonce.len()).position(|w| w == nonce) {
+            hasher.update(&rest[..pos]);
+            rest = &rest[pos + nonce.len()..];
+        }
+    }
+    hasher.update(rest);
+    let hash = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(hasher.finalize());
+    format!("\"{hash}\"")
+}
+
+/// Whether an `If-None-Match` header matches the given etag, with the weak comparison of RFC 9110
+#[must_use]
+pub fn if_none_match(header: &str, etag: &str) -> bool {
+    header.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+#[test]
+fn test_etag() {
+    let a = etag(b"<script nonce=\"abc\">x</script>", Some("abc"));
+    let b = etag(b"<script nonce=\"xyz\">x</script>", Some("xyz"));
+    assert_eq!(a, b);
+    assert_ne!(a, etag(b"<script nonce=\"abc\">y</script>", Some("abc")));
+    assert!(if_none_match(&format!("\"other\", W/{a}"), &a));
+    assert!(if_none_match("*", &a));
+    assert!(!if_none_match("\"other\"", &a));
+}
@@ -0,0 +1,188 @@
+//! Client address and scheme of requests that go through a reverse proxy.
+//!
+//! Reverse proxies pass the address of the client and the scheme it used in `X-Forwarded-For`,
+//! `X-Forwarded-Proto` and `Forwarded` headers. Anyone can set these headers, so they are only
+//! honored when the request comes from one of the `trusted_proxies` of the configuration.
+
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header::HeaderMap;
+use actix_web::HttpRequest;
+use serde::{Deserialize, Deserializer};
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+/// An ip address, or a range of addresses in CIDR notation, such as `10.0.0.0/8`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct IpNetwork {
+    address: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    #[must_use]
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+            IpAddr::V4(_) => ip,
+        };
+        match (self.address, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                prefix_matches(&network.octets(), &ip.octets(), self.prefix_len)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                prefix_matches(&network.octets(), &ip.octets(), self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn prefix_matches(network: &[u8], ip: &[u8], prefix_len: u8) -> bool {
+    let full_bytes = usize::from(prefix_len / 8);
+    let remaining_bits = prefix_len % 8;
+    if network[..full_bytes] != ip[..full_bytes] {
+        return false;
+    }
+    if remaining_bits == 0 {
+        return true;
+    }
+    let mask = 0xFF_u8 << (8 - remaining_bits);
+    network[full_bytes] & mask == ip[full_bytes] & mask
+}
+
+impl FromStr for IpNetwork {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, prefix_len) = match s.split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len)),
+            None => (s, None),
+        };
+        let address: IpAddr = address
+            .trim()
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid ip address in {s:?}: {e}"))?;
+        let max_prefix_len = if address.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(len) => len
+                .trim()
+                .parse()
+                .ok()
+                .filter(|&len| len <= max_prefix_len)
+                .ok_or_else(|| anyhow::anyhow!("Invalid network prefix length in {s:?}"))?,
+            None => max_prefix_len,
+        };
+        Ok(IpNetwork {
+            address,
+            prefix_len,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for IpNetwork {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+fn is_trusted(ip: IpAddr, trusted_proxies: &[IpNetwork]) -> bool {
+    trusted_proxies.iter().any(|network| network.contains(ip))
+}
+
+/// The address of the client. When the request comes from a trusted proxy, the addresses in
+/// `X-Forwarded-For` are read from the right, and the first one that is not a trusted proxy
+/// is the client.
+#[must_use]
+pub fn client_ip(req: &HttpRequest, trusted_proxies: &[IpNetwork]) -> Option<IpAddr> {
+    let peer_ip = req.peer_addr()?.ip();
+    Some(forwarded_client_ip(peer_ip, req.headers(), trusted_proxies))
+}
+
+fn forwarded_client_ip(
+    peer_ip: IpAddr,
+    headers: &HeaderMap,
+    trusted_proxies: &[IpNetwork],
+) -> IpAddr {
+    let hops: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .collect();
+    let mut ip = peer_ip;
+    for hop in hops.into_iter().rev() {
+        if !is_trusted(ip, trusted_proxies) {
+            break;
+        }
+        let hop = hop.trim();
+        match hop
+            .parse()
+            .or_else(|_| hop.parse::<SocketAddr>().map(|a| a.ip()))
+        {
+            Ok(hop_ip) => ip = hop_ip,
+            Err(_) => break,
+        }
+    }
+    ip
+}
+
+/// `http` or `https`. The scheme set by a trusted proxy in `Forwarded` or `X-Forwarded-Proto`
+/// takes precedence over the one of the connection.
+#[must_use]
+pub fn scheme(req: &HttpRequest, trusted_proxies: &[IpNetwork]) -> String {
+    let from_trusted_proxy = req
+        .peer_addr()
+        .is_some_and(|addr| is_trusted(addr.ip(), trusted_proxies));
+    if from_trusted_proxy {
+        req.connection_info().scheme().to_string()
+    } else if req.app_config().secure() {
+        "https".to_string()
+    } else {
+        "http".to_string()
+    }
+}
+
+/// Client address shown in the access logs
+pub(crate) fn log_client_ip(req: &ServiceRequest, trusted_proxies: &[IpNetwork]) -> String {
+    client_ip(req.request(), trusted_proxies).map_or_else(|| "-".to_string(), |ip| ip.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn networks(networks: &[&str]) -> Vec<IpNetwork> {
+        networks.iter().map(|n| n.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_ip_network() {
+        let network: IpNetwork = "10.1.0.0/16".parse().unwrap();
+        assert!(network.contains("10.1.2.3".parse().unwrap()));
+        assert!(!network.contains("10.2.0.1".parse().unwrap()));
+        assert!(network.contains("::ffff:10.1.0.1".parse().unwrap()));
+        let network: IpNetwork = "2001:db8::/32".parse().unwrap();
+        assert!(network.contains("2001:db8::1".parse().unwrap()));
+        assert!(!network.contains("10.1.0.1".parse().unwrap()));
+        let network: IpNetwork = "192.168.1.128/25".parse().unwrap();
+        assert!(network.contains("192.168.1.200".parse().unwrap()));
+        assert!(!network.contains("192.168.1.100".parse().unwrap()));
+        assert!("1.2.3.4/33".parse::<IpNetwork>().is_err());
+        assert!("localhost".parse::<IpNetwork>().is_err());
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "6.6.6.6, 1.2.3.4, 10.0.0.2"))
+            .insert_header(("X-Forwarded-Proto", "https"))
+            .to_http_request();
+        let trusted = networks(&["10.0.0.0/8"]);
+        assert_eq!(client_ip(&req, &trusted), Some("1.2.3.4".parse().unwrap()));
+        assert_eq!(scheme(&req, &trusted), "https");
+        assert_eq!(client_ip(&req, &[]), Some("10.0.0.1".parse().unwrap()));
+        assert_eq!(scheme(&req, &[]), "http");
+    }
+}
@@ -1,32 +1,54 @@
-use crate::render::{HeaderContext, PageContext, RenderContext};
+use crate::app_config::TrailingSlash;
+use crate::render::{
+    AnyRenderBodyContext, HeaderContext, PageContext, PageOptions, PdfBodyRenderer,
+};
 use crate::webserver::database::{execute_queries::stream_query_results, DbItem};
-use crate::webserver::http_request_info::extract_request_info;
+use crate::webserver::http_request_info::{extract_request_info, RequestInfo};
+use crate::webserver::session::Session;
 use crate::webserver::ErrorWithStatus;
-use crate::{app_config, AppConfig, AppState, ParsedSqlFile};
-use actix_web::dev::{fn_service, ServiceFactory, ServiceRequest};
+use crate::{AppConfig, AppState, ParsedSqlFile};
+use actix_web::dev::{fn_service, Service, ServiceFactory, ServiceRequest};
 use actix_web::error::ErrorInternalServerError;
-use actix_web::http::header::{ContentType, Header, HttpDate, IfModifiedSince, LastModified};
-use actix_web::http::{header, StatusCode, Uri};
+use actix_web::http::header::ContentType;
+use actix_web::http::{header, KeepAlive, Method, StatusCode, Uri};
 use actix_web::web::PayloadConfig;
 use actix_web::{
-    dev::ServiceResponse, middleware, middleware::Logger, web, web::Bytes, App, HttpResponse,
-    HttpServer,
+    dev::ServiceResponse, guard, middleware, middleware::Logger, web, web::Bytes, App, HttpMessage,
+    HttpResponse, HttpServer,
 };
 
-use super::https::make_auto_rustls_config;
+use super::access_log::{self, AccessLogEntry, SqlFile};
+use super::basic_auth;
+use super::chunked_upload;
+use super::content_security_policy;
+use super::csrf;
+use super::directory_listing;
+use super::etag;
+use super::forwarded;
+use super::https::{make_auto_rustls_config, make_file_rustls_config};
+use super::ip_restrictions;
+use super::maintenance;
+use super::oidc;
+use super::page_cache::{self, PendingPage};
+use super::proxy;
+use super::request_id;
+use super::routing::{self, PathParams};
+use super::server_sent_events;
 use super::static_content;
+use super::static_files;
+use super::websocket;
+use super::well_known;
 use actix_web::body::{BoxBody, MessageBody};
 use anyhow::{bail, Context};
-use chrono::{DateTime, Utc};
 use futures_util::stream::Stream;
-use futures_util::StreamExt;
+use futures_util::{FutureExt, StreamExt};
 use std::borrow::Cow;
 use std::io::Write;
 use std::mem;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::Duration;
 use tokio::sync::mpsc;
 
 /// If the sending queue exceeds this number of outgoing messages, an error will be thrown
@@ -37,6 +59,10 @@ const MAX_PENDING_MESSAGES: usize = 128;
 pub struct ResponseWriter {
     buffer: Vec<u8>,
     response_bytes: mpsc::Sender<actix_web::Result<Bytes>>,
+    /// Whether some data was already sent to the client
+    sent: bool,
+    /// A copy of everything sent to the client, kept when the page will be cached
+    copy: Option<Vec<u8>>,
 }
 
 impl ResponseWriter {
@@ -44,6 +70,32 @@ impl ResponseWriter {
         Self {
             response_bytes,
             buffer: Vec::new(),
+            sent: false,
+            copy: None,
+        }
+    }
+
+    /// Starts keeping a copy of the response body. Returns false if it is too late to get the whole body.
+    fn keep_copy(&mut self) -> bool {
+        if self.sent {
+            return false;
+        }
+        self.copy = Some(Vec::new());
+        true
+    }
+
+    fn take_copy(&mut self) -> Option<Vec<u8>> {
+        self.copy.take()
+    }
+
+    fn copy_buffer(&mut self) {
+        self.sent = true;
+        if let Some(copy) = &mut self.copy {
+            if copy.len() + self.buffer.len() > page_cache::MAX_CACHED_PAGE_SIZE {
+                self.copy = None;
+            } else {
+                copy.extend_from_slice(&self.buffer);
+            }
         }
     }
     async fn close_with_error(&mut self, mut msg: String) {
@@ -65,6 +117,7 @@ impl ResponseWriter {
         if self.buffer.is_empty() {
             return Ok(());
         }
+        self.copy_buffer();
         self.response_bytes
             .send(Ok(mem::take(&mut self.buffer).into()))
             .await
@@ -85,6 +138,7 @@ impl Write for ResponseWriter {
         Ok(buf.len())
     }
     fn flush(&mut self) -> std::io::Result<()> {
+        self.copy_buffer();
         self.response_bytes
             .try_send(Ok(mem::take(&mut self.buffer).into()))
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::WouldBlock, e.to_string()))
@@ -99,19 +153,25 @@ impl Drop for ResponseWriter {
     }
 }
 
+/// Renders the body of the page. Returns the writer when the whole page was rendered without errors.
 async fn stream_response(
     stream: impl Stream<Item = DbItem>,
-    mut renderer: RenderContext<ResponseWriter>,
-) {
+    mut renderer: AnyRenderBodyContext<ResponseWriter>,
+) -> Option<ResponseWriter> {
     let mut stream = Box::pin(stream);
+    let mut has_errors = false;
     while let Some(item) = stream.next().await {
         log::trace!("Received item from database: {item:?}");
         let render_result = match item {
             DbItem::FinishedQuery => renderer.finish_query().await,
             DbItem::Row(row) => renderer.handle_row(&row).await,
-            DbItem::Error(e) => renderer.handle_error(&e).await,
+            DbItem::Error(e) => {
+                has_errors = true;
+                renderer.handle_error(&e).await
+            }
         };
         if let Err(e) = render_result {
+            has_errors = true;
             if let Err(nested_err) = renderer.handle_error(&e).await {
                 renderer
                     .close()
@@ -123,31 +183,77 @@ async fn stream_response(
                     \nRoot error: {e}\n
                     \nNested error: {nested_err}"
                 );
-                return;
+                return None;
             }
         }
-        if let Err(e) = &renderer.writer.async_flush().await {
+        if let Err(e) = &renderer.writer_mut().async_flush().await {
             log::error!(
                 "Stopping rendering early because we were unable to flush data to client: {e:#}"
             );
             // If we cannot write to the client anymore, there is nothing we can do, so we just stop rendering
-            return;
+            return None;
         }
     }
-    if let Err(e) = &renderer.close().await.async_flush().await {
+    let mut writer = renderer.close().await;
+    if let Err(e) = &writer.async_flush().await {
         log::error!("Unable to flush data to client after rendering the page end: {e}");
-        return;
+        return None;
     }
     log::debug!("Successfully finished rendering the page");
+    (!has_errors).then_some(writer)
+}
+
+/// Rendering a page took longer than `request_timeout_seconds`
+#[derive(Debug)]
+struct RequestTimeout(Duration);
+
+impl std::fmt::Display for RequestTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The page was interrupted because it took more than {:?} to render. \
+            You can change this limit with the request_timeout_seconds configuration option.",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for RequestTimeout {}
+
+/// Ends the stream of database results with a [`RequestTimeout`] error when the timeout expires.
+/// The queries that are still running are dropped, which releases their database connection.
+fn with_timeout<S: Stream<Item = DbItem>>(
+    stream: S,
+    timeout: Option<Duration>,
+) -> impl Stream<Item = DbItem> {
+    let deadline = timeout.map(|timeout| Box::pin(tokio::time::sleep(timeout)));
+    futures_util::stream::unfold(Some((Box::pin(stream), deadline)), |state| async move {
+        let (mut stream, mut deadline) = state?;
+        let expired = async {
+            match &mut deadline {
+                Some(deadline) => deadline.await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            item = stream.next() => item.map(|item| (item, Some((stream, deadline)))),
+            () = expired => {
+                let timeout = timeout.unwrap_or_default();
+                Some((DbItem::Error(RequestTimeout(timeout).into()), None))
+            }
+        }
+    })
 }
 
 async fn build_response_header_and_stream<S: Stream<Item = DbItem>>(
     app_state: Arc<AppState>,
+    session: Session,
+    options: PageOptions,
     database_entries: S,
 ) -> anyhow::Result<ResponseWithWriter<S>> {
     let (sender, receiver) = mpsc::channel(MAX_PENDING_MESSAGES);
     let writer = ResponseWriter::new(sender);
-    let mut head_context = HeaderContext::new(app_state, writer);
+    let mut head_context = HeaderContext::new(app_state, session, writer, options);
     let mut stream = Box::pin(database_entries);
     while let Some(item) = stream.next().await {
         let page_context = match item {
@@ -164,6 +270,10 @@ async fn build_response_header_and_stream<S: Stream<Item = DbItem>>(
             {
                 return Err(source_err)
             }
+            DbItem::Error(source_err) if source_err.is::<RequestTimeout>() => {
+                log::error!("{source_err}");
+                head_context.timeout().await?
+            }
             DbItem::Error(source_err) => head_context.handle_error(source_err).await?,
         };
         match page_context {
@@ -185,6 +295,14 @@ async fn build_response_header_and_stream<S: Stream<Item = DbItem>>(
             PageContext::Close(http_response) => {
                 return Ok(ResponseWithWriter::FinishedResponse { http_response })
             }
+            PageContext::Pdf {
+                mut http_response,
+                renderer,
+            } => {
+                let pdf = render_pdf(renderer, stream).await?;
+                let http_response = http_response.body(pdf);
+                return Ok(ResponseWithWriter::FinishedResponse { http_response });
+            }
         }
     }
     log::debug!("No SQL statements left to execute for the body of the response");
@@ -192,10 +310,25 @@ async fn build_response_header_and_stream<S: Stream<Item = DbItem>>(
     Ok(ResponseWithWriter::FinishedResponse { http_response })
 }
 
+/// Renders the rest of the page, and converts it to pdf
+async fn render_pdf<S: Stream<Item = DbItem>>(
+    mut renderer: PdfBodyRenderer,
+    mut stream: Pin<Box<S>>,
+) -> anyhow::Result<Vec<u8>> {
+    while let Some(item) = stream.next().await {
+        match item {
+            DbItem::Row(row) => renderer.handle_row(&row).await?,
+            DbItem::FinishedQuery => renderer.finish_query().await?,
+            DbItem::Error(e) => renderer.handle_error(&e).await?,
+        }
+    }
+    renderer.into_pdf().await
+}
+
 enum ResponseWithWriter<S> {
     RenderStream {
         http_response: HttpResponse,
-        renderer: RenderContext<ResponseWriter>,
+        renderer: AnyRenderBodyContext<ResponseWriter>,
         database_entries_stream: Pin<Box<S>>,
     },
     FinishedResponse {
@@ -213,25 +346,122 @@ async fn render_sql(
         .clone() // Cheap reference count increase
         .into_inner();
 
+    let csp_nonce = (!app_state.config.content_security_policy.is_empty())
+        .then(content_security_policy::new_nonce);
+    let cache_key = page_cache::cache_key(srv_req);
+    if let Some(response) = cache_key
+        .as_deref()
+        .and_then(|key| app_state.page_cache.get(key, csp_nonce.as_deref()))
+    {
+        return Ok(response);
+    }
+
+    let is_head = srv_req.method() == Method::HEAD;
     let mut req_param = extract_request_info(srv_req, Arc::clone(&app_state)).await;
     log::debug!("Received a request with the following parameters: {req_param:?}");
+    if app_state.config.csrf_protection && csrf::is_forged(&mut req_param) {
+        log::warn!(
+            "Rejected a form submission to {} without a valid CSRF token",
+            req_param.path
+        );
+        return Ok(HttpResponse::Forbidden()
+            .content_type(ContentType::plaintext())
+            .body("This form has expired or was sent from another website. Please reload the page and try again."));
+    }
 
     let (resp_send, resp_recv) = tokio::sync::oneshot::channel::<HttpResponse>();
     actix_web::rt::spawn(async move {
-        let database_entries_stream =
-            stream_query_results(&app_state.db, &sql_file, &mut req_param);
-        let response_with_writer =
-            build_response_header_and_stream(Arc::clone(&app_state), database_entries_stream).await;
+        let session = req_param.session.clone();
+        let options = PageOptions {
+            fragment: is_fragment_request(&req_param),
+            directory: req_param
+                .path
+                .trim_start_matches('/')
+                .rsplit_once('/')
+                .map(|(directory, _file)| directory.to_string())
+                .unwrap_or_default(),
+            languages: accepted_languages(&req_param),
+            path: req_param.path.clone(),
+            print: is_print_request(&req_param),
+            csrf: app_state
+                .config
+                .csrf_protection
+                .then(|| csrf::csrf_token(&req_param)),
+            csp_nonce: csp_nonce.clone(),
+            request_id: Some(req_param.request_id.clone()),
+        };
+        let csrf_token = options.csrf.as_ref().map(|csrf| csrf.token.clone());
+        let if_none_match = match req_param.headers.get("if-none-match") {
+            Some(SingleOrVec::Single(value)) => Some(value.clone()),
+            _ => None,
+        };
+        let path = req_param.path.clone();
+        let request_id = req_param.request_id.clone();
+        let timeout = app_state
+            .config
+            .request_timeout_seconds
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok());
+        let database_entries_stream = with_timeout(
+            stream_query_results(&app_state.db, &sql_file, &mut req_param),
+            timeout,
+        );
+        let response_with_writer = build_response_header_and_stream(
+            Arc::clone(&app_state),
+            session,
+            options,
+            database_entries_stream,
+        )
+        .await;
         match response_with_writer {
             Ok(ResponseWithWriter::RenderStream {
                 http_response,
-                renderer,
+                mut renderer,
                 database_entries_stream,
             }) => {
+                let with_etag = app_state
+                    .config
+                    .etag_paths
+                    .iter()
+                    .any(|p| path.starts_with(p));
+                if is_head && !with_etag {
+                    // The headers are known: the body is not rendered, and its queries are not executed
+                    resp_send
+                        .send(http_response)
+                        .unwrap_or_else(|e| log::error!("could not send headers {e:?}"));
+                    return;
+                }
+                let pending_page = cache_key.filter(|_| !is_head).and_then(|key| {
+                    let rules = &app_state.config.page_cache;
+                    let ttl = page_cache::time_to_live(rules, &path, &http_response)?;
+                    page_cache::pending_page(key, &path, ttl, &http_response, csp_nonce.as_deref())
+                });
+                if with_etag {
+                    let (http_response, body) = render_with_etag(
+                        http_response,
+                        renderer,
+                        database_entries_stream,
+                        if_none_match.as_deref(),
+                        csp_nonce.as_deref(),
+                    )
+                    .await;
+                    resp_send
+                        .send(http_response)
+                        .unwrap_or_else(|e| log::error!("could not send headers {e:?}"));
+                    if let (Some(page), Some(body)) = (pending_page, body) {
+                        cache_page(&app_state, page, body.to_vec(), csrf_token.as_deref());
+                    }
+                    return;
+                }
+                let pending_page = pending_page.filter(|_| renderer.writer_mut().keep_copy());
                 resp_send
                     .send(http_response)
                     .unwrap_or_else(|e| log::error!("could not send headers {e:?}"));
-                stream_response(database_entries_stream, renderer).await;
+                // The page is cached before the writer is dropped, which ends the response
+                if let Some(mut writer) = stream_response(database_entries_stream, renderer).await {
+                    if let (Some(page), Some(body)) = (pending_page, writer.take_copy()) {
+                        cache_page(&app_state, page, body, csrf_token.as_deref());
+                    }
+                }
             }
             Ok(ResponseWithWriter::FinishedResponse { http_response }) => {
                 resp_send
@@ -239,27 +469,131 @@ async fn render_sql(
                     .unwrap_or_else(|e| log::error!("could not send headers {e:?}"));
             }
             Err(err) => {
-                send_anyhow_error(&err, resp_send, app_state.config.environment);
+                send_anyhow_error(
+                    &err,
+                    resp_send,
+                    app_state.config.verbose_errors(),
+                    &request_id,
+                );
             }
         }
     });
     resp_recv.await.map_err(ErrorInternalServerError)
 }
 
+/// Renders the whole page before sending it, to compute its `ETag`, and answers with `304 Not Modified`
+/// when the client already has this version of the page.
+/// Also returns the body of the page, when it was rendered without errors and can be cached.
+async fn render_with_etag<S: Stream<Item = DbItem>>(
+    http_response: HttpResponse,
+    renderer: AnyRenderBodyContext<ResponseWriter>,
+    database_entries_stream: S,
+    if_none_match: Option<&str>,
+    csp_nonce: Option<&str>,
+) -> (HttpResponse, Option<Bytes>) {
+    let (mut response, body) = http_response.into_parts();
+    // The writer is dropped at the end of the rendering, which ends the body
+    let rendering = async {
+        stream_response(database_entries_stream, renderer)
+            .await
+            .is_some()
+    };
+    let (body, rendered) = tokio::join!(actix_web::body::to_bytes(body), rendering);
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => {
+            log::error!("Unable to render the page: {e}");
+            let response = HttpResponse::InternalServerError()
+                .content_type(ContentType::plaintext())
+                .body("Sorry, but we were not able to render this page.");
+            return (response, None);
+        }
+    };
+    let etag = etag::etag(&body, csp_nonce);
+    if let Ok(value) = header::HeaderValue::from_str(&etag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    let cacheable_body = rendered.then(|| body.clone());
+    if response.status() == StatusCode::OK
+        && if_none_match.is_some_and(|header| etag::if_none_match(header, &etag))
+    {
+        log::debug!("The client already has the latest version of the page");
+        *response.status_mut() = StatusCode::NOT_MODIFIED;
+        // The browser keeps the policy it received with the page, that contains the nonce of its scripts
+        response
+            .headers_mut()
+            .remove(header::CONTENT_SECURITY_POLICY);
+        return (response.set_body(BoxBody::new(())), cacheable_body);
+    }
+    (response.set_body(BoxBody::new(body)), cacheable_body)
+}
+
+/// Pages that contain the CSRF token of a user are not cached, because other users cannot use it
+fn cache_page(app_state: &AppState, page: PendingPage, body: Vec<u8>, csrf_token: Option<&str>) {
+    if let Some(token) = csrf_token {
+        if body.windows(token.len()).any(|w| w == token.as_bytes()) {
+            log::debug!("Not caching a page that contains a CSRF token");
+            return;
+        }
+    }
+    app_state.page_cache.insert(page, body);
+}
+
+/// Pages requested with the `_sqlpage_embed` url parameter, or by htmx outside of a boosted link,
+/// are rendered without the shell, to be inserted in an existing page
+fn is_fragment_request(request: &RequestInfo) -> bool {
+    let has_header = |name: &str| matches!(request.headers.get(name), Some(SingleOrVec::Single(v)) if v == "true");
+    request.get_variables.contains_key("_sqlpage_embed")
+        || (has_header("hx-request") && !has_header("hx-boosted"))
+}
+
+/// A page requested with `?_print=1` is rendered in its print-friendly version
+fn is_print_request(request: &RequestInfo) -> bool {
+    matches!(
+        request.get_variables.get("_print"),
+        Some(SingleOrVec::Single(v)) if !matches!(v.as_str(), "" | "0" | "false")
+    )
+}
+
+/// Parses the `Accept-Language` header, and returns the language tags by decreasing preference
+fn accepted_languages(request: &RequestInfo) -> Vec<String> {
+    let Some(SingleOrVec::Single(header)) = request.headers.get("accept-language") else {
+        return Vec::new();
+    };
+    let mut languages: Vec<(f32, String)> = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let tag = parts.next()?.trim().to_ascii_lowercase();
+            let quality = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(Some(1.), |q| q.trim().parse().ok())?;
+            (!tag.is_empty() && tag != "*" && quality > 0.).then_some((quality, tag))
+        })
+        .collect();
+    // The sort is stable, so languages with the same quality keep their order
+    languages.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    languages.into_iter().map(|(_, tag)| tag).collect()
+}
+
 fn send_anyhow_error(
     e: &anyhow::Error,
     resp_send: tokio::sync::oneshot::Sender<HttpResponse>,
-    env: app_config::DevOrProd,
+    verbose_errors: bool,
+    request_id: &str,
 ) {
-    log::error!("An error occurred before starting to send the response body: {e:#}");
+    use std::fmt::Write;
+    log::error!(
+        "An error occurred before starting to send the response body of request {request_id}: {e:#}"
+    );
     let mut resp = HttpResponse::new(StatusCode::INTERNAL_SERVER_ERROR);
     let mut body = "Sorry, but we were not able to process your request. \n\n".to_owned();
-    if env.is_prod() {
-        body.push_str("Contact the administrator for more information. A detailed error message has been logged.");
-    } else {
-        use std::fmt::Write;
+    if verbose_errors {
         write!(body, "{e:#}").unwrap();
+    } else {
+        body.push_str("Contact the administrator for more information. A detailed error message has been logged.");
     }
+    write!(body, "\n\nRequest id: {request_id}").unwrap();
     resp = resp.set_body(BoxBody::new(body));
     resp.headers_mut().insert(
         header::CONTENT_TYPE,
@@ -293,7 +627,7 @@ fn send_anyhow_error(
         .unwrap_or_else(|_| log::error!("could not send headers"));
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Clone)]
 #[serde(untagged)]
 pub enum SingleOrVec {
     Single(String),
@@ -327,34 +661,265 @@ impl SingleOrVec {
     }
 }
 
-/// Resolves the path in a query to the path to a local SQL file if there is one that matches
-fn path_to_sql_file(path: &str) -> Option<PathBuf> {
-    let mut path = PathBuf::from(path.strip_prefix('/').unwrap_or(path));
-    match path.extension() {
-        None => {
-            path.push("index.sql");
-            Some(path)
+/// Whether the path in a query is handled by a SQL file: a path to a SQL file, a directory, or a clean url
+fn is_page_path(path: &str) -> bool {
+    match Path::new(path).extension() {
+        None => true,
+        Some(ext) => ext == "sql",
+    }
+}
+
+/// Finds the file that handles a page path, following the `index_files`, `trailing_slash` and
+/// `clean_urls` configuration options
+async fn serve_page(
+    mut service_request: ServiceRequest,
+    path: &str,
+) -> actix_web::Result<ServiceResponse> {
+    let app_state = service_request.extract::<web::Data<AppState>>().await?;
+    let config = &app_state.config;
+    let fs = &app_state.file_system;
+    let relative = path.strip_prefix('/').unwrap_or(path);
+    let uri = service_request.uri().clone();
+    if let Some(stem) = relative.strip_suffix(".sql") {
+        let is_get = matches!(*service_request.method(), Method::GET | Method::HEAD);
+        if config.clean_urls && is_get {
+            let clean = match stem.strip_suffix("index") {
+                Some(dir) if dir.is_empty() || dir.ends_with('/') => {
+                    uri.path().strip_suffix("index.sql")
+                }
+                _ => uri.path().strip_suffix(".sql"),
+            };
+            if let Some(clean) = clean {
+                let clean = match config.trailing_slash {
+                    TrailingSlash::Remove if clean.len() > 1 => clean.trim_end_matches('/'),
+                    _ => clean,
+                };
+                return Ok(service_request.into_response(redirect_to(&uri, clean)));
+            }
         }
-        Some(ext) if ext == "sql" => Some(path),
-        Some(_other) => None,
+        log::debug!("Processing SQL request: {:?}", relative);
+        return process_sql_request(service_request, PathBuf::from(relative)).await;
     }
+    if relative.is_empty() || relative.ends_with('/') {
+        if config.trailing_slash == TrailingSlash::Remove && !relative.is_empty() {
+            let without_slash = uri.path().trim_end_matches('/');
+            if !without_slash.is_empty() {
+                let redirect = redirect_to(&uri, without_slash);
+                return Ok(service_request.into_response(redirect));
+            }
+        }
+        return serve_index(service_request, &app_state, Path::new(relative)).await;
+    }
+    // A path without extension: a directory without its trailing slash, or a clean url
+    if config.clean_urls {
+        let sql_path = PathBuf::from(format!("{relative}.sql"));
+        if matches!(fs.local_metadata(&sql_path).await, Ok(Some(m)) if m.is_file()) {
+            return process_sql_request(service_request, sql_path).await;
+        }
+    }
+    if find_index(&app_state, Path::new(relative)).await.is_none() {
+        // `/users/42` is a clean url for `users/[id].sql`, unless `users/42/` exists
+        if let Some(route) = routing::find_route(fs, path, service_request.method()).await {
+            service_request
+                .extensions_mut()
+                .insert(PathParams(route.params));
+            return process_sql_request(service_request, route.sql_file).await;
+        }
+        if config.clean_urls {
+            // The file may be stored in the database
+            let sql_path = PathBuf::from(format!("{relative}.sql"));
+            return process_sql_request(service_request, sql_path).await;
+        }
+    }
+    if config.trailing_slash == TrailingSlash::Add {
+        let redirect = redirect_to(&uri, &format!("{}/", uri.path()));
+        return Ok(service_request.into_response(redirect));
+    }
+    serve_index(service_request, &app_state, Path::new(relative)).await
+}
+
+/// The first of the `index_files` that exists in a local directory of the web root
+async fn find_index(app_state: &AppState, dir: &Path) -> Option<PathBuf> {
+    for name in &app_state.config.index_files {
+        let path = dir.join(name);
+        if let Ok(Some(metadata)) = app_state.file_system.local_metadata(&path).await {
+            if metadata.is_file() {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+async fn serve_index(
+    service_request: ServiceRequest,
+    app_state: &AppState,
+    dir: &Path,
+) -> actix_web::Result<ServiceResponse> {
+    // Without a local index file, the directory is listed if enabled,
+    // or the first index file is looked up in the database
+    let index = match find_index(app_state, dir).await {
+        Some(index) => index,
+        None if directory_listing::is_enabled(app_state, &req_path(&service_request), dir)
+            .await =>
+        {
+            return serve_directory_listing(service_request, dir).await;
+        }
+        None => dir.join(
+            app_state
+                .config
+                .index_files
+                .first()
+                .map_or("index.sql", String::as_str),
+        ),
+    };
+    if index.extension().is_some_and(|ext| ext == "sql") {
+        log::debug!("Processing SQL request: {:?}", index);
+        return process_sql_request(service_request, index).await;
+    }
+    log::debug!("Serving index file: {:?}", index);
+    let response = static_files::serve_file(&service_request, &index.to_string_lossy(), app_state)
+        .await
+        .map_err(anyhow_err_to_actix)?;
+    Ok(service_request.into_response(response))
+}
+
+/// Renders the list of the files of a local directory that has no index file
+async fn serve_directory_listing(
+    service_request: ServiceRequest,
+    dir: &Path,
+) -> actix_web::Result<ServiceResponse> {
+    let app_state = service_request
+        .app_data::<web::Data<AppState>>()
+        .expect("app_state")
+        .clone()
+        .into_inner();
+    let path = req_path(&service_request).into_owned();
+    log::debug!("Listing the files in {dir:?}");
+    let rows = directory_listing::listing_rows(&app_state.file_system, dir, &path)
+        .await
+        .map_err(anyhow_err_to_actix)?;
+    let options = PageOptions {
+        path,
+        csp_nonce: (!app_state.config.content_security_policy.is_empty())
+            .then(content_security_policy::new_nonce),
+        ..PageOptions::default()
+    };
+    let rows = futures_util::stream::iter(rows.into_iter().map(DbItem::Row));
+    let response =
+        match build_response_header_and_stream(app_state, Session::default(), options, rows)
+            .await
+            .map_err(anyhow_err_to_actix)?
+        {
+            ResponseWithWriter::RenderStream {
+                http_response,
+                renderer,
+                database_entries_stream,
+            } => {
+                actix_web::rt::spawn(stream_response(database_entries_stream, renderer));
+                http_response
+            }
+            ResponseWithWriter::FinishedResponse { http_response } => http_response,
+        };
+    Ok(service_request.into_response(response))
 }
 
 async fn process_sql_request(
     mut req: ServiceRequest,
     sql_path: PathBuf,
 ) -> actix_web::Result<ServiceResponse> {
-    let app_state: &web::Data<AppState> = req.app_data().expect("app_state");
-    let sql_file = app_state
-        .sql_file_cache
-        .get(app_state, &sql_path)
+    let app_state = req.extract::<web::Data<AppState>>().await?;
+    if req.method() == Method::OPTIONS {
+        let response = options_response(&mut req, &app_state, &sql_path)
+            .await
+            .map_err(anyhow_err_to_actix)?;
+        return Ok(req.into_response(response));
+    }
+    let sql_file = find_sql_file(&mut req, &app_state, &sql_path)
         .await
         .with_context(|| format!("Unable to get SQL file {sql_path:?}"))
         .map_err(anyhow_err_to_actix)?;
+    if websocket::is_websocket_request(&req) {
+        return websocket::serve_websocket(req, sql_file).await;
+    }
+    if server_sent_events::is_event_stream_request(&req) {
+        return server_sent_events::serve_events(req, sql_file).await;
+    }
     let response = render_sql(&mut req, sql_file).await?;
     Ok(req.into_response(response))
 }
 
+/// Answers an `OPTIONS` request with the methods accepted by a sql file, without executing it.
+/// A file like `item.delete.sql` only accepts its own method, and other files accept all methods.
+async fn options_response(
+    req: &mut ServiceRequest,
+    app_state: &AppState,
+    sql_path: &PathBuf,
+) -> anyhow::Result<HttpResponse> {
+    let allow = if let Some(method) = routing::file_method(sql_path) {
+        app_state.sql_file_cache.get(app_state, sql_path).await?;
+        format!("OPTIONS, {method}")
+    } else {
+        find_sql_file(req, app_state, sql_path).await?;
+        "OPTIONS, GET, HEAD, POST, PUT, PATCH, DELETE".to_string()
+    };
+    Ok(HttpResponse::NoContent()
+        .insert_header((header::ALLOW, allow))
+        .finish())
+}
+
+/// Loads the file that handles the request: `item.delete.sql` for a DELETE request to `item.sql`
+/// if it exists, then `item.sql` itself, and then a file with path parameters, like `[id].sql`.
+async fn find_sql_file(
+    req: &mut ServiceRequest,
+    app_state: &AppState,
+    sql_path: &PathBuf,
+) -> anyhow::Result<Arc<ParsedSqlFile>> {
+    let cache = &app_state.sql_file_cache;
+    let method_path = sql_path
+        .file_name()
+        .and_then(|name| routing::method_file_name(name.to_str()?, req.method()))
+        .map(|name| sql_path.with_file_name(name));
+    if let Some(method_path) = method_path {
+        match cache.get(app_state, &method_path).await {
+            Err(e) if is_not_found(&e) => {}
+            Err(e) => return Err(e),
+            Ok(sql_file) => {
+                req.extensions_mut().insert(SqlFile(method_path));
+                return Ok(sql_file);
+            }
+        }
+    }
+    if routing::file_method(sql_path).is_some_and(|method| method != req.method()) {
+        bail!(ErrorWithStatus {
+            status: StatusCode::METHOD_NOT_ALLOWED
+        });
+    }
+    let (path, sql_file) = match cache.get(app_state, sql_path).await {
+        Err(e) if is_not_found(&e) => {
+            let path = req_path(req).into_owned();
+            let Some(route) =
+                routing::find_route(&app_state.file_system, &path, req.method()).await
+            else {
+                return Err(e);
+            };
+            log::debug!("Routing {path} to {:?}", route.sql_file);
+            req.extensions_mut().insert(PathParams(route.params));
+            let sql_file = cache.get(app_state, &route.sql_file).await?;
+            (route.sql_file, sql_file)
+        }
+        result => (sql_path.clone(), result?),
+    };
+    // Logged in the access log
+    req.extensions_mut().insert(SqlFile(path));
+    Ok(sql_file)
+}
+
+fn is_not_found(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<ErrorWithStatus>()
+        .is_some_and(|e| e.status == StatusCode::NOT_FOUND)
+}
+
 fn anyhow_err_to_actix(e: anyhow::Error) -> actix_web::Error {
     log::error!("{e:#}");
     match e.downcast::<ErrorWithStatus>() {
@@ -365,93 +930,128 @@ fn anyhow_err_to_actix(e: anyhow::Error) -> actix_web::Error {
     }
 }
 
-async fn serve_file(
-    path: &str,
-    state: &AppState,
-    if_modified_since: Option<IfModifiedSince>,
-) -> actix_web::Result<HttpResponse> {
-    let path = path.strip_prefix('/').unwrap_or(path);
-    if let Some(IfModifiedSince(date)) = if_modified_since {
-        let since = DateTime::<Utc>::from(SystemTime::from(date));
-        let modified = state
-            .file_system
-            .modified_since(state, path.as_ref(), since, false)
-            .await
-            .with_context(|| format!("Unable to get modification time of file {path:?}"))
-            .map_err(anyhow_err_to_actix)?;
-        if !modified {
-            return Ok(HttpResponse::NotModified().finish());
-        }
-    }
-    state
-        .file_system
-        .read_file(state, path.as_ref(), false)
-        .await
-        .with_context(|| format!("Unable to read file {path:?}"))
-        .map_err(anyhow_err_to_actix)
-        .map(|b| {
-            HttpResponse::Ok()
-                .insert_header(
-                    mime_guess::from_path(path)
-                        .first()
-                        .map_or_else(ContentType::octet_stream, ContentType),
-                )
-                .insert_header(LastModified(HttpDate::from(SystemTime::now())))
-                .body(b)
-        })
-}
-
 pub async fn main_handler(
     mut service_request: ServiceRequest,
 ) -> actix_web::Result<ServiceResponse> {
-    let path = req_path(&service_request);
-    let sql_file_path = path_to_sql_file(&path);
-    if let Some(sql_path) = sql_file_path {
-        if let Some(redirect) = redirect_missing_trailing_slash(service_request.uri()) {
-            return Ok(service_request.into_response(redirect));
-        }
-        log::debug!("Processing SQL request: {:?}", sql_path);
-        process_sql_request(service_request, sql_path).await
+    let path = req_path(&service_request).into_owned();
+    let is_page = is_page_path(&path);
+    if is_page {
+        if let Some(response) = check_rate_limit(&service_request, &path) {
+            return Ok(service_request.into_response(response));
+        }
+    }
+    // The state is cloned, so that it does not borrow the request that some handlers modify
+    let app_state = web::Data::clone(
+        service_request
+            .app_data::<web::Data<AppState>>()
+            .expect("app_state"),
+    );
+    let denied = ip_restrictions::check(&service_request, &app_state.config, &path)
+        .map(|rule| rule.denied_page.clone());
+    if let Some(denied_page) = denied {
+        return access_denied(service_request, denied_page).await;
+    }
+    if let Some(response) = maintenance::check(&service_request, &app_state, &path).await {
+        return Ok(service_request.into_response(response));
+    }
+    if let Some(response) = well_known::handle(&app_state.config, &path) {
+        return Ok(service_request.into_response(response));
+    }
+    if let Some(response) = basic_auth::check(&service_request, &app_state, &path)
+        .await
+        .map_err(anyhow_err_to_actix)?
+    {
+        return Ok(service_request.into_response(response));
+    }
+    if let Some(response) = oidc::handle(&service_request, &app_state, &path)
+        .await
+        .map_err(anyhow_err_to_actix)?
+    {
+        return Ok(service_request.into_response(response));
+    }
+    if let Some(response) = chunked_upload::handle(&mut service_request, &app_state, &path)
+        .await
+        .map_err(anyhow_err_to_actix)?
+    {
+        return Ok(service_request.into_response(response));
+    }
+    if !app_state.config.proxy.is_empty() {
+        if let Some(response) = proxy::handle(&mut service_request, &app_state, &path).await {
+            return Ok(service_request.into_response(response));
+        }
+    }
+    if is_page {
+        serve_page(service_request, &path).await
     } else {
         log::debug!("Serving file: {:?}", path);
         let app_state = service_request.extract::<web::Data<AppState>>().await?;
         let path = req_path(&service_request);
-        let if_modified_since = IfModifiedSince::parse(&service_request).ok();
-        let response = serve_file(&path, &app_state, if_modified_since).await?;
+        let response = static_files::serve_file(&service_request, &path, &app_state)
+            .await
+            .map_err(anyhow_err_to_actix)?;
         Ok(service_request.into_response(response))
     }
 }
 
+/// Returns a `429 Too Many Requests` response when the client made too many requests
+fn check_rate_limit(req: &ServiceRequest, path: &str) -> Option<HttpResponse> {
+    let app_state: &web::Data<AppState> = req.app_data().expect("app_state");
+    let config = &app_state.config;
+    if config.rate_limits.is_empty() {
+        return None;
+    }
+    let client_ip = forwarded::client_ip(req.request(), &config.trusted_proxies)?;
+    let wait = app_state
+        .rate_limiter
+        .check(&config.rate_limits, client_ip, path)
+        .err()?;
+    log::debug!("Rate limit exceeded by {client_ip} on {path}");
+    Some(
+        HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, wait.as_secs_f64().ceil().to_string()))
+            .content_type(ContentType::plaintext())
+            .body("Too many requests. Please try again later."),
+    )
+}
+
+/// Responds with `403 Forbidden` to a client rejected by an ip restriction,
+/// displaying the `denied_page` of the rule when there is one
+async fn access_denied(
+    service_request: ServiceRequest,
+    denied_page: Option<String>,
+) -> actix_web::Result<ServiceResponse> {
+    let Some(page) = denied_page else {
+        let response = HttpResponse::Forbidden()
+            .content_type(ContentType::plaintext())
+            .body("Access denied.");
+        return Ok(service_request.into_response(response));
+    };
+    let page = PathBuf::from(page.trim_start_matches('/'));
+    let mut response = process_sql_request(service_request, page).await?;
+    *response.response_mut().status_mut() = StatusCode::FORBIDDEN;
+    Ok(response)
+}
+
 /// Extracts the path from a request and percent-decodes it
-fn req_path(req: &ServiceRequest) -> Cow<'_, str> {
+pub(super) fn req_path(req: &ServiceRequest) -> Cow<'_, str> {
     let encoded_path = req.path();
     percent_encoding::percent_decode_str(encoded_path).decode_utf8_lossy()
 }
 
-fn redirect_missing_trailing_slash(uri: &Uri) -> Option<HttpResponse> {
-    let path = uri.path();
-    if !path.ends_with('/')
-        && path
-            .rsplit_once('.')
-            .map(|(_, ext)| ext.eq_ignore_ascii_case("sql"))
-            != Some(true)
-    {
-        let mut redirect_path = path.to_owned();
-        redirect_path.push('/');
-        if let Some(query) = uri.query() {
-            redirect_path.push('?');
-            redirect_path.push_str(query);
-        }
-        Some(
-            HttpResponse::MovedPermanently()
-                .insert_header((header::LOCATION, redirect_path))
-                .finish(),
-        )
-    } else {
-        None
+/// A permanent redirection to another path, with the same query string
+fn redirect_to(uri: &Uri, path: &str) -> HttpResponse {
+    let mut redirect_path = path.to_owned();
+    if let Some(query) = uri.query() {
+        redirect_path.push('?');
+        redirect_path.push_str(query);
     }
+    HttpResponse::MovedPermanently()
+        .insert_header((header::LOCATION, redirect_path))
+        .finish()
 }
 
+/// The actix application that serves a website. Programs that embed SQLPage can add their own
+/// middleware and routes to it with `.wrap()` and `.service()`, and run it with [`run_server_with`].
 pub fn create_app(
     app_state: web::Data<AppState>,
 ) -> App<
@@ -465,67 +1065,290 @@ pub fn create_app(
         InitError = (),
     >,
 > {
-    App::new()
+    let mut app = App::new()
         .service(static_content::js())
         .service(static_content::apexcharts_js())
         .service(static_content::css())
-        .service(static_content::icons())
-        .default_service(fn_service(main_handler))
-        .wrap(Logger::default())
-        .wrap(
-            middleware::DefaultHeaders::new()
-                .add((
-                    "Server",
-                    format!("{} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
-                ))
-                .add((
-                    "Content-Security-Policy",
-                    "script-src 'self' https://cdn.jsdelivr.net",
-                )),
-        )
-        .wrap(middleware::Compress::default())
+        .service(static_content::rtl_css())
+        .service(static_content::icons());
+    for (host, host_state) in &app_state.virtual_hosts {
+        app = app.service(
+            web::scope("")
+                .guard(guard::Host(host))
+                .app_data(web::Data::clone(host_state))
+                .default_service(fn_service(main_handler)),
+        );
+    }
+    let mut default_headers = middleware::DefaultHeaders::new().add((
+        "Server",
+        format!("{} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+    ));
+    let policy = &app_state.config.content_security_policy;
+    if !policy.is_empty() {
+        // Pages rendered from sql files have their own policy, with a nonce
+        default_headers = default_headers.add((
+            header::CONTENT_SECURITY_POLICY,
+            content_security_policy::without_nonce(policy),
+        ));
+    }
+    let access_log_state = web::Data::clone(&app_state);
+    app.default_service(fn_service(main_handler))
+        .wrap_fn(move |req, srv| {
+            let entry = access_log_state
+                .access_log
+                .as_ref()
+                .map(|log| AccessLogEntry::new(log, &req, &access_log_state.config));
+            let http_request = req.request().clone();
+            srv.call(req).map(move |response| {
+                // Errors are turned into responses here, to be logged too
+                let response =
+                    response.unwrap_or_else(|e| ServiceResponse::from_err(e, http_request));
+                Ok(access_log::log_response(response, entry))
+            })
+        })
+        .wrap(middleware::Condition::new(
+            app_state.config.access_log.is_none(),
+            access_logger(&app_state.config),
+        ))
+        .wrap(default_headers)
+        .wrap(middleware::Condition::new(
+            app_state.config.compress_responses,
+            middleware::Compress::default(),
+        ))
         .wrap(middleware::NormalizePath::new(
             middleware::TrailingSlash::MergeOnly,
         ))
+        // Outermost, so that the id is known by the loggers and sent back even in error responses
+        .wrap_fn(|req, srv| {
+            let id = request_id::assign(&req);
+            srv.call(req).map(move |response| {
+                response.map(|mut response| {
+                    if let Ok(value) = header::HeaderValue::from_str(&id) {
+                        response
+                            .headers_mut()
+                            .insert(header::HeaderName::from_static(request_id::HEADER), value);
+                    }
+                    response
+                })
+            })
+        })
         .app_data(PayloadConfig::default().limit(app_state.config.max_uploaded_file_size * 2))
         .app_data(app_state)
 }
 
+/// Logs the requests in the application log when there is no separate access log.
+/// Same format as the default actix logger, but with the client address resolved
+/// from the headers of trusted proxies, and the id of the request at the end
+fn access_logger(config: &AppConfig) -> Logger {
+    let trusted_proxies = config.trusted_proxies.clone();
+    Logger::new(r#"%{client_ip}xi "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T %{x-request-id}o"#)
+        .custom_request_replace("client_ip", move |req| {
+            forwarded::log_client_ip(req, &trusted_proxies)
+        })
+}
+
 pub async fn run_server(config: &AppConfig, state: AppState) -> anyhow::Result<()> {
-    let listen_on = config.listen_on();
+    run_server_with(config, state, create_app).await
+}
+
+/// Runs the web server with the application returned by `make_app`, for programs that embed SQLPage.
+/// `make_app` usually calls [`create_app`], and wraps the result in middleware or adds routes to it:
+/// `|state| create_app(state).wrap(my_middleware).service(my_route)`.
+/// It is called for every worker thread, and again when the configuration is reloaded.
+pub async fn run_server_with<F, T, B>(
+    config: &AppConfig,
+    state: AppState,
+    make_app: F,
+) -> anyhow::Result<()>
+where
+    F: Fn(web::Data<AppState>) -> App<T> + Clone + Send + 'static,
+    T: ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<B>,
+            Error = actix_web::Error,
+            InitError = (),
+        > + 'static,
+    B: MessageBody + 'static,
+    B::Error: std::fmt::Display + std::fmt::Debug,
+{
     let state = web::Data::new(state);
-    let factory = move || create_app(web::Data::clone(&state));
 
     #[cfg(feature = "lambda-web")]
     if lambda_web::is_running_on_lambda() {
+        let factory = move || make_app(web::Data::clone(&state));
         lambda_web::run_actix_on_lambda(factory)
             .await
             .map_err(|e| anyhow::anyhow!("Unable to start the lambda: {e}"))?;
         return Ok(());
     }
-    let mut server = HttpServer::new(factory);
-    if let Some(domain) = &config.https_domain {
-        let mut listen_on_https = listen_on;
-        listen_on_https.set_port(443);
-        log::info!("Will start HTTPS server on {listen_on}");
-        let config = make_auto_rustls_config(domain, config);
-        server = server
-            .bind_rustls_021(listen_on, config)
-            .map_err(|e| bind_error(e, listen_on))?;
-    } else if listen_on.port() == 443 {
-        bail!("Please specify a value for https_domain in the configuration file. This is required when using HTTPS (port 443)");
-    }
-    if listen_on.port() != 443 {
-        log::info!("Will start HTTP server on {listen_on}");
-        server = server
-            .bind(listen_on)
-            .map_err(|e| bind_error(e, listen_on))?;
-    }
-    server
-        .run()
-        .await
-        .with_context(|| "Unable to start the application")?;
-    Ok(())
+    let acme_tls_config = config
+        .https_domain
+        .as_ref()
+        .map(|domain| make_auto_rustls_config(domain, config));
+    let mut listeners = Vec::new();
+    for listen_on in config.listen_on() {
+        let https = config.is_https_address(&listen_on);
+        if https {
+            log::info!("Will start HTTPS server on {listen_on}");
+        } else if listen_on.port() == 443 {
+            bail!("Please specify a value for https_domain, or for tls_certificate and tls_key in the configuration file. This is required when using HTTPS (port 443)");
+        } else {
+            log::info!("Will start HTTP server on {listen_on}");
+        }
+        let listener =
+            std::net::TcpListener::bind(listen_on).map_err(|e| bind_error(e, listen_on))?;
+        listeners.push((listener, https));
+    }
+    let mut config = config.clone();
+    let mut state = state;
+    let (mut handle, mut running) = start_server(
+        &config,
+        &state,
+        &listeners,
+        acme_tls_config.as_ref(),
+        make_app.clone(),
+    )?;
+    let mut reload_signal = ReloadSignal::new()?;
+    loop {
+        tokio::select! {
+            result = &mut running => {
+                result
+                    .with_context(|| "The web server stopped unexpectedly")?
+                    .with_context(|| "Unable to start the application")?;
+                return Ok(());
+            }
+            () = reload_signal.recv() => {}
+        }
+        log::info!("Reloading the configuration");
+        // The new server accepts connections on the same sockets, so no connection is refused
+        // while the old one finishes handling its requests
+        match reload(&config, &state)
+            .await
+            .and_then(|(new_config, new_state)| {
+                let server = start_server(
+                    &new_config,
+                    &new_state,
+                    &listeners,
+                    acme_tls_config.as_ref(),
+                    make_app.clone(),
+                )?;
+                Ok((new_config, new_state, server))
+            }) {
+            Ok((new_config, new_state, (new_handle, new_running))) => {
+                drop(handle.stop(true));
+                (config, state, handle, running) = (new_config, new_state, new_handle, new_running);
+                log::info!("Configuration reloaded");
+            }
+            Err(e) => {
+                log::error!("Unable to reload the configuration, keeping the current one: {e:#}")
+            }
+        }
+    }
+}
+
+/// Reads the configuration again, and creates a new application state from it
+async fn reload(
+    config: &AppConfig,
+    state: &AppState,
+) -> anyhow::Result<(AppConfig, web::Data<AppState>)> {
+    let new_config = crate::app_config::load()?;
+    if new_config.listen_on() != config.listen_on() {
+        log::warn!("listen_on cannot be changed without restarting SQLPage");
+    }
+    let new_state = state.reload(&new_config).await?;
+    new_state.apply_migrations().await?;
+    Ok((new_config, web::Data::new(new_state)))
+}
+
+fn start_server<F, T, B>(
+    config: &AppConfig,
+    state: &web::Data<AppState>,
+    listeners: &[(std::net::TcpListener, bool)],
+    acme_tls_config: Option<&rustls_acme::futures_rustls::rustls::ServerConfig>,
+    make_app: F,
+) -> anyhow::Result<(
+    actix_web::dev::ServerHandle,
+    tokio::task::JoinHandle<std::io::Result<()>>,
+)>
+where
+    F: Fn(web::Data<AppState>) -> App<T> + Clone + Send + 'static,
+    T: ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<B>,
+            Error = actix_web::Error,
+            InitError = (),
+        > + 'static,
+    B: MessageBody + 'static,
+{
+    let state = web::Data::clone(state);
+    let mut server = HttpServer::new(move || make_app(web::Data::clone(&state)));
+    let workers = match config.http_workers {
+        Some(0) => bail!("http_workers must be at least 1"),
+        Some(workers) => workers,
+        None => std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get),
+    };
+    server = server.workers(workers);
+    if let Some(seconds) = config.keep_alive_seconds {
+        let Ok(keep_alive) = Duration::try_from_secs_f64(seconds) else {
+            bail!(
+                "keep_alive_seconds must be a positive number of seconds, \
+                or 0 to disable keep-alive, not {seconds}"
+            );
+        };
+        server = server.keep_alive(if keep_alive.is_zero() {
+            KeepAlive::Disabled
+        } else {
+            KeepAlive::Timeout(keep_alive)
+        });
+    }
+    if let Some(max_connections) = config.max_connections {
+        // The limit of actix is per worker
+        server = server.max_connections(max_connections.div_ceil(workers));
+    }
+    // Certificate files are read again on every reload, to pick up renewed certificates
+    let tls_config = match acme_tls_config {
+        Some(tls_config) => Some(tls_config.clone()),
+        None => make_file_rustls_config(config)?,
+    };
+    for (listener, https) in listeners {
+        let listener = listener.try_clone()?;
+        server = if *https {
+            let tls_config = tls_config
+                .clone()
+                .with_context(|| "Missing TLS configuration")?;
+            server.listen_rustls_0_21(listener, tls_config)?
+        } else {
+            // Accepts both HTTP/1.x and HTTP/2 without TLS (h2c), for reverse proxies that support it.
+            // Over TLS, HTTP/2 is negotiated with ALPN.
+            server.listen_auto_h2c(listener)?
+        };
+    }
+    let server = server.run();
+    Ok((server.handle(), tokio::task::spawn_local(server)))
+}
+
+/// Resolves every time SQLPage receives a SIGHUP signal. Signals are not supported on Windows.
+struct ReloadSignal {
+    #[cfg(unix)]
+    hangup: tokio::signal::unix::Signal,
+}
+
+impl ReloadSignal {
+    fn new() -> std::io::Result<Self> {
+        Ok(ReloadSignal {
+            #[cfg(unix)]
+            hangup: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?,
+        })
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        self.hangup.recv().await;
+        #[cfg(not(unix))]
+        std::future::pending::<()>().await;
+    }
 }
 
 fn bind_error(e: std::io::Error, listen_on: std::net::SocketAddr) -> anyhow::Error {
@@ -1,4 +1,9 @@
+use super::basic_auth::VerifiedUser;
+use super::forwarded;
 use super::http::SingleOrVec;
+use super::request_id;
+use super::routing::PathParams;
+use super::session::{load_session, Session};
 use crate::AppState;
 use actix_multipart::form::bytes::Bytes;
 use actix_multipart::form::tempfile::TempFile;
@@ -8,9 +13,11 @@ use actix_multipart::Multipart;
 use actix_web::dev::ServiceRequest;
 use actix_web::http::header::Header;
 use actix_web::http::header::CONTENT_TYPE;
+use actix_web::http::Method;
 use actix_web::web;
 use actix_web::web::Form;
 use actix_web::FromRequest;
+use actix_web::HttpMessage;
 use actix_web::HttpRequest;
 use actix_web_httpauth::headers::authorization::Authorization;
 use actix_web_httpauth::headers::authorization::Basic;
@@ -23,6 +30,7 @@ use tokio_stream::StreamExt;
 
 #[derive(Debug)]
 pub struct RequestInfo {
+    pub method: Method,
     pub path: String,
     pub protocol: String,
     pub get_variables: ParamMap,
@@ -32,17 +40,27 @@ pub struct RequestInfo {
     pub client_ip: Option<IpAddr>,
     pub cookies: ParamMap,
     pub basic_auth: Option<Basic>,
+    /// The basic auth user, when its password was checked by a `basic_auth` rule of the configuration
+    pub verified_user: Option<String>,
+    pub session: Session,
+    /// Identifies the request in the logs and in error pages, returned by `sqlpage.request_id()`
+    pub request_id: String,
     pub app_state: Arc<AppState>,
+    /// Number of `sqlpage.render_to_storage` calls that this request is nested in
+    pub(crate) render_depth: usize,
 }
 
 pub(crate) async fn extract_request_info(
     req: &mut ServiceRequest,
     app_state: Arc<AppState>,
 ) -> RequestInfo {
+    let request_id = request_id::get(req.request()).unwrap_or_else(|| request_id::assign(req));
     let (http_req, payload) = req.parts_mut();
-    let protocol = http_req.connection_info().scheme().to_string();
     let config = &app_state.config;
+    let protocol = forwarded::scheme(http_req, &config.trusted_proxies);
+    let client_ip = forwarded::client_ip(http_req, &config.trusted_proxies);
     let (post_variables, uploaded_files) = extract_post_data(http_req, payload, config).await;
+    let session = load_session(http_req, &app_state).await;
 
     let headers = req.headers().iter().map(|(name, value)| {
         (
@@ -53,7 +71,11 @@ pub(crate) async fn extract_request_info(
     let get_variables = web::Query::<Vec<(String, String)>>::from_query(req.query_string())
         .map(web::Query::into_inner)
         .unwrap_or_default();
-    let client_ip = req.peer_addr().map(|addr| addr.ip());
+    let path_params = req
+        .extensions()
+        .get::<PathParams>()
+        .cloned()
+        .unwrap_or_default();
 
     let raw_cookies = req.cookies();
     let cookies = raw_cookies
@@ -64,18 +86,27 @@ pub(crate) async fn extract_request_info(
     let basic_auth = Authorization::<Basic>::parse(req)
         .ok()
         .map(Authorization::into_scheme);
+    let verified_user = req
+        .extensions()
+        .get::<VerifiedUser>()
+        .map(|user| user.0.clone());
 
     RequestInfo {
+        method: req.method().clone(),
         path: req.path().to_string(),
         headers: param_map(headers),
-        get_variables: param_map(get_variables),
+        get_variables: with_path_params(param_map(get_variables), path_params),
         post_variables: param_map(post_variables),
         uploaded_files: HashMap::from_iter(uploaded_files),
         client_ip,
         cookies: param_map(cookies),
         basic_auth,
+        verified_user,
+        session,
+        request_id,
         app_state,
         protocol,
+        render_depth: 0,
     }
 }
 
@@ -189,6 +220,14 @@ async fn extract_file(
 
 pub type ParamMap = HashMap<String, SingleOrVec>;
 
+/// Variables from the path of the request take precedence over the query string
+fn with_path_params(mut get_variables: ParamMap, path_params: PathParams) -> ParamMap {
+    for (name, value) in path_params.0 {
+        get_variables.insert(name, SingleOrVec::Single(value));
+    }
+    get_variables
+}
+
 fn param_map<PAIRS: IntoIterator<Item = (String, String)>>(values: PAIRS) -> ParamMap {
     values
         .into_iter()
@@ -1,4 +1,7 @@
+use anyhow::Context;
+use rustls_acme::futures_rustls::rustls::{Certificate, PrivateKey};
 use rustls_acme::{caches::DirCache, futures_rustls::rustls::ServerConfig, AcmeConfig};
+use std::path::Path;
 use tokio_stream::StreamExt;
 
 use crate::app_config::AppConfig;
@@ -30,3 +33,62 @@ pub fn make_auto_rustls_config(domain: &str, config: &AppConfig) -> ServerConfig
 
     ServerConfig::clone(&rustls_config)
 }
+
+/// Builds the TLS configuration from the `tls_certificate` and `tls_key` PEM files, if they are set
+pub fn make_file_rustls_config(config: &AppConfig) -> anyhow::Result<Option<ServerConfig>> {
+    let (certificate_path, key_path) = match (&config.tls_certificate, &config.tls_key) {
+        (Some(certificate), Some(key)) => (certificate, key),
+        (None, None) => return Ok(None),
+        _ => anyhow::bail!("tls_certificate and tls_key must be set together"),
+    };
+    log::info!(
+        "Loading the TLS certificate from {} and its key from {}",
+        certificate_path.display(),
+        key_path.display()
+    );
+    let certificates = load_certificates(certificate_path)?;
+    let key = load_private_key(key_path)?;
+    let rustls_config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certificates, key)
+        .with_context(|| format!("Invalid TLS certificate or key in {}", key_path.display()))?;
+    Ok(Some(rustls_config))
+}
+
+fn load_certificates(path: &Path) -> anyhow::Result<Vec<Certificate>> {
+    let pem = std::fs::read(path)
+        .with_context(|| format!("Unable to read the TLS certificate {}", path.display()))?;
+    let certificates = rustls_pemfile::certs(&mut pem.as_slice())
+        .with_context(|| format!("Invalid PEM file {}", path.display()))?;
+    if certificates.is_empty() {
+        anyhow::bail!("No certificate found in {}", path.display());
+    }
+    Ok(certificates.into_iter().map(Certificate).collect())
+}
+
+fn load_private_key(path: &Path) -> anyhow::Result<PrivateKey> {
+    let pem = std::fs::read(path)
+        .with_context(|| format!("Unable to read the TLS key {}", path.display()))?;
+    let mut reader = pem.as_slice();
+    while let Some(item) = rustls_pemfile::read_one(&mut reader)
+        .with_context(|| format!("Invalid PEM file {}", path.display()))?
+    {
+        match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => return Ok(PrivateKey(key)),
+            _ => {}
+        }
+    }
+    anyhow::bail!("No private key found in {}", path.display())
+}
+
+#[test]
+fn test_tls_certificate_without_key() {
+    let mut config = crate::app_config::tests::test_config();
+    assert!(make_file_rustls_config(&config).unwrap().is_none());
+    config.tls_certificate = Some("cert.pem".into());
+    let err = make_file_rustls_config(&config).unwrap_err();
+    assert!(err.to_string().contains("tls_key"), "{err}");
+}
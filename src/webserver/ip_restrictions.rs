@@ -0,0 +1,73 @@
+//! Restricts parts of the website to some client addresses, configured in `ip_restrictions`.
+//!
+//! Every rule whose `path_prefix` matches the path of a request applies to it, before any sql file
+//! is executed or any static file is served. The address of the client is read from the
+//! `X-Forwarded-For` and `Forwarded` headers only when the request comes from a trusted proxy.
+
+use actix_web::dev::ServiceRequest;
+use serde::Deserialize;
+use std::net::IpAddr;
+
+use super::forwarded::{self, IpNetwork};
+use crate::app_config::AppConfig;
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct IpRestriction {
+    /// The rule applies to the requests whose path starts with this prefix, such as `/admin/`
+    pub path_prefix: String,
+    /// When not empty, only the clients whose address is in one of these ranges are let in
+    #[serde(default)]
+    pub allow: Vec<IpNetwork>,
+    /// Clients whose address is in one of these ranges are rejected, even when they are allowed
+    #[serde(default)]
+    pub deny: Vec<IpNetwork>,
+    /// Sql file displayed with a `403 Forbidden` status to rejected clients, such as `/forbidden.sql`
+    pub denied_page: Option<String>,
+}
+
+impl IpRestriction {
+    fn allows(&self, client_ip: Option<IpAddr>) -> bool {
+        let Some(ip) = client_ip else {
+            // The address of the client is unknown, so it cannot be checked
+            return self.allow.is_empty() && self.deny.is_empty();
+        };
+        let in_any = |networks: &[IpNetwork]| networks.iter().any(|n| n.contains(ip));
+        (self.allow.is_empty() || in_any(&self.allow)) && !in_any(&self.deny)
+    }
+}
+
+/// Returns the first rule that rejects the client of the request, if any
+pub(crate) fn check<'a>(
+    req: &ServiceRequest,
+    config: &'a AppConfig,
+    path: &str,
+) -> Option<&'a IpRestriction> {
+    if config.ip_restrictions.is_empty() {
+        return None;
+    }
+    let client_ip = forwarded::client_ip(req.request(), &config.trusted_proxies);
+    let rule = config
+        .ip_restrictions
+        .iter()
+        .filter(|rule| path.starts_with(&rule.path_prefix))
+        .find(|rule| !rule.allows(client_ip))?;
+    log::debug!(
+        "Access to {path} denied to {client_ip:?} by the rule for {}",
+        rule.path_prefix
+    );
+    Some(rule)
+}
+
+#[test]
+fn test_ip_restriction() {
+    let rule = IpRestriction {
+        path_prefix: "/admin/".into(),
+        allow: vec!["10.0.0.0/8".parse().unwrap()],
+        deny: vec!["10.0.0.13".parse().unwrap()],
+        denied_page: None,
+    };
+    assert!(rule.allows(Some("10.1.2.3".parse().unwrap())));
+    assert!(!rule.allows(Some("10.0.0.13".parse().unwrap())));
+    assert!(!rule.allows(Some("192.168.1.1".parse().unwrap())));
+    assert!(!rule.allows(None));
+}
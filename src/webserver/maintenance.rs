@@ -0,0 +1,113 @@
+//! Maintenance mode, configured in `maintenance`: while it is on, every request receives a
+//! `503 Service Unavailable` page, except the requests from the allowed addresses and to the allowed paths.
+//!
+//! It is turned on with `enabled`, which can be changed by reloading the configuration, or while the
+//! `switch_file` exists, which does not require a reload. The process and its database connections
+//! stay alive, so that the website is back as soon as maintenance mode is turned off.
+
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header::{self, ContentType};
+use actix_web::HttpResponse;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::forwarded::{self, IpNetwork};
+use crate::AppState;
+
+/// How long the existence of the switch file is cached
+const SWITCH_FILE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+const DEFAULT_PAGE: &str = "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Maintenance</title></head>\
+<body><h1>Maintenance in progress</h1><p>This website is temporarily unavailable. Please try again in a few minutes.</p></body></html>";
+
+#[derive(Debug, Deserialize, PartialEq, Clone, Default)]
+pub struct Maintenance {
+    /// Turns maintenance mode on
+    #[serde(default)]
+    pub enabled: bool,
+    /// Maintenance mode is also on while this file exists
+    pub switch_file: Option<PathBuf>,
+    /// Html file of the web root displayed during maintenance, such as `maintenance.html`
+    pub page: Option<PathBuf>,
+    /// Client addresses that can still use the website, such as the ones of the administrators
+    #[serde(default)]
+    pub allow: Vec<IpNetwork>,
+    /// Path prefixes that can still be requested, such as a health check used by a load balancer
+    #[serde(default)]
+    pub allowed_paths: Vec<String>,
+    /// Number of seconds after which clients are told to try again, in the `Retry-After` header
+    pub retry_after: Option<u64>,
+}
+
+/// Remembers whether the switch file exists, to avoid checking it on every request
+#[derive(Default)]
+pub(crate) struct MaintenanceSwitch {
+    checked: Mutex<Option<(Instant, bool)>>,
+}
+
+impl MaintenanceSwitch {
+    fn file_exists(&self, path: &Path) -> bool {
+        let mut checked = self
+            .checked
+            .lock()
+            .expect("maintenance switch lock poisoned");
+        match *checked {
+            Some((at, exists)) if at.elapsed() < SWITCH_FILE_CHECK_INTERVAL => exists,
+            _ => {
+                let exists = path.exists();
+                *checked = Some((Instant::now(), exists));
+                exists
+            }
+        }
+    }
+}
+
+/// Returns the maintenance page when maintenance mode is on, and the request is not allowed
+pub(crate) async fn check(
+    req: &ServiceRequest,
+    app_state: &AppState,
+    path: &str,
+) -> Option<HttpResponse> {
+    let config = &app_state.config;
+    let maintenance = &config.maintenance;
+    let is_on = maintenance.enabled
+        || maintenance
+            .switch_file
+            .as_deref()
+            .is_some_and(|file| app_state.maintenance_switch.file_exists(file));
+    if !is_on
+        || maintenance
+            .allowed_paths
+            .iter()
+            .any(|p| path.starts_with(p))
+    {
+        return None;
+    }
+    let client_ip = forwarded::client_ip(req.request(), &config.trusted_proxies);
+    if client_ip.is_some_and(|ip| maintenance.allow.iter().any(|n| n.contains(ip))) {
+        return None;
+    }
+    let mut response = HttpResponse::ServiceUnavailable();
+    response
+        .content_type(ContentType::html())
+        .insert_header((header::CACHE_CONTROL, "no-store"));
+    if let Some(retry_after) = maintenance.retry_after {
+        response.insert_header((header::RETRY_AFTER, retry_after.to_string()));
+    }
+    Some(response.body(maintenance_page(app_state, maintenance).await))
+}
+
+async fn maintenance_page(app_state: &AppState, maintenance: &Maintenance) -> Vec<u8> {
+    let Some(page) = &maintenance.page else {
+        return DEFAULT_PAGE.into();
+    };
+    match app_state.file_system.read_file(app_state, page, true).await {
+        Ok(contents) => contents,
+        Err(e) => {
+            log::error!("Unable to read the maintenance page {page:?}: {e:#}");
+            DEFAULT_PAGE.into()
+        }
+    }
+}
@@ -1,8 +1,29 @@
+pub mod access_log;
+pub mod basic_auth;
+mod chunked_upload;
+pub mod content_security_policy;
+pub mod csrf;
 pub mod database;
+mod directory_listing;
 pub mod error_with_status;
+pub mod etag;
+pub mod forwarded;
 pub mod http;
 pub mod http_request_info;
 mod https;
+pub mod ip_restrictions;
+pub mod maintenance;
+pub mod oidc;
+pub mod page_cache;
+pub mod proxy;
+pub mod rate_limit;
+mod request_id;
+mod routing;
+pub mod server_sent_events;
+pub mod session;
+pub mod session_store;
+mod websocket;
+pub mod well_known;
 
 pub use database::Database;
 pub use error_with_status::ErrorWithStatus;
@@ -10,3 +31,4 @@ pub use error_with_status::ErrorWithStatus;
 pub use database::make_placeholder;
 pub use database::migrations::apply;
 mod static_content;
+mod static_files;
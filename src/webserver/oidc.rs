@@ -0,0 +1,486 @@
+//! Single sign-on with an OpenID Connect provider, such as Keycloak, Auth0, Google or Microsoft Entra ID.
+//!
+//! Visitors of the `oidc_protected_paths` who are not logged in are redirected to the provider,
+//! that sends them back to `/sqlpage/oidc/callback` with an authorization code. The code is exchanged
+//! for an ID token at the token endpoint of the provider. The token is received directly from the
+//! provider, so its signature is not checked, but its issuer, audience, expiration date and nonce are.
+//! The `sub` claim of the token is stored in the `user` session variable, used by `sqlpage.current_user()`,
+//! and the claims in the `oidc_claims` session variable, read by `sqlpage.user_info()`. Without a
+//! `session_store`, only `sub` is kept there, because browsers do not accept cookies larger than 4 KB.
+//!
+//! The redirect url given to the provider is built from `oidc_public_url` or `https_domain`,
+//! and never from the `Host` header, which is chosen by the client.
+
+use actix_web::cookie::Cookie;
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header;
+use actix_web::{web, HttpResponse};
+use anyhow::{anyhow, bail, ensure, Context};
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::{Arc, RwLock};
+
+use super::forwarded;
+use super::http_request_info::RequestInfo;
+use super::session::{
+    constant_time_eq, destroy_session, get_session_variable, load_session, set_session_variable,
+    Session, SESSION_USER,
+};
+use crate::AppState;
+
+pub const OIDC_CALLBACK_PATH: &str = "/sqlpage/oidc/callback";
+pub const OIDC_LOGOUT_PATH: &str = "/sqlpage/oidc/logout";
+/// Session variable containing the claims of the ID token of the user
+const SESSION_CLAIMS: &str = "oidc_claims";
+/// Session variable containing the state of a login that is in progress
+const SESSION_LOGIN: &str = "oidc_login";
+/// Tolerated difference between the clock of the provider and ours, in seconds
+const CLOCK_SKEW: i64 = 60;
+
+#[derive(Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    #[serde(default)]
+    token_endpoint_auth_methods_supported: Vec<String>,
+    end_session_endpoint: Option<String>,
+}
+
+/// The metadata of the provider, fetched from its discovery document when it is first needed
+#[derive(Default)]
+pub(crate) struct OidcProvider {
+    metadata: RwLock<Option<Arc<ProviderMetadata>>>,
+}
+
+impl OidcProvider {
+    async fn metadata(&self, issuer_url: &str) -> anyhow::Result<Arc<ProviderMetadata>> {
+        if let Some(metadata) = &*self.metadata.read().expect("oidc metadata lock poisoned") {
+            return Ok(Arc::clone(metadata));
+        }
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            issuer_url.trim_end_matches('/')
+        );
+        log::debug!("Fetching the OpenID Connect provider metadata from {url}");
+        let mut response = awc::Client::default()
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Unable to fetch {url}: {e}"))?;
+        ensure!(
+            response.status().is_success(),
+            "Unable to fetch {url}: status {}",
+            response.status()
+        );
+        let metadata: ProviderMetadata = response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Invalid OpenID Connect discovery document at {url}: {e}"))?;
+        let metadata = Arc::new(metadata);
+        *self.metadata.write().expect("oidc metadata lock poisoned") = Some(Arc::clone(&metadata));
+        Ok(metadata)
+    }
+}
+
+/// Stored in a session variable between the redirection to the provider and the callback
+#[derive(Serialize, Deserialize)]
+struct LoginState {
+    state: String,
+    nonce: String,
+    /// Path and query string of the page requested before the login
+    redirect: String,
+}
+
+#[derive(Deserialize)]
+struct CallbackParams {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// Handles the callback and logout urls, and redirects the visitors of the protected paths
+/// who are not logged in to the provider.
+pub(crate) async fn handle(
+    req: &ServiceRequest,
+    app_state: &AppState,
+    path: &str,
+) -> anyhow::Result<Option<HttpResponse>> {
+    let config = &app_state.config;
+    let Some(issuer_url) = &config.oidc_issuer_url else {
+        return Ok(None);
+    };
+    let response = match path {
+        OIDC_CALLBACK_PATH => callback(req, app_state, issuer_url).await?,
+        OIDC_LOGOUT_PATH => logout(req, app_state, issuer_url).await?,
+        _ if is_protected(&config.oidc_protected_paths, path) => {
+            let session = load_session(req.request(), app_state).await;
+            if session.variables.contains_key(SESSION_USER) {
+                return Ok(None);
+            }
+            log::debug!("Redirecting to the OpenID Connect provider for {path}");
+            login(req, app_state, issuer_url, session).await?
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(response))
+}
+
+fn is_protected(protected_paths: &[String], path: &str) -> bool {
+    protected_paths
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+}
+
+async fn login(
+    req: &ServiceRequest,
+    app_state: &AppState,
+    issuer_url: &str,
+    mut session: Session,
+) -> anyhow::Result<HttpResponse> {
+    let config = &app_state.config;
+    let metadata = app_state.oidc_provider.metadata(issuer_url).await?;
+    let redirect = req
+        .uri()
+        .path_and_query()
+        .map_or("/", |path_and_query| path_and_query.as_str());
+    let login_state = LoginState {
+        state: random_token(),
+        nonce: random_token(),
+        // `//example.com` would send the user to another website
+        redirect: if redirect.starts_with("//") {
+            "/"
+        } else {
+            redirect
+        }
+        .to_string(),
+    };
+    let mut url = url::Url::parse(&metadata.authorization_endpoint).with_context(|| {
+        format!(
+            "Invalid authorization endpoint: {}",
+            metadata.authorization_endpoint
+        )
+    })?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", client_id(app_state)?)
+        .append_pair("redirect_uri", &redirect_uri(app_state)?)
+        .append_pair("scope", &config.oidc_scopes)
+        .append_pair("state", &login_state.state)
+        .append_pair("nonce", &login_state.nonce);
+    let login_state = encode(&login_state)?;
+    let cookies = set_variables(
+        req,
+        app_state,
+        &mut session,
+        &[(SESSION_LOGIN, Some(&login_state))],
+    )
+    .await?;
+    Ok(redirect(url.as_str(), cookies))
+}
+
+async fn callback(
+    req: &ServiceRequest,
+    app_state: &AppState,
+    issuer_url: &str,
+) -> anyhow::Result<HttpResponse> {
+    let params = web::Query::<CallbackParams>::from_query(req.query_string())
+        .map_err(|e| anyhow!("Invalid OpenID Connect callback parameters: {e}"))?
+        .into_inner();
+    if let Some(error) = params.error {
+        let description = params.error_description.unwrap_or_default();
+        log::warn!("The OpenID Connect provider returned an error: {error} {description}");
+        return Ok(login_failed());
+    }
+    let mut session = load_session(req.request(), app_state).await;
+    let login_state = session
+        .variables
+        .get(SESSION_LOGIN)
+        .and_then(Value::as_str)
+        .and_then(decode::<LoginState>);
+    let (Some(login_state), Some(code), Some(state)) = (login_state, params.code, params.state)
+    else {
+        log::warn!("OpenID Connect callback without a login in progress");
+        return Ok(login_failed());
+    };
+    if !constant_time_eq(&login_state.state, &state) {
+        log::warn!("OpenID Connect callback with an invalid state");
+        return Ok(login_failed());
+    }
+    let metadata = app_state.oidc_provider.metadata(issuer_url).await?;
+    let id_token = request_id_token(app_state, &metadata, &code).await?;
+    let claims = id_token_claims(
+        &id_token,
+        &metadata.issuer,
+        client_id(app_state)?,
+        &login_state.nonce,
+        chrono::Utc::now().timestamp(),
+    )?;
+    let user = claims
+        .get("sub")
+        .and_then(Value::as_str)
+        .context("The ID token does not contain a sub claim")?;
+    log::debug!("{user} logged in with OpenID Connect");
+    let stored_claims = if app_state.session_store.is_some() {
+        claims.clone()
+    } else {
+        Map::from_iter([("sub".to_string(), Value::from(user))])
+    };
+    let cookies = set_variables(
+        req,
+        app_state,
+        &mut session,
+        &[
+            (SESSION_USER, Some(user)),
+            (SESSION_CLAIMS, Some(&encode(&stored_claims)?)),
+            (SESSION_LOGIN, None),
+        ],
+    )
+    .await?;
+    Ok(redirect(&login_state.redirect, cookies))
+}
+
+/// Exchanges the authorization code for an ID token at the token endpoint of the provider
+async fn request_id_token(
+    app_state: &AppState,
+    metadata: &ProviderMetadata,
+    code: &str,
+) -> anyhow::Result<String> {
+    let client_id = client_id(app_state)?;
+    let client_secret = app_state
+        .config
+        .oidc_client_secret
+        .as_deref()
+        .context("oidc_client_secret must be set in the configuration to use OpenID Connect")?;
+    let redirect_uri = redirect_uri(app_state)?;
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", redirect_uri.as_str()),
+    ];
+    let mut request = awc::Client::default()
+        .post(&metadata.token_endpoint)
+        .insert_header((header::ACCEPT, "application/json"));
+    // Providers that do not list their authentication methods support client_secret_basic
+    let methods = &metadata.token_endpoint_auth_methods_supported;
+    if methods.is_empty() || methods.iter().any(|m| m == "client_secret_basic") {
+        request = request.basic_auth(client_id, client_secret);
+    } else {
+        form.extend([("client_id", client_id), ("client_secret", client_secret)]);
+    }
+    let mut response = request.send_form(&form).await.map_err(|e| {
+        anyhow!(
+            "Unable to reach the token endpoint {}: {e}",
+            metadata.token_endpoint
+        )
+    })?;
+    if !response.status().is_success() {
+        let error_body = response.body().await.unwrap_or_default();
+        bail!(
+            "The OpenID Connect provider refused the authorization code with status {}: {}",
+            response.status(),
+            String::from_utf8_lossy(&error_body)
+        );
+    }
+    let tokens: TokenResponse = response
+        .json()
+        .await
+        .map_err(|e| anyhow!("Invalid response from the token endpoint: {e}"))?;
+    Ok(tokens.id_token)
+}
+
+/// Validates an ID token received from the token endpoint, and returns its claims
+fn id_token_claims(
+    id_token: &str,
+    issuer: &str,
+    client_id: &str,
+    nonce: &str,
+    now: i64,
+) -> anyhow::Result<Map<String, Value>> {
+    let payload = id_token
+        .split('.')
+        .nth(1)
+        .context("The ID token is not a JSON web token")?;
+    let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .context("The ID token payload is not valid base64")?;
+    let claims: Map<String, Value> =
+        serde_json::from_slice(&payload).context("The ID token payload is not a JSON object")?;
+    ensure!(
+        claims.get("iss").and_then(Value::as_str) == Some(issuer),
+        "The ID token was not issued by {issuer}"
+    );
+    let for_client = match claims.get("aud") {
+        Some(Value::String(audience)) => audience == client_id,
+        Some(Value::Array(audiences)) => audiences.iter().any(|a| a.as_str() == Some(client_id)),
+        _ => false,
+    };
+    ensure!(for_client, "The ID token is not intended for {client_id}");
+    let expires_at = claims
+        .get("exp")
+        .and_then(Value::as_i64)
+        .context("The ID token has no expiration date")?;
+    ensure!(expires_at + CLOCK_SKEW > now, "The ID token has expired");
+    ensure!(
+        claims.get("nonce").and_then(Value::as_str) == Some(nonce),
+        "The nonce of the ID token does not match the one of the login"
+    );
+    Ok(claims)
+}
+
+/// Removes the session of the user, and logs them out of the provider when it supports it
+async fn logout(
+    req: &ServiceRequest,
+    app_state: &AppState,
+    issuer_url: &str,
+) -> anyhow::Result<HttpResponse> {
+    let metadata = app_state.oidc_provider.metadata(issuer_url).await?;
+    let location = match &metadata.end_session_endpoint {
+        Some(end_session_endpoint) => {
+            let mut url = url::Url::parse(end_session_endpoint)
+                .with_context(|| format!("Invalid end session endpoint: {end_session_endpoint}"))?;
+            url.query_pairs_mut()
+                .append_pair("client_id", client_id(app_state)?)
+                .append_pair(
+                    "post_logout_redirect_uri",
+                    &format!("{}/", origin(app_state)?),
+                );
+            url.to_string()
+        }
+        None => "/".to_string(),
+    };
+    let mut session = load_session(req.request(), app_state).await;
+    let cookies = destroy_session(app_state, &mut session, is_secure(req, app_state)).await?;
+    Ok(redirect(&location, cookies))
+}
+
+/// The value of a claim of the ID token of the logged-in user, or all of them as a json object
+pub(crate) fn user_info(request: &RequestInfo, claim: Option<&str>) -> Option<String> {
+    let claims = get_session_variable(request, SESSION_CLAIMS)?;
+    let claims: Map<String, Value> = decode(claims)?;
+    match claim {
+        None => Some(Value::Object(claims).to_string()),
+        Some(claim) => match claims.get(claim)? {
+            Value::Null => None,
+            Value::String(value) => Some(value.clone()),
+            value => Some(value.to_string()),
+        },
+    }
+}
+
+/// Changes session variables, and returns the cookies to send to the browser
+async fn set_variables(
+    req: &ServiceRequest,
+    app_state: &AppState,
+    session: &mut Session,
+    variables: &[(&str, Option<&str>)],
+) -> anyhow::Result<Vec<Cookie<'static>>> {
+    let secure = is_secure(req, app_state);
+    let mut cookies = Vec::new();
+    for &(name, value) in variables {
+        cookies.extend(set_session_variable(app_state, session, name, value, secure).await?);
+    }
+    Ok(cookies)
+}
+
+fn redirect(location: &str, cookies: Vec<Cookie<'static>>) -> HttpResponse {
+    let mut response = HttpResponse::SeeOther();
+    response.insert_header((header::LOCATION, location));
+    for cookie in cookies {
+        response.cookie(cookie);
+    }
+    response.finish()
+}
+
+fn login_failed() -> HttpResponse {
+    HttpResponse::BadRequest()
+        .content_type("text/plain")
+        .body("Login failed. Please go back and try again.")
+}
+
+fn client_id(app_state: &AppState) -> anyhow::Result<&str> {
+    app_state
+        .config
+        .oidc_client_id
+        .as_deref()
+        .context("oidc_client_id must be set in the configuration to use OpenID Connect")
+}
+
+fn is_secure(req: &ServiceRequest, app_state: &AppState) -> bool {
+    forwarded::scheme(req.request(), &app_state.config.trusted_proxies) == "https"
+}
+
+/// The public address of the website, like `https://example.com`
+fn origin(app_state: &AppState) -> anyhow::Result<String> {
+    let config = &app_state.config;
+    if let Some(public_url) = &config.oidc_public_url {
+        return Ok(public_url.trim_end_matches('/').to_string());
+    }
+    let domain = config.https_domain.as_deref().context(
+        "oidc_public_url (or https_domain) must be set in the configuration to use OpenID Connect",
+    )?;
+    Ok(format!("https://{domain}"))
+}
+
+fn redirect_uri(app_state: &AppState) -> anyhow::Result<String> {
+    Ok(format!("{}{OIDC_CALLBACK_PATH}", origin(app_state)?))
+}
+
+fn random_token() -> String {
+    let mut token = [0; 24];
+    rand::RngCore::fill_bytes(&mut password_hash::rand_core::OsRng, &mut token);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(token)
+}
+
+/// Session variables can be stored in cookies, so json values are base64-encoded
+fn encode<T: Serialize>(value: &T) -> anyhow::Result<String> {
+    let json = serde_json::to_vec(value)?;
+    Ok(base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json))
+}
+
+fn decode<T: DeserializeOwned>(encoded: &str) -> Option<T> {
+    let json = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(encoded)
+        .ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
+#[test]
+fn test_id_token_claims() {
+    let token = |claims: &Value| format!("header.{}.signature", encode(claims).unwrap());
+    let valid = serde_json::json!({
+        "iss": "https://idp.example.com",
+        "aud": ["other", "sqlpage"],
+        "sub": "alice",
+        "exp": 1000,
+        "nonce": "n0nce",
+    });
+    let check = |claims: &Value, now| {
+        id_token_claims(
+            &token(claims),
+            "https://idp.example.com",
+            "sqlpage",
+            "n0nce",
+            now,
+        )
+    };
+    assert_eq!(check(&valid, 900).unwrap()["sub"], "alice");
+    assert!(check(&valid, 2000).is_err(), "expired");
+    for (claim, value) in [
+        ("iss", "https://evil.com"),
+        ("aud", "other"),
+        ("nonce", "x"),
+    ] {
+        let mut invalid = valid.clone();
+        invalid[claim] = Value::from(value);
+        assert!(check(&invalid, 900).is_err(), "invalid {claim}");
+    }
+}
@@ -0,0 +1,211 @@
+//! Full-page output caching.
+//!
+//! A page can be kept in memory for a few seconds after it is rendered, when its sql file uses the
+//! `cache` component, or when its path matches one of the `page_cache` rules of the configuration.
+//! The following GET requests to the same url are answered from memory, without running any query.
+//! Entries expire after their time to live, or are removed earlier with `sqlpage.purge_cache(prefix)`.
+//! The content security policy nonce of the cached page is replaced by a new one on every hit.
+
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header::{HeaderMap, HeaderValue, CONTENT_SECURITY_POLICY, SET_COOKIE};
+use actix_web::http::{Method, StatusCode};
+use actix_web::web::Bytes;
+use actix_web::HttpResponse;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Above this number of cached pages, the expired ones are removed, and new pages are not cached
+const MAX_CACHED_PAGES: usize = 1000;
+/// Larger pages are not cached
+pub(crate) const MAX_CACHED_PAGE_SIZE: usize = 10 * 1024 * 1024;
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct PageCacheRule {
+    /// The rule applies to the pages whose path starts with this prefix. Defaults to `/`.
+    #[serde(default = "default_path_prefix")]
+    pub path_prefix: String,
+    /// Number of seconds during which a rendered page is served from the cache
+    pub ttl_seconds: f64,
+}
+
+fn default_path_prefix() -> String {
+    "/".to_string()
+}
+
+/// Inserted in the extensions of a response by the `cache` component
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CacheFor(pub Duration);
+
+struct CachedPage {
+    path: String,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    csp_nonce: Option<String>,
+    expires_at: Instant,
+}
+
+#[derive(Default)]
+pub(crate) struct PageCache {
+    pages: Mutex<HashMap<String, Arc<CachedPage>>>,
+}
+
+/// A page that is being rendered, and will be cached once its body is complete
+pub(crate) struct PendingPage {
+    key: String,
+    path: String,
+    status: StatusCode,
+    headers: HeaderMap,
+    csp_nonce: Option<String>,
+    expires_at: Instant,
+}
+
+impl PageCache {
+    /// The cached response to a request, if there is one that did not expire.
+    /// The nonce the page was rendered with is replaced by the one of the current request.
+    pub(crate) fn get(&self, key: &str, csp_nonce: Option<&str>) -> Option<HttpResponse> {
+        let page = {
+            let pages = self.pages.lock().expect("page cache lock poisoned");
+            Arc::clone(pages.get(key)?)
+        };
+        if page.expires_at <= Instant::now() {
+            return None;
+        }
+        log::debug!("Serving {key:?} from the page cache");
+        let mut response = HttpResponse::build(page.status);
+        let Some((old_nonce, new_nonce)) = page.csp_nonce.as_deref().zip(csp_nonce) else {
+            for (name, value) in &page.headers {
+                response.append_header((name.clone(), value.clone()));
+            }
+            return Some(response.body(page.body.clone()));
+        };
+        for (name, value) in &page.headers {
+            let value = if name == CONTENT_SECURITY_POLICY {
+                let policy = value.to_str().unwrap_or_default();
+                HeaderValue::from_str(&policy.replace(old_nonce, new_nonce)).ok()?
+            } else {
+                value.clone()
+            };
+            response.append_header((name.clone(), value));
+        }
+        Some(response.body(replace_nonce(&page.body, old_nonce, new_nonce)))
+    }
+
+    pub(crate) fn insert(&self, page: PendingPage, body: Vec<u8>) {
+        let mut pages = self.pages.lock().expect("page cache lock poisoned");
+        if pages.len() >= MAX_CACHED_PAGES {
+            let now = Instant::now();
+            pages.retain(|_, page| page.expires_at > now);
+            if pages.len() >= MAX_CACHED_PAGES {
+                log::debug!("The page cache is full, not caching {:?}", page.key);
+                return;
+            }
+        }
+        let cached = CachedPage {
+            path: page.path,
+            status: page.status,
+            headers: page.headers,
+            body: body.into(),
+            csp_nonce: page.csp_nonce,
+            expires_at: page.expires_at,
+        };
+        pages.insert(page.key, Arc::new(cached));
+    }
+
+    /// Removes the cached pages whose path starts with the given prefix, and returns their number
+    pub(crate) fn purge(&self, path_prefix: &str) -> usize {
+        let mut pages = self.pages.lock().expect("page cache lock poisoned");
+        let before = pages.len();
+        pages.retain(|_, page| !page.path.starts_with(path_prefix));
+        before - pages.len()
+    }
+}
+
+/// Replaces every occurrence of the nonce a page was rendered with by a new one
+fn replace_nonce(body: &[u8], old_nonce: &str, new_nonce: &str) -> Vec<u8> {
+    let (old_nonce, new_nonce) = (old_nonce.as_bytes(), new_nonce.as_bytes());
+    let mut replaced = Vec::with_capacity(body.len());
+    let mut rest = body;
+    if !old_nonce.is_empty() {
+        while let Some(pos) = rest.windows(old_nonce.len()).position(|w| w == old_nonce) {
+            replaced.extend_from_slice(&rest[..pos]);
+            replaced.extend_from_slice(new_nonce);
+            rest = &rest[pos + old_nonce.len()..];
+        }
+    }
+    replaced.extend_from_slice(rest);
+    replaced
+}
+
+/// Identifies the responses that can be shared between requests: the url, and the headers
+/// that change how the page is rendered. Only GET requests are cached, and HEAD requests use their cache.
+pub(crate) fn cache_key(req: &ServiceRequest) -> Option<String> {
+    if req.method() != Method::GET && req.method() != Method::HEAD {
+        return None;
+    }
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+    };
+    let url = req.uri().path_and_query()?.as_str();
+    Some(format!(
+        "{url}\n{}\n{}\n{}",
+        header("accept-language"),
+        header("hx-request"),
+        header("hx-boosted")
+    ))
+}
+
+/// The time to live of a page: the one set by the `cache` component, or by the first matching rule
+pub(crate) fn time_to_live(
+    rules: &[PageCacheRule],
+    path: &str,
+    response: &HttpResponse,
+) -> Option<Duration> {
+    if let Some(&CacheFor(ttl)) = response.extensions().get::<CacheFor>() {
+        return Some(ttl);
+    }
+    let rule = rules
+        .iter()
+        .find(|rule| path.starts_with(&rule.path_prefix))?;
+    Duration::try_from_secs_f64(rule.ttl_seconds).ok()
+}
+
+/// Prepares the caching of a response, if it is a successful response that sets no cookie
+pub(crate) fn pending_page(
+    key: String,
+    path: &str,
+    ttl: Duration,
+    response: &HttpResponse,
+    csp_nonce: Option<&str>,
+) -> Option<PendingPage> {
+    if ttl.is_zero() || response.status() != StatusCode::OK {
+        return None;
+    }
+    if response.headers().contains_key(SET_COOKIE) {
+        log::debug!("Not caching {path} because it sets cookies");
+        return None;
+    }
+    Some(PendingPage {
+        key,
+        path: path.to_string(),
+        status: response.status(),
+        headers: response.headers().clone(),
+        csp_nonce: csp_nonce.map(str::to_string),
+        expires_at: Instant::now() + ttl,
+    })
+}
+
+#[test]
+fn test_replace_nonce() {
+    let body = b"<script nonce=\"abc\">1</script><style nonce=\"abc\"></style>";
+    assert_eq!(
+        replace_nonce(body, "abc", "xyz"),
+        b"<script nonce=\"xyz\">1</script><style nonce=\"xyz\"></style>"
+    );
+    assert_eq!(replace_nonce(b"no nonce", "abc", "xyz"), b"no nonce");
+}
@@ -0,0 +1,122 @@
+//! Forwards the requests to some path prefixes to other web servers, configured in `proxy`.
+//!
+//! This lets SQLPage serve a website made of sql files and of another application, such as an api
+//! written in another language, without an additional reverse proxy in front of them.
+//! Request and response bodies are streamed, and the client address, scheme and host are sent
+//! to the upstream server in the `X-Forwarded-For`, `X-Forwarded-Proto` and `X-Forwarded-Host` headers.
+
+use super::forwarded;
+use crate::AppState;
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header::{self, HeaderMap, HeaderName};
+use actix_web::HttpResponse;
+use serde::Deserialize;
+
+/// Headers that only apply to a single connection, and are not forwarded
+const HOP_BY_HOP_HEADERS: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct ProxyRoute {
+    /// The requests whose path starts with this prefix are forwarded, such as `/api/`
+    pub path_prefix: String,
+    /// Url of the server the requests are forwarded to, such as `http://localhost:5000`.
+    /// The path and query string of the request are appended to it.
+    pub upstream: String,
+    /// Remove the `path_prefix` from the path of the request before appending it to the upstream url
+    #[serde(default)]
+    pub strip_prefix: bool,
+}
+
+thread_local! {
+    /// Keeps the connections to the upstream servers open between requests
+    static CLIENT: awc::Client = awc::Client::builder()
+        .disable_redirects()
+        .disable_timeout()
+        .finish();
+}
+
+/// Forwards the request to an upstream server when its path matches one of the `proxy` routes.
+/// The longest matching prefix wins.
+pub(crate) async fn handle(
+    req: &mut ServiceRequest,
+    app_state: &AppState,
+    path: &str,
+) -> Option<HttpResponse> {
+    let config = &app_state.config;
+    let route = config
+        .proxy
+        .iter()
+        .filter(|route| path.starts_with(&route.path_prefix))
+        .max_by_key(|route| route.path_prefix.len())?;
+    let mut path_and_query = req.uri().path_and_query().map_or("/", |p| p.as_str());
+    if route.strip_prefix {
+        let prefix = route.path_prefix.trim_end_matches('/');
+        path_and_query = path_and_query
+            .strip_prefix(prefix)
+            .unwrap_or(path_and_query);
+    }
+    let separator = if path_and_query.starts_with('/') {
+        ""
+    } else {
+        "/"
+    };
+    let url = format!(
+        "{}{separator}{path_and_query}",
+        route.upstream.trim_end_matches('/')
+    );
+    log::debug!("Proxying {} to {url}", req.path());
+
+    let mut upstream_request = CLIENT
+        .with(|client| client.request(req.method().clone(), &url))
+        .no_decompress();
+    for (name, value) in forwarded_headers(req.headers()) {
+        upstream_request = upstream_request.append_header((name.clone(), value.clone()));
+    }
+    let client_ip = forwarded::client_ip(req.request(), &config.trusted_proxies);
+    if let Some(client_ip) = client_ip {
+        upstream_request =
+            upstream_request.insert_header(("X-Forwarded-For", client_ip.to_string()));
+    }
+    let scheme = forwarded::scheme(req.request(), &config.trusted_proxies);
+    upstream_request = upstream_request.insert_header(("X-Forwarded-Proto", scheme));
+    if let Some(host) = req.headers().get(header::HOST) {
+        upstream_request = upstream_request.insert_header(("X-Forwarded-Host", host.clone()));
+    }
+
+    let upstream_response = match upstream_request.send_stream(req.take_payload()).await {
+        Ok(response) => response,
+        Err(e) => {
+            log::error!("Unable to forward the request to {url}: {e}");
+            return Some(
+                HttpResponse::BadGateway()
+                    .content_type("text/plain")
+                    .body("The server that handles this page is not available."),
+            );
+        }
+    };
+    let mut response = HttpResponse::build(upstream_response.status());
+    for (name, value) in forwarded_headers(upstream_response.headers()) {
+        // The body is streamed, with chunked encoding when its size is unknown
+        if name != header::CONTENT_LENGTH {
+            response.append_header((name.clone(), value.clone()));
+        }
+    }
+    Some(response.streaming(upstream_response))
+}
+
+fn forwarded_headers(
+    headers: &HeaderMap,
+) -> impl Iterator<Item = (&HeaderName, &header::HeaderValue)> {
+    headers
+        .iter()
+        .filter(|(name, _)| *name != header::HOST && !HOP_BY_HOP_HEADERS.contains(&name.as_str()))
+}
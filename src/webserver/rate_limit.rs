@@ -0,0 +1,191 @@
+//! Limits the number of requests that each client can make, with a token bucket per client address
+//! and per rule of the `rate_limits` configuration.
+//!
+//! Every rule whose `path_prefix` matches the path of a request applies to it,
+//! so a stricter rule for `/login.sql` can be combined with a global one for `/`.
+//! IPv6 clients are grouped by network (`/64` by default), because each of them usually
+//! has a whole network at their disposal, and could otherwise use a new address for every request.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv6Addr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Above this number of buckets, the buckets of clients that did not make requests recently are removed
+const MAX_BUCKETS: usize = 10_000;
+const MAX_WAIT: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct RateLimit {
+    /// The rule applies to the requests whose path starts with this prefix. Defaults to `/`.
+    #[serde(default = "default_path_prefix")]
+    pub path_prefix: String,
+    /// Number of requests that each client can make per minute, on average
+    pub requests_per_minute: f64,
+    /// Number of requests that each client can make at once. Defaults to `requests_per_minute`.
+    pub burst: Option<f64>,
+    /// IPv6 addresses that share this number of leading bits count as a single client. Defaults to 64.
+    #[serde(default = "default_ipv6_prefix_length")]
+    pub ipv6_prefix_length: u8,
+}
+
+fn default_path_prefix() -> String {
+    "/".to_string()
+}
+
+fn default_ipv6_prefix_length() -> u8 {
+    64
+}
+
+impl RateLimit {
+    fn tokens_per_second(&self) -> f64 {
+        self.requests_per_minute / 60.
+    }
+
+    fn burst(&self) -> f64 {
+        self.burst.unwrap_or(self.requests_per_minute).max(1.)
+    }
+
+    /// The address that identifies the client in this rule: the network of IPv6 clients
+    fn client_key(&self, client_ip: IpAddr) -> IpAddr {
+        match client_ip {
+            IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+                Some(v4) => IpAddr::V4(v4),
+                None => {
+                    let prefix_length = u32::from(self.ipv6_prefix_length.min(128));
+                    let mask = u128::MAX.checked_shl(128 - prefix_length).unwrap_or(0);
+                    IpAddr::V6(Ipv6Addr::from(u128::from(v6) & mask))
+                }
+            },
+            IpAddr::V4(_) => client_ip,
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, rule: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rule.tokens_per_second()).min(rule.burst());
+        self.updated_at = now;
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct RateLimiter {
+    buckets: Mutex<HashMap<(usize, IpAddr), Bucket>>,
+}
+
+impl RateLimiter {
+    /// Takes a token from the buckets of the client for all the rules that apply to the path.
+    /// When a bucket is empty, returns how long the client has to wait before its next request.
+    pub(crate) fn check(
+        &self,
+        rules: &[RateLimit],
+        client_ip: IpAddr,
+        path: &str,
+    ) -> Result<(), Duration> {
+        self.check_at(rules, client_ip, path, Instant::now())
+    }
+
+    fn check_at(
+        &self,
+        rules: &[RateLimit],
+        client_ip: IpAddr,
+        path: &str,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let matching_rules = || {
+            rules
+                .iter()
+                .enumerate()
+                .filter(|(_, rule)| path.starts_with(&rule.path_prefix))
+        };
+        if matching_rules().next().is_none() {
+            return Ok(());
+        }
+        let mut buckets = self.buckets.lock().expect("rate limiter lock");
+        if buckets.len() > MAX_BUCKETS {
+            buckets.retain(|(rule_index, _), bucket| {
+                let rule = &rules[*rule_index];
+                bucket.refill(rule, now);
+                bucket.tokens < rule.burst()
+            });
+        }
+        let mut wait = Duration::ZERO;
+        for (rule_index, rule) in matching_rules() {
+            let bucket = buckets
+                .entry((rule_index, rule.client_key(client_ip)))
+                .or_insert_with(|| Bucket {
+                    tokens: rule.burst(),
+                    updated_at: now,
+                });
+            bucket.refill(rule, now);
+            if bucket.tokens < 1. {
+                let missing = (1. - bucket.tokens) / rule.tokens_per_second();
+                // A rule that allows no requests at all makes clients wait for an hour
+                let rule_wait = Duration::try_from_secs_f64(missing).unwrap_or(MAX_WAIT);
+                wait = wait.max(rule_wait.min(MAX_WAIT));
+            }
+        }
+        if wait > Duration::ZERO {
+            return Err(wait);
+        }
+        for (rule_index, rule) in matching_rules() {
+            if let Some(bucket) = buckets.get_mut(&(rule_index, rule.client_key(client_ip))) {
+                bucket.tokens -= 1.;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_rate_limit() {
+    let rules: Vec<RateLimit> = serde_json::from_str(
+        r#"[
+            {"requests_per_minute": 600},
+            {"path_prefix": "/login.sql", "requests_per_minute": 2}
+        ]"#,
+    )
+    .unwrap();
+    let limiter = RateLimiter::default();
+    let ip: IpAddr = "1.2.3.4".parse().unwrap();
+    let other_ip: IpAddr = "5.6.7.8".parse().unwrap();
+    let now = Instant::now();
+    assert_eq!(limiter.check_at(&rules, ip, "/login.sql", now), Ok(()));
+    assert_eq!(limiter.check_at(&rules, ip, "/login.sql", now), Ok(()));
+    assert_eq!(
+        limiter.check_at(&rules, ip, "/login.sql", now),
+        Err(Duration::from_secs(30))
+    );
+    assert_eq!(limiter.check_at(&rules, ip, "/index.sql", now), Ok(()));
+    assert_eq!(
+        limiter.check_at(&rules, other_ip, "/login.sql", now),
+        Ok(())
+    );
+    let later = now + Duration::from_secs(30);
+    assert_eq!(limiter.check_at(&rules, ip, "/login.sql", later), Ok(()));
+}
+
+#[test]
+fn test_rate_limit_ipv6_network() {
+    let rules: Vec<RateLimit> = serde_json::from_str(r#"[{"requests_per_minute": 1}]"#).unwrap();
+    let limiter = RateLimiter::default();
+    let now = Instant::now();
+    let check = |ip: &str| {
+        limiter
+            .check_at(&rules, ip.parse().unwrap(), "/", now)
+            .is_ok()
+    };
+    assert!(check("2001:db8:1:2::1"));
+    assert!(!check("2001:db8:1:2:ffff::2"), "same /64 network");
+    assert!(check("2001:db8:1:3::1"));
+    assert!(check("::ffff:1.2.3.4"));
+    assert!(check("::ffff:1.2.3.5"), "IPv4 clients are not grouped");
+}
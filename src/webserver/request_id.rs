@@ -0,0 +1,64 @@
+//! Identifies every request with an id, so that an error reported by a user can be found in the logs,
+//! and matched with the rows that the request wrote in the database with `sqlpage.request_id()`.
+//!
+//! The id is sent back in the `X-Request-Id` response header, written in the access log, and displayed
+//! in error pages. An id set in the `X-Request-Id` header of the request, for instance by a reverse proxy
+//! or by a load balancer, is reused, so that the same id appears in the logs of all the servers.
+
+use actix_web::dev::ServiceRequest;
+use actix_web::{HttpMessage, HttpRequest};
+
+pub(crate) const HEADER: &str = "x-request-id";
+/// Longer ids in requests are replaced by a new one
+const MAX_LENGTH: usize = 200;
+const GENERATED_LENGTH: usize = 20;
+
+/// The id of a request, stored in the request extensions
+#[derive(Debug, Clone)]
+pub(crate) struct RequestId(pub String);
+
+/// Gives an id to the request: the one of its `X-Request-Id` header, or a new random one
+pub(crate) fn assign(req: &ServiceRequest) -> String {
+    let id = req
+        .headers()
+        .get(HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| is_valid(id))
+        .map_or_else(new_id, ToString::to_string);
+    req.extensions_mut().insert(RequestId(id.clone()));
+    id
+}
+
+/// The id given to the request by [`assign`], if any
+pub(crate) fn get(req: &HttpRequest) -> Option<String> {
+    req.extensions()
+        .get::<RequestId>()
+        .map(|RequestId(id)| id.clone())
+}
+
+/// Ids are written in log lines and response headers: they cannot contain spaces or control characters
+fn is_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_LENGTH
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"-_.:/+=@".contains(&b))
+}
+
+fn new_id() -> String {
+    use rand::{distributions::Alphanumeric, Rng};
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(GENERATED_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+#[test]
+fn test_is_valid() {
+    assert!(is_valid("f47ac10b-58cc-4372-a567-0e02b2c3d479"));
+    assert!(!is_valid(""));
+    assert!(!is_valid("abc\ndef"));
+    assert!(!is_valid("abc def"));
+    assert!(!is_valid(&"a".repeat(MAX_LENGTH + 1)));
+}
@@ -0,0 +1,212 @@
+//! Path parameters.
+//!
+//! Files and directories with a name in brackets, like `users/[id].sql` or `blog/[slug]/comments.sql`,
+//! match any single segment of the requested path. The value of the segment is accessible in the file
+//! as a variable with the name in brackets: `$id` or `$slug`.
+//! Files with a literal name are always preferred to files with a name in brackets.
+//!
+//! Only the files in the local web root are considered, not the files stored in the database.
+//!
+//! Requests with the methods `POST`, `PUT`, `PATCH` and `DELETE` are routed to a file with the name
+//! of the method before the extension, like `item.delete.sql`, when it exists, and to `item.sql` otherwise.
+
+use crate::filesystem::FileSystem;
+use actix_web::http::Method;
+use std::path::{Path, PathBuf};
+
+/// Methods that can have their own file, like `item.delete.sql`
+const ROUTED_METHODS: [Method; 4] = [Method::POST, Method::PUT, Method::PATCH, Method::DELETE];
+
+/// The name of the file that handles the given method, like `item.delete.sql` for `item.sql`
+#[must_use]
+pub(crate) fn method_file_name(file_name: &str, method: &Method) -> Option<String> {
+    if !ROUTED_METHODS.contains(method) {
+        return None;
+    }
+    let stem = file_name.strip_suffix(".sql")?;
+    Some(format!(
+        "{stem}.{}.sql",
+        method.as_str().to_ascii_lowercase()
+    ))
+}
+
+/// The method that a file like `item.delete.sql` is reserved to
+#[must_use]
+pub(crate) fn file_method(sql_file: &Path) -> Option<Method> {
+    let stem = sql_file.file_stem()?.to_str()?;
+    let (_, method) = stem.rsplit_once('.')?;
+    ROUTED_METHODS
+        .into_iter()
+        .find(|m| m.as_str().eq_ignore_ascii_case(method))
+}
+
+/// Variables extracted from the path of a request, stored in the request extensions
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PathParams(pub Vec<(String, String)>);
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Route {
+    pub sql_file: PathBuf,
+    pub params: Vec<(String, String)>,
+}
+
+/// Finds the sql file with a name in brackets that matches the given percent-decoded url path.
+///
+/// The last segment of the path can be a file name (`/users/42.sql`), a clean url without
+/// extension (`/users/42`), or empty for the `index.sql` of a directory (`/users/42/`).
+pub(crate) async fn find_route(
+    file_system: &FileSystem,
+    path: &str,
+    method: &Method,
+) -> Option<Route> {
+    let path = path.strip_prefix('/').unwrap_or(path);
+    let (dirs, file) = path.rsplit_once('/').unwrap_or(("", path));
+    let dirs: Vec<&str> = dirs.split('/').filter(|s| !s.is_empty()).collect();
+    // Depth-first search, where literal names are explored before names in brackets
+    let mut candidates = vec![(PathBuf::new(), Vec::new())];
+    while let Some((dir, params)) = candidates.pop() {
+        let depth = dir.components().count();
+        let Ok(entries) = file_system.local_dir_entries(&dir).await else {
+            continue;
+        };
+        let Some(segment) = dirs.get(depth) else {
+            if let Some(route) = match_file(&dir, &entries, file, method, params) {
+                // Without parameters, the file exists and is served without routing
+                return (!route.params.is_empty()).then_some(route);
+            }
+            continue;
+        };
+        for (name, _) in entries.iter().rev().filter(|(_, is_dir)| *is_dir) {
+            if let Some(param) = param_name(name) {
+                let mut params = params.clone();
+                params.push((param.to_string(), (*segment).to_string()));
+                candidates.push((dir.join(name), params));
+            }
+        }
+        if entries
+            .iter()
+            .any(|(name, is_dir)| *is_dir && name == segment)
+        {
+            candidates.push((dir.join(segment), params));
+        }
+    }
+    None
+}
+
+fn match_file(
+    dir: &Path,
+    entries: &[(String, bool)],
+    file: &str,
+    method: &Method,
+    params: Vec<(String, String)>,
+) -> Option<Route> {
+    let is_file = |name: &str| entries.iter().any(|(n, is_dir)| !is_dir && n == name);
+    let literal = if file.is_empty() { "index.sql" } else { file };
+    if Path::new(literal)
+        .extension()
+        .is_some_and(|ext| ext == "sql")
+    {
+        let names = [method_file_name(literal, method), Some(literal.to_string())];
+        if let Some(name) = names.into_iter().flatten().find(|name| is_file(name)) {
+            let sql_file = dir.join(name);
+            return Some(Route { sql_file, params });
+        }
+    }
+    if file.is_empty() {
+        return None;
+    }
+    let param_files = entries
+        .iter()
+        .filter(|(_, is_dir)| !is_dir)
+        .filter_map(|(name, _)| Some((name, name.strip_suffix(".sql")?)));
+    // `[id].delete.sql` is preferred to `[id].sql` for a DELETE request
+    let method_suffix = format!(".{}", method.as_str().to_ascii_lowercase());
+    let method_param_file = param_files
+        .clone()
+        .filter(|_| ROUTED_METHODS.contains(method))
+        .find_map(|(name, stem)| Some((name, param_name(stem.strip_suffix(&method_suffix)?)?)));
+    let (name, param) = method_param_file.or_else(|| {
+        param_files
+            .clone()
+            .find_map(|(name, stem)| Some((name, param_name(stem)?)))
+    })?;
+    let value = file.strip_suffix(".sql").unwrap_or(file);
+    let mut params = params;
+    params.push((param.to_string(), value.to_string()));
+    Some(Route {
+        sql_file: dir.join(name),
+        params,
+    })
+}
+
+fn param_name(file_name: &str) -> Option<&str> {
+    file_name
+        .strip_prefix('[')?
+        .strip_suffix(']')
+        .filter(|name| !name.is_empty())
+}
+
+#[actix_web::test]
+async fn test_find_route() {
+    let root = std::env::temp_dir().join(format!("sqlpage_routing_test_{}", std::process::id()));
+    for file in [
+        "users/[id].sql",
+        "users/new.sql",
+        "users/[id]/index.sql",
+        "blog/[slug]/comments.sql",
+        "blog/archive/comments.sql",
+        "items/[id].sql",
+        "items/[id].delete.sql",
+    ] {
+        let file = root.join(file);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, "").unwrap();
+    }
+    let config = crate::app_config::tests::test_config();
+    let state = crate::AppState::init(&config).await.unwrap();
+    let fs = FileSystem::init(&root, &state.db).await;
+    let route = |sql_file: &str, params: &[(&str, &str)]| {
+        Some(Route {
+            sql_file: PathBuf::from(sql_file),
+            params: params
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                .collect(),
+        })
+    };
+    assert_eq!(
+        find_route(&fs, "/users/42", &Method::GET).await,
+        route("users/[id].sql", &[("id", "42")])
+    );
+    assert_eq!(
+        find_route(&fs, "/users/42.sql", &Method::GET).await,
+        route("users/[id].sql", &[("id", "42")])
+    );
+    assert_eq!(
+        find_route(&fs, "/users/42/", &Method::GET).await,
+        route("users/[id]/index.sql", &[("id", "42")])
+    );
+    assert_eq!(
+        find_route(&fs, "/blog/hello/comments.sql", &Method::GET).await,
+        route("blog/[slug]/comments.sql", &[("slug", "hello")])
+    );
+    // literal names are preferred, and are not routes
+    assert_eq!(find_route(&fs, "/users/new.sql", &Method::GET).await, None);
+    assert_eq!(
+        find_route(&fs, "/blog/archive/comments.sql", &Method::GET).await,
+        None
+    );
+    assert_eq!(
+        find_route(&fs, "/blog/hello/other.sql", &Method::GET).await,
+        None
+    );
+    assert_eq!(
+        find_route(&fs, "/items/7", &Method::DELETE).await,
+        route("items/[id].delete.sql", &[("id", "7")])
+    );
+    assert_eq!(
+        find_route(&fs, "/items/7", &Method::PUT).await,
+        route("items/[id].sql", &[("id", "7")])
+    );
+    std::fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,319 @@
+//! Live pages, with server-sent events.
+//!
+//! When a page is requested with `Accept: text/event-stream`, as done by the `EventSource` of
+//! browsers, its sql file is executed again and again, and the rows of its `sse` components are
+//! sent as events. The file is executed again after the `interval` set in the `sse` component,
+//! or when a notification is received on its postgres `channel`.
+//!
+//! Components such as `authentication` and `redirect` are handled as in normal pages: when they deny
+//! access, a `denied` event is sent and the stream ends, before the next statement is executed.
+//! The notifications of all the channels are received on a single database connection, shared by all
+//! the streams.
+
+use crate::render::{is_header_component, HeaderContext, PageContext, PageOptions};
+use crate::webserver::database::{execute_queries::stream_query_results, DbItem};
+use crate::webserver::http_request_info::{extract_request_info, RequestInfo};
+use crate::{AppState, ParsedSqlFile};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, ContentEncoding};
+use actix_web::rt::time::{sleep, timeout};
+use actix_web::web::{self, Bytes};
+use actix_web::HttpResponse;
+use futures_util::StreamExt;
+use serde_json::Value;
+use sqlx::any::AnyKind;
+use sqlx::postgres::PgListener;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+
+const SSE_COMPONENT: &str = "sse";
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+/// Shorter intervals would make the server execute the page in a busy loop
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_PENDING_EVENTS: usize = 128;
+
+/// Whether the request comes from an `EventSource`
+pub(crate) fn is_event_stream_request(req: &ServiceRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/event-stream"))
+}
+
+pub(crate) async fn serve_events(
+    mut req: ServiceRequest,
+    sql_file: Arc<ParsedSqlFile>,
+) -> actix_web::Result<ServiceResponse> {
+    let app_state = req
+        .app_data::<web::Data<AppState>>()
+        .expect("app_state")
+        .clone()
+        .into_inner();
+    let request_info = extract_request_info(&mut req, Arc::clone(&app_state)).await;
+    let (sender, receiver) = mpsc::channel(MAX_PENDING_EVENTS);
+    actix_web::rt::spawn(async move {
+        let mut stream = EventStream {
+            app_state,
+            sql_file,
+            request_info,
+            sender,
+            listener: None,
+        };
+        if let Err(e) = stream.run().await {
+            log::debug!("Event stream closed: {e:#}");
+        }
+    });
+    let body = tokio_stream::wrappers::ReceiverStream::new(receiver).map(Ok::<_, Infallible>);
+    let response = HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        // Events have to reach the browser as soon as they are sent
+        .insert_header(ContentEncoding::Identity)
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(body);
+    Ok(req.into_response(response))
+}
+
+/// Properties of the `sse` component, that control when the page is executed again
+#[derive(Default)]
+struct EventOptions {
+    interval: Option<Duration>,
+    channel: Option<String>,
+}
+
+struct EventStream {
+    app_state: Arc<AppState>,
+    sql_file: Arc<ParsedSqlFile>,
+    request_info: RequestInfo,
+    sender: mpsc::Sender<Bytes>,
+    /// Notifications of the channel of the `sse` component
+    listener: Option<(String, broadcast::Receiver<()>)>,
+}
+
+impl EventStream {
+    async fn run(&mut self) -> anyhow::Result<()> {
+        loop {
+            let Some(options) = self.send_events().await? else {
+                return Ok(());
+            };
+            let sender = self.sender.clone();
+            tokio::select! {
+                () = sender.closed() => return Ok(()),
+                result = self.wait(&options) => result?,
+            }
+        }
+    }
+
+    /// Executes the sql file, and sends the rows of its `sse` components.
+    /// Returns `None` when the stream was ended because a component denied access.
+    async fn send_events(&mut self) -> anyhow::Result<Option<EventOptions>> {
+        let mut options = EventOptions::default();
+        let mut in_sse_component = false;
+        let mut header = HeaderContext::new(
+            Arc::clone(&self.app_state),
+            self.request_info.session.clone(),
+            Vec::new(),
+            PageOptions::default(),
+        );
+        let mut rows = Box::pin(stream_query_results(
+            &self.app_state.db,
+            &self.sql_file,
+            &mut self.request_info,
+        ));
+        while let Some(item) = rows.next().await {
+            let event = match item {
+                DbItem::Row(row) if is_header_component(&row) => {
+                    match header.handle_row(row).await {
+                        Ok(PageContext::Header(h)) => {
+                            header = h;
+                            continue;
+                        }
+                        Ok(_) => log::info!("Closed an event stream that was denied access"),
+                        Err(e) => log::error!("Error in event stream query: {e:#}"),
+                    }
+                    // Browsers reconnect when a stream ends, unless the page closes it on this event
+                    let error = serde_json::json!({ "error": "Access denied" });
+                    let event = format!("event: denied\ndata: {error}\n\n");
+                    self.sender.send(Bytes::from(event)).await?;
+                    return Ok(None);
+                }
+                DbItem::Row(row) => {
+                    if let Some(component) = row.get("component").and_then(Value::as_str) {
+                        in_sse_component = component == SSE_COMPONENT;
+                        if in_sse_component {
+                            options = event_options(&row);
+                        }
+                        continue;
+                    }
+                    if !in_sse_component {
+                        continue;
+                    }
+                    format!("data: {row}\n\n")
+                }
+                DbItem::FinishedQuery => continue,
+                DbItem::Error(e) => {
+                    log::error!("Error in event stream query: {e:#}");
+                    let error = if self.app_state.config.verbose_errors() {
+                        format!("{e:#}")
+                    } else {
+                        "An error occurred while executing the query".to_string()
+                    };
+                    format!(
+                        "event: error\ndata: {}\n\n",
+                        serde_json::json!({ "error": error })
+                    )
+                }
+            };
+            self.sender.send(Bytes::from(event)).await?;
+        }
+        Ok(Some(options))
+    }
+
+    /// Waits until the page has to be executed again
+    async fn wait(&mut self, options: &EventOptions) -> anyhow::Result<()> {
+        let Some(channel) = &options.channel else {
+            sleep(options.interval.unwrap_or(DEFAULT_INTERVAL)).await;
+            return Ok(());
+        };
+        if self.app_state.db.connection.any_kind() != AnyKind::Postgres {
+            anyhow::bail!("The channel property of the sse component requires a postgres database");
+        }
+        let notifications = match &mut self.listener {
+            Some((current_channel, notifications)) if current_channel == channel => notifications,
+            listener => {
+                let notifications = self
+                    .app_state
+                    .notifications
+                    .subscribe(&self.app_state.config.database_url, channel);
+                &mut listener.insert((channel.clone(), notifications)).1
+            }
+        };
+        let notified = async {
+            match notifications.recv().await {
+                Ok(()) | Err(broadcast::error::RecvError::Lagged(_)) => Ok(()),
+                Err(broadcast::error::RecvError::Closed) => Err(anyhow::anyhow!(
+                    "Stopped listening to the channel {channel}"
+                )),
+            }
+        };
+        match options.interval {
+            Some(interval) => drop(timeout(interval, notified).await),
+            None => notified.await?,
+        }
+        Ok(())
+    }
+}
+
+/// The notifications of the postgres channels used by `sse` components. A single connection listens to
+/// all of them, instead of one connection for each event stream, which could exhaust the connections
+/// that the database accepts.
+#[derive(Default)]
+pub(crate) struct Notifications {
+    channels: Arc<Mutex<HashMap<String, broadcast::Sender<()>>>>,
+    /// Asks the task that owns the connection to listen to a new channel
+    listen: Mutex<Option<mpsc::UnboundedSender<String>>>,
+}
+
+impl Notifications {
+    fn subscribe(&self, database_url: &str, channel: &str) -> broadcast::Receiver<()> {
+        let mut channels = self.channels.lock().expect("notification channels lock");
+        if let Some(sender) = channels.get(channel) {
+            return sender.subscribe();
+        }
+        let (sender, receiver) = broadcast::channel(1);
+        channels.insert(channel.to_string(), sender);
+        drop(channels);
+        let mut listen = self.listen.lock().expect("notification listener lock");
+        let is_running = listen
+            .as_ref()
+            .is_some_and(|listen| listen.send(channel.to_string()).is_ok());
+        if !is_running {
+            let (sender, commands) = mpsc::unbounded_channel();
+            *listen = Some(sender);
+            let channels = Arc::clone(&self.channels);
+            let database_url = database_url.to_string();
+            actix_web::rt::spawn(async move {
+                if let Err(e) = listen_to_channels(&database_url, commands, &channels).await {
+                    log::error!("Unable to receive the notifications of the sse component: {e:#}");
+                }
+                // Ends the streams that wait for notifications, and lets the next one start a new listener
+                channels.lock().expect("notification channels lock").clear();
+            });
+        }
+        receiver
+    }
+}
+
+async fn listen_to_channels(
+    database_url: &str,
+    mut commands: mpsc::UnboundedReceiver<String>,
+    channels: &Mutex<HashMap<String, broadcast::Sender<()>>>,
+) -> anyhow::Result<()> {
+    let mut listener = PgListener::connect(database_url).await?;
+    let initial_channels: Vec<String> = channels
+        .lock()
+        .expect("notification channels lock")
+        .keys()
+        .cloned()
+        .collect();
+    listener
+        .listen_all(initial_channels.iter().map(String::as_str))
+        .await?;
+    loop {
+        tokio::select! {
+            command = commands.recv() => {
+                // The state of the website was dropped
+                let Some(channel) = command else { return Ok(()) };
+                listener.listen(&channel).await?;
+            }
+            notification = listener.recv() => {
+                let channel = notification?.channel().to_string();
+                let is_used = {
+                    let mut channels = channels.lock().expect("notification channels lock");
+                    let is_used = channels.get(&channel).is_some_and(|sender| sender.send(()).is_ok());
+                    if !is_used {
+                        channels.remove(&channel);
+                    }
+                    is_used
+                };
+                if !is_used {
+                    // No stream listens to this channel anymore
+                    listener.unlisten(&channel).await?;
+                }
+            }
+        }
+    }
+}
+
+fn event_options(properties: &Value) -> EventOptions {
+    EventOptions {
+        interval: properties
+            .get("interval")
+            .and_then(|v| v.as_f64().or_else(|| v.as_str()?.parse().ok()))
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+            .map(|interval| interval.max(MIN_INTERVAL)),
+        channel: properties
+            .get("channel")
+            .and_then(Value::as_str)
+            .map(String::from),
+    }
+}
+
+#[test]
+fn test_event_options() {
+    let interval = |value: Value| event_options(&serde_json::json!({ "interval": value })).interval;
+    assert_eq!(
+        interval(serde_json::json!(2.5)),
+        Some(Duration::from_millis(2500))
+    );
+    assert_eq!(
+        interval(serde_json::json!("10")),
+        Some(Duration::from_secs(10))
+    );
+    assert_eq!(interval(serde_json::json!(1e-9)), Some(MIN_INTERVAL));
+    assert_eq!(interval(serde_json::json!(1e300)), None);
+    assert_eq!(interval(serde_json::json!(-1)), None);
+}
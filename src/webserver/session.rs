@@ -0,0 +1,306 @@
+//! Server-side session variables.
+//!
+//! By default, each session variable is stored in its own cookie, named `sqlpage_session_<name>`,
+//! whose value is preceded by its expiration time and followed by an HMAC signature computed with the
+//! server's session key. The signature also covers the session id, stored in the `sqlpage_session` cookie,
+//! so that a variable cannot be replayed after it expires or moved to another session.
+//! Users can read their session variables, but cannot modify them.
+//! When a `session_store` is configured, the variables are stored on the server instead,
+//! and the browser only receives a signed session id in the `sqlpage_session` cookie.
+
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::HttpRequest;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::http_request_info::RequestInfo;
+use crate::app_config::AppConfig;
+use crate::AppState;
+
+const SESSION_COOKIE_PREFIX: &str = "sqlpage_session_";
+/// Cookie containing the signed session id
+const SESSION_ID_COOKIE: &str = "sqlpage_session";
+/// Session variable that identifies the logged-in user
+pub(crate) const SESSION_USER: &str = "user";
+/// Session variable that identifies the user who is impersonating the logged-in user
+pub(crate) const SESSION_IMPERSONATOR: &str = "impersonator";
+
+/// Returns the key used to sign session cookies.
+/// When no `session_secret` is configured, a random key is generated,
+/// and sessions do not survive a server restart.
+pub(crate) fn session_key(config: &AppConfig) -> Vec<u8> {
+    if let Some(secret) = &config.session_secret {
+        return secret.as_bytes().to_vec();
+    }
+    log::info!(
+        "No session_secret configured. Session variables will be lost when the server restarts."
+    );
+    let mut key = vec![0; 32];
+    rand::RngCore::fill_bytes(&mut password_hash::rand_core::OsRng, &mut key);
+    key
+}
+
+pub(super) fn signature(key: &[u8], name: &str, value: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(name.as_bytes());
+    mac.update(b"=");
+    mac.update(value.as_bytes());
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+}
+
+/// Verifies the signature of a cookie value, and returns the signed value
+fn verify<'a>(key: &[u8], name: &str, signed_value: &'a str) -> Option<&'a str> {
+    let (value, sig) = signed_value.rsplit_once('.')?;
+    let expected = signature(key, name, value);
+    constant_time_eq(&expected, sig).then_some(value)
+}
+
+/// Verifies a session variable cookie, and returns the variable value
+/// if it belongs to the given session and has not expired at `now` (in seconds since the epoch)
+fn verify_variable<'a>(
+    key: &[u8],
+    session_id: &str,
+    name: &str,
+    signed_value: &'a str,
+    now: u64,
+) -> Option<&'a str> {
+    let expires_and_value = verify(key, &format!("{session_id}/{name}"), signed_value)?;
+    let (expires, value) = expires_and_value.split_once('.')?;
+    (expires.parse::<u64>().ok()? > now).then_some(value)
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Compares two strings in constant time, to avoid leaking the expected one
+pub(super) fn constant_time_eq(expected: &str, actual: &str) -> bool {
+    expected.len() == actual.len()
+        && expected
+            .bytes()
+            .zip(actual.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// The session variables sent with a request
+#[derive(Debug, Default, Clone)]
+pub struct Session {
+    /// Identifier of the session, which is also its key in the `session_store` when one is configured
+    pub id: Option<String>,
+    pub variables: Map<String, Value>,
+}
+
+/// Reads the session variables of a request, from its cookies or from the session store.
+/// Variables with an invalid signature, from another session or expired are ignored.
+pub(crate) async fn load_session(req: &HttpRequest, app_state: &AppState) -> Session {
+    let key = &app_state.session_key;
+    let Some(id) = session_id(req, app_state) else {
+        return Session::default();
+    };
+    let Some(store) = &app_state.session_store else {
+        let now = unix_time();
+        let cookies = req.cookies();
+        let variables = cookies
+            .iter()
+            .flat_map(|c| c.iter())
+            .filter_map(|cookie| {
+                let name = cookie.name().strip_prefix(SESSION_COOKIE_PREFIX)?;
+                let value = verify_variable(key, &id, name, cookie.value(), now)?;
+                Some((name.to_string(), Value::String(value.to_string())))
+            })
+            .collect();
+        return Session {
+            id: Some(id),
+            variables,
+        };
+    };
+    match store.load(&id).await {
+        Ok(Some(variables)) => Session {
+            id: Some(id),
+            variables,
+        },
+        Ok(None) => Session::default(),
+        Err(e) => {
+            log::error!("Unable to load the session from the session store: {e:#}");
+            Session::default()
+        }
+    }
+}
+
+/// The id of the session of a request, when its signature is valid, without loading its variables
+pub(crate) fn session_id(req: &HttpRequest, app_state: &AppState) -> Option<String> {
+    let cookie = req.cookie(SESSION_ID_COOKIE)?;
+    verify(&app_state.session_key, SESSION_ID_COOKIE, cookie.value()).map(str::to_string)
+}
+
+/// Reads a session variable of the request
+pub(crate) fn get_session_variable<'a>(request: &'a RequestInfo, name: &str) -> Option<&'a str> {
+    request.session.variables.get(name).and_then(Value::as_str)
+}
+
+/// Changes a session variable of the request, for the rest of its execution only.
+/// The new value is sent to the browser by the `session` component.
+pub(crate) fn set_request_session_variable(
+    request: &mut RequestInfo,
+    name: &str,
+    value: Option<&str>,
+) {
+    let variables = &mut request.session.variables;
+    match value {
+        Some(value) => variables.insert(name.to_string(), Value::String(value.to_string())),
+        None => variables.remove(name),
+    };
+}
+
+/// Returns all the session variables of the request
+pub(crate) fn get_session_variables(request: &RequestInfo) -> Map<String, Value> {
+    request.session.variables.clone()
+}
+
+/// Sets (or removes, if `value` is `None`) a session variable, in the session store or in its own cookie.
+/// Returns the cookies to send to the browser.
+pub(crate) async fn set_session_variable(
+    app_state: &AppState,
+    session: &mut Session,
+    name: &str,
+    value: Option<&str>,
+    secure: bool,
+) -> anyhow::Result<Vec<Cookie<'static>>> {
+    match value {
+        Some(value) => session
+            .variables
+            .insert(name.to_string(), Value::String(value.to_string())),
+        None => session.variables.remove(name),
+    };
+    let key = &app_state.session_key;
+    let Some(store) = &app_state.session_store else {
+        let expires = unix_time() + app_state.config.session_max_age_seconds;
+        let Some(id) = session.id.as_deref().filter(|_| name != SESSION_USER) else {
+            // A new session id is given when the user changes, so all the variables are signed again with it
+            let id = session.id.insert(new_session_id());
+            let mut cookies: Vec<_> = session
+                .variables
+                .iter()
+                .map(|(name, value)| session_cookie(key, id, name, value.as_str(), expires, secure))
+                .collect();
+            if value.is_none() {
+                cookies.push(session_cookie(key, id, name, None, expires, secure));
+            }
+            cookies.push(session_id_cookie(key, Some(id), secure));
+            return Ok(cookies);
+        };
+        return Ok(vec![session_cookie(key, id, name, value, expires, secure)]);
+    };
+    // A new session id is given when the user changes, so that an id known before a login is useless after it
+    let mut new_id = session.id.is_none();
+    if name == SESSION_USER {
+        if let Some(old_id) = session.id.take() {
+            store.delete(&old_id).await?;
+            new_id = true;
+        }
+    }
+    let id = session.id.get_or_insert_with(new_session_id);
+    store.save(id, &session.variables).await?;
+    Ok(if new_id {
+        vec![session_id_cookie(key, Some(id.as_str()), secure)]
+    } else {
+        Vec::new()
+    })
+}
+
+/// Removes all the session variables. Returns the cookies to send to the browser.
+pub(crate) async fn destroy_session(
+    app_state: &AppState,
+    session: &mut Session,
+    secure: bool,
+) -> anyhow::Result<Vec<Cookie<'static>>> {
+    let variables = std::mem::take(&mut session.variables);
+    let key = &app_state.session_key;
+    let Some(id) = session.id.take() else {
+        return Ok(Vec::new());
+    };
+    let Some(store) = &app_state.session_store else {
+        let mut cookies: Vec<_> = variables
+            .keys()
+            .map(|name| session_cookie(key, &id, name, None, 0, secure))
+            .collect();
+        cookies.push(session_id_cookie(key, None, secure));
+        return Ok(cookies);
+    };
+    store.delete(&id).await?;
+    Ok(vec![session_id_cookie(key, None, secure)])
+}
+
+fn new_session_id() -> String {
+    let mut id = [0; 32];
+    rand::RngCore::fill_bytes(&mut password_hash::rand_core::OsRng, &mut id);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(id)
+}
+
+/// The cookie that contains the signed id of a server-side session
+fn session_id_cookie(key: &[u8], id: Option<&str>, secure: bool) -> Cookie<'static> {
+    let mut cookie = Cookie::named(SESSION_ID_COOKIE);
+    cookie.set_path("/");
+    cookie.set_http_only(true);
+    cookie.set_secure(secure);
+    cookie.set_same_site(SameSite::Lax);
+    match id {
+        Some(id) => cookie.set_value(format!("{id}.{}", signature(key, SESSION_ID_COOKIE, id))),
+        None => cookie.make_removal(),
+    }
+    cookie
+}
+
+/// Creates the cookie that sets (or removes, if `value` is `None`) a session variable of the session `id`.
+/// The variable is valid until `expires`, in seconds since the epoch.
+fn session_cookie(
+    key: &[u8],
+    id: &str,
+    name: &str,
+    value: Option<&str>,
+    expires: u64,
+    secure: bool,
+) -> Cookie<'static> {
+    let mut cookie = Cookie::named(format!("{SESSION_COOKIE_PREFIX}{name}"));
+    cookie.set_path("/");
+    cookie.set_http_only(true);
+    cookie.set_secure(secure);
+    cookie.set_same_site(SameSite::Lax);
+    match value {
+        Some(value) => {
+            let expires_and_value = format!("{expires}.{value}");
+            let sig = signature(key, &format!("{id}/{name}"), &expires_and_value);
+            cookie.set_value(format!("{expires_and_value}.{sig}"));
+        }
+        None => cookie.make_removal(),
+    }
+    cookie
+}
+
+#[test]
+fn test_session_signature() {
+    let key = b"secret";
+    let cookie = session_cookie(key, "id1", "step", Some("2.5"), 1000, true);
+    let signed = cookie.value();
+    assert_eq!(
+        verify_variable(key, "id1", "step", signed, 999),
+        Some("2.5")
+    );
+    assert_eq!(verify_variable(key, "id1", "step", signed, 1000), None);
+    assert_eq!(verify_variable(key, "id2", "step", signed, 999), None);
+    assert_eq!(verify_variable(key, "id1", "other_var", signed, 999), None);
+    assert_eq!(
+        verify_variable(b"other key", "id1", "step", signed, 999),
+        None
+    );
+    let tampered = signed.replacen("2.5", "3.5", 1);
+    assert_eq!(verify_variable(key, "id1", "step", &tampered, 999), None);
+    let extended = signed.replacen("1000", "9000", 1);
+    assert_eq!(verify_variable(key, "id1", "step", &extended, 999), None);
+}
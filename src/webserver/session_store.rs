@@ -0,0 +1,286 @@
+//! Server-side storage of the session variables, configured with `session_store`.
+//!
+//! The browser only receives a signed session id, and the variables of the session are kept
+//! as a json object in memory, in the `sqlpage_sessions` table of the database, or in Redis.
+//! The `sqlpage_sessions` table is created when the server starts, if it does not exist yet.
+//! Sessions expire when they have not been used for `session_max_age_seconds`.
+
+use anyhow::{bail, Context};
+use serde_json::{Map, Value};
+use sqlx::any::AnyKind;
+use sqlx::Row;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::{make_placeholder, Database};
+use crate::app_config::AppConfig;
+
+/// Above this number of sessions in memory, the expired ones are removed
+const MAX_MEMORY_SESSIONS: usize = 10_000;
+const REDIS_KEY_PREFIX: &str = "sqlpage_session:";
+
+pub(crate) type SessionVariables = Map<String, Value>;
+
+pub(crate) struct SessionStore {
+    max_age: Duration,
+    backend: Backend,
+}
+
+enum Backend {
+    Memory(Mutex<HashMap<String, MemorySession>>),
+    Database(Database),
+    /// Reconnects automatically, and is cheap to clone for each command
+    Redis(redis::aio::ConnectionManager),
+}
+
+struct MemorySession {
+    variables: SessionVariables,
+    expires_at: Instant,
+}
+
+impl SessionStore {
+    /// The store configured in `session_store`, or `None` when session variables are stored in cookies
+    pub(crate) async fn from_config(
+        config: &AppConfig,
+        db: &Database,
+    ) -> anyhow::Result<Option<Self>> {
+        let backend = match config.session_store.as_str() {
+            "cookie" => return Ok(None),
+            "memory" => Backend::Memory(Mutex::default()),
+            "database" => {
+                create_sessions_table(db).await?;
+                Backend::Database(db.clone())
+            }
+            url if url.starts_with("redis://") => Backend::Redis(connect_to_redis(url).await?),
+            other => bail!(
+                "Invalid session_store: {other:?}. Expected cookie, memory, database, or a redis:// url"
+            ),
+        };
+        Ok(Some(SessionStore {
+            max_age: Duration::from_secs(config.session_max_age_seconds),
+            backend,
+        }))
+    }
+
+    pub(crate) fn is_memory(&self) -> bool {
+        matches!(self.backend, Backend::Memory(_))
+    }
+
+    /// Returns the variables of a session, or `None` if it does not exist or has expired.
+    /// Loading a session extends its lifetime.
+    pub(crate) async fn load(&self, id: &str) -> anyhow::Result<Option<SessionVariables>> {
+        match &self.backend {
+            Backend::Memory(sessions) => {
+                let mut sessions = sessions.lock().expect("session store lock poisoned");
+                let now = Instant::now();
+                match sessions.get_mut(id) {
+                    Some(session) if session.expires_at > now => {
+                        session.expires_at = now + self.max_age;
+                        Ok(Some(session.variables.clone()))
+                    }
+                    Some(_) => {
+                        sessions.remove(id);
+                        Ok(None)
+                    }
+                    None => Ok(None),
+                }
+            }
+            Backend::Database(db) => self.load_from_database(db, id).await,
+            Backend::Redis(redis) => {
+                let json: Option<String> = redis::cmd("GETEX")
+                    .arg(format!("{REDIS_KEY_PREFIX}{id}"))
+                    .arg("EX")
+                    .arg(self.max_age.as_secs())
+                    .query_async(&mut redis.clone())
+                    .await
+                    .context("Unable to read the session from redis")?;
+                json.map(|json| serde_json::from_str(&json))
+                    .transpose()
+                    .map_err(Into::into)
+            }
+        }
+    }
+
+    pub(crate) async fn save(&self, id: &str, variables: &SessionVariables) -> anyhow::Result<()> {
+        match &self.backend {
+            Backend::Memory(sessions) => {
+                let mut sessions = sessions.lock().expect("session store lock poisoned");
+                let now = Instant::now();
+                if sessions.len() >= MAX_MEMORY_SESSIONS {
+                    sessions.retain(|_, session| session.expires_at > now);
+                }
+                let session = MemorySession {
+                    variables: variables.clone(),
+                    expires_at: now + self.max_age,
+                };
+                sessions.insert(id.to_string(), session);
+            }
+            Backend::Database(db) => self.save_to_database(db, id, variables).await?,
+            Backend::Redis(redis) => {
+                redis::cmd("SET")
+                    .arg(format!("{REDIS_KEY_PREFIX}{id}"))
+                    .arg(serde_json::to_string(variables)?)
+                    .arg("EX")
+                    .arg(self.max_age.as_secs())
+                    .query_async::<_, ()>(&mut redis.clone())
+                    .await
+                    .context("Unable to save the session in redis")?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn delete(&self, id: &str) -> anyhow::Result<()> {
+        match &self.backend {
+            Backend::Memory(sessions) => {
+                sessions
+                    .lock()
+                    .expect("session store lock poisoned")
+                    .remove(id);
+            }
+            Backend::Database(db) => {
+                let kind = db.connection.any_kind();
+                let sql = format!(
+                    "DELETE FROM sqlpage_sessions WHERE id = {}",
+                    make_placeholder(kind, 1)
+                );
+                sqlx::query(&sql)
+                    .bind(id)
+                    .execute(&db.connection)
+                    .await
+                    .context("Unable to delete the session from the sqlpage_sessions table")?;
+            }
+            Backend::Redis(redis) => {
+                redis::cmd("DEL")
+                    .arg(format!("{REDIS_KEY_PREFIX}{id}"))
+                    .query_async::<_, ()>(&mut redis.clone())
+                    .await
+                    .context("Unable to delete the session from redis")?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn load_from_database(
+        &self,
+        db: &Database,
+        id: &str,
+    ) -> anyhow::Result<Option<SessionVariables>> {
+        let kind = db.connection.any_kind();
+        let sql = format!(
+            "SELECT data, expires_at FROM sqlpage_sessions WHERE id = {}",
+            make_placeholder(kind, 1)
+        );
+        let Some(row) = sqlx::query(&sql)
+            .bind(id)
+            .fetch_optional(&db.connection)
+            .await
+            .context("Unable to read the session from the sqlpage_sessions table")?
+        else {
+            return Ok(None);
+        };
+        let data: String = row.try_get(0)?;
+        let expires_at: i64 = row.try_get(1)?;
+        let now = chrono::Utc::now().timestamp();
+        if expires_at <= now {
+            return Ok(None);
+        }
+        // The expiration date is only pushed back when half of the lifetime of the session has passed,
+        // to avoid writing to the database on every request
+        let max_age = i64::try_from(self.max_age.as_secs()).unwrap_or(i64::MAX);
+        if expires_at - now < max_age / 2 {
+            let sql = format!(
+                "UPDATE sqlpage_sessions SET expires_at = {} WHERE id = {}",
+                make_placeholder(kind, 1),
+                make_placeholder(kind, 2)
+            );
+            sqlx::query(&sql)
+                .bind(now.saturating_add(max_age))
+                .bind(id)
+                .execute(&db.connection)
+                .await
+                .context("Unable to extend the session in the sqlpage_sessions table")?;
+        }
+        Ok(Some(serde_json::from_str(&data)?))
+    }
+
+    async fn save_to_database(
+        &self,
+        db: &Database,
+        id: &str,
+        variables: &SessionVariables,
+    ) -> anyhow::Result<()> {
+        let kind = db.connection.any_kind();
+        let data = serde_json::to_string(variables)?;
+        let now = chrono::Utc::now().timestamp();
+        let max_age = i64::try_from(self.max_age.as_secs()).unwrap_or(i64::MAX);
+        let expires_at = now.saturating_add(max_age);
+        let sql = format!(
+            "UPDATE sqlpage_sessions SET data = {}, expires_at = {} WHERE id = {}",
+            make_placeholder(kind, 1),
+            make_placeholder(kind, 2),
+            make_placeholder(kind, 3)
+        );
+        let updated = sqlx::query(&sql)
+            .bind(&data)
+            .bind(expires_at)
+            .bind(id)
+            .execute(&db.connection)
+            .await
+            .context("Unable to save the session in the sqlpage_sessions table")?;
+        if updated.rows_affected() > 0 {
+            return Ok(());
+        }
+        // New sessions are rare enough to clean up the expired ones at the same time
+        let sql = format!(
+            "DELETE FROM sqlpage_sessions WHERE expires_at <= {}",
+            make_placeholder(kind, 1)
+        );
+        sqlx::query(&sql)
+            .bind(now)
+            .execute(&db.connection)
+            .await
+            .context("Unable to remove the expired sessions from the sqlpage_sessions table")?;
+        let sql = format!(
+            "INSERT INTO sqlpage_sessions (id, data, expires_at) VALUES ({}, {}, {})",
+            make_placeholder(kind, 1),
+            make_placeholder(kind, 2),
+            make_placeholder(kind, 3)
+        );
+        sqlx::query(&sql)
+            .bind(id)
+            .bind(&data)
+            .bind(expires_at)
+            .execute(&db.connection)
+            .await
+            .context("Unable to create the session in the sqlpage_sessions table")?;
+        Ok(())
+    }
+}
+
+async fn connect_to_redis(url: &str) -> anyhow::Result<redis::aio::ConnectionManager> {
+    let client = redis::Client::open(url).with_context(|| format!("Invalid redis url: {url}"))?;
+    client
+        .get_connection_manager()
+        .await
+        .with_context(|| format!("Unable to connect to redis at {url}"))
+}
+
+/// Creates the `sqlpage_sessions` table, unless it already exists
+async fn create_sessions_table(db: &Database) -> anyhow::Result<()> {
+    let columns =
+        "id VARCHAR(64) NOT NULL PRIMARY KEY, data TEXT NOT NULL, expires_at BIGINT NOT NULL";
+    let sql = match db.connection.any_kind() {
+        AnyKind::Mssql => format!(
+            "IF OBJECT_ID('sqlpage_sessions', 'U') IS NULL CREATE TABLE sqlpage_sessions ({})",
+            columns.replace("TEXT", "NVARCHAR(MAX)")
+        ),
+        _ => format!("CREATE TABLE IF NOT EXISTS sqlpage_sessions ({columns})"),
+    };
+    sqlx::query(&sql)
+        .execute(&db.connection)
+        .await
+        .context("Unable to create the sqlpage_sessions table")?;
+    Ok(())
+}
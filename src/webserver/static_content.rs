@@ -42,6 +42,10 @@ pub fn css() -> Resource {
     static_file_endpoint!("sqlpage", "css", "text/css")
 }
 
+pub fn rtl_css() -> Resource {
+    static_file_endpoint!("sqlpage.rtl", "css", "text/css")
+}
+
 pub fn icons() -> Resource {
     static_file_endpoint!("tabler-icons", "svg", "image/svg+xml")
 }
@@ -0,0 +1,356 @@
+//! Serves the files of the website that are not sql files: images, scripts, stylesheets, downloads...
+//!
+//! Files are served with a strong `ETag`, and with their `Last-Modified` date when they are stored on
+//! the local filesystem, so that browsers can revalidate them with conditional requests and get a
+//! `304 Not Modified` response when they did not change. The `ETag` of a local file is derived from its
+//! modification date and size, so that it is computed without reading the file. The `ETag` of a file
+//! stored in the database is a hash of its contents.
+//!
+//! When a precompressed version of a local file exists next to it, like `app.js.br` or `app.js.gz`
+//! for `app.js`, it is sent to the clients that accept its encoding, instead of compressing the file
+//! again for every request.
+
+use crate::AppState;
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header::{
+    self, CacheControl, CacheDirective, ContentRangeSpec, ContentType, ETag, EntityTag, Header,
+    HttpDate, IfModifiedSince, IfNoneMatch, IfRange, LastModified, Range,
+};
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Cache-Control of the files with a hash of their contents in their name, which never change
+const HASHED_FILE_MAX_AGE: u32 = 365 * 24 * 3600;
+
+pub(crate) async fn serve_file(
+    req: &ServiceRequest,
+    path: &str,
+    state: &AppState,
+) -> anyhow::Result<HttpResponse> {
+    let path = Path::new(path.strip_prefix('/').unwrap_or(path));
+    let precompressed = precompressed_file(req, path, state).await;
+    let file_path = precompressed
+        .as_ref()
+        .map_or(path, |(file, _)| file.as_path());
+    let if_none_match = IfNoneMatch::parse(req)
+        .ok()
+        .filter(|h| !matches!(h, IfNoneMatch::Items(etags) if etags.is_empty()));
+    // The last modification date is only known for local files
+    let metadata = state.file_system.local_metadata(file_path).await?;
+    let last_modified = metadata.as_ref().and_then(|m| m.modified().ok());
+    // If-Modified-Since is ignored when If-None-Match is present
+    if let (None, Some(IfModifiedSince(since))) = (&if_none_match, IfModifiedSince::parse(req).ok())
+    {
+        let since = SystemTime::from(since);
+        let modified = if let Some(last_modified) = last_modified {
+            // Dates in http headers have a precision of one second
+            unix_seconds(last_modified) > unix_seconds(since)
+        } else {
+            state
+                .file_system
+                .modified_since(state, file_path, DateTime::<Utc>::from(since), false)
+                .await
+                .with_context(|| format!("Unable to get modification time of file {path:?}"))?
+        };
+        if !modified {
+            return Ok(HttpResponse::NotModified().finish());
+        }
+    }
+    let read_file = || async move {
+        state
+            .file_system
+            .read_file(state, file_path, false)
+            .await
+            .with_context(|| format!("Unable to read file {file_path:?}"))
+    };
+    let (etag, length, contents) = match (&metadata, last_modified) {
+        (Some(metadata), Some(modified)) => {
+            (file_etag(modified, metadata.len()), metadata.len(), None)
+        }
+        _ => {
+            let contents = read_file().await?;
+            (
+                content_etag(&contents),
+                contents.len() as u64,
+                Some(contents),
+            )
+        }
+    };
+    let not_modified = match if_none_match {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(etags)) => etags.iter().any(|e| e.weak_eq(&etag)),
+        None => false,
+    };
+    let mut response = if not_modified {
+        HttpResponse::NotModified()
+    } else {
+        HttpResponse::Ok()
+    };
+    let range = requested_range(req, &etag, last_modified, length);
+    response
+        .insert_header(ETag(etag))
+        .insert_header(cache_control(path, state));
+    if let Some(last_modified) = last_modified {
+        response.insert_header(LastModified(HttpDate::from(last_modified)));
+    }
+    if not_modified {
+        return Ok(response.finish());
+    }
+    let mime = mime_guess::from_path(path).first();
+    if let Some((_, encoding)) = &precompressed {
+        response
+            .insert_header((header::CONTENT_ENCODING, *encoding))
+            .insert_header((header::VARY, "accept-encoding"));
+    } else if mime.as_ref().is_some_and(is_compressed) {
+        // Compressing the file again would only waste time
+        response.insert_header(header::ContentEncoding::Identity);
+    }
+    response
+        .insert_header(mime.map_or_else(ContentType::octet_stream, ContentType))
+        .insert_header((header::ACCEPT_RANGES, "bytes"));
+    let instance_length = Some(length);
+    let body = match range {
+        RequestedRange::Full => match contents {
+            Some(contents) => contents,
+            None => read_file().await?,
+        },
+        RequestedRange::Partial(start, end) => {
+            response
+                .status(StatusCode::PARTIAL_CONTENT)
+                .insert_header((
+                    header::CONTENT_RANGE,
+                    ContentRangeSpec::Bytes {
+                        range: Some((start, end)),
+                        instance_length,
+                    },
+                ))
+                // The range refers to the bytes of the file, not to the bytes of a compressed response
+                .insert_header(header::ContentEncoding::Identity);
+            match contents {
+                Some(contents) => {
+                    let (start, end) = (usize::try_from(start)?, usize::try_from(end)?);
+                    contents[start..=end].to_vec()
+                }
+                // Only the requested bytes of local files are read
+                None => {
+                    state
+                        .file_system
+                        .read_local_file_range(file_path, start, end - start + 1)
+                        .await?
+                }
+            }
+        }
+        RequestedRange::Unsatisfiable => {
+            return Ok(HttpResponse::RangeNotSatisfiable()
+                .insert_header((
+                    header::CONTENT_RANGE,
+                    ContentRangeSpec::Bytes {
+                        range: None,
+                        instance_length,
+                    },
+                ))
+                .finish());
+        }
+    };
+    Ok(response.body(body))
+}
+
+/// The precompressed version of a local file in the encoding preferred by the client, with its encoding.
+/// Requests for a part of a file get the file itself.
+async fn precompressed_file(
+    req: &ServiceRequest,
+    path: &Path,
+    state: &AppState,
+) -> Option<(PathBuf, &'static str)> {
+    if req.headers().contains_key(header::RANGE) {
+        return None;
+    }
+    let accept_encoding = req.headers().get(header::ACCEPT_ENCODING)?.to_str().ok()?;
+    for (encoding, extension) in [("br", "br"), ("gzip", "gz")] {
+        if !accepts_encoding(accept_encoding, encoding) {
+            continue;
+        }
+        let mut file = path.as_os_str().to_owned();
+        file.push(".");
+        file.push(extension);
+        let file = PathBuf::from(file);
+        if matches!(state.file_system.local_metadata(&file).await, Ok(Some(m)) if m.is_file()) {
+            return Some((file, encoding));
+        }
+    }
+    None
+}
+
+/// Whether an `Accept-Encoding` header accepts the given encoding, with a quality above zero
+fn accepts_encoding(accept_encoding: &str, encoding: &str) -> bool {
+    let quality = |name: &str| {
+        accept_encoding.split(',').find_map(|item| {
+            let mut parts = item.split(';').map(str::trim);
+            if !parts.next()?.eq_ignore_ascii_case(name) {
+                return None;
+            }
+            let quality = parts
+                .find_map(|param| param.strip_prefix("q="))
+                .map_or(Some(1.), |q| q.parse::<f32>().ok());
+            Some(quality.unwrap_or(0.))
+        })
+    };
+    quality(encoding)
+        .or_else(|| quality("*"))
+        .is_some_and(|q| q > 0.)
+}
+
+enum RequestedRange {
+    Full,
+    /// First and last bytes of the requested part of the file
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+/// The part of the file requested with a `Range` header. The range is ignored when the file changed
+/// since the client got the first part, according to `If-Range`. Requests for several ranges
+/// are answered with the whole file.
+fn requested_range(
+    req: &ServiceRequest,
+    etag: &EntityTag,
+    last_modified: Option<SystemTime>,
+    length: u64,
+) -> RequestedRange {
+    let Ok(Range::Bytes(ranges)) = Range::parse(req) else {
+        return RequestedRange::Full;
+    };
+    let unchanged = match IfRange::parse(req) {
+        Err(_) => true,
+        Ok(IfRange::EntityTag(expected)) => expected.strong_eq(etag),
+        Ok(IfRange::Date(date)) => last_modified
+            .is_some_and(|modified| unix_seconds(modified) == unix_seconds(date.into())),
+    };
+    match ranges.as_slice() {
+        [range] if unchanged => match range.to_satisfiable_range(length) {
+            Some((start, end)) => RequestedRange::Partial(start, end),
+            None => RequestedRange::Unsatisfiable,
+        },
+        _ => RequestedRange::Full,
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// The `ETag` of a local file, that changes when the file is modified
+fn file_etag(modified: SystemTime, length: u64) -> EntityTag {
+    let modified = modified
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    EntityTag::new_strong(format!("{modified:x}-{length:x}"))
+}
+
+fn content_etag(contents: &[u8]) -> EntityTag {
+    let hash = Sha256::digest(contents);
+    EntityTag::new_strong(hex::encode(&hash[..16]))
+}
+
+fn cache_control(path: &Path, state: &AppState) -> CacheControl {
+    if has_content_hash(path) {
+        CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(HASHED_FILE_MAX_AGE),
+            CacheDirective::Extension("immutable".to_owned(), None),
+        ])
+    } else {
+        CacheControl(vec![CacheDirective::Extension(
+            state.config.static_files_cache_control.clone(),
+            None,
+        )])
+    }
+}
+
+/// Whether the name of the file contains a hash of its contents, like `app.3f2a9c1b.js` or
+/// `app-3f2a9c1b.js`, as generated by javascript bundlers. Such files never change.
+/// A hash has at least 8 hexadecimal digits, with both letters and numbers, so that dates
+/// like `report-20240101.pdf` and words like `my-facade.png` are not mistaken for hashes.
+fn has_content_hash(path: &Path) -> bool {
+    let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+        return false;
+    };
+    stem.rsplit(['.', '-'])
+        .next()
+        .filter(|_| stem.contains(['.', '-']))
+        .is_some_and(|hash| {
+            hash.len() >= 8
+                && hash.bytes().all(|b| b.is_ascii_hexdigit())
+                && hash.bytes().any(|b| b.is_ascii_digit())
+                && hash.bytes().any(|b| b.is_ascii_alphabetic())
+        })
+}
+
+/// Whether files of this type are already compressed. Images and videos are never compressed
+/// by the compression middleware, so they do not need to be listed here.
+fn is_compressed(mime: &mime_guess::Mime) -> bool {
+    match (mime.type_().as_str(), mime.subtype().as_str()) {
+        ("audio", _) | ("font", "woff" | "woff2") => true,
+        ("application", subtype) => {
+            matches!(
+                subtype,
+                "zip"
+                    | "gzip"
+                    | "x-gzip"
+                    | "x-bzip"
+                    | "x-bzip2"
+                    | "x-xz"
+                    | "zstd"
+                    | "x-7z-compressed"
+                    | "vnd.rar"
+                    | "x-rar-compressed"
+                    | "pdf"
+                    | "epub+zip"
+                    | "font-woff"
+            ) || subtype.starts_with("vnd.openxmlformats-officedocument.")
+                || subtype.starts_with("vnd.oasis.opendocument.")
+        }
+        _ => false,
+    }
+}
+
+#[test]
+fn test_is_compressed() {
+    let is_compressed_file =
+        |file: &str| is_compressed(&mime_guess::from_path(file).first().unwrap());
+    assert!(is_compressed_file("archive.zip"));
+    assert!(is_compressed_file("font.woff2"));
+    assert!(is_compressed_file("report.xlsx"));
+    assert!(!is_compressed_file("data.csv"));
+    assert!(!is_compressed_file("script.js"));
+    assert!(!is_compressed_file("page.html"));
+}
+
+#[test]
+fn test_accepts_encoding() {
+    assert!(accepts_encoding("gzip, deflate, br", "br"));
+    assert!(accepts_encoding("GZIP;q=0.5", "gzip"));
+    assert!(accepts_encoding("*", "br"));
+    assert!(!accepts_encoding("gzip, br;q=0", "br"));
+    assert!(!accepts_encoding("identity", "gzip"));
+    assert!(!accepts_encoding("*, br;q=0", "br"));
+}
+
+#[test]
+fn test_has_content_hash() {
+    assert!(has_content_hash(Path::new("assets/app.3f2a9c1b.js")));
+    assert!(has_content_hash(Path::new(
+        "main-5d41402abc4b2a76b9719d911017c592.css"
+    )));
+    assert!(!has_content_hash(Path::new("app.js")));
+    assert!(!has_content_hash(Path::new("deadbeef.js")));
+    assert!(!has_content_hash(Path::new("my-favorite.png")));
+    assert!(!has_content_hash(Path::new("report-20240101.pdf")));
+    assert!(!has_content_hash(Path::new("logo.12345678.png")));
+    assert!(!has_content_hash(Path::new("my-deadbeef.png")));
+}
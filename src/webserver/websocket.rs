@@ -0,0 +1,281 @@
+//! Serves sql files over websockets.
+//!
+//! When a browser opens a websocket connection to a `.sql` file, the file is executed once for each
+//! message it receives. A message containing a json object sets the variables of the same name
+//! (accessible as `:name`, like the fields of a form), and any other message is accessible as
+//! `:message`. Each row returned by the queries is sent back to the browser as a json message.
+//!
+//! Only the files listed in `websocket_paths` accept connections, and only from pages of the website
+//! itself or of the `websocket_allowed_origins`. Components such as `authentication` and `redirect` are
+//! not sent: they close the connection when they deny access, before the next statement is executed.
+
+use crate::render::{is_header_component, HeaderContext, PageContext, PageOptions};
+use crate::webserver::database::{execute_queries::stream_query_results, DbItem};
+use crate::webserver::http::{req_path, SingleOrVec};
+use crate::webserver::http_request_info::{extract_request_info, RequestInfo};
+use crate::{AppState, ParsedSqlFile};
+use actix_http::ws::{self, CloseCode, CloseReason, OpCode, Parser};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, ContentType};
+use actix_web::web::{self, Bytes, BytesMut};
+use actix_web::HttpResponse;
+use futures_util::StreamExt;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Number of outgoing messages that can wait to be sent before the queries are paused
+const MAX_PENDING_MESSAGES: usize = 128;
+
+/// Whether the request asks to open a websocket connection
+pub(crate) fn is_websocket_request(req: &ServiceRequest) -> bool {
+    req.headers()
+        .get(header::UPGRADE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("websocket"))
+}
+
+pub(crate) async fn serve_websocket(
+    mut req: ServiceRequest,
+    sql_file: Arc<ParsedSqlFile>,
+) -> actix_web::Result<ServiceResponse> {
+    ws::verify_handshake(req.head())?;
+    let key = req
+        .headers()
+        .get(header::SEC_WEBSOCKET_KEY)
+        .map(|key| ws::hash_key(key.as_bytes()))
+        .expect("the handshake was verified");
+    let app_state = req
+        .app_data::<web::Data<AppState>>()
+        .expect("app_state")
+        .clone()
+        .into_inner();
+    if !is_allowed_origin(&req, &app_state.config.websocket_allowed_origins) {
+        log::warn!(
+            "Rejected a WebSocket connection to {} from another website",
+            req.path()
+        );
+        return Ok(req.into_response(forbidden(
+            "WebSocket connections are not accepted from this website.",
+        )));
+    }
+    let path = req_path(&req);
+    if !app_state
+        .config
+        .websocket_paths
+        .iter()
+        .any(|p| path.starts_with(p))
+    {
+        return Ok(req.into_response(forbidden(
+            "This page does not accept WebSocket connections. Add it to websocket_paths in the configuration.",
+        )));
+    }
+    let payload = std::mem::replace(req.parts_mut().1, Payload::None);
+    let request_info = extract_request_info(&mut req, Arc::clone(&app_state)).await;
+    let (sender, receiver) = mpsc::channel(MAX_PENDING_MESSAGES);
+    actix_web::rt::spawn(async move {
+        let connection = WebSocketConnection {
+            app_state,
+            sql_file,
+            request_info,
+            sender,
+        };
+        if let Err(e) = connection.run(payload).await {
+            log::debug!("WebSocket connection closed: {e:#}");
+        }
+    });
+    let body = tokio_stream::wrappers::ReceiverStream::new(receiver).map(Ok::<_, Infallible>);
+    let response = HttpResponse::SwitchingProtocols()
+        .upgrade("websocket")
+        .insert_header((header::SEC_WEBSOCKET_ACCEPT, &key[..]))
+        .streaming(body);
+    Ok(req.into_response(response))
+}
+
+fn forbidden(message: &'static str) -> HttpResponse {
+    HttpResponse::Forbidden()
+        .content_type(ContentType::plaintext())
+        .body(message)
+}
+
+/// Browsers send the `Origin` of the page that opens a connection, which prevents other websites from
+/// opening connections with the cookies of their visitors. Clients that are not browsers do not send it.
+fn is_allowed_origin(req: &ServiceRequest, allowed_origins: &[String]) -> bool {
+    let Some(origin) = req.headers().get(header::ORIGIN) else {
+        return true;
+    };
+    let Ok(origin) = origin.to_str() else {
+        return false;
+    };
+    let same_host = origin
+        .split_once("://")
+        .is_some_and(|(_scheme, host)| host.eq_ignore_ascii_case(req.connection_info().host()));
+    same_host
+        || allowed_origins
+            .iter()
+            .any(|allowed| allowed.trim_end_matches('/').eq_ignore_ascii_case(origin))
+}
+
+struct WebSocketConnection {
+    app_state: Arc<AppState>,
+    sql_file: Arc<ParsedSqlFile>,
+    request_info: RequestInfo,
+    sender: mpsc::Sender<Bytes>,
+}
+
+impl WebSocketConnection {
+    async fn run(mut self, mut payload: Payload) -> anyhow::Result<()> {
+        let max_size = self.app_state.config.max_uploaded_file_size;
+        let mut buffer = BytesMut::new();
+        // Contents of a message that is split in several frames
+        let mut fragments: Option<BytesMut> = None;
+        loop {
+            let Some((finished, opcode, data)) = Parser::parse(&mut buffer, true, max_size)? else {
+                match payload.next().await {
+                    Some(chunk) => buffer.extend_from_slice(&chunk?),
+                    None => return Ok(()),
+                }
+                continue;
+            };
+            let data = data.unwrap_or_default();
+            match opcode {
+                OpCode::Text | OpCode::Binary if finished => {
+                    if !self.handle_message(&data).await? {
+                        return Ok(());
+                    }
+                }
+                OpCode::Text | OpCode::Binary => fragments = Some(data),
+                OpCode::Continue => {
+                    let Some(message) = fragments.as_mut() else {
+                        anyhow::bail!("Unexpected continuation frame");
+                    };
+                    if message.len() + data.len() > max_size {
+                        anyhow::bail!("WebSocket message larger than {max_size} bytes");
+                    }
+                    message.extend_from_slice(&data);
+                    if finished {
+                        let message = fragments.take().unwrap_or_default();
+                        if !self.handle_message(&message).await? {
+                            return Ok(());
+                        }
+                    }
+                }
+                OpCode::Ping => send(&self.sender, OpCode::Pong, &data).await?,
+                OpCode::Pong => {}
+                OpCode::Close => {
+                    let reason = Parser::parse_close_payload(&data)
+                        .unwrap_or_else(|| CloseReason::from(CloseCode::Normal));
+                    close(&self.sender, reason).await?;
+                    return Ok(());
+                }
+                OpCode::Bad => anyhow::bail!("Invalid WebSocket frame"),
+            }
+        }
+    }
+
+    /// Executes the sql file with the variables from the message, and sends back the rows.
+    /// Returns `false` when the connection was closed because a component denied access.
+    async fn handle_message(&mut self, message: &[u8]) -> anyhow::Result<bool> {
+        let message = String::from_utf8_lossy(message);
+        log::debug!("Received a WebSocket message: {message}");
+        self.request_info.post_variables = message_variables(&message);
+        let mut header = HeaderContext::new(
+            Arc::clone(&self.app_state),
+            self.request_info.session.clone(),
+            Vec::new(),
+            PageOptions::default(),
+        );
+        let mut rows = Box::pin(stream_query_results(
+            &self.app_state.db,
+            &self.sql_file,
+            &mut self.request_info,
+        ));
+        while let Some(item) = rows.next().await {
+            let json = match item {
+                DbItem::Row(row) if is_header_component(&row) => {
+                    match header.handle_row(row).await {
+                        Ok(PageContext::Header(h)) => {
+                            header = h;
+                            continue;
+                        }
+                        Ok(_) => log::info!("Closed a WebSocket connection that was denied access"),
+                        Err(e) => log::error!("Error in WebSocket query: {e:#}"),
+                    }
+                    let reason = CloseReason {
+                        code: CloseCode::Policy,
+                        description: Some("Access denied".to_string()),
+                    };
+                    close(&self.sender, reason).await?;
+                    return Ok(false);
+                }
+                DbItem::Row(row) => row,
+                DbItem::FinishedQuery => continue,
+                DbItem::Error(e) => {
+                    log::error!("Error in WebSocket query: {e:#}");
+                    let error = if self.app_state.config.verbose_errors() {
+                        format!("{e:#}")
+                    } else {
+                        "An error occurred while executing the query".to_string()
+                    };
+                    serde_json::json!({ "error": error })
+                }
+            };
+            send(&self.sender, OpCode::Text, json.to_string().as_bytes()).await?;
+        }
+        Ok(true)
+    }
+}
+
+async fn send(sender: &mpsc::Sender<Bytes>, opcode: OpCode, data: &[u8]) -> anyhow::Result<()> {
+    let mut frame = BytesMut::new();
+    Parser::write_message(&mut frame, data, opcode, true, false);
+    sender.send(frame.freeze()).await?;
+    Ok(())
+}
+
+async fn close(sender: &mpsc::Sender<Bytes>, reason: CloseReason) -> anyhow::Result<()> {
+    let mut frame = BytesMut::new();
+    Parser::write_close(&mut frame, Some(reason), false);
+    sender.send(frame.freeze()).await?;
+    Ok(())
+}
+
+/// A json object sets the variables of the same name, and anything else sets `message`
+fn message_variables(message: &str) -> crate::webserver::http_request_info::ParamMap {
+    match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(message) {
+        Ok(object) => object
+            .into_iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    serde_json::Value::String(s) => SingleOrVec::Single(s),
+                    serde_json::Value::Array(values) => SingleOrVec::Vec(
+                        values
+                            .into_iter()
+                            .map(|v| match v {
+                                serde_json::Value::String(s) => s,
+                                other => other.to_string(),
+                            })
+                            .collect(),
+                    ),
+                    other => SingleOrVec::Single(other.to_string()),
+                };
+                (name, value)
+            })
+            .collect(),
+        Err(_) => [(
+            "message".to_string(),
+            SingleOrVec::Single(message.to_string()),
+        )]
+        .into(),
+    }
+}
+
+#[test]
+fn test_message_variables() {
+    let vars = message_variables(r#"{"name": "Ann", "age": 42, "tags": ["a", 1]}"#);
+    assert_eq!(vars["name"], SingleOrVec::Single("Ann".into()));
+    assert_eq!(vars["age"], SingleOrVec::Single("42".into()));
+    assert_eq!(vars["tags"], SingleOrVec::Vec(vec!["a".into(), "1".into()]));
+    let vars = message_variables("hello");
+    assert_eq!(vars["message"], SingleOrVec::Single("hello".into()));
+}
@@ -0,0 +1,122 @@
+//! Generates `/robots.txt` and `/.well-known/security.txt` from the `robots_txt` and `security_txt`
+//! configuration options, so that public websites do not need static files for these conventions.
+//!
+//! When an option is not set, the request is handled normally, and an existing file is served.
+
+use actix_web::http::header::{self, ContentType};
+use actix_web::HttpResponse;
+use serde::Deserialize;
+use std::fmt::Write;
+
+use crate::app_config::AppConfig;
+
+#[derive(Debug, Deserialize, PartialEq, Clone, Default)]
+pub struct RobotsTxt {
+    /// Crawlers the rules apply to. Defaults to `*`, all crawlers.
+    #[serde(default = "default_user_agent")]
+    pub user_agent: String,
+    /// Path prefixes crawlers may visit, even inside a disallowed prefix
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Path prefixes crawlers should not visit, such as `/admin/`
+    #[serde(default)]
+    pub disallow: Vec<String>,
+    /// Absolute url of the sitemap of the website
+    pub sitemap: Option<String>,
+}
+
+fn default_user_agent() -> String {
+    "*".to_string()
+}
+
+/// Fields of a security.txt file, described in RFC 9116
+#[derive(Debug, Deserialize, PartialEq, Clone, Default)]
+pub struct SecurityTxt {
+    /// Where to report security issues: `mailto:` or `https://` urls
+    pub contact: Vec<String>,
+    /// Date after which the information should not be trusted, such as `2030-01-01T00:00:00Z`
+    pub expires: String,
+    /// Url of the key to use to encrypt reports
+    pub encryption: Option<String>,
+    /// Url of a page thanking the people who reported issues
+    pub acknowledgments: Option<String>,
+    /// Languages reports can be written in, such as `en, fr`
+    pub preferred_languages: Option<String>,
+    /// Url where this file is published
+    pub canonical: Option<String>,
+    /// Url of the vulnerability disclosure policy
+    pub policy: Option<String>,
+    /// Url of security-related job offers
+    pub hiring: Option<String>,
+}
+
+/// Returns the generated file when the request is for one of the configured well-known files
+pub(crate) fn handle(config: &AppConfig, path: &str) -> Option<HttpResponse> {
+    let body = match path {
+        "/robots.txt" => robots_txt(config.robots_txt.as_ref()?),
+        "/.well-known/security.txt" | "/security.txt" => {
+            security_txt(config.security_txt.as_ref()?)
+        }
+        _ => return None,
+    };
+    Some(
+        HttpResponse::Ok()
+            .content_type(ContentType::plaintext())
+            .insert_header((header::CACHE_CONTROL, "max-age=3600"))
+            .body(body),
+    )
+}
+
+fn robots_txt(robots: &RobotsTxt) -> String {
+    let mut txt = format!("User-agent: {}\n", robots.user_agent);
+    for path in &robots.allow {
+        writeln!(txt, "Allow: {path}").unwrap();
+    }
+    for path in &robots.disallow {
+        writeln!(txt, "Disallow: {path}").unwrap();
+    }
+    if robots.allow.is_empty() && robots.disallow.is_empty() {
+        // An empty Disallow rule allows everything
+        txt.push_str("Disallow:\n");
+    }
+    if let Some(sitemap) = &robots.sitemap {
+        writeln!(txt, "\nSitemap: {sitemap}").unwrap();
+    }
+    txt
+}
+
+fn security_txt(security: &SecurityTxt) -> String {
+    let mut txt = String::new();
+    for contact in &security.contact {
+        writeln!(txt, "Contact: {contact}").unwrap();
+    }
+    writeln!(txt, "Expires: {}", security.expires).unwrap();
+    let optional_fields = [
+        ("Encryption", &security.encryption),
+        ("Acknowledgments", &security.acknowledgments),
+        ("Preferred-Languages", &security.preferred_languages),
+        ("Canonical", &security.canonical),
+        ("Policy", &security.policy),
+        ("Hiring", &security.hiring),
+    ];
+    for (name, value) in optional_fields {
+        if let Some(value) = value {
+            writeln!(txt, "{name}: {value}").unwrap();
+        }
+    }
+    txt
+}
+
+#[test]
+fn test_robots_txt() {
+    let robots = RobotsTxt {
+        user_agent: default_user_agent(),
+        allow: vec!["/admin/public/".into()],
+        disallow: vec!["/admin/".into()],
+        sitemap: Some("https://example.com/sitemap.xml".into()),
+    };
+    assert_eq!(
+        robots_txt(&robots),
+        "User-agent: *\nAllow: /admin/public/\nDisallow: /admin/\n\nSitemap: https://example.com/sitemap.xml\n"
+    );
+}
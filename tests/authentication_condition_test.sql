@@ -0,0 +1,2 @@
+select 'authentication' as component, 'login.sql' as link, $token = 'secret' as condition;
+select 'text' as component, 'Protected content' as contents;
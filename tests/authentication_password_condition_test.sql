@@ -0,0 +1,3 @@
+select 'authentication' as component, 'login.sql' as link,
+    $hash as password_hash, $password as password, true as condition;
+select 'text' as component, 'Protected content' as contents;
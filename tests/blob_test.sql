@@ -0,0 +1 @@
+select 'blob' as component, 'image/png' as content_type, x'89504e470d0a1a0a' as body;
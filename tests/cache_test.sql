@@ -0,0 +1,2 @@
+select 'cache' as component, 60 as ttl;
+select 'text' as component, 'Random: ' || sqlpage.random_string(20) as contents;
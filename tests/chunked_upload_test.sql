@@ -0,0 +1,2 @@
+set path = sqlpage.chunked_upload_path($id);
+select 'text' as component, sqlpage.read_file_as_text($path) as contents;
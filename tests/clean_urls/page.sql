@@ -0,0 +1 @@
+select 'text' as component, 'Clean page' as contents;
@@ -0,0 +1,4 @@
+select 'content_type' as component, 'text/calendar' as type, 'event.ics' as filename;
+select 'shell-empty' as component;
+select 'html' as component, 'BEGIN:VCALENDAR' as html;
+select 'html' as component, 'END:VCALENDAR' as html;
@@ -0,0 +1,4 @@
+select 'cookie' as component, 'preferences' as name, 'dark' as value, 3600 as max_age, '/app' as path, 'lax' as same_site, false as http_only;
+select 'cookie' as component, 'token' as name, 'abc' as value, NULL as http_only, NULL as secure;
+select 'cookie' as component, 'old_session' as name, '/app' as path, true as remove;
+select 'text' as component, 'Cookies set' as contents;
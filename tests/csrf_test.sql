@@ -0,0 +1,3 @@
+select 'form' as component;
+select 'name' as name;
+select 'text' as component, 'Hello, ' || coalesce(:name, 'nobody') || '!' as contents;
@@ -0,0 +1,3 @@
+select 'csv' as component, 'people.csv' as filename, ';' as separator, TRUE as bom;
+select 'Ophir' as name, 'Paris; France' as city, 29 as age;
+select 'Max "the great"' as name, NULL as city, 99 as age;
@@ -0,0 +1 @@
+select 'text' as component, 'user: ' || coalesce(sqlpage.current_user(), 'anonymous') as contents;
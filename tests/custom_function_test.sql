@@ -0,0 +1 @@
+select 'text' as component, sqlpage.greet($name) as contents;
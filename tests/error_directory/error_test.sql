@@ -0,0 +1,2 @@
+select 'text' as component, 'Before the error' as contents;
+select * from table_that_does_not_exist;
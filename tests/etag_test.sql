@@ -0,0 +1 @@
+select 'text' as component, 'Hello, ' || coalesce($name, 'world') || '!' as contents;
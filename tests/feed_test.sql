@@ -0,0 +1,3 @@
+select 'feed' as component, 'My <blog>' as title, 'https://example.com/' as link, 'News & updates' as description, 'en' as language;
+select 'First post' as title, 'https://example.com/first' as link, 'Hello' as description, '2024-01-15 10:30:00' as date;
+select 'Tom & Jerry' as title, 'https://example.com/second' as link, '2024-01-16' as date, 'post-2' as id;
@@ -0,0 +1 @@
+select 'text' as component, 'Requested with ' || sqlpage.protocol() || ' by ' || sqlpage.client_ip() as contents;
@@ -0,0 +1,2 @@
+select 'shell' as component, 'Ignored in fragments' as title;
+select 'text' as component, 'Only the components' as contents;
@@ -0,0 +1,2 @@
+select 'http_header' as component, 'header phase' as "X-Head-Test";
+select 'text' as component, 'Only rendered for GET requests' as contents;
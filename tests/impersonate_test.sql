@@ -0,0 +1,6 @@
+select 'session' as component, 'user' as name, 'alice' as value where $action = 'login';
+select 'impersonate' as component, 'bob' as user, 'support ticket 42' as reason, true as allowed where $action = 'start';
+select 'impersonate' as component, 'bob' as user, false as allowed where $action = 'deny';
+select 'impersonate' as component, true as stop where $action = 'stop';
+select 'text' as component,
+    'user: ' || coalesce(sqlpage.current_user(), '') || ', impersonator: ' || coalesce(sqlpage.impersonator(), '') as contents;
@@ -3,7 +3,13 @@ use actix_web::{
     http::{self, header::ContentType, StatusCode},
     test::{self, TestRequest},
 };
-use sqlpage::{app_config::AppConfig, webserver::http::main_handler, AppState};
+use sqlpage::{
+    app_config::{AppConfig, DevOrProd, TrailingSlash, VirtualHost},
+    webserver::access_log::AccessLogFormat,
+    webserver::http::{create_app, main_handler},
+    webserver::proxy::ProxyRoute,
+    AppState,
+};
 
 #[actix_web::test]
 async fn test_index_ok() {
@@ -109,6 +115,14 @@ async fn test_file_upload() -> actix_web::Result<()> {
         body_str.contains("Hello, world!"),
         "{body_str}\nexpected to contain: Hello, world!"
     );
+    assert!(
+        body_str.contains("Uploaded testfile.txt (text/plain)"),
+        "{body_str}\nexpected to contain the uploaded file name and type"
+    );
+    assert!(
+        body_str.contains("Mime type filter ok"),
+        "{body_str}\nexpected uploaded_file_path to filter by mime type"
+    );
     Ok(())
 }
 
@@ -140,20 +154,1884 @@ async fn test_csv_upload() -> actix_web::Result<()> {
     Ok(())
 }
 
-async fn get_request_to(path: &str) -> actix_web::Result<TestRequest> {
+#[actix_web::test]
+async fn test_csv_upload_as_json() -> actix_web::Result<()> {
+    let req = get_request_to("/tests/upload_csv_json_test.sql")
+        .await?
+        .insert_header(("content-type", "multipart/form-data; boundary=1234567890"))
+        .set_payload(
+            "--1234567890\r\n\
+            Content-Disposition: form-data; name=\"people_file\"; filename=\"people.csv\"\r\n\
+            Content-Type: text/csv\r\n\
+            \r\n\
+            name,age\r\n\
+            Ophir,29\r\n\
+            Max,99\r\n\
+            --1234567890--\r\n",
+        )
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = test::read_body(resp).await;
+    let body_str = String::from_utf8(body.to_vec()).unwrap();
+    assert!(
+        body_str.contains("Max is 99 years old"),
+        "{body_str}\nexpected to contain: Max is 99 years old"
+    );
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_status_code() -> actix_web::Result<()> {
+    let resp = req_path("/tests/status_code_test.sql?status=404").await?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("Record not found"), "{body}");
+
+    let resp = req_path("/tests/status_code_test.sql?status=201").await?;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    let resp = req_path("/tests/status_code_test.sql?status=42").await?;
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_redirect_status() -> actix_web::Result<()> {
+    for (query, expected) in [
+        ("", StatusCode::FOUND),
+        ("?status=303", StatusCode::SEE_OTHER),
+        ("?status=307", StatusCode::TEMPORARY_REDIRECT),
+    ] {
+        let resp = req_path(&format!("/tests/redirect_test.sql{query}")).await?;
+        assert_eq!(resp.status(), expected, "{query}");
+        assert_eq!(resp.headers().get("location").unwrap(), "target.sql");
+        let body = test::read_body(resp).await;
+        assert!(
+            body.is_empty(),
+            "{query}: the rest of the page should not be rendered"
+        );
+    }
+    let resp = req_path("/tests/redirect_test.sql?status=200").await?;
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_authentication_condition() -> actix_web::Result<()> {
+    let resp = req_path("/tests/authentication_condition_test.sql?token=secret").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("Protected content"), "{body}");
+
+    for query in ["", "?token=wrong"] {
+        let resp = req_path(&format!("/tests/authentication_condition_test.sql{query}")).await?;
+        assert_eq!(resp.status(), StatusCode::FOUND, "{query}");
+        assert_eq!(resp.headers().get("location").unwrap(), "login.sql");
+        let body = test::read_body(resp).await;
+        assert!(
+            body.is_empty(),
+            "{query}: no protected content should be sent"
+        );
+    }
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_authentication_password_and_condition() -> actix_web::Result<()> {
+    use password_hash::PasswordHasher;
+    let salt = password_hash::SaltString::generate(&mut password_hash::rand_core::OsRng);
+    let hash = argon2::Argon2::default()
+        .hash_password(b"secret", &salt)
+        .unwrap()
+        .to_string();
+    let hash = percent_encoding::utf8_percent_encode(&hash, percent_encoding::NON_ALPHANUMERIC);
+    // The condition is true, but it does not replace the password check
+    for (password, expected_status) in [
+        ("", StatusCode::FOUND),
+        ("&password=wrong", StatusCode::FOUND),
+        ("&password=secret", StatusCode::OK),
+    ] {
+        let path =
+            format!("/tests/authentication_password_condition_test.sql?hash={hash}{password}");
+        let resp = req_path(&path).await?;
+        assert_eq!(resp.status(), expected_status, "{password}");
+    }
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_cookie_attributes() -> actix_web::Result<()> {
+    let resp = req_path("/tests/cookie_test.sql").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let cookies: Vec<_> = resp
+        .headers()
+        .get_all("set-cookie")
+        .map(|h| h.to_str().unwrap().to_string())
+        .collect();
+    let preferences = cookies
+        .iter()
+        .find(|c| c.starts_with("preferences="))
+        .expect("preferences cookie");
+    for attribute in [
+        "preferences=dark",
+        "Max-Age=3600",
+        "Path=/app",
+        "SameSite=Lax",
+        "Secure",
+    ] {
+        assert!(
+            preferences.contains(attribute),
+            "{preferences} should contain {attribute}"
+        );
+    }
+    assert!(!preferences.contains("HttpOnly"), "{preferences}");
+    let token = cookies
+        .iter()
+        .find(|c| c.starts_with("token="))
+        .expect("token cookie");
+    assert!(
+        token.contains("HttpOnly"),
+        "null keeps the default: {token}"
+    );
+    assert!(token.contains("Secure"), "null keeps the default: {token}");
+    let removed = cookies
+        .iter()
+        .find(|c| c.starts_with("old_session="))
+        .expect("removal cookie");
+    assert!(removed.contains("Path=/app"), "{removed}");
+    assert!(removed.contains("Max-Age=0"), "{removed}");
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_json_component_streams_rows() -> actix_web::Result<()> {
+    let resp = req_path("/tests/json_array_test.sql").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/json"
+    );
+    let body = test::read_body(resp).await;
+    let rows: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        rows,
+        serde_json::json!([{"id": 1, "name": "Ophir"}, {"id": 2, "name": "Max"}])
+    );
+
+    let resp = req_path("/tests/json_array_test.sql?type=jsonlines").await?;
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/x-ndjson"
+    );
+    let body = test::read_body(resp).await;
+    assert_eq!(
+        String::from_utf8_lossy(&body),
+        "{\"id\":1,\"name\":\"Ophir\"}\n{\"id\":2,\"name\":\"Max\"}\n"
+    );
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_csv_download() -> actix_web::Result<()> {
+    let resp = req_path("/tests/csv_download_test.sql").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get("content-disposition").unwrap(),
+        "attachment; filename=\"people.csv\""
+    );
+    let body = test::read_body(resp).await;
+    assert_eq!(
+        String::from_utf8_lossy(&body),
+        "\u{feff}name;city;age\r\n\
+        Ophir;\"Paris; France\";29\r\n\
+        \"Max \"\"the great\"\"\";;99\r\n"
+    );
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_feed() -> actix_web::Result<()> {
+    let resp = req_path("/tests/feed_test.sql").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/rss+xml; charset=utf-8"
+    );
+    let body = test::read_body(resp).await;
+    assert_eq!(
+        String::from_utf8_lossy(&body),
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <rss version=\"2.0\"><channel><title>My &lt;blog&gt;</title>\
+        <link>https://example.com/</link><description>News &amp; updates</description>\
+        <language>en</language>\n\
+        <item><title>First post</title><link>https://example.com/first</link>\
+        <description>Hello</description>\
+        <guid isPermaLink=\"true\">https://example.com/first</guid>\
+        <pubDate>Mon, 15 Jan 2024 10:30:00 +0000</pubDate></item>\n\
+        <item><title>Tom &amp; Jerry</title><link>https://example.com/second</link>\
+        <guid isPermaLink=\"false\">post-2</guid>\
+        <pubDate>Tue, 16 Jan 2024 00:00:00 +0000</pubDate></item>\n\
+        </channel></rss>\n"
+    );
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_blob() -> actix_web::Result<()> {
+    let resp = req_path("/tests/blob_test.sql").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("content-type").unwrap(), "image/png");
+    let body = test::read_body(resp).await;
+    assert_eq!(&body[..], b"\x89PNG\r\n\x1a\n");
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_content_type_override() -> actix_web::Result<()> {
+    let resp = req_path("/tests/content_type_test.sql").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/calendar; charset=utf-8"
+    );
+    assert_eq!(
+        resp.headers().get("content-disposition").unwrap(),
+        "attachment; filename=\"event.ics\""
+    );
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.trim().starts_with("BEGIN:VCALENDAR"), "{body}");
+    assert!(body.trim().ends_with("END:VCALENDAR"), "{body}");
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_pdf() -> actix_web::Result<()> {
+    // The test configuration uses `cat` as a pdf converter, so the body is the html page sent to it
+    let resp = req_path("/tests/pdf_test.sql").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/pdf"
+    );
+    assert_eq!(
+        resp.headers().get("content-disposition").unwrap(),
+        "inline; filename=\"invoice.pdf\""
+    );
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    let expected_setup = "<base href=\"http://111.111.111.111:1/\">\
+        <style>@page{size:A4 landscape;margin:1.5cm;\
+        @top-center{content:\"Invoice 42\";}\
+        @bottom-center{content:\"Page \" counter(page) \" of \" counter(pages) \"\";}}</style></head>";
+    assert!(body.contains(expected_setup), "{body}");
+    assert!(body.contains("Total: 42 €"), "{body}");
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_pdf_conversion_error() -> actix_web::Result<()> {
     init_log();
-    let config = test_config();
+    let mut config = test_config();
+    config.pdf_converter = Some("false".into());
+    let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    let req = test::TestRequest::get()
+        .uri("/tests/pdf_test.sql")
+        .app_data(data)
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    // The client does not receive an empty pdf document
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_ne!(
+        resp.headers().get("content-type").unwrap(),
+        "application/pdf"
+    );
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_fragment_rendering() -> actix_web::Result<()> {
+    let full_page = req_path("/tests/fragment_test.sql").await?;
+    let body = test::read_body(full_page).await;
+    assert!(body.starts_with(b"<!DOCTYPE html>"));
+
+    let embed = req_path("/tests/fragment_test.sql?_sqlpage_embed").await?;
+    let htmx = get_request_to("/tests/fragment_test.sql")
+        .await?
+        .insert_header(("HX-Request", "true"))
+        .to_srv_request();
+    for resp in [embed, main_handler(htmx).await?] {
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains("Only the components"), "{body}");
+        assert!(!body.contains("<html"), "{body}");
+        assert!(!body.contains("Ignored in fragments"), "{body}");
+    }
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_flush_disables_buffering() -> actix_web::Result<()> {
+    let resp = req_path("/tests/sql_test_files/it_works_flush.sql").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("content-encoding").unwrap(), "identity");
+    assert_eq!(resp.headers().get("x-accel-buffering").unwrap(), "no");
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_custom_error_component() -> actix_web::Result<()> {
+    init_log();
+    // Errors in errors/ are displayed with sqlpage/templates/errors/error.handlebars
+    let web_root = std::env::temp_dir().join(format!(
+        "sqlpage_test_error_component_{}",
+        std::process::id()
+    ));
+    let template_dir = web_root.join("sqlpage/templates/errors");
+    std::fs::create_dir_all(&template_dir)?;
+    std::fs::write(
+        template_dir.join("error.handlebars"),
+        "<p>Custom error: {{description}}</p>",
+    )?;
+    std::fs::create_dir_all(web_root.join("errors"))?;
+    std::fs::copy(
+        "tests/error_directory/error_test.sql",
+        web_root.join("errors/body_error.sql"),
+    )?;
+    std::fs::write(
+        web_root.join("errors/header_error.sql"),
+        "select * from table_that_does_not_exist;",
+    )?;
+    let mut config = test_config();
+    config.web_root = web_root.clone();
+    let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    for (path, expected_status) in [
+        ("/errors/body_error.sql", StatusCode::OK),
+        // Errors before the body of the page is started use the same component
+        (
+            "/errors/header_error.sql",
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ),
+    ] {
+        let req = test::TestRequest::get()
+            .uri(path)
+            .app_data(data.clone())
+            .to_srv_request();
+        let resp = main_handler(req).await?;
+        assert_eq!(resp.status(), expected_status, "{path}");
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains("Custom error: "), "{path}: {body}");
+        assert!(body.contains("table_that_does_not_exist"), "{path}: {body}");
+    }
+    std::fs::remove_dir_all(web_root)?;
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_hidden_error_details() -> actix_web::Result<()> {
+    let mut config = test_config();
+    config.verbose_errors = Some(false);
     let state = AppState::init(&config).await.unwrap();
-    let data = actix_web::web::Data::new(state);
-    Ok(test::TestRequest::get().uri(path).app_data(data))
+    let req = test::TestRequest::get()
+        .uri("/tests/error_directory/error_test.sql")
+        .app_data(actix_web::web::Data::new(state))
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("Please contact the administrator"), "{body}");
+    assert!(!body.contains("table_that_does_not_exist"), "{body}");
+    Ok(())
 }
 
-async fn req_path(path: &str) -> Result<actix_web::dev::ServiceResponse, actix_web::Error> {
-    let req = get_request_to(path)
+#[actix_web::test]
+async fn test_accessibility_audit() -> actix_web::Result<()> {
+    let mut config = test_config();
+    config.accessibility_audit = true;
+    for (environment, expected) in [
+        (DevOrProd::Development, true),
+        (DevOrProd::Production, false),
+    ] {
+        config.environment = environment;
+        let state = AppState::init(&config).await.unwrap();
+        let req = test::TestRequest::get()
+            .uri("/tests/sql_test_files/it_works_icon.sql")
+            .app_data(actix_web::web::Data::new(state))
+            .to_srv_request();
+        let resp = main_handler(req).await?;
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert_eq!(
+            body.contains("axe.min.js"),
+            expected,
+            "{environment:?}: {body}"
+        );
+        assert_eq!(body.contains("data-sqlpage-accessibility-audit"), expected);
+    }
+    Ok(())
+}
+
+/// A WebSocket handshake that sends the given messages, and then closes the connection
+fn websocket_request(path: &str, messages: &[&str]) -> TestRequest {
+    use actix_http::ws::{OpCode, Parser};
+    let mut frames = actix_web::web::BytesMut::new();
+    for message in messages {
+        Parser::write_message(&mut frames, *message, OpCode::Text, true, true);
+    }
+    Parser::write_close(&mut frames, None, true);
+    test::TestRequest::get()
+        .uri(path)
+        .insert_header(("Upgrade", "websocket"))
+        .insert_header(("Connection", "Upgrade"))
+        .insert_header(("Sec-WebSocket-Version", "13"))
+        .insert_header(("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="))
+        .set_payload(frames.freeze())
+}
+
+async fn websocket_messages(
+    resp: actix_web::dev::ServiceResponse,
+) -> Vec<(actix_http::ws::OpCode, Vec<u8>)> {
+    let mut body = actix_web::web::BytesMut::from(&test::read_body(resp).await[..]);
+    let mut messages = Vec::new();
+    while let Some((_, opcode, data)) =
+        actix_http::ws::Parser::parse(&mut body, false, 1 << 20).unwrap()
+    {
+        messages.push((opcode, data.unwrap_or_default().to_vec()));
+    }
+    messages
+}
+
+async fn websocket_app_data() -> actix_web::web::Data<AppState> {
+    init_log();
+    let mut config = test_config();
+    config.websocket_paths = vec!["/tests/websocket_".into()];
+    config.websocket_allowed_origins = vec!["https://allowed.example.com".into()];
+    actix_web::web::Data::new(AppState::init(&config).await.unwrap())
+}
+
+#[actix_web::test]
+async fn test_websocket() -> actix_web::Result<()> {
+    use actix_http::ws::OpCode;
+    let data = websocket_app_data().await;
+    let req = websocket_request(
+        "/tests/websocket_test.sql?room=lobby",
+        &[r#"{"name": "Ann"}"#, "hello"],
+    )
+    .app_data(data)
+    .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::SWITCHING_PROTOCOLS);
+    assert_eq!(
+        resp.headers().get("sec-websocket-accept").unwrap(),
+        "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+    );
+    assert_eq!(
+        websocket_messages(resp).await,
+        [
+            (
+                OpCode::Text,
+                br#"{"name":"Ann","message":null,"room":"lobby"}"#.to_vec()
+            ),
+            (
+                OpCode::Text,
+                br#"{"name":null,"message":"hello","room":"lobby"}"#.to_vec()
+            ),
+            // Normal closure
+            (OpCode::Close, 1000u16.to_be_bytes().to_vec()),
+        ]
+    );
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_websocket_origin_and_paths() -> actix_web::Result<()> {
+    let data = websocket_app_data().await;
+    for (path, origin, expected) in [
+        (
+            "/tests/websocket_test.sql",
+            "http://localhost:8080",
+            StatusCode::SWITCHING_PROTOCOLS,
+        ),
+        (
+            "/tests/websocket_test.sql",
+            "https://allowed.example.com",
+            StatusCode::SWITCHING_PROTOCOLS,
+        ),
+        (
+            "/tests/websocket_test.sql",
+            "https://evil.example.com",
+            StatusCode::FORBIDDEN,
+        ),
+        // Files that are not listed in websocket_paths do not accept connections
+        (
+            "/tests/sql_test_files/it_works_alert_toast.sql",
+            "http://localhost:8080",
+            StatusCode::FORBIDDEN,
+        ),
+    ] {
+        let req = websocket_request(path, &[])
+            .insert_header(("Host", "localhost:8080"))
+            .insert_header(("Origin", origin))
+            .app_data(data.clone())
+            .to_srv_request();
+        let resp = main_handler(req).await?;
+        assert_eq!(resp.status(), expected, "{path} from {origin}");
+    }
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_websocket_authentication() -> actix_web::Result<()> {
+    use actix_http::ws::OpCode;
+    let data = websocket_app_data().await;
+    let req = websocket_request(
+        "/tests/websocket_auth_test.sql",
+        &[
+            r#"{"token": "secret"}"#,
+            r#"{"token": "wrong"}"#,
+            r#"{"token": "secret"}"#,
+        ],
+    )
+    .app_data(data)
+    .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::SWITCHING_PROTOCOLS);
+    let mut access_denied = 1008u16.to_be_bytes().to_vec();
+    access_denied.extend_from_slice(b"Access denied");
+    // The authentication row is not sent, and the connection is closed when it fails
+    assert_eq!(
+        websocket_messages(resp).await,
+        [
+            (OpCode::Text, br#"{"text":"Secret message"}"#.to_vec()),
+            (OpCode::Close, access_denied),
+        ]
+    );
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_server_sent_events() -> actix_web::Result<()> {
+    let req = get_request_to("/tests/sse_test.sql?name=Ann")
         .await?
-        .insert_header(ContentType::plaintext())
+        .insert_header(("Accept", "text/event-stream"))
         .to_srv_request();
-    main_handler(req).await
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/event-stream"
+    );
+    // The stream stays open: only read the events of the first execution of the page
+    let mut body = resp.into_body();
+    let first_event =
+        futures_util::future::poll_fn(|cx| std::pin::Pin::new(&mut body).poll_next(cx)).await;
+    assert_eq!(
+        first_event.unwrap()?,
+        "data: {\"id\":\"status\",\"contents\":\"Live: Ann\"}\n\n"
+    );
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_server_sent_events_authentication() -> actix_web::Result<()> {
+    let req = get_request_to("/tests/sse_auth_test.sql")
+        .await?
+        .insert_header(("Accept", "text/event-stream"))
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    // The events are not sent, and the stream ends
+    let body = test::read_body(resp).await;
+    assert_eq!(
+        body,
+        "event: denied\ndata: {\"error\":\"Access denied\"}\n\n"
+    );
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_trusted_proxies() -> actix_web::Result<()> {
+    for (peer_addr, expected) in [
+        // The default trusted proxies are the loopback addresses
+        ("127.0.0.1:1234", "Requested with https by 1.2.3.4"),
+        ("5.6.7.8:1234", "Requested with http by 5.6.7.8"),
+    ] {
+        let req = get_request_to("/tests/forwarded_test.sql")
+            .await?
+            .peer_addr(peer_addr.parse().unwrap())
+            .insert_header(("X-Forwarded-For", "6.6.6.6, 1.2.3.4"))
+            .insert_header(("X-Forwarded-Proto", "https"))
+            .to_srv_request();
+        let resp = main_handler(req).await?;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = test::read_body(resp).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(expected), "from {peer_addr}: {body}");
+    }
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_rate_limit_decoded_path() -> actix_web::Result<()> {
+    init_log();
+    let mut config = test_config();
+    config.rate_limits =
+        serde_json::from_str(r#"[{"path_prefix": "/api/", "requests_per_minute": 1}]"#).unwrap();
+    let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    for (path, expected_status) in [
+        ("/api/login.sql", StatusCode::NOT_FOUND),
+        // The rule applies to the decoded path, that designates the same file
+        ("/%61pi/login.sql", StatusCode::TOO_MANY_REQUESTS),
+    ] {
+        let req = test::TestRequest::get()
+            .uri(path)
+            .peer_addr("5.6.7.8:1234".parse().unwrap())
+            .app_data(data.clone());
+        let status = match main_handler(req.to_srv_request()).await {
+            Ok(resp) => resp.status(),
+            Err(err) => err.as_response_error().status_code(),
+        };
+        assert_eq!(status, expected_status, "{path}");
+    }
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_ip_restrictions() -> actix_web::Result<()> {
+    init_log();
+    let mut config = test_config();
+    config.ip_restrictions = serde_json::from_str(
+        r#"[
+            {"path_prefix": "/tests/", "allow": ["10.0.0.0/8"], "denied_page": "/tests/ip_denied_test.sql"},
+            {"path_prefix": "/tests/", "deny": ["10.6.6.6"]}
+        ]"#,
+    )
+    .unwrap();
+    let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    for (peer_addr, forwarded_for, expected_status, expected_body) in [
+        ("10.1.2.3:1234", None, StatusCode::OK, "by 10.1.2.3"),
+        (
+            "5.6.7.8:1234",
+            None,
+            StatusCode::FORBIDDEN,
+            "not allowed from 5.6.7.8",
+        ),
+        (
+            "10.6.6.6:1234",
+            None,
+            StatusCode::FORBIDDEN,
+            "Access denied.",
+        ),
+        // The address given by a trusted proxy is checked, not the address of the proxy
+        (
+            "127.0.0.1:1234",
+            Some("10.1.2.3"),
+            StatusCode::OK,
+            "by 10.1.2.3",
+        ),
+        (
+            "127.0.0.1:1234",
+            Some("5.6.7.8"),
+            StatusCode::FORBIDDEN,
+            "not allowed",
+        ),
+        // Forwarded addresses are ignored when the request does not come from a trusted proxy
+        (
+            "5.6.7.8:1234",
+            Some("10.1.2.3"),
+            StatusCode::FORBIDDEN,
+            "not allowed",
+        ),
+    ] {
+        let mut req = test::TestRequest::get()
+            .uri("/tests/forwarded_test.sql")
+            .peer_addr(peer_addr.parse().unwrap())
+            .app_data(data.clone());
+        if let Some(forwarded_for) = forwarded_for {
+            req = req.insert_header(("X-Forwarded-For", forwarded_for));
+        }
+        let resp = main_handler(req.to_srv_request()).await?;
+        assert_eq!(resp.status(), expected_status, "from {peer_addr}");
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains(expected_body), "from {peer_addr}: {body}");
+    }
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_maintenance_mode() -> actix_web::Result<()> {
+    init_log();
+    let mut config = test_config();
+    config.maintenance = serde_json::from_str(
+        r#"{"enabled": true, "allow": ["10.0.0.0/8"], "allowed_paths": ["/tests/forwarded_test.sql"], "retry_after": 60}"#,
+    )
+    .unwrap();
+    let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    for (peer_addr, path, expected_status) in [
+        ("5.6.7.8:1234", "/", StatusCode::SERVICE_UNAVAILABLE),
+        ("5.6.7.8:1234", "/tests/forwarded_test.sql", StatusCode::OK),
+        ("10.1.2.3:1234", "/", StatusCode::OK),
+    ] {
+        let req = test::TestRequest::get()
+            .uri(path)
+            .peer_addr(peer_addr.parse().unwrap())
+            .app_data(data.clone())
+            .to_srv_request();
+        let resp = main_handler(req).await?;
+        assert_eq!(resp.status(), expected_status, "{path} from {peer_addr}");
+        if expected_status == StatusCode::SERVICE_UNAVAILABLE {
+            assert_eq!(resp.headers()[http::header::RETRY_AFTER], "60");
+            let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+            assert!(body.contains("Maintenance in progress"), "{body}");
+        }
+    }
+
+    // Maintenance mode is also on while the switch file exists
+    let switch_file = std::env::temp_dir().join("sqlpage_test_maintenance_switch");
+    std::fs::write(&switch_file, "").unwrap();
+    let mut config = test_config();
+    config.maintenance.switch_file = Some(switch_file.clone());
+    let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    let req = test::TestRequest::get()
+        .uri("/")
+        .app_data(data)
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    std::fs::remove_file(&switch_file).unwrap();
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_path_params() -> actix_web::Result<()> {
+    for path in [
+        "/tests/path_params/Ann?page=1",
+        "/tests/path_params/Ann.sql?page=1",
+        "/tests/path_params/Ann?page=1&name=Bob",
+    ] {
+        let resp = req_path(path).await?;
+        assert_eq!(resp.status(), StatusCode::OK, "{path}");
+        let body = test::read_body(resp).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("Hello, Ann (1)"), "{path}: {body}");
+    }
+    let resp = req_path("/tests/path_params/Ann/other.sql").await;
+    assert_eq!(
+        resp.map_or_else(|e| e.as_response_error().status_code(), |r| r.status()),
+        StatusCode::NOT_FOUND
+    );
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_clean_urls() -> actix_web::Result<()> {
+    let mut config = test_config();
+    config.clean_urls = true;
+    config.trailing_slash = TrailingSlash::Remove;
+    config.index_files = vec!["index.sql".into(), "index.html".into()];
+    let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    let get = |path: &str| {
+        test::TestRequest::get()
+            .uri(path)
+            .app_data(data.clone())
+            .to_srv_request()
+    };
+    for (path, location) in [
+        (
+            "/tests/clean_urls/page.sql?x=1",
+            "/tests/clean_urls/page?x=1",
+        ),
+        ("/tests/clean_urls/docs/", "/tests/clean_urls/docs"),
+    ] {
+        let resp = main_handler(get(path)).await?;
+        assert_eq!(resp.status(), StatusCode::MOVED_PERMANENTLY, "{path}");
+        assert_eq!(resp.headers().get("location").unwrap(), location, "{path}");
+    }
+    for (path, expected) in [
+        ("/tests/clean_urls/page?x=1", "Clean page"),
+        ("/tests/clean_urls/docs", "Documentation index"),
+    ] {
+        let resp = main_handler(get(path)).await?;
+        assert_eq!(resp.status(), StatusCode::OK, "{path}");
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains(expected), "{path}: {body}");
+    }
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_method_routing() -> actix_web::Result<()> {
+    use actix_web::http::Method;
+    for (method, path, expected) in [
+        (
+            Method::GET,
+            "/tests/method_routing/item.sql",
+            "item.sql for GET",
+        ),
+        (
+            Method::PUT,
+            "/tests/method_routing/item.sql",
+            "item.sql for PUT",
+        ),
+        (
+            Method::DELETE,
+            "/tests/method_routing/item.sql",
+            "item.delete.sql",
+        ),
+        (
+            Method::DELETE,
+            "/tests/method_routing/item.delete.sql",
+            "item.delete.sql",
+        ),
+    ] {
+        let req = get_request_to(path)
+            .await?
+            .method(method.clone())
+            .to_srv_request();
+        let resp = main_handler(req).await?;
+        assert_eq!(resp.status(), StatusCode::OK, "{method} {path}");
+        let body = test::read_body(resp).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(expected), "{method} {path}: {body}");
+    }
+    // Files named after a method only handle that method
+    let req = get_request_to("/tests/method_routing/item.delete.sql")
+        .await?
+        .to_srv_request();
+    let err = main_handler(req).await.unwrap_err();
+    assert_eq!(
+        err.as_response_error().status_code(),
+        StatusCode::METHOD_NOT_ALLOWED
+    );
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_head_and_options() -> actix_web::Result<()> {
+    use actix_web::http::Method;
+    let req = get_request_to("/tests/head_test.sql")
+        .await?
+        .method(Method::HEAD)
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("x-head-test").unwrap(), "header phase");
+    for (path, allow) in [
+        (
+            "/tests/method_routing/item.sql",
+            "OPTIONS, GET, HEAD, POST, PUT, PATCH, DELETE",
+        ),
+        ("/tests/method_routing/item.delete.sql", "OPTIONS, DELETE"),
+    ] {
+        let req = get_request_to(path)
+            .await?
+            .method(Method::OPTIONS)
+            .to_srv_request();
+        let resp = main_handler(req).await?;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT, "{path}");
+        assert_eq!(resp.headers().get("allow").unwrap(), allow, "{path}");
+    }
+    let req = get_request_to("/tests/does_not_exist.sql")
+        .await?
+        .method(Method::OPTIONS)
+        .to_srv_request();
+    let err = main_handler(req).await.unwrap_err();
+    assert_eq!(err.as_response_error().status_code(), StatusCode::NOT_FOUND);
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_directory_listing() -> actix_web::Result<()> {
+    let mut config = test_config();
+    config.directory_listing_paths = vec!["/tests/clean_urls/".into()];
+    let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    let req = test::TestRequest::get()
+        .uri("/tests/clean_urls/")
+        .app_data(data)
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("Index of /tests/clean_urls/"), "{body}");
+    assert!(body.contains("href=\"/tests/\""), "{body}");
+    assert!(body.contains("href=\"/tests/clean_urls/docs/\""), "{body}");
+    assert!(
+        body.contains("href=\"/tests/clean_urls/page.sql\""),
+        "{body}"
+    );
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_precompressed_static_files() -> actix_web::Result<()> {
+    let req = get_request_to("/tests/precompressed/app.js")
+        .await?
+        .insert_header(("Accept-Encoding", "br, gzip"))
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("content-encoding").unwrap(), "gzip");
+    assert_eq!(resp.headers().get("vary").unwrap(), "accept-encoding");
+    let content_type = resp
+        .headers()
+        .get("content-type")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert!(content_type.ends_with("javascript"), "{content_type}");
+    let body = test::read_body(resp).await;
+    assert_eq!(body, std::fs::read("tests/precompressed/app.js.gz")?);
+
+    let req = get_request_to("/tests/precompressed/app.js")
+        .await?
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert!(resp.headers().get("content-encoding").is_none());
+    let body = test::read_body(resp).await;
+    assert_eq!(body, "console.log(\"It works!\");\n");
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_chunked_upload() -> actix_web::Result<()> {
+    use actix_web::http::Method;
+    let mut config = test_config();
+    config.max_chunked_upload_size = Some(100);
+    let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    let request = |method: Method, uri: &str| {
+        test::TestRequest::default()
+            .method(method)
+            .uri(uri)
+            .app_data(data.clone())
+    };
+    let json_body = |resp: actix_web::dev::ServiceResponse| async move {
+        let body = test::read_body(resp).await;
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+    };
+
+    let req = request(Method::POST, "/sqlpage/uploads")
+        .insert_header(("Upload-Length", "1000"))
+        .to_srv_request();
+    let err = main_handler(req).await.unwrap_err();
+    assert_eq!(
+        err.as_response_error().status_code(),
+        StatusCode::PAYLOAD_TOO_LARGE
+    );
+
+    let req = request(Method::POST, "/sqlpage/uploads")
+        .insert_header(("Upload-Length", "11"))
+        .insert_header(("Upload-Filename", "hello%20world.txt"))
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let status = json_body(resp).await;
+    assert_eq!(status["file_name"], "hello world.txt");
+    assert_eq!(status["offset"], 0);
+    let upload = format!("/sqlpage/uploads/{}", status["id"].as_str().unwrap());
+
+    for (offset, part, expected_status, expected_offset) in [
+        ("0", "hello ", StatusCode::OK, 6),
+        // Parts that do not start where the previous one ended are refused
+        ("0", "hello ", StatusCode::CONFLICT, 6),
+        ("6", "world", StatusCode::OK, 11),
+    ] {
+        let req = request(Method::PATCH, &upload)
+            .insert_header(("Upload-Offset", offset))
+            .set_payload(part)
+            .to_srv_request();
+        let resp = main_handler(req).await?;
+        assert_eq!(resp.status(), expected_status);
+        assert_eq!(json_body(resp).await["offset"], expected_offset);
+    }
+    let resp = main_handler(request(Method::GET, &upload).to_srv_request()).await?;
+    assert_eq!(json_body(resp).await["complete"], true);
+
+    let id = upload.rsplit('/').next().unwrap();
+    let uri = format!("/tests/chunked_upload_test.sql?id={id}");
+    let resp = main_handler(request(Method::GET, &uri).to_srv_request()).await?;
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("hello world"), "{body}");
+
+    let resp = main_handler(request(Method::DELETE, &upload).to_srv_request()).await?;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    let err = main_handler(request(Method::GET, &upload).to_srv_request())
+        .await
+        .unwrap_err();
+    assert_eq!(err.as_response_error().status_code(), StatusCode::NOT_FOUND);
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_chunked_upload_limits() -> actix_web::Result<()> {
+    use actix_web::http::Method;
+    let mut config = test_config();
+    config.max_chunked_upload_size = Some(100);
+    config.max_chunked_uploads_total_size = Some(50);
+    let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    let request = |method: Method, uri: &str| {
+        test::TestRequest::default()
+            .method(method)
+            .uri(uri)
+            .app_data(data.clone())
+    };
+    let req = request(Method::POST, "/sqlpage/uploads")
+        .insert_header(("Upload-Length", "60"))
+        .to_srv_request();
+    let err = main_handler(req).await.unwrap_err();
+    assert_eq!(
+        err.as_response_error().status_code(),
+        StatusCode::INSUFFICIENT_STORAGE
+    );
+
+    // An upload created in a session can only be used in this session
+    let resp =
+        main_handler(request(Method::GET, "/tests/session_test.sql").to_srv_request()).await?;
+    let session = response_cookies(&resp)
+        .into_iter()
+        .find(|c| c.name() == "sqlpage_session")
+        .unwrap();
+    let req = request(Method::POST, "/sqlpage/uploads")
+        .insert_header(("Upload-Length", "5"))
+        .cookie(session.clone())
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let upload = resp
+        .headers()
+        .get("location")
+        .unwrap()
+        .to_str()?
+        .to_string();
+    let err = main_handler(request(Method::GET, &upload).to_srv_request())
+        .await
+        .unwrap_err();
+    assert_eq!(err.as_response_error().status_code(), StatusCode::NOT_FOUND);
+    let req = request(Method::DELETE, &upload)
+        .cookie(session)
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_static_file_conditional_requests() -> actix_web::Result<()> {
+    let resp = req_path("/tests/it_works.txt").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("cache-control").unwrap(), "no-cache");
+    assert!(resp.headers().contains_key("last-modified"));
+    let etag = resp.headers().get("etag").unwrap().clone();
+    let req = get_request_to("/tests/it_works.txt")
+        .await?
+        .insert_header(("If-None-Match", etag))
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    let req = get_request_to("/tests/it_works.txt")
+        .await?
+        .insert_header(("If-None-Match", "\"outdated\""))
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_static_file_range_requests() -> actix_web::Result<()> {
+    let resp = req_path("/tests/it_works.txt").await?;
+    assert_eq!(resp.headers().get("accept-ranges").unwrap(), "bytes");
+    let etag = resp.headers().get("etag").unwrap().clone();
+    let req = get_request_to("/tests/it_works.txt")
+        .await?
+        .insert_header(("Range", "bytes=3-7"))
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(resp.headers().get("content-range").unwrap(), "bytes 3-7/10");
+    assert_eq!(test::read_body(resp).await, "works");
+    let req = get_request_to("/tests/it_works.txt")
+        .await?
+        .insert_header(("Range", "bytes=100-"))
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(resp.headers().get("content-range").unwrap(), "bytes */10");
+    let req = get_request_to("/tests/it_works.txt")
+        .await?
+        .insert_header(("Range", "bytes=3-7"))
+        .insert_header(("If-Range", etag))
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+    // The whole file is sent when it changed since the client got the first part
+    let req = get_request_to("/tests/it_works.txt")
+        .await?
+        .insert_header(("Range", "bytes=3-7"))
+        .insert_header(("If-Range", "\"outdated\""))
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_virtual_hosts() {
+    init_log();
+    let mut config = test_config();
+    config.virtual_hosts = vec![VirtualHost {
+        host: "blog.example.com".to_string(),
+        web_root: "tests/virtual_host".into(),
+        database_url: None,
+    }];
+    let state = AppState::init(&config).await.unwrap();
+    let app = test::init_service(create_app(actix_web::web::Data::new(state))).await;
+    for (host, expected) in [
+        ("blog.example.com:8080", true),
+        ("blog.example.com", true),
+        ("example.com", false),
+    ] {
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header(("Host", host))
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert_eq!(
+            body.contains("Hello from the virtual host"),
+            expected,
+            "{host}"
+        );
+    }
+}
+
+#[actix_web::test]
+async fn test_access_log() {
+    init_log();
+    let log_file = std::env::temp_dir().join(format!(
+        "sqlpage_access_log_test_{}.log",
+        std::process::id()
+    ));
+    let mut config = test_config();
+    config.access_log = Some(log_file.to_string_lossy().into_owned());
+    config.access_log_format = AccessLogFormat::Json;
+    let state = AppState::init(&config).await.unwrap();
+    let app = test::init_service(create_app(actix_web::web::Data::new(state))).await;
+    let req = test::TestRequest::get()
+        .uri("/tests/sql_test_files/it_works_big_number.sql?x=1")
+        .to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    let req = test::TestRequest::get()
+        .uri("/tests/does_not_exist.sql")
+        .to_request();
+    test::call_and_read_body(&app, req).await;
+    let log = std::fs::read_to_string(&log_file).unwrap();
+    std::fs::remove_file(&log_file).unwrap();
+    let lines: Vec<serde_json::Value> = log
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2, "{log}");
+    assert_eq!(lines[0]["method"], "GET");
+    assert_eq!(
+        lines[0]["uri"],
+        "/tests/sql_test_files/it_works_big_number.sql?x=1"
+    );
+    assert_eq!(lines[0]["status"], 200);
+    assert_eq!(lines[0]["size"], body.len());
+    assert_eq!(
+        lines[0]["sql_file"],
+        "tests/sql_test_files/it_works_big_number.sql"
+    );
+    assert_eq!(lines[1]["status"], 404);
+}
+
+#[actix_web::test]
+async fn test_request_id() {
+    init_log();
+    let state = AppState::init(&test_config()).await.unwrap();
+    let app = test::init_service(create_app(actix_web::web::Data::new(state))).await;
+
+    // An id set by a reverse proxy is kept
+    let req = test::TestRequest::get()
+        .uri("/tests/request_id_test.sql")
+        .insert_header(("X-Request-Id", "proxy-id-123"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers()["X-Request-Id"], "proxy-id-123");
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("Request proxy-id-123"), "{body}");
+
+    // Otherwise, a new id is generated for every request
+    let req = test::TestRequest::get()
+        .uri("/tests/request_id_test.sql")
+        .insert_header(("X-Request-Id", "invalid id with spaces"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let id = resp.headers()["X-Request-Id"].to_str().unwrap().to_string();
+    assert_ne!(id, "invalid id with spaces");
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains(&format!("Request {id}")), "{body}");
+}
+
+#[actix_web::test]
+async fn test_robots_and_security_txt() -> actix_web::Result<()> {
+    let mut config = test_config();
+    config.robots_txt = Some(serde_json::from_str(
+        r#"{"disallow": ["/admin/"], "sitemap": "https://example.com/sitemap.xml"}"#,
+    )?);
+    config.security_txt = Some(serde_json::from_str(
+        r#"{"contact": ["mailto:security@example.com"], "expires": "2030-01-01T00:00:00Z"}"#,
+    )?);
+    let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    let get = |uri: &str| {
+        test::TestRequest::get()
+            .uri(uri)
+            .app_data(data.clone())
+            .to_srv_request()
+    };
+    let resp = main_handler(get("/robots.txt")).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = test::read_body(resp).await;
+    assert_eq!(
+        body,
+        "User-agent: *\nDisallow: /admin/\n\nSitemap: https://example.com/sitemap.xml\n"
+    );
+    let resp = main_handler(get("/.well-known/security.txt")).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = test::read_body(resp).await;
+    assert_eq!(
+        body,
+        "Contact: mailto:security@example.com\nExpires: 2030-01-01T00:00:00Z\n"
+    );
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_proxy() -> actix_web::Result<()> {
+    init_log();
+    let upstream = actix_web::HttpServer::new(|| {
+        actix_web::App::new().default_service(actix_web::web::to(
+            |req: actix_web::HttpRequest, body: String| async move {
+                let forwarded_for = req.headers().get("x-forwarded-for").unwrap();
+                format!("{} {} {forwarded_for:?} {body}", req.method(), req.uri())
+            },
+        ))
+    })
+    .workers(1)
+    .bind("127.0.0.1:0")?;
+    let port = upstream.addrs()[0].port();
+    let upstream = upstream.run();
+    let upstream_handle = upstream.handle();
+    actix_web::rt::spawn(upstream);
+
+    let mut config = test_config();
+    config.proxy = vec![ProxyRoute {
+        path_prefix: "/api/".into(),
+        upstream: format!("http://127.0.0.1:{port}/v1"),
+        strip_prefix: true,
+    }];
+    let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    let req = test::TestRequest::post()
+        .uri("/api/echo?x=1")
+        .peer_addr("1.2.3.4:1234".parse().unwrap())
+        .set_payload("hello")
+        .app_data(data)
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert_eq!(body, "POST /v1/echo?x=1 \"1.2.3.4\" hello");
+    upstream_handle.stop(true).await;
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_render_to_storage() -> actix_web::Result<()> {
+    init_log();
+    // A stub of an S3-compatible object storage, that records the objects it receives
+    let uploads = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = std::sync::Arc::clone(&uploads);
+    let storage = actix_web::HttpServer::new(move || {
+        let recorded = std::sync::Arc::clone(&recorded);
+        actix_web::App::new().default_service(actix_web::web::to(
+            move |req: actix_web::HttpRequest, body: String| {
+                let authorization = req.headers().get("authorization").unwrap();
+                recorded.lock().unwrap().push((
+                    req.method().to_string(),
+                    req.path().to_string(),
+                    authorization.to_str().unwrap().to_string(),
+                    body,
+                ));
+                async { actix_web::HttpResponse::Ok().finish() }
+            },
+        ))
+    })
+    .workers(1)
+    .bind("127.0.0.1:0")?;
+    let port = storage.addrs()[0].port();
+    let storage = storage.run();
+    let storage_handle = storage.handle();
+    actix_web::rt::spawn(storage);
+
+    let mut config = test_config();
+    config.storage_endpoint = Some(format!("http://127.0.0.1:{port}"));
+    config.storage_access_key_id = Some("test-key".into());
+    config.storage_secret_access_key = Some("test-secret".into());
+    let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    let get_body = |path: &str| {
+        let req = test::TestRequest::get()
+            .uri(path)
+            .app_data(data.clone())
+            .to_srv_request();
+        async move {
+            let resp = main_handler(req).await.unwrap();
+            String::from_utf8(test::read_body(resp).await.to_vec()).unwrap()
+        }
+    };
+    let body = get_body("/tests/render_to_storage_test.sql").await;
+    let expected_url = format!("http://127.0.0.1:{port}/reports/hello.html");
+    assert!(
+        body.contains(&format!("Uploaded to {expected_url}")),
+        "{body}"
+    );
+    {
+        let uploads = uploads.lock().unwrap();
+        let [(method, path, authorization, uploaded)] = uploads.as_slice() else {
+            panic!("expected a single upload, got {uploads:?}");
+        };
+        assert_eq!(method, "PUT");
+        assert_eq!(path, "/reports/hello.html");
+        assert!(
+            authorization.starts_with("AWS4-HMAC-SHA256 Credential=test-key/"),
+            "{authorization}"
+        );
+        assert!(uploaded.contains("Hello Bob"), "{uploaded}");
+    }
+
+    let body = get_body("/tests/render_to_storage_recursive_test.sql").await;
+    assert!(
+        body.contains("cannot be nested more than 3 levels"),
+        "{body}"
+    );
+    assert_eq!(uploads.lock().unwrap().len(), 1);
+    storage_handle.stop(true).await;
+    Ok(())
+}
+
+struct Greet;
+
+#[async_trait::async_trait(?Send)]
+impl sqlpage::webserver::database::custom_functions::SqlPageFunction for Greet {
+    async fn call(
+        &self,
+        _request: &sqlpage::webserver::http_request_info::RequestInfo,
+        arguments: Vec<Option<String>>,
+    ) -> anyhow::Result<Option<String>> {
+        let name = arguments.into_iter().next().flatten();
+        Ok(name.map(|name| format!("Hello, {name}!")))
+    }
+}
+
+#[actix_web::test]
+async fn test_embedding() {
+    init_log();
+    let state = AppState::builder(&test_config())
+        .function("greet", Greet)
+        .build()
+        .await
+        .unwrap();
+    let app = create_app(actix_web::web::Data::new(state))
+        .wrap(actix_web::middleware::DefaultHeaders::new().add(("X-Embedded", "yes")))
+        .route("/rust", actix_web::web::get().to(|| async { "from rust" }));
+    let app = test::init_service(app).await;
+
+    let req = test::TestRequest::get()
+        .uri("/tests/custom_function_test.sql?name=Ferris")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["X-Embedded"], "yes");
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("Hello, Ferris!"), "{body}");
+
+    let req = test::TestRequest::get().uri("/rust").to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    assert_eq!(body, "from rust");
+}
+
+#[actix_web::test]
+async fn test_translations() -> actix_web::Result<()> {
+    for (accept_language, expected) in [
+        ("fr-CH, fr;q=0.9, en;q=0.8", "Créé avec"),
+        ("de;q=0.5, es", "Creado con"),
+        ("xx, *;q=0.5", "Built with"),
+    ] {
+        let req = get_request_to("/tests/translation_test.sql")
+            .await?
+            .insert_header(("Accept-Language", accept_language))
+            .to_srv_request();
+        let resp = main_handler(req).await?;
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains(expected), "{accept_language}: {body}");
+    }
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_session_variables() -> actix_web::Result<()> {
+    let resp = req_path("/tests/session_test.sql").await?;
+    let cookies = response_cookies(&resp);
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("Greeting: none"), "{body}");
+    let (ids, variables): (Vec<_>, Vec<_>) = cookies
+        .into_iter()
+        .partition(|c| c.name() == "sqlpage_session");
+    let (id, variable) = (ids[0].clone(), variables[0].clone());
+
+    let body = session_test_body(vec![id.clone(), variable.clone()]).await?;
+    assert!(body.contains("Greeting: It works !"), "{body}");
+
+    let mut tampered = variable.clone();
+    tampered.set_value(tampered.value().replacen("It works", "Hacked", 1));
+    let body = session_test_body(vec![id, tampered]).await?;
+    assert!(body.contains("Greeting: none"), "{body}");
+
+    // A variable is ignored in another session
+    let other_id = response_cookies(&req_path("/tests/session_test.sql").await?)
+        .into_iter()
+        .find(|c| c.name() == "sqlpage_session")
+        .unwrap();
+    let body = session_test_body(vec![other_id, variable]).await?;
+    assert!(body.contains("Greeting: none"), "{body}");
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_set_session_variable() -> actix_web::Result<()> {
+    let mut cookies = Vec::new();
+    for expected in ["Path: x", "Path: xx", "Path: xxx"] {
+        let mut req = get_request_to("/tests/session_set_test.sql").await?;
+        for cookie in &cookies {
+            req = req.cookie(actix_web::cookie::Cookie::clone(cookie));
+        }
+        let resp = main_handler(req.to_srv_request()).await?;
+        for cookie in response_cookies(&resp) {
+            cookies.retain(|c: &actix_web::cookie::Cookie| c.name() != cookie.name());
+            cookies.push(cookie);
+        }
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains(expected), "{expected}: {body}");
+    }
+    Ok(())
+}
+
+async fn session_test_body(
+    cookies: Vec<actix_web::cookie::Cookie<'static>>,
+) -> actix_web::Result<String> {
+    let mut req = get_request_to("/tests/session_test.sql").await?;
+    for cookie in cookies {
+        req = req.cookie(cookie);
+    }
+    let resp = main_handler(req.to_srv_request()).await?;
+    Ok(String::from_utf8(test::read_body(resp).await.to_vec()).unwrap())
+}
+
+#[actix_web::test]
+async fn test_impersonation() -> actix_web::Result<()> {
+    let mut jar = std::collections::HashMap::new();
+    for (action, expected) in [
+        ("login", "user: , impersonator: "),
+        ("deny", "The impersonate component requires an"),
+        ("show", "user: alice, impersonator: "),
+        ("start", "user: alice, impersonator: "),
+        ("show", "user: bob, impersonator: alice"),
+        ("stop", "user: bob, impersonator: alice"),
+        ("show", "user: alice, impersonator: "),
+    ] {
+        let mut req =
+            get_request_to(&format!("/tests/impersonate_test.sql?action={action}")).await?;
+        for cookie in jar.values() {
+            req = req.cookie(actix_web::cookie::Cookie::clone(cookie));
+        }
+        let resp = main_handler(req.to_srv_request()).await?;
+        for set_cookie in resp.headers().get_all(http::header::SET_COOKIE) {
+            let cookie =
+                actix_web::cookie::Cookie::parse_encoded(set_cookie.to_str().unwrap().to_string())
+                    .unwrap();
+            if cookie.value().is_empty() {
+                jar.remove(cookie.name());
+            } else {
+                jar.insert(cookie.name().to_string(), cookie);
+            }
+        }
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(
+            body.contains(expected),
+            "{action}: expected {expected:?}\n{body}"
+        );
+    }
+    assert_eq!(jar.len(), 2, "{jar:?}");
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_basic_auth() {
+    use base64::Engine;
+    use password_hash::PasswordHasher;
+    init_log();
+    let salt = password_hash::SaltString::generate(&mut password_hash::rand_core::OsRng);
+    let hash = argon2::Argon2::default()
+        .hash_password(b"secret", &salt)
+        .unwrap()
+        .to_string();
+    let mut config = test_config();
+    config.basic_auth = serde_json::from_value(serde_json::json!([{
+        "path_prefix": "/tests/",
+        "users": { "alice": hash },
+        "query": format!("SELECT '{hash}' WHERE $username = 'bob'"),
+        "realm": "Tests"
+    }]))
+    .unwrap();
+    let state = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    for (path, credentials, expected_status) in [
+        ("/tests/it_works.txt", None, StatusCode::UNAUTHORIZED),
+        (
+            "/tests/it_works.txt",
+            Some("alice:wrong"),
+            StatusCode::UNAUTHORIZED,
+        ),
+        (
+            "/tests/it_works.txt",
+            Some("mallory:secret"),
+            StatusCode::UNAUTHORIZED,
+        ),
+        ("/tests/it_works.txt", Some("alice:secret"), StatusCode::OK),
+        ("/tests/it_works.txt", Some("bob:secret"), StatusCode::OK),
+        (
+            "/tests/session_test.sql",
+            Some("bob:secret"),
+            StatusCode::OK,
+        ),
+        ("/", None, StatusCode::OK),
+    ] {
+        let mut req = test::TestRequest::get().uri(path).app_data(state.clone());
+        if let Some(credentials) = credentials {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+            req = req.insert_header(("Authorization", format!("Basic {encoded}")));
+        }
+        let resp = main_handler(req.to_srv_request()).await.unwrap();
+        assert_eq!(resp.status(), expected_status, "{path} {credentials:?}");
+        if expected_status == StatusCode::UNAUTHORIZED {
+            assert_eq!(
+                resp.headers().get(http::header::WWW_AUTHENTICATE).unwrap(),
+                "Basic realm=\"Tests\", charset=\"UTF-8\""
+            );
+        }
+    }
+
+    // The user is only known when a rule checked its password
+    let encoded = base64::engine::general_purpose::STANDARD.encode("bob:secret");
+    let authorization = ("Authorization", format!("Basic {encoded}"));
+    let req = test::TestRequest::get()
+        .uri("/tests/current_user_test.sql")
+        .app_data(state.clone())
+        .insert_header(authorization.clone());
+    let body = test::read_body(main_handler(req.to_srv_request()).await.unwrap()).await;
+    assert!(String::from_utf8_lossy(&body).contains("user: bob"));
+    let req = get_request_to("/tests/current_user_test.sql")
+        .await
+        .unwrap()
+        .insert_header(authorization);
+    let body = test::read_body(main_handler(req.to_srv_request()).await.unwrap()).await;
+    assert!(String::from_utf8_lossy(&body).contains("user: anonymous"));
+}
+
+#[actix_web::test]
+async fn test_content_security_policy_nonce() {
+    init_log();
+    let state = AppState::init(&test_config()).await.unwrap();
+    let app = test::init_service(create_app(actix_web::web::Data::new(state))).await;
+    let mut nonces = Vec::new();
+    for _ in 0..2 {
+        let req = test::TestRequest::get().uri("/").to_request();
+        let resp = test::call_service(&app, req).await;
+        let policy = resp
+            .headers()
+            .get(http::header::CONTENT_SECURITY_POLICY)
+            .expect("pages should have a content security policy")
+            .to_str()
+            .unwrap()
+            .to_string();
+        let nonce = policy
+            .split_once("'nonce-")
+            .and_then(|(_, rest)| rest.split_once('\''))
+            .map(|(nonce, _)| nonce.to_string())
+            .unwrap_or_else(|| panic!("the policy should contain a nonce: {policy}"));
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains(&format!("nonce=\"{nonce}\"")), "{body}");
+        nonces.push(nonce);
+    }
+    assert_ne!(nonces[0], nonces[1], "each page should have its own nonce");
+
+    let req = test::TestRequest::get()
+        .uri("/tests/it_works.txt")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(
+        resp.headers()
+            .get(http::header::CONTENT_SECURITY_POLICY)
+            .unwrap(),
+        "script-src 'self' https://cdn.jsdelivr.net"
+    );
+}
+
+#[actix_web::test]
+async fn test_csrf_protection() -> actix_web::Result<()> {
+    init_log();
+    let mut config = test_config();
+    config.csrf_protection = true;
+    let state = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    let req = test::TestRequest::get()
+        .uri("/tests/csrf_test.sql")
+        .app_data(state.clone())
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    let set_cookie = resp
+        .headers()
+        .get(http::header::SET_COOKIE)
+        .expect("a csrf cookie should be set")
+        .to_str()
+        .unwrap()
+        .to_string();
+    let cookie = actix_web::cookie::Cookie::parse_encoded(set_cookie).unwrap();
+    assert_eq!(cookie.name(), "sqlpage_csrf");
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    let token = body
+        .split_once(r#"name="_sqlpage_csrf" value=""#)
+        .and_then(|(_, rest)| rest.split_once('"'))
+        .map(|(token, _)| token.to_string())
+        .unwrap_or_else(|| panic!("the form should contain the csrf token: {body}"));
+
+    for (form, with_cookie, expected_status) in [
+        (
+            format!("name=Bob&_sqlpage_csrf={token}"),
+            true,
+            StatusCode::OK,
+        ),
+        ("name=Mallory".to_string(), true, StatusCode::FORBIDDEN),
+        (
+            format!("name=Mallory&_sqlpage_csrf={token}"),
+            false,
+            StatusCode::FORBIDDEN,
+        ),
+        (
+            "name=Mallory&_sqlpage_csrf=forged".to_string(),
+            true,
+            StatusCode::FORBIDDEN,
+        ),
+    ] {
+        let mut req = test::TestRequest::post()
+            .uri("/tests/csrf_test.sql")
+            .app_data(state.clone())
+            .insert_header(ContentType::form_url_encoded())
+            .set_payload(form.clone());
+        if with_cookie {
+            req = req.cookie(cookie.clone());
+        }
+        let resp = main_handler(req.to_srv_request()).await?;
+        assert_eq!(resp.status(), expected_status, "{form}");
+        if expected_status == StatusCode::OK {
+            let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+            assert!(body.contains("Hello, Bob!"), "{body}");
+        }
+    }
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_reload_keeps_sessions() -> actix_web::Result<()> {
+    init_log();
+    let mut config = test_config();
+    config.session_secret = None;
+    let state = AppState::init(&config).await.unwrap();
+    let req = test::TestRequest::get()
+        .uri("/tests/session_test.sql")
+        .app_data(actix_web::web::Data::new(
+            state.reload(&config).await.unwrap(),
+        ))
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    let cookies = response_cookies(&resp);
+
+    let reloaded = state.reload(&config).await.unwrap();
+    let mut req = test::TestRequest::get()
+        .uri("/tests/session_test.sql")
+        .app_data(actix_web::web::Data::new(reloaded));
+    for cookie in cookies {
+        req = req.cookie(cookie);
+    }
+    let req = req.to_srv_request();
+    let body = String::from_utf8(test::read_body(main_handler(req).await?).await.to_vec()).unwrap();
+    assert!(body.contains("Greeting: It works !"), "{body}");
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_server_side_session_stores() -> actix_web::Result<()> {
+    init_log();
+    // The sqlpage_sessions table of the database store is created automatically
+    for store in ["memory", "database"] {
+        let mut config = test_config();
+        config.session_store = store.to_string();
+        let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+        let req = test::TestRequest::get()
+            .uri("/tests/session_test.sql")
+            .app_data(data.clone())
+            .to_srv_request();
+        let resp = main_handler(req).await?;
+        let set_cookie = resp.headers().get(http::header::SET_COOKIE).unwrap();
+        let cookie =
+            actix_web::cookie::Cookie::parse_encoded(set_cookie.to_str().unwrap().to_string())
+                .unwrap();
+        assert_eq!(cookie.name(), "sqlpage_session", "{store}");
+        assert!(!cookie.value().contains("works"), "{store}: {cookie}");
+
+        let req = test::TestRequest::get()
+            .uri("/tests/session_test.sql")
+            .app_data(data.clone())
+            .cookie(cookie)
+            .to_srv_request();
+        let body =
+            String::from_utf8(test::read_body(main_handler(req).await?).await.to_vec()).unwrap();
+        assert!(body.contains("Greeting: It works !"), "{store}: {body}");
+    }
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_page_cache() -> actix_web::Result<()> {
+    init_log();
+    let data = actix_web::web::Data::new(AppState::init(&test_config()).await.unwrap());
+    let get_page = |path: &'static str| {
+        let req = test::TestRequest::get()
+            .uri(path)
+            .app_data(data.clone())
+            .to_srv_request();
+        async move {
+            let resp = main_handler(req).await.unwrap();
+            assert_eq!(resp.status(), http::StatusCode::OK);
+            let policy = resp
+                .headers()
+                .get(http::header::CONTENT_SECURITY_POLICY)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string();
+            let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+            (policy, body)
+        }
+    };
+    let get_body = |path: &'static str| {
+        let page = get_page(path);
+        async move { page.await.1 }
+    };
+    let random = |body: &str| body.split("Random: ").nth(1).unwrap()[..20].to_string();
+    let (first_policy, first) = get_page("/tests/cache_test.sql").await;
+    assert!(first.contains("Random: "), "{first}");
+    let (policy, cached) = get_page("/tests/cache_test.sql").await;
+    assert_eq!(random(&cached), random(&first));
+    // Each response has its own content security policy nonce, in the header and in the scripts
+    assert_ne!(policy, first_policy);
+    let nonce = policy
+        .split("'nonce-")
+        .nth(1)
+        .unwrap()
+        .split('\'')
+        .next()
+        .unwrap();
+    assert!(cached.contains(&format!("nonce=\"{nonce}\"")), "{cached}");
+    assert_ne!(
+        random(&get_body("/tests/cache_test.sql?other=1").await),
+        random(&first)
+    );
+
+    let purge = get_body("/tests/purge_cache_test.sql").await;
+    assert!(purge.contains("Purged pages: 2"), "{purge}");
+    assert_ne!(
+        random(&get_body("/tests/cache_test.sql").await),
+        random(&first)
+    );
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_request_timeout() -> actix_web::Result<()> {
+    init_log();
+    let mut config = test_config();
+    config.request_timeout_seconds = Some(0.1);
+    let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    let req = test::TestRequest::get()
+        .uri("/tests/timeout_test.sql")
+        .app_data(data)
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("This page took too long to load"), "{body}");
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_etag() -> actix_web::Result<()> {
+    init_log();
+    let mut config = test_config();
+    config.etag_paths = vec!["/tests/etag".to_string()];
+    let data = actix_web::web::Data::new(AppState::init(&config).await.unwrap());
+    let request = |path: &str| test::TestRequest::get().uri(path).app_data(data.clone());
+    let resp = main_handler(request("/tests/etag_test.sql").to_srv_request()).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let etag = resp.headers().get(http::header::ETAG).unwrap().clone();
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("Hello, world!"), "{body}");
+
+    let req = request("/tests/etag_test.sql")
+        .insert_header((http::header::IF_NONE_MATCH, etag.clone()))
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+    let req = request("/tests/etag_test.sql?name=Bob")
+        .insert_header((http::header::IF_NONE_MATCH, etag))
+        .to_srv_request();
+    let resp = main_handler(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("Hello, Bob!"), "{body}");
+    Ok(())
+}
+
+async fn get_request_to(path: &str) -> actix_web::Result<TestRequest> {
+    init_log();
+    let config = test_config();
+    let state = AppState::init(&config).await.unwrap();
+    let data = actix_web::web::Data::new(state);
+    Ok(test::TestRequest::get().uri(path).app_data(data))
+}
+
+async fn req_path(path: &str) -> Result<actix_web::dev::ServiceResponse, actix_web::Error> {
+    let req = get_request_to(path)
+        .await?
+        .insert_header(ContentType::plaintext())
+        .to_srv_request();
+    main_handler(req).await
+}
+
+/// The cookies set by a response
+fn response_cookies(
+    resp: &actix_web::dev::ServiceResponse,
+) -> Vec<actix_web::cookie::Cookie<'static>> {
+    resp.headers()
+        .get_all(http::header::SET_COOKIE)
+        .map(|c| actix_web::cookie::Cookie::parse_encoded(c.to_str().unwrap().to_string()).unwrap())
+        .collect()
 }
 
 pub fn test_config() -> AppConfig {
@@ -164,6 +2042,10 @@ pub fn test_config() -> AppConfig {
         "database_connection_retries": 2,
         "database_connection_acquire_timeout_seconds": 1,
         "allow_exec": true,
+        "pdf_converter": "cat",
+        "template_directories": ["tests/theme"],
+        "session_secret": "test session secret",
+        "csrf_protection": false,
         "listen_on": "111.111.111.111:1"
     }}"#,
         db_url
@@ -0,0 +1 @@
+select 'text' as component, 'This page is not allowed from ' || sqlpage.client_ip() as contents;
@@ -0,0 +1,3 @@
+select 'json' as component, $type as type;
+select 1 as id, 'Ophir' as name;
+select 2 as id, 'Max' as name;
@@ -0,0 +1 @@
+select 'text' as component, 'Item deleted by item.delete.sql' as contents;
@@ -0,0 +1 @@
+select 'text' as component, 'Item handled by item.sql for ' || sqlpage.request_method() as contents;
@@ -0,0 +1,2 @@
+select 'pdf' as component, 'invoice' as filename, 'landscape' as orientation, 'Invoice 42' as header, 'Page {page} of {pages}' as footer;
+select 'text' as component, 'Total: 42 €' as contents;
@@ -0,0 +1 @@
+select 'text' as component, 'Purged pages: ' || sqlpage.purge_cache('/tests/cache_test') as contents;
@@ -0,0 +1,2 @@
+select 'redirect' as component, 'target.sql' as link, $status as status;
+select 'text' as component, 'This should never be rendered' as contents;
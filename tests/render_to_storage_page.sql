@@ -0,0 +1 @@
+select 'text' as component, 'Hello ' || $name as contents;
@@ -0,0 +1 @@
+select 'text' as component, sqlpage.render_to_storage('/tests/render_to_storage_recursive_test.sql', '{}', 'reports/loop.html') as contents;
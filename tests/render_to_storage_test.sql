@@ -0,0 +1 @@
+select 'text' as component, 'Uploaded to ' || sqlpage.render_to_storage('/tests/render_to_storage_page.sql', '{"name": "Bob"}', 'reports/hello.html') as contents;
@@ -0,0 +1 @@
+select 'text' as component, 'Request ' || sqlpage.request_id() as contents;
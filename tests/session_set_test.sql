@@ -0,0 +1,2 @@
+set $session.path = coalesce($session.path, '') || 'x';
+select 'text' as component, 'Path: ' || $session.path as contents;
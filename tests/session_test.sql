@@ -0,0 +1,2 @@
+select 'session' as component, 'greeting' as name, 'It works !' as value;
+select 'text' as component, 'Greeting: ' || coalesce(sqlpage.session('greeting'), 'none') as contents;
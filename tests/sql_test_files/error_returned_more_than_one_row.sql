@@ -0,0 +1,2 @@
+set x = (select 'a' as letter union all select 'b' as letter);
+select 'text' as component, 'x is ' || $x as contents;
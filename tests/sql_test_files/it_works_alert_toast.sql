@@ -0,0 +1,2 @@
+select 'text' as component, 'The page starts here.' as contents;
+select 'alert' as component, 'It works !' as title, TRUE as toast, 0 as delay;
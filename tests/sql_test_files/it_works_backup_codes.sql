@@ -0,0 +1,6 @@
+set codes = sqlpage.generate_backup_codes(1);
+set code = sqlpage.json_param('$codes', '$[0].code');
+set hash = sqlpage.json_param('$codes', '$[0].hash');
+set matched = sqlpage.verify_backup_code($code, $hash);
+select 'text' as component,
+    case when $matched = $hash then 'It works !' else 'The backup code did not match its hash' end as contents;
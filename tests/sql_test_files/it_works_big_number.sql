@@ -0,0 +1,3 @@
+select 'big_number' as component;
+select 'It works !' as title, 42 as value, 1.5 as delta, '[1, 2.5, 2]' as sparkline;
+select 'Costs' as title, '1 000' as value, 0 as delta, TRUE as inverse;
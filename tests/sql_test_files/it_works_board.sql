@@ -0,0 +1,2 @@
+select 'board' as component, '["To do", "Done"]' as columns, 'move.sql' as action;
+select 1 as id, 'Done' as status, 'It works !' as title;
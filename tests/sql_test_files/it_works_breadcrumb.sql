@@ -0,0 +1,3 @@
+select 'breadcrumb' as component, 'arrows' as separator;
+select 'Home' as title, '/' as link, 'home' as icon;
+select 'It works !' as title, TRUE as active;
@@ -0,0 +1,2 @@
+select 'card' as component, 'compact' as density, 100 as image_height;
+select 'It works !' as title, '/favicon.ico' as image_url, 'An icon' as image_alt, 'Footer' as footer;
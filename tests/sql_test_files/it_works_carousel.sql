@@ -0,0 +1,3 @@
+select 'carousel' as component, TRUE as thumbnails, TRUE as lightbox;
+select '/favicon.ico' as image_url, 'It works !' as title;
+select '/favicon.ico' as image_url;
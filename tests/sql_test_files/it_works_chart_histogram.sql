@@ -0,0 +1,4 @@
+select 'chart' as component, 'histogram' as type, 'It works !' as title, 3 as bins;
+select 1 as value;
+select 2 as value;
+select 10 as value;
@@ -0,0 +1,3 @@
+select 'chart' as component, 'line' as type, 'It works !' as title, true as time, true as zoom, 2 as moving_average;
+select '2024-01-15 10:00:00' as x, 1 as y;
+select '2024-01-15 10:01:00' as x, 3 as y;
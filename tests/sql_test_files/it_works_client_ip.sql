@@ -0,0 +1,5 @@
+-- Test requests do not come from a network connection
+select 'text' as component,
+    CASE WHEN sqlpage.client_ip() IS NULL THEN 'It works !'
+    ELSE 'It failed ! Expected NULL, got "' || sqlpage.client_ip() || '".'
+    END AS contents;
@@ -0,0 +1,2 @@
+select 'code' as component;
+select 'It works !' as title, 'select 1;' as contents, 'sql' as language;
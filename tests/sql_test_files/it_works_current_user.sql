@@ -0,0 +1,6 @@
+select 'text' as component,
+    CASE
+        WHEN sqlpage.current_user() IS NULL
+        THEN 'It works !'
+        ELSE 'error: expected an anonymous request, got: ' || sqlpage.current_user()
+    END as contents;
@@ -0,0 +1,6 @@
+select 'text' as component,
+    CASE
+        WHEN sqlpage.feature('a_flag_that_does_not_exist') = 'false'
+        THEN 'It works !'
+        ELSE 'error: unknown feature flags should be disabled'
+    END as contents;
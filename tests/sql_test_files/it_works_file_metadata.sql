@@ -0,0 +1,10 @@
+select 'text' as component,
+    CASE
+        WHEN sqlpage.file_exists('tests/it_works.txt') = 'true'
+            AND sqlpage.file_exists('tests/does_not_exist.txt') = 'false'
+            AND sqlpage.file_size('tests/it_works.txt') = '10'
+            AND sqlpage.file_modified('tests/it_works.txt') LIKE '2%'
+            AND sqlpage.file_size('tests/does_not_exist.txt') IS NULL
+        THEN 'It works !'
+        ELSE 'error: unexpected file metadata: ' || sqlpage.file_size('tests/it_works.txt')
+    END as contents;
@@ -0,0 +1,4 @@
+select 'flush' as component, false as buffering;
+select 'text' as component, 'It works !' as contents;
+select 'flush' as component;
+select 'text' as component, 'Rendered after a flush' as contents;
@@ -0,0 +1,2 @@
+select 'form' as component;
+select 'email' as name, 'email' as type, true as required, 'It works !' as value, 'Invalid email' as error;
@@ -0,0 +1,6 @@
+select 'table' as component, 'category' as group_by, 'amount' as group_sum;
+select 'It works !' as category, 'a' as name, 1 as amount
+union all select 'It works !', 'b', 2
+union all select 'Other', 'c', 3;
+select 'list' as component, 'category' as group_by;
+select 'It works !' as category, 'item' as title;
@@ -0,0 +1 @@
+select 'hero' as component, 'It works !' as title, '/favicon.ico' as background_image, '/' as link, '/docs' as secondary_link;
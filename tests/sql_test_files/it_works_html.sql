@@ -0,0 +1,2 @@
+select 'html' as component, 'basic' as sanitize;
+select '<p>It works !</p><script>alert("error")</script>' as html;
@@ -0,0 +1,3 @@
+select 'title' as component, 'check' as icon, 'green' as color, 'Icons' as contents;
+select 'text' as component, 'Icons' as title, 'star' as icon;
+select 'It works !' as contents, 'check' as icon, 'green' as color;
@@ -0,0 +1,2 @@
+set payload = '{"items": [{"sku": "abc", "qty": 2}, {"sku": "It works !"}]}';
+select 'text' as component, sqlpage.json_param('$payload', '$.items[1].sku') as contents;
@@ -0,0 +1,4 @@
+select 'map' as component;
+select 'Null Island' as title, 0 as latitude, 0 as longitude;
+select 'text' as component,
+    'It works !' as contents;
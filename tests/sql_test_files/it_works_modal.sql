@@ -0,0 +1,3 @@
+select 'modal' as component, 'Open' as button, 'edit' as id, 'edit.sql' as action;
+select 'It works !' as contents;
+select 'name' as name, TRUE as required;
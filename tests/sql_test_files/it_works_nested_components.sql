@@ -0,0 +1,5 @@
+select 'card' as component;
+select 'Parent card' as title, '[
+    {"component": "list", "title": "Child list"},
+    {"title": "It works !"}
+]' as _sqlpage_children;
@@ -0,0 +1,3 @@
+-- The partial_test component in tests/theme reuses the table_header and table_row partials of the table component
+select 'partial_test' as component;
+select 'It works !' as result;
@@ -0,0 +1,6 @@
+select 'progress' as component, 80 as danger, 50 as warning;
+select 'CPU' as title, 90 as value;
+select 'Disk' as title, '60' as value, 200 as max;
+select 'progress' as component, 'gauge' as type;
+select 'Memory' as title, 42 as value, '%' as unit;
+select 'text' as component, 'It works !' as contents;
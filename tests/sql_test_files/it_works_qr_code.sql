@@ -0,0 +1,3 @@
+select 'qr_code' as component, 'H' as error_correction;
+select 'https://sql.ophir.dev' as contents, 'It works !' as title;
+select 42 as contents;
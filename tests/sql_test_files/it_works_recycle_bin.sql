@@ -0,0 +1,2 @@
+select 'recycle_bin' as component, 'It works !' as title;
+select 'Old invoice' as title, '2023-12-01' as deleted_at, 'restore.sql?id=1' as restore_link;
@@ -0,0 +1,7 @@
+set person = (select 'It works !' as greeting, 42 as answer);
+select 'text' as component,
+    CASE
+        WHEN sqlpage.json_param('$person', '$.answer') = '42'
+        THEN sqlpage.json_param('$person', '$.greeting')
+        ELSE 'error: expected a JSON object, got: ' || COALESCE($person, 'null')
+    END as contents;
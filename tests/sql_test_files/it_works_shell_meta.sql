@@ -0,0 +1,4 @@
+select 'shell' as component, 'It works !' as title, '/favicon.ico' as favicon,
+    'https://example.com/og.png' as image, 'https://example.com/page' as canonical,
+    'A page with metadata' as description;
+select 'text' as component, 'It works !' as contents;
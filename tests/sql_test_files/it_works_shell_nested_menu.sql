@@ -0,0 +1,7 @@
+select 'dynamic' as component, '{"component": "shell", "title": "It works !", "menu_item": [
+    "home",
+    {"title": "Docs", "icon": "book", "submenu": [
+        {"title": "Nested", "submenu": [{"title": "Deep", "link": "deep.sql", "icon": "star"}]}
+    ]}
+]}' as properties;
+select 'text' as component, 'It works !' as contents;
@@ -0,0 +1,2 @@
+select 'shell' as component, 'Report' as title, true as print, 60 as refresh;
+select 'text' as component, 'It works !' as contents;
@@ -0,0 +1,2 @@
+select 'shell' as component, 'It works !' as title, 'ar' as language;
+select 'text' as component, 'It works !' as contents;
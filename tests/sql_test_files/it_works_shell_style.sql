@@ -0,0 +1,2 @@
+select 'shell' as component, 'Styled page' as title, '.card { border-radius: 0; }' as style;
+select 'text' as component, 'It works !' as contents;
@@ -0,0 +1,2 @@
+select 'shell' as component, 'It works !' as title, 'auto' as theme, TRUE as theme_toggle;
+select 'text' as component, 'It works !' as contents;
@@ -0,0 +1,3 @@
+select 'steps' as component, coalesce($step, '2') as current;
+select 'Account' as title;
+select 'It works !' as title;
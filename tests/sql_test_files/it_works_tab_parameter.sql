@@ -0,0 +1,4 @@
+select 'tab' as component, 'view' as parameter;
+select 'Details' as title;
+select 'Orders' as title;
+select 'text' as component, 'It works !' as contents where coalesce($view, 'Details') = 'Details';
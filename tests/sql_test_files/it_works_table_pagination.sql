@@ -0,0 +1,4 @@
+select 'table' as component, true as sort, true as search, 2 as page_size;
+select 'It works !' as message;
+select 'Second row' as message;
+select 'Third row' as message;
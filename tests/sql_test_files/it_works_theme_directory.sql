@@ -0,0 +1,3 @@
+-- The theme_test component is defined in tests/theme, which is in template_directories in the tests configuration
+select 'theme_test' as component, 'It' as title;
+select 'works !' as contents;
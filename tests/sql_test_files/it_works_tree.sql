@@ -0,0 +1,5 @@
+select 'tree' as component;
+select 1 as id, 'It works !' as title;
+select 2 as id, 1 as parent_id, 'Child' as title;
+select 'tree' as component;
+select 'a/b' as path;
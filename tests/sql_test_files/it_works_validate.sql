@@ -0,0 +1,11 @@
+set email = 'john@example.com';
+set phone = '+33 6 12';
+select 'text' as component,
+    CASE
+        WHEN sqlpage.validate($email, 'email') = $email
+            AND sqlpage.validate($phone, 'integer') IS NULL
+            AND sqlpage.matches($phone, '\+?[0-9 ]+') = $phone
+            AND sqlpage.matches($email, '[a-z]+') IS NULL
+        THEN 'It works !'
+        ELSE 'Input validation failed'
+    END as contents;
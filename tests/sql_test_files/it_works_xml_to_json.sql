@@ -0,0 +1,3 @@
+set payload = '<?xml version="1.0"?><feed><entry id="1">first</entry><entry id="2">It works !</entry></feed>';
+set doc = sqlpage.xml_to_json($payload);
+select 'text' as component, sqlpage.json_param('$doc', '$.feed.entry[1].#text') as contents;
@@ -0,0 +1,3 @@
+select 'authentication' as component, $token = 'secret' as condition;
+select 'sse' as component, 60 as interval;
+select 'status' as id, 'Secret' as contents;
@@ -0,0 +1,3 @@
+select 'text' as component, 'Not sent' as contents;
+select 'sse' as component, 60 as interval;
+select 'status' as id, 'Live: ' || $name as contents;
@@ -0,0 +1,2 @@
+select 'status_code' as component, $status as status;
+select 'text' as component, 'Record not found' as contents;
@@ -0,0 +1,2 @@
+with recursive counter(x) as (select 1 union all select x + 1 from counter where x < 10000000)
+select 'text' as component, count(*) as contents from counter;
@@ -0,0 +1,2 @@
+select 'modal' as component, 'Translated' as title, TRUE as open;
+select 'Hello' as contents;
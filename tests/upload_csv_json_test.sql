@@ -0,0 +1,3 @@
+select 'text' as component,
+    json_extract(person.value, '$.name') || ' is ' || json_extract(person.value, '$.age') || ' years old. ' as contents
+from json_each(sqlpage.read_uploaded_csv('people_file')) as person;
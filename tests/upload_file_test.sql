@@ -1,2 +1,9 @@
 select 'text' as component,
-    sqlpage.read_file_as_text(sqlpage.uploaded_file_path('my_file')) as contents;
\ No newline at end of file
+    sqlpage.read_file_as_text(sqlpage.uploaded_file_path('my_file')) as contents;
+select 'text' as component,
+    'Uploaded ' || sqlpage.uploaded_file_name('my_file') || ' (' || sqlpage.uploaded_file_mime_type('my_file') || ')' as contents;
+
+select 'text' as component,
+    CASE WHEN sqlpage.uploaded_file_path('my_file', 'image/*') IS NULL
+        AND sqlpage.uploaded_file_path('my_file', 'text/*') IS NOT NULL
+    THEN 'Mime type filter ok' END as contents;
@@ -0,0 +1 @@
+select 'text' as component, 'Hello from the virtual host' as contents;
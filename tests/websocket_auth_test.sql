@@ -0,0 +1,2 @@
+select 'authentication' as component, :token = 'secret' as condition;
+select 'Secret message' as text;
@@ -0,0 +1 @@
+select :name as name, :message as message, $room as room;